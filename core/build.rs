@@ -0,0 +1,28 @@
+fn main() {
+    #[cfg(feature = "speex")]
+    generate_speex_bindings();
+}
+
+/// Generates bindings to the decoder and bit buffer API of the system's libspeex, so that the
+/// types match the installed headers.
+#[cfg(feature = "speex")]
+fn generate_speex_bindings() {
+    let header = "src/backend/audio/decoders/speex.h";
+    println!("cargo:rerun-if-changed={}", header);
+    println!("cargo:rustc-link-lib=speex");
+
+    let bindings = bindgen::Builder::default()
+        .header(header)
+        .whitelist_function("speex_bits_.*")
+        .whitelist_function("speex_decode.*")
+        .whitelist_function("speex_lib_get_mode")
+        .whitelist_var("SPEEX_GET_FRAME_SIZE")
+        .whitelist_var("SPEEX_RESET_STATE")
+        .whitelist_var("SPEEX_MODEID_WB")
+        .generate()
+        .expect("Unable to generate libspeex bindings");
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    bindings
+        .write_to_file(out_dir.join("speex.rs"))
+        .expect("Unable to write libspeex bindings");
+}
@@ -0,0 +1,508 @@
+//! Tracking of playing sounds, so that sound transforms can be applied to them and
+//! `Sound` objects can be notified when they complete, and of sounds being downloaded
+//! by `Sound.loadSound`.
+
+use crate::avm1::{Object, ScriptObject, SoundObject, TObject, Value};
+use crate::backend::audio::decoders::{mp3_frames, Mp3StreamBuffer};
+use crate::backend::audio::{
+    swf, AudioBackend, AudioStreamHandle, SoundHandle, SoundInstanceHandle, SoundTransform,
+};
+use crate::backend::navigator::{NavigatorBackend, RequestHandle, RequestStatus};
+use crate::context::{ActionType, UpdateContext};
+use crate::display_object::{DisplayObject, TDisplayObject};
+use crate::id3;
+use enumset::EnumSet;
+use gc_arena::Collect;
+
+/// Keeps track of the sounds started by the movie, and which display objects own them.
+///
+/// In Flash, a sound is affected by the sound transforms of the clip that owns it and all of
+/// that clip's ancestors. When one of these transforms changes, the sound's volume and pan
+/// change immediately, even if the sound is already playing.
+pub struct AudioManager<'gc> {
+    /// The sounds that are currently playing.
+    sounds: Vec<ActiveSound<'gc>>,
+
+    /// The transform applied to every sound.
+    /// This is set by `Sound` objects that were created without an owner.
+    global_sound_transform: SoundTransform,
+
+    /// The sounds being downloaded by `Sound.loadSound`.
+    loading_sounds: Vec<LoadingSound<'gc>>,
+}
+
+/// A playing sound and the display object that owns it.
+struct ActiveSound<'gc> {
+    instance: ActiveSoundInstance,
+
+    /// The sound that is playing, if it was registered with the audio backend.
+    sound: Option<SoundHandle>,
+
+    /// The `Sound` object that started this sound, if any.
+    /// Its position is updated while the sound plays, and it receives `onSoundComplete`.
+    sound_object: Option<SoundObject<'gc>>,
+
+    /// The display object that started this sound, if any.
+    /// Sounds without an owner are only affected by the global sound transform.
+    owner: Option<DisplayObject<'gc>>,
+}
+
+/// An MP3 file being downloaded by `Sound.loadSound`.
+struct LoadingSound<'gc> {
+    sound_object: SoundObject<'gc>,
+
+    /// The download request, or `None` if the request could not be made.
+    request: Option<RequestHandle>,
+
+    /// The data downloaded so far.
+    data: Vec<u8>,
+
+    /// The buffer that a streaming sound plays from while it downloads.
+    /// `None` for event sounds, which only play once they have fully loaded.
+    stream: Option<Mp3StreamBuffer>,
+
+    /// Whether the streaming sound has started playing.
+    is_stream_started: bool,
+
+    /// Whether the beginning of the file has been checked for an ID3v2 tag.
+    is_id3v2_checked: bool,
+
+    /// The duration of the MP3 frames loaded so far.
+    duration: Mp3Duration,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ActiveSoundInstance {
+    Sound(SoundInstanceHandle),
+    Stream(AudioStreamHandle),
+}
+
+impl<'gc> AudioManager<'gc> {
+    pub fn new() -> Self {
+        Self {
+            sounds: vec![],
+            global_sound_transform: Default::default(),
+            loading_sounds: vec![],
+        }
+    }
+
+    /// The transform applied to every sound.
+    pub fn global_sound_transform(&self) -> &SoundTransform {
+        &self.global_sound_transform
+    }
+
+    /// Sets the transform applied to every sound, and updates all playing sounds.
+    pub fn set_global_sound_transform(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        sound_transform: SoundTransform,
+    ) {
+        self.global_sound_transform = sound_transform;
+        self.update_sound_transforms(audio);
+    }
+
+    /// Starts an event sound.
+    ///
+    /// `owner_transform` is the global sound transform of `owner`. It is passed in separately
+    /// so that sounds can be started while the owner is borrowed.
+    pub fn start_sound(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        sound: SoundHandle,
+        settings: &swf::SoundInfo,
+        sound_object: Option<SoundObject<'gc>>,
+        owner: Option<DisplayObject<'gc>>,
+        owner_transform: &SoundTransform,
+    ) -> SoundInstanceHandle {
+        let instance = audio.start_sound(sound, settings);
+        self.add_sound(
+            audio,
+            instance,
+            Some(sound),
+            sound_object,
+            owner,
+            owner_transform,
+        );
+        instance
+    }
+
+    /// Tracks a sound instance that was started by the audio backend, and applies its
+    /// sound transform.
+    fn add_sound(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        instance: SoundInstanceHandle,
+        sound: Option<SoundHandle>,
+        sound_object: Option<SoundObject<'gc>>,
+        owner: Option<DisplayObject<'gc>>,
+        owner_transform: &SoundTransform,
+    ) {
+        let sound_transform = self.global_sound_transform.concat(owner_transform);
+        if sound_transform != SoundTransform::default() {
+            audio.set_sound_transform(instance, &sound_transform);
+        }
+        self.sounds.push(ActiveSound {
+            instance: ActiveSoundInstance::Sound(instance),
+            sound,
+            sound_object,
+            owner,
+        });
+    }
+
+    /// Stops a sound instance started by `start_sound`.
+    /// Stopped sounds do not receive `onSoundComplete`.
+    pub fn stop_sound(&mut self, audio: &mut dyn AudioBackend, instance: SoundInstanceHandle) {
+        audio.stop_sound(instance);
+        self.sounds
+            .retain(|sound| sound.instance != ActiveSoundInstance::Sound(instance));
+    }
+
+    /// Stops all instances of a sound.
+    pub fn stop_sounds_with_handle(&mut self, audio: &mut dyn AudioBackend, handle: SoundHandle) {
+        audio.stop_sounds_with_handle(handle);
+        self.sounds.retain(|sound| sound.sound != Some(handle));
+    }
+
+    /// Tracks a stream sound that was started for the timeline of `owner`, and applies
+    /// its sound transform.
+    ///
+    /// `owner_transform` is the global sound transform of `owner`.
+    pub fn add_stream(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        stream: AudioStreamHandle,
+        owner: DisplayObject<'gc>,
+        owner_transform: &SoundTransform,
+    ) {
+        let sound_transform = self.global_sound_transform.concat(owner_transform);
+        if sound_transform != SoundTransform::default() {
+            audio.set_stream_transform(stream, &sound_transform);
+        }
+        self.sounds.push(ActiveSound {
+            instance: ActiveSoundInstance::Stream(stream),
+            sound: None,
+            sound_object: None,
+            owner: Some(owner),
+        });
+    }
+
+    /// Stops a stream sound added by `add_stream`.
+    pub fn stop_stream(&mut self, audio: &mut dyn AudioBackend, stream: AudioStreamHandle) {
+        audio.stop_stream(stream);
+        self.sounds
+            .retain(|sound| sound.instance != ActiveSoundInstance::Stream(stream));
+    }
+
+    /// Stops all sounds, as `stopAllSounds` does.
+    pub fn stop_all_sounds(&mut self, audio: &mut dyn AudioBackend) {
+        audio.stop_all_sounds();
+        self.sounds.clear();
+    }
+
+    /// Reapplies the sound transforms of every playing sound.
+    /// This should be called whenever the sound transform of a display object changes.
+    pub fn update_sound_transforms(&mut self, audio: &mut dyn AudioBackend) {
+        for sound in &self.sounds {
+            let owner_transform = sound
+                .owner
+                .map(|owner| owner.global_sound_transform())
+                .unwrap_or_default();
+            let sound_transform = self.global_sound_transform.concat(&owner_transform);
+            match sound.instance {
+                ActiveSoundInstance::Sound(instance) => {
+                    audio.set_sound_transform(instance, &sound_transform)
+                }
+                ActiveSoundInstance::Stream(stream) => {
+                    audio.set_stream_transform(stream, &sound_transform)
+                }
+            }
+        }
+    }
+
+    /// Starts downloading an MP3 file for `Sound.loadSound`.
+    ///
+    /// Streaming sounds start playing as soon as enough data has arrived, while event sounds
+    /// are attached to the `Sound` object once they have fully loaded.
+    /// Call `update_loading_sounds` each frame to receive the data.
+    pub fn load_sound(
+        &mut self,
+        navigator: &mut dyn NavigatorBackend,
+        sound_object: SoundObject<'gc>,
+        url: &str,
+        is_streaming: bool,
+    ) {
+        // Loading a new sound cancels any previous load.
+        self.loading_sounds
+            .retain(|load| !Object::ptr_eq(load.sound_object.into(), sound_object.into()));
+
+        let request = navigator.fetch(url);
+        if request.is_none() {
+            log::warn!("Sound.loadSound: Unable to load {}", url);
+        }
+        self.loading_sounds.push(LoadingSound {
+            sound_object,
+            request,
+            data: vec![],
+            stream: if is_streaming {
+                Some(Mp3StreamBuffer::new())
+            } else {
+                None
+            },
+            is_stream_started: false,
+            is_id3v2_checked: false,
+            duration: Default::default(),
+        });
+    }
+
+    /// Receives any data downloaded for the sounds started by `load_sound`,
+    /// and queues the `onID3` and `onLoad` events of their `Sound` objects.
+    pub fn update_loading_sounds(context: &mut UpdateContext<'_, 'gc, '_>) {
+        let loading_sounds = std::mem::take(&mut context.audio_manager.loading_sounds);
+        for mut load in loading_sounds {
+            if Self::update_loading_sound(context, &mut load) {
+                context.audio_manager.loading_sounds.push(load);
+            }
+        }
+    }
+
+    /// Updates a sound being downloaded by `load_sound`.
+    /// Returns `false` once it has finished loading.
+    fn update_loading_sound(
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        load: &mut LoadingSound<'gc>,
+    ) -> bool {
+        let sound_object = load.sound_object;
+        let prev_len = load.data.len();
+        let status = match load.request {
+            Some(request) => context.navigator.poll_request(request, &mut load.data),
+            None => RequestStatus::Failed,
+        };
+        if let Some(stream) = &load.stream {
+            stream.append(&load.data[prev_len..]);
+        }
+
+        let bytes_loaded = load.data.len() as u32;
+        sound_object.set_bytes_loaded(context.gc_context, Some(bytes_loaded));
+        match status {
+            RequestStatus::Loading { bytes_total } => {
+                sound_object.set_bytes_total(context.gc_context, bytes_total)
+            }
+            _ => sound_object.set_bytes_total(context.gc_context, Some(bytes_loaded)),
+        }
+
+        // The ID3v2 tag is at the beginning of the file, so it is available while loading.
+        if !load.is_id3v2_checked && load.data.len() >= 10 {
+            match id3::id3v2_tag_len(&load.data) {
+                Some(tag_len) if tag_len <= load.data.len() => {
+                    if let Some(properties) = id3::parse_id3v2(&load.data) {
+                        Self::add_id3_properties(context, sound_object, properties);
+                    }
+                    load.is_id3v2_checked = true;
+                }
+                Some(_) => (),
+                None => load.is_id3v2_checked = true,
+            }
+        }
+
+        if let Some(stream) = &load.stream {
+            if status != RequestStatus::Failed && !load.is_stream_started {
+                if let Some(instance) = context.audio.start_mp3_stream(stream.clone()) {
+                    let owner = sound_object.owner();
+                    let owner_transform = owner
+                        .map(|owner| owner.global_sound_transform())
+                        .unwrap_or_default();
+                    context.audio_manager.add_sound(
+                        context.audio,
+                        instance,
+                        None,
+                        Some(sound_object),
+                        owner,
+                        &owner_transform,
+                    );
+                    sound_object.set_sound_instance(context.gc_context, Some(instance));
+                    load.is_stream_started = true;
+                }
+            }
+            // The duration of a streaming sound is the duration of the data loaded so far.
+            let duration = load.duration.update(&load.data);
+            sound_object.set_duration(context.gc_context, duration);
+        }
+
+        let is_success = match status {
+            RequestStatus::Loading { .. } => return true,
+            RequestStatus::Complete => {
+                if let Some(properties) = id3::parse_id3v1(&load.data) {
+                    Self::add_id3_properties(context, sound_object, properties);
+                }
+                if let Some(stream) = &load.stream {
+                    stream.finish();
+                    load.is_stream_started || mp3_frames(&load.data).next().is_some()
+                } else {
+                    match context.audio.register_mp3(&load.data) {
+                        Ok(sound) => {
+                            sound_object.set_sound(context.gc_context, Some(sound));
+                            sound_object.set_duration(
+                                context.gc_context,
+                                context.audio.get_sound_duration(sound).unwrap_or(0),
+                            );
+                            true
+                        }
+                        Err(e) => {
+                            log::warn!("Sound.loadSound: Invalid MP3: {}", e);
+                            false
+                        }
+                    }
+                }
+            }
+            RequestStatus::Failed => {
+                if let Some(stream) = &load.stream {
+                    stream.finish();
+                }
+                false
+            }
+        };
+        Self::queue_sound_event(context, sound_object, "onLoad", vec![is_success.into()]);
+        false
+    }
+
+    /// Adds ID3 metadata to the `id3` object of a `Sound`, and queues its `onID3` event.
+    fn add_id3_properties(
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        sound_object: SoundObject<'gc>,
+        properties: Vec<(String, String)>,
+    ) {
+        let id3 = sound_object.id3().unwrap_or_else(|| {
+            let id3 =
+                ScriptObject::object(context.gc_context, Some(context.system_prototypes.object));
+            sound_object.set_id3(context.gc_context, Some(id3.into()));
+            id3.into()
+        });
+        for (name, value) in properties {
+            // Values from an ID3v2 tag take precedence over an ID3v1 tag.
+            if !id3.has_own_property(&name) {
+                id3.define_value(context.gc_context, &name, value.into(), EnumSet::empty());
+            }
+        }
+        Self::queue_sound_event(context, sound_object, "onID3", vec![]);
+    }
+
+    /// Queues a call to an event handler method of a `Sound` object.
+    fn queue_sound_event(
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        sound_object: SoundObject<'gc>,
+        name: &'static str,
+        args: Vec<Value<'gc>>,
+    ) {
+        let clip = sound_object.owner().unwrap_or(context.root);
+        context.action_queue.queue_actions(
+            clip,
+            ActionType::ObjectMethod {
+                object: sound_object.into(),
+                name,
+                args,
+            },
+            false,
+        );
+    }
+
+    /// Updates the positions of the sounds started by `Sound` objects, and stops tracking
+    /// the sounds that have finished playing, queuing their `onSoundComplete` events.
+    /// This should be called once per frame.
+    pub fn update_sounds(context: &mut UpdateContext<'_, 'gc, '_>) {
+        let sounds = std::mem::take(&mut context.audio_manager.sounds);
+        for sound in sounds {
+            let instance = match sound.instance {
+                ActiveSoundInstance::Sound(instance) => instance,
+                // Streams are removed when their timeline stops them.
+                ActiveSoundInstance::Stream(_) => {
+                    context.audio_manager.sounds.push(sound);
+                    continue;
+                }
+            };
+
+            // Only the last sound started by a `Sound` object affects its position.
+            let sound_object = sound
+                .sound_object
+                .filter(|sound_object| sound_object.sound_instance() == Some(instance));
+            if context.audio.is_sound_playing(instance) {
+                if let Some(sound_object) = sound_object {
+                    if let Some(position) = context.audio.get_sound_position(instance) {
+                        sound_object.set_position(context.gc_context, position);
+                    }
+                }
+                context.audio_manager.sounds.push(sound);
+            } else if let Some(sound_object) = sound_object {
+                // The position stays at the end of the sound after it completes.
+                sound_object.set_position(context.gc_context, sound_object.duration());
+                Self::queue_sound_event(context, sound_object, "onSoundComplete", vec![]);
+            }
+        }
+    }
+}
+
+impl<'gc> Default for AudioManager<'gc> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<'gc> Collect for AudioManager<'gc> {
+    #[inline]
+    fn trace(&self, cc: gc_arena::CollectionContext) {
+        for sound in &self.sounds {
+            sound.sound_object.trace(cc);
+            sound.owner.trace(cc);
+        }
+        for load in &self.loading_sounds {
+            load.sound_object.trace(cc);
+        }
+    }
+}
+
+/// Adds up the duration of MP3 data as it downloads, scanning each frame only once.
+#[derive(Default)]
+struct Mp3Duration {
+    /// The length of the data that has been scanned, up to the end of the last whole frame.
+    scanned_len: usize,
+
+    num_samples: u64,
+
+    /// The sample rate of the first frame, which is used for the whole sound.
+    sample_rate: Option<u16>,
+}
+
+impl Mp3Duration {
+    /// Scans the whole frames in `data` that weren't scanned by previous calls, and returns
+    /// the duration of all the frames so far in milliseconds. `data` must begin with the data
+    /// given to previous calls.
+    fn update(&mut self, data: &[u8]) -> u32 {
+        let start = self.scanned_len;
+        for (offset, header) in mp3_frames(&data[start..]) {
+            self.scanned_len = start + offset + header.frame_len;
+            self.num_samples += u64::from(header.num_samples);
+            self.sample_rate.get_or_insert(header.sample_rate);
+        }
+        self.sample_rate.map_or(0, |sample_rate| {
+            (self.num_samples * 1000 / u64::from(sample_rate)) as u32
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mp3_duration_while_loading() {
+        // Three 128 kbps, 44.1 kHz frames of 1152 samples each.
+        let mut frame = vec![0xff, 0xfb, 0x90, 0x00];
+        frame.resize(417, 0);
+        let data = frame.repeat(3);
+
+        let mut duration = Mp3Duration::default();
+        assert_eq!(duration.update(&data[..400]), 0);
+        assert_eq!(duration.update(&data[..1000]), 52);
+        assert_eq!(duration.update(&data), 78);
+        assert_eq!(duration.update(&data), 78);
+    }
+}
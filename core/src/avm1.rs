@@ -16,12 +16,13 @@ use crate::tag_utils::SwfSlice;
 
 #[cfg(test)]
 #[macro_use]
-mod test_utils;
+pub mod test_utils;
 
 #[macro_use]
 pub mod listeners;
 
 mod activation;
+pub mod debugger;
 mod fscommand;
 pub mod function;
 pub mod globals;
@@ -44,6 +45,7 @@ mod tests;
 
 use crate::avm1::listeners::SystemListener;
 use activation::Activation;
+use debugger::Debugger;
 pub use globals::SystemPrototypes;
 pub use object::{Object, ObjectPtr, TObject};
 use scope::Scope;
@@ -88,6 +90,9 @@ pub struct Avm1<'gc> {
     /// The register slots (also shared across functions).
     /// `ActionDefineFunction2` defined functions do not use these slots.
     registers: [Value<'gc>; 4],
+
+    /// Breakpoints and stepping state.
+    debugger: Debugger,
 }
 
 unsafe impl<'gc> gc_arena::Collect for Avm1<'gc> {
@@ -128,6 +133,7 @@ impl<'gc> Avm1<'gc> {
                 Value::Undefined,
                 Value::Undefined,
             ],
+            debugger: Debugger::new(),
         }
     }
 
@@ -291,6 +297,60 @@ impl<'gc> Avm1<'gc> {
         }
     }
 
+    /// Add a stack frame that calls the method `name` of `object`, if it exists.
+    pub fn insert_stack_frame_for_method(
+        &mut self,
+        active_clip: DisplayObject<'gc>,
+        object: Object<'gc>,
+        swf_version: u8,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        name: &str,
+        args: &[Value<'gc>],
+    ) {
+        // Grab the property with the given name.
+        // Requires a dummy stack frame.
+        self.stack_frames.push(GcCell::allocate(
+            context.gc_context,
+            Activation::from_nothing(swf_version, self.globals, context.gc_context, active_clip),
+        ));
+        let callback = object
+            .get(name, self, context)
+            .and_then(|prop| prop.resolve(self, context));
+        self.stack_frames.pop();
+
+        // Run the callback.
+        // The function exec pushes its own stack frame.
+        // The function is now ready to execute with `run_stack_till_empty`.
+        if let Ok(callback) = callback {
+            let _ = callback.call(self, context, object, args);
+        }
+    }
+
+    /// Runs `f` with a dummy stack frame, which is needed to get properties of objects
+    /// from outside of ActionScript.
+    pub fn with_dummy_frame<F, R>(
+        &mut self,
+        active_clip: DisplayObject<'gc>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        f: F,
+    ) -> R
+    where
+        F: FnOnce(&mut Self, &mut UpdateContext<'_, 'gc, '_>) -> R,
+    {
+        self.stack_frames.push(GcCell::allocate(
+            context.gc_context,
+            Activation::from_nothing(
+                context.swf_version,
+                self.globals,
+                context.gc_context,
+                active_clip,
+            ),
+        ));
+        let ret = f(self, context);
+        self.stack_frames.pop();
+        ret
+    }
+
     /// Add a stack frame for any arbitrary code.
     pub fn insert_stack_frame(&mut self, frame: GcCell<'gc, Activation<'gc>>) {
         self.stack_frames.push(frame);
@@ -413,12 +473,17 @@ impl<'gc> Avm1<'gc> {
         Ok(())
     }
 
-    /// Execute the AVM stack until it is exhausted.
+    /// Execute the AVM stack until it is exhausted, or until the debugger pauses execution.
     pub fn run_stack_till_empty(
         &mut self,
         context: &mut UpdateContext<'_, 'gc, '_>,
     ) -> Result<(), Error> {
         while !self.stack_frames.is_empty() {
+            if self.debugger_should_pause() {
+                // Leave the stack intact; execution continues once the debugger resumes.
+                return Ok(());
+            }
+
             self.with_current_reader_mut(context, |this, r, context| {
                 this.do_next_action(context, r)
             })?;
@@ -2289,7 +2354,7 @@ impl<'gc> Avm1<'gc> {
     }
 
     fn action_stop_sounds(&mut self, context: &mut UpdateContext) -> Result<(), Error> {
-        context.audio.stop_all_sounds();
+        context.audio_manager.stop_all_sounds(context.audio);
         Ok(())
     }
 
@@ -0,0 +1,496 @@
+//! AVM1 debugger: breakpoints, stepping and inspection of paused code.
+//!
+//! The debugger can only pause code that is run directly by the player's
+//! action queue. Code invoked re-entrantly from native functions (such as
+//! getters, setters, or `Array.sort` callbacks) runs to completion.
+
+use crate::avm1::activation::Activation;
+use crate::avm1::property::Property;
+use crate::avm1::{Avm1, Error, Object, TObject, Value};
+use crate::context::UpdateContext;
+use crate::prelude::*;
+use gc_arena::GcCell;
+use swf::avm1::read::Reader;
+
+/// The location of an action being executed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugLocation {
+    /// The character ID of the clip containing the code.
+    pub character_id: CharacterId,
+
+    /// The current frame of that clip, if it is a movie clip.
+    pub frame: Option<u16>,
+
+    /// The offset of the action, relative to the start of its code block.
+    pub offset: usize,
+
+    /// The offset of the action in the uncompressed SWF data.
+    pub swf_offset: usize,
+}
+
+/// A location at which execution should pause.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    /// Pause at the action at the given offset in the uncompressed SWF data.
+    SwfOffset(usize),
+
+    /// Pause at the action at the given offset within the code of a clip.
+    /// If `frame` is `None`, this applies to code run on any frame of the clip.
+    Action {
+        character_id: CharacterId,
+        frame: Option<u16>,
+        offset: usize,
+    },
+}
+
+impl Breakpoint {
+    fn matches(&self, location: &DebugLocation) -> bool {
+        match *self {
+            Breakpoint::SwfOffset(swf_offset) => swf_offset == location.swf_offset,
+            Breakpoint::Action {
+                character_id,
+                frame,
+                offset,
+            } => {
+                character_id == location.character_id
+                    && offset == location.offset
+                    && (frame.is_none() || frame == location.frame)
+            }
+        }
+    }
+}
+
+/// How execution should continue after being paused.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepMode {
+    /// Pause on the very next action, including inside of called functions.
+    Into,
+
+    /// Pause on the next action in the current function or one of its callers.
+    Over,
+
+    /// Pause on the next action after the current function returns.
+    Out,
+}
+
+/// Breakpoint and stepping state of the AVM.
+#[derive(Debug, Default)]
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+
+    /// The active step, with the stack depth it was started from.
+    step: Option<(StepMode, usize)>,
+
+    /// The location execution is paused at.
+    paused_at: Option<DebugLocation>,
+
+    /// Set when resuming, so that the action we paused on is allowed to run.
+    skip_next_check: bool,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Removes a breakpoint, returning whether it existed.
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|b| b != breakpoint);
+        self.breakpoints.len() != len
+    }
+
+    /// Returns whether execution is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Returns the location execution is paused at, if any.
+    pub fn paused_at(&self) -> Option<&DebugLocation> {
+        self.paused_at.as_ref()
+    }
+
+    /// Requests that execution pauses on the next action that runs.
+    pub fn pause(&mut self) {
+        self.step = Some((StepMode::Into, 0));
+    }
+
+    /// Resumes execution, optionally pausing again after a step.
+    /// `depth` is the number of activations on the AVM stack.
+    pub fn resume(&mut self, step: Option<StepMode>, depth: usize) {
+        if self.paused_at.take().is_some() {
+            self.skip_next_check = true;
+        }
+        self.step = step.map(|mode| (mode, depth));
+    }
+
+    /// Returns whether the debugger needs to check each action before it is run.
+    pub fn is_active(&self) -> bool {
+        !self.breakpoints.is_empty() || self.step.is_some() || self.skip_next_check
+    }
+
+    /// Checks whether execution should pause before running the action at `location`,
+    /// and enters the paused state if so.
+    fn check(&mut self, location: DebugLocation, depth: usize) -> bool {
+        if self.skip_next_check {
+            self.skip_next_check = false;
+            return false;
+        }
+
+        let step_done = match self.step {
+            Some((StepMode::Into, _)) => true,
+            Some((StepMode::Over, start_depth)) => depth <= start_depth,
+            Some((StepMode::Out, start_depth)) => depth < start_depth,
+            None => false,
+        };
+
+        if step_done || self.breakpoints.iter().any(|b| b.matches(&location)) {
+            self.step = None;
+            self.paused_at = Some(location);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A single activation on the AVM stack.
+#[derive(Debug, Clone)]
+pub struct DebugFrame {
+    pub location: DebugLocation,
+
+    /// The path of the clip containing the code, e.g. `_level0.clip`.
+    pub clip_path: String,
+
+    /// Whether this activation is a function call.
+    pub is_function: bool,
+
+    /// The action about to be executed in this activation, if it can be decoded.
+    pub action: Option<String>,
+}
+
+/// A scope in the scope chain of an activation.
+#[derive(Debug, Clone)]
+pub struct DebugScope {
+    /// The kind of scope (`Global`, `Target`, `Local` or `With`).
+    pub class: String,
+
+    /// The scope object itself.
+    pub object: String,
+
+    /// The names of the variables defined directly on the scope object.
+    pub variables: Vec<String>,
+}
+
+/// The full state visible to an activation.
+#[derive(Debug, Clone)]
+pub struct FrameInspection {
+    pub frame: DebugFrame,
+    pub this: String,
+
+    /// The scope chain, from innermost to outermost.
+    pub scope_chain: Vec<DebugScope>,
+
+    /// The registers in use; either the function's local registers or the four global ones.
+    pub registers: Vec<String>,
+
+    /// The operand stack, from bottom to top. This is shared by all activations.
+    pub stack: Vec<String>,
+
+    /// The variables of the innermost scope, and their values.
+    pub locals: Vec<(String, String)>,
+}
+
+/// A value and the properties it has at each level of its prototype chain.
+#[derive(Debug, Clone)]
+pub struct ValueInspection {
+    pub value: String,
+
+    /// The properties of the object, followed by those of each of its prototypes.
+    pub prototype_chain: Vec<Vec<(String, String)>>,
+}
+
+/// Formats a value for display in the debugger, without calling into AVM code.
+pub fn debug_value(value: &Value<'_>) -> String {
+    match value {
+        Value::String(s) => format!("{:?}", s),
+        Value::Object(object) => debug_object(*object),
+        value => value.clone().into_string(),
+    }
+}
+
+/// Formats a property of an object for display in the debugger. Virtual properties are shown
+/// as `[getter]` instead of calling their getter, so that inspecting an object runs no code.
+fn debug_property(object: Object<'_>, name: &str) -> String {
+    match object
+        .as_script_object()
+        .and_then(|object| object.property(name))
+    {
+        Some(Property::Stored { value, .. }) => debug_value(&value),
+        Some(Property::Virtual { .. }) => "[getter]".to_string(),
+        None => debug_value(&Value::Undefined),
+    }
+}
+
+fn debug_object(object: Object<'_>) -> String {
+    if let Some(display_object) = object.as_display_object() {
+        format!("[{} {}]", object.type_of(), display_object.path())
+    } else if object.as_executable().is_some() {
+        "[function]".to_string()
+    } else {
+        format!("[{} @{:p}]", object.type_of(), object.as_ptr())
+    }
+}
+
+impl<'gc> Avm1<'gc> {
+    pub fn debugger(&self) -> &Debugger {
+        &self.debugger
+    }
+
+    pub fn debugger_mut(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    /// Resumes paused execution, optionally pausing again after a step.
+    /// The caller is responsible for running the AVM stack afterwards.
+    pub fn debugger_resume(&mut self, step: Option<StepMode>) {
+        let depth = self.stack_frames.len();
+        self.debugger.resume(step, depth);
+    }
+
+    /// Checks whether the debugger wants to pause before the next action runs.
+    pub(crate) fn debugger_should_pause(&mut self) -> bool {
+        if !self.debugger.is_active() {
+            return false;
+        }
+
+        let depth = self.stack_frames.len();
+        match self.current_stack_frame() {
+            Some(frame) => {
+                let location = Self::debug_location(&frame.read());
+                self.debugger.check(location, depth)
+            }
+            None => false,
+        }
+    }
+
+    fn debug_location(activation: &Activation<'gc>) -> DebugLocation {
+        let base_clip = activation.base_clip();
+        let data = activation.data();
+        DebugLocation {
+            character_id: base_clip.id(),
+            frame: base_clip.as_movie_clip().map(|clip| clip.current_frame()),
+            offset: activation.pc(),
+            swf_offset: data.start + activation.pc(),
+        }
+    }
+
+    /// Returns the activations on the AVM stack, from innermost to outermost.
+    pub fn debug_stack_trace(&self) -> Vec<DebugFrame> {
+        self.stack_frames
+            .iter()
+            .rev()
+            .map(|frame| Self::debug_frame(&frame.read()))
+            .collect()
+    }
+
+    fn debug_frame(activation: &Activation<'gc>) -> DebugFrame {
+        let data = activation.data();
+        let mut reader = Reader::new(data.as_ref(), activation.swf_version());
+        reader.seek(activation.pc() as isize);
+        let action = match reader.read_action() {
+            Ok(Some(action)) => Some(format!("{:?}", action)),
+            _ => None,
+        };
+
+        DebugFrame {
+            location: Self::debug_location(activation),
+            clip_path: activation.base_clip().path(),
+            is_function: activation.can_return(),
+            action,
+        }
+    }
+
+    /// Returns the stack frame at `index`, counting from the innermost activation.
+    fn debug_stack_frame(&self, index: usize) -> Option<GcCell<'gc, Activation<'gc>>> {
+        let len = self.stack_frames.len();
+        if index < len {
+            Some(self.stack_frames[len - 1 - index])
+        } else {
+            None
+        }
+    }
+
+    /// Inspects the activation at `index`, counting from the innermost activation.
+    /// This runs no ActionScript; virtual properties are shown without calling their getters.
+    pub fn debug_inspect_frame(&self, index: usize) -> Option<FrameInspection> {
+        let frame = self.debug_stack_frame(index)?;
+        let activation = frame.read();
+
+        let mut scope_chain = vec![];
+        let mut scope = Some(activation.scope_cell());
+        while let Some(cell) = scope {
+            let scope_ref = cell.read();
+            let mut variables: Vec<String> = scope_ref.locals().get_keys().into_iter().collect();
+            variables.sort();
+            scope_chain.push(DebugScope {
+                class: format!("{:?}", scope_ref.class()),
+                object: debug_object(*scope_ref.locals()),
+                variables,
+            });
+            scope = scope_ref.parent_cell();
+        }
+
+        let registers = if activation.has_local_register(0) {
+            let mut registers = vec![];
+            let mut id = 0;
+            while let Some(value) = activation.local_register(id) {
+                registers.push(debug_value(&value));
+                id += 1;
+            }
+            registers
+        } else {
+            self.registers.iter().map(debug_value).collect()
+        };
+
+        let locals_object = *activation.scope().locals();
+        let mut names: Vec<String> = locals_object.get_keys().into_iter().collect();
+        names.sort();
+        let locals = names
+            .into_iter()
+            .map(|name| {
+                let value = debug_property(locals_object, &name);
+                (name, value)
+            })
+            .collect();
+
+        Some(FrameInspection {
+            frame: Self::debug_frame(&activation),
+            this: debug_object(activation.this_cell()),
+            scope_chain,
+            registers,
+            stack: self.stack.iter().map(debug_value).collect(),
+            locals,
+        })
+    }
+
+    /// Evaluates a dotted path (such as `this.clip._x`) in the scope of the activation at
+    /// `index`, and inspects the resulting value and its prototype chain.
+    ///
+    /// The path is evaluated as ActionScript would, so getters and `__resolve` handlers along
+    /// it are called. The properties of the result are listed without calling their getters.
+    pub fn debug_inspect_value(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        index: usize,
+        path: &str,
+    ) -> Result<ValueInspection, Error> {
+        let frame = self.debug_stack_frame(index).ok_or("No such stack frame")?;
+
+        let mut segments = path.split('.');
+        let first = segments.next().unwrap_or_default();
+        let resolved = frame.read().resolve(first, self, context)?;
+        let mut value = resolved.resolve(self, context)?;
+        for segment in segments {
+            let object = value.as_object()?;
+            value = object.get(segment, self, context)?.resolve(self, context)?;
+        }
+
+        let mut prototype_chain = vec![];
+        if let Value::Object(this) = value {
+            let mut object = Some(this);
+            while let Some(level) = object {
+                let mut names: Vec<String> = level.get_keys().into_iter().collect();
+                names.sort();
+                let properties = names
+                    .into_iter()
+                    .map(|name| {
+                        let value = debug_property(level, &name);
+                        (name, value)
+                    })
+                    .collect();
+                prototype_chain.push(properties);
+                object = level.proto();
+            }
+        }
+
+        Ok(ValueInspection {
+            value: debug_value(&value),
+            prototype_chain,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(character_id: CharacterId, offset: usize) -> DebugLocation {
+        DebugLocation {
+            character_id,
+            frame: Some(1),
+            offset,
+            swf_offset: 100 + offset,
+        }
+    }
+
+    #[test]
+    fn breakpoint_pauses_once() {
+        let mut debugger = Debugger::new();
+        debugger.add_breakpoint(Breakpoint::Action {
+            character_id: 1,
+            frame: None,
+            offset: 5,
+        });
+        assert!(!debugger.check(location(1, 0), 1));
+        assert!(debugger.check(location(1, 5), 1));
+        assert_eq!(debugger.paused_at(), Some(&location(1, 5)));
+
+        // Resuming runs the action we paused on without pausing again.
+        debugger.resume(None, 1);
+        assert!(!debugger.is_paused());
+        assert!(!debugger.check(location(1, 5), 1));
+        assert!(!debugger.check(location(1, 6), 1));
+    }
+
+    #[test]
+    fn swf_offset_breakpoint() {
+        let mut debugger = Debugger::new();
+        debugger.add_breakpoint(Breakpoint::SwfOffset(103));
+        assert!(!debugger.check(location(2, 2), 1));
+        assert!(debugger.check(location(2, 3), 1));
+    }
+
+    #[test]
+    fn step_modes() {
+        let mut debugger = Debugger::new();
+        debugger.pause();
+        assert!(debugger.check(location(1, 0), 1));
+
+        // Stepping over skips actions in deeper functions.
+        debugger.resume(Some(StepMode::Over), 1);
+        assert!(!debugger.check(location(1, 0), 1));
+        assert!(!debugger.check(location(3, 0), 2));
+        assert!(debugger.check(location(1, 2), 1));
+
+        // Stepping out only pauses once the stack is shallower.
+        debugger.resume(Some(StepMode::Out), 2);
+        assert!(!debugger.check(location(1, 2), 2));
+        assert!(!debugger.check(location(1, 4), 2));
+        assert!(debugger.check(location(1, 6), 1));
+
+        debugger.resume(None, 1);
+        assert!(!debugger.check(location(1, 6), 1));
+        assert!(!debugger.is_active());
+    }
+}
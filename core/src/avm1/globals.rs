@@ -11,7 +11,10 @@ use std::f64;
 
 mod array;
 pub(crate) mod boolean;
+pub(crate) mod button;
 mod color;
+mod context_menu;
+mod context_menu_item;
 mod function;
 mod key;
 mod math;
@@ -122,6 +125,8 @@ pub struct SystemPrototypes<'gc> {
     pub string: Object<'gc>,
     pub number: Object<'gc>,
     pub boolean: Object<'gc>,
+    pub context_menu: Object<'gc>,
+    pub context_menu_item: Object<'gc>,
 }
 
 unsafe impl<'gc> gc_arena::Collect for SystemPrototypes<'gc> {
@@ -138,6 +143,8 @@ unsafe impl<'gc> gc_arena::Collect for SystemPrototypes<'gc> {
         self.string.trace(cc);
         self.number.trace(cc);
         self.boolean.trace(cc);
+        self.context_menu.trace(cc);
+        self.context_menu_item.trace(cc);
     }
 }
 
@@ -172,6 +179,10 @@ pub fn create_globals<'gc>(
     let number_proto: Object<'gc> = number::create_proto(gc_context, object_proto, function_proto);
     let boolean_proto: Object<'gc> =
         boolean::create_proto(gc_context, object_proto, function_proto);
+    let context_menu_proto: Object<'gc> =
+        context_menu::create_proto(gc_context, object_proto, function_proto);
+    let context_menu_item_proto: Object<'gc> =
+        context_menu_item::create_proto(gc_context, object_proto, function_proto);
 
     //TODO: These need to be constructors and should also set `.prototype` on each one
     let object = FunctionObject::function(
@@ -235,6 +246,18 @@ pub fn create_globals<'gc>(
         Some(function_proto),
         Some(xml_proto),
     );
+    let context_menu = FunctionObject::function(
+        gc_context,
+        Executable::Native(context_menu::constructor),
+        Some(function_proto),
+        Some(context_menu_proto),
+    );
+    let context_menu_item = FunctionObject::function(
+        gc_context,
+        Executable::Native(context_menu_item::constructor),
+        Some(function_proto),
+        Some(context_menu_item_proto),
+    );
     let string = string::create_string_object(gc_context, Some(string_proto), Some(function_proto));
     let number = number::create_number_object(gc_context, Some(number_proto), Some(function_proto));
     let boolean =
@@ -245,6 +268,18 @@ pub fn create_globals<'gc>(
     let mut globals = ScriptObject::bare_object(gc_context);
     globals.define_value(gc_context, "Array", array.into(), EnumSet::empty());
     globals.define_value(gc_context, "Color", color.into(), EnumSet::empty());
+    globals.define_value(
+        gc_context,
+        "ContextMenu",
+        context_menu.into(),
+        EnumSet::empty(),
+    );
+    globals.define_value(
+        gc_context,
+        "ContextMenuItem",
+        context_menu_item.into(),
+        EnumSet::empty(),
+    );
     globals.define_value(gc_context, "Object", object.into(), EnumSet::empty());
     globals.define_value(gc_context, "Function", function.into(), EnumSet::empty());
     globals.define_value(gc_context, "MovieClip", movie_clip.into(), EnumSet::empty());
@@ -290,6 +325,7 @@ pub fn create_globals<'gc>(
             gc_context,
             Some(object_proto),
             Some(function_proto),
+            &listeners.key,
         )),
         EnumSet::empty(),
     );
@@ -301,6 +337,7 @@ pub fn create_globals<'gc>(
             Some(object_proto),
             Some(array_proto),
             Some(function_proto),
+            &listeners.stage,
         )),
         EnumSet::empty(),
     );
@@ -360,6 +397,8 @@ pub fn create_globals<'gc>(
             string: string_proto,
             number: number_proto,
             boolean: boolean_proto,
+            context_menu: context_menu_proto,
+            context_menu_item: context_menu_item_proto,
         },
         globals.into(),
         listeners,
@@ -0,0 +1,114 @@
+//! `Button` impl
+
+use crate::avm1::function::Executable;
+use crate::avm1::property::Attribute::*;
+use crate::avm1::return_value::ReturnValue;
+use crate::avm1::{Avm1, Error, Object, TObject, UpdateContext, Value};
+use crate::display_object::{Button, TDisplayObject};
+use gc_arena::MutationContext;
+
+/// Adds the properties of a button that are backed by the button itself.
+pub fn attach_virtual_properties<'gc>(gc_context: MutationContext<'gc, '_>, object: Object<'gc>) {
+    object.add_property(
+        gc_context,
+        "enabled",
+        Executable::Native(enabled),
+        Some(Executable::Native(set_enabled)),
+        DontDelete | DontEnum,
+    );
+    object.add_property(
+        gc_context,
+        "trackAsMenu",
+        Executable::Native(track_as_menu),
+        Some(Executable::Native(set_track_as_menu)),
+        DontDelete | DontEnum,
+    );
+    object.add_property(
+        gc_context,
+        "useHandCursor",
+        Executable::Native(use_hand_cursor),
+        Some(Executable::Native(set_use_hand_cursor)),
+        DontDelete | DontEnum,
+    );
+}
+
+fn as_button(this: Object<'_>) -> Option<Button<'_>> {
+    this.as_display_object().and_then(|dobj| dobj.as_button())
+}
+
+/// Reads the first argument of a setter as a boolean.
+fn bool_arg<'gc>(avm: &Avm1<'gc>, args: &[Value<'gc>]) -> bool {
+    args.get(0)
+        .cloned()
+        .unwrap_or(Value::Undefined)
+        .as_bool(avm.current_swf_version())
+}
+
+fn enabled<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok(as_button(this)
+        .map_or(Value::Undefined, |button| button.enabled().into())
+        .into())
+}
+
+fn set_enabled<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(button) = as_button(this) {
+        button.set_enabled(context.gc_context, bool_arg(avm, args));
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn track_as_menu<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok(as_button(this)
+        .map_or(Value::Undefined, |button| button.track_as_menu().into())
+        .into())
+}
+
+fn set_track_as_menu<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(button) = as_button(this) {
+        button.set_track_as_menu(context.gc_context, bool_arg(avm, args));
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn use_hand_cursor<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok(as_button(this)
+        .map_or(Value::Undefined, |button| button.use_hand_cursor().into())
+        .into())
+}
+
+fn set_use_hand_cursor<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(button) = as_button(this) {
+        button.set_use_hand_cursor(context.gc_context, bool_arg(avm, args));
+    }
+    Ok(Value::Undefined.into())
+}
@@ -0,0 +1,127 @@
+//! `ContextMenu` impl
+
+use crate::avm1::globals::context_menu_item;
+use crate::avm1::property::Attribute::*;
+use crate::avm1::return_value::ReturnValue;
+use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext, Value};
+use gc_arena::MutationContext;
+
+/// The names of the flags in `builtInItems`, one for each group of built-in menu items.
+const BUILT_IN_ITEMS: [&str; 8] = [
+    "forward_back",
+    "loop",
+    "play",
+    "print",
+    "quality",
+    "rewind",
+    "save",
+    "zoom",
+];
+
+/// `ContextMenu` constructor
+pub fn constructor<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    let callback = args.get(0).cloned().unwrap_or(Value::Undefined);
+    this.set("onSelect", callback, avm, context)?;
+
+    let built_in_items = ScriptObject::object(context.gc_context, Some(avm.prototypes.object));
+    for name in BUILT_IN_ITEMS.iter() {
+        built_in_items.set(name, true.into(), avm, context)?;
+    }
+    this.set("builtInItems", built_in_items.into(), avm, context)?;
+
+    let custom_items = ScriptObject::array(context.gc_context, Some(avm.prototypes.array));
+    this.set("customItems", custom_items.into(), avm, context)?;
+
+    Ok(Value::Undefined.into())
+}
+
+pub fn create_proto<'gc>(
+    gc_context: MutationContext<'gc, '_>,
+    proto: Object<'gc>,
+    fn_proto: Object<'gc>,
+) -> Object<'gc> {
+    let mut object = ScriptObject::object(gc_context, Some(proto));
+
+    object.force_set_function(
+        "copy",
+        copy,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+
+    object.force_set_function(
+        "hideBuiltInItems",
+        hide_built_in_items,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+
+    object.into()
+}
+
+/// Creates a copy of this menu, including copies of its custom items.
+fn copy<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    let copy = ScriptObject::object(context.gc_context, Some(avm.prototypes.context_menu));
+    let callback = this.get("onSelect", avm, context)?.resolve(avm, context)?;
+    copy.set("onSelect", callback, avm, context)?;
+
+    let built_in_items = ScriptObject::object(context.gc_context, Some(avm.prototypes.object));
+    if let Value::Object(original) = this
+        .get("builtInItems", avm, context)?
+        .resolve(avm, context)?
+    {
+        for name in BUILT_IN_ITEMS.iter() {
+            let enabled = original.get(name, avm, context)?.resolve(avm, context)?;
+            built_in_items.set(name, enabled, avm, context)?;
+        }
+    }
+    copy.set("builtInItems", built_in_items.into(), avm, context)?;
+
+    let custom_items = ScriptObject::array(context.gc_context, Some(avm.prototypes.array));
+    if let Value::Object(original) = this
+        .get("customItems", avm, context)?
+        .resolve(avm, context)?
+    {
+        for i in 0..original.length() {
+            let item = match original.array_element(i) {
+                Value::Object(item) => context_menu_item::copy_item(avm, context, item)?.into(),
+                item => item,
+            };
+            custom_items.set_array_element(i, item, context.gc_context);
+        }
+    }
+    copy.set("customItems", custom_items.into(), avm, context)?;
+
+    Ok(copy.into())
+}
+
+/// Hides all built-in items, except for the settings and about items which are always shown.
+fn hide_built_in_items<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Value::Object(built_in_items) = this
+        .get("builtInItems", avm, context)?
+        .resolve(avm, context)?
+    {
+        for name in BUILT_IN_ITEMS.iter() {
+            built_in_items.set(name, false.into(), avm, context)?;
+        }
+    }
+
+    Ok(Value::Undefined.into())
+}
@@ -1,3 +1,4 @@
+use crate::avm1::listeners::Listeners;
 use crate::avm1::property::Attribute;
 use crate::avm1::return_value::ReturnValue;
 use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext, Value};
@@ -23,6 +24,33 @@ pub fn is_down<'gc>(
     }
 }
 
+pub fn is_toggled<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    _this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(key) = args
+        .get(0)
+        .and_then(|v| v.as_number(avm, context).ok())
+        .and_then(|k| KeyCode::try_from(k as u8).ok())
+    {
+        Ok(context.input.is_key_toggled(key).into())
+    } else {
+        Ok(false.into())
+    }
+}
+
+pub fn get_ascii<'gc>(
+    _avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    _this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    let ascii = context.input.get_last_key_char().map_or(0, u32::from);
+    Ok(ascii.into())
+}
+
 pub fn get_code<'gc>(
     _avm: &mut Avm1<'gc>,
     context: &mut UpdateContext<'_, 'gc, '_>,
@@ -37,9 +65,12 @@ pub fn create_key_object<'gc>(
     gc_context: MutationContext<'gc, '_>,
     proto: Option<Object<'gc>>,
     fn_proto: Option<Object<'gc>>,
+    listener: &Listeners<'gc>,
 ) -> Object<'gc> {
     let mut key = ScriptObject::object(gc_context, proto);
 
+    register_listener!(gc_context, key, listener, fn_proto, key);
+
     key.define_value(
         gc_context,
         "ALT",
@@ -163,6 +194,22 @@ pub fn create_key_object<'gc>(
         fn_proto,
     );
 
+    key.force_set_function(
+        "isToggled",
+        is_toggled,
+        gc_context,
+        Attribute::DontEnum | Attribute::DontDelete | Attribute::ReadOnly,
+        fn_proto,
+    );
+
+    key.force_set_function(
+        "getAscii",
+        get_ascii,
+        gc_context,
+        Attribute::DontEnum | Attribute::DontDelete | Attribute::ReadOnly,
+        fn_proto,
+    );
+
     key.force_set_function(
         "getCode",
         get_code,
@@ -178,6 +178,7 @@ pub fn create_proto<'gc>(
         },
         "gotoAndPlay" => goto_and_play,
         "gotoAndStop" => goto_and_stop,
+        "setMask" => set_mask,
         "startDrag" => start_drag,
         "swapDepths" => swap_depths,
         "toString" => |movie_clip: MovieClip<'gc>, _avm: &mut Avm1<'gc>, _context: &mut UpdateContext<'_, 'gc, '_>, _args| {
@@ -220,6 +221,39 @@ pub fn create_proto<'gc>(
     object.into()
 }
 
+/// Adds the properties of a movie clip that are backed by the clip itself.
+pub fn attach_virtual_properties<'gc>(gc_context: MutationContext<'gc, '_>, object: Object<'gc>) {
+    // Clips with button event handlers respond to these like buttons.
+    object.add_property(
+        gc_context,
+        "enabled",
+        Executable::Native(enabled),
+        Some(Executable::Native(set_enabled)),
+        DontDelete | DontEnum,
+    );
+    object.add_property(
+        gc_context,
+        "trackAsMenu",
+        Executable::Native(track_as_menu),
+        Some(Executable::Native(set_track_as_menu)),
+        DontDelete | DontEnum,
+    );
+    object.add_property(
+        gc_context,
+        "useHandCursor",
+        Executable::Native(use_hand_cursor),
+        Some(Executable::Native(set_use_hand_cursor)),
+        DontDelete | DontEnum,
+    );
+    object.add_property(
+        gc_context,
+        "scale9Grid",
+        Executable::Native(scale_9_grid),
+        Some(Executable::Native(set_scale_9_grid)),
+        DontDelete | DontEnum,
+    );
+}
+
 fn attach_movie<'gc>(
     mut movie_clip: MovieClip<'gc>,
     avm: &mut Avm1<'gc>,
@@ -547,6 +581,175 @@ pub fn stop_drag<'gc>(
     Ok(Value::Undefined.into())
 }
 
+/// Masks the clip with another display object, or removes its mask if the mask is `null`.
+fn set_mask<'gc>(
+    movie_clip: MovieClip<'gc>,
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    let mask = match args.get(0).cloned().unwrap_or(Value::Undefined) {
+        Value::Undefined | Value::Null => None,
+        mask => {
+            let mask = avm.resolve_target_display_object(context, movie_clip.into(), mask)?;
+            if mask.is_none() {
+                log::warn!("MovieClip.setMask: Invalid mask");
+                return Ok(false.into());
+            }
+            mask
+        }
+    };
+
+    let display_object: DisplayObject<'gc> = movie_clip.into();
+    display_object.set_mask(context.gc_context, mask);
+    Ok(true.into())
+}
+
+fn as_movie_clip(this: Object<'_>) -> Option<MovieClip<'_>> {
+    this.as_display_object()
+        .and_then(|dobj| dobj.as_movie_clip())
+}
+
+/// Reads the first argument of a setter as a boolean.
+fn bool_arg<'gc>(avm: &Avm1<'gc>, args: &[Value<'gc>]) -> bool {
+    args.get(0)
+        .cloned()
+        .unwrap_or(Value::Undefined)
+        .as_bool(avm.current_swf_version())
+}
+
+fn enabled<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok(as_movie_clip(this)
+        .map_or(Value::Undefined, |movie_clip| movie_clip.enabled().into())
+        .into())
+}
+
+fn set_enabled<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(movie_clip) = as_movie_clip(this) {
+        movie_clip.set_enabled(context.gc_context, bool_arg(avm, args));
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn track_as_menu<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok(as_movie_clip(this)
+        .map_or(Value::Undefined, |movie_clip| {
+            movie_clip.track_as_menu().into()
+        })
+        .into())
+}
+
+fn set_track_as_menu<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(movie_clip) = as_movie_clip(this) {
+        movie_clip.set_track_as_menu(context.gc_context, bool_arg(avm, args));
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn use_hand_cursor<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok(as_movie_clip(this)
+        .map_or(Value::Undefined, |movie_clip| {
+            movie_clip.use_hand_cursor().into()
+        })
+        .into())
+}
+
+fn set_use_hand_cursor<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(movie_clip) = as_movie_clip(this) {
+        movie_clip.set_use_hand_cursor(context.gc_context, bool_arg(avm, args));
+    }
+    Ok(Value::Undefined.into())
+}
+
+/// Returns the 9-slice scaling grid of the clip as an object with `x`, `y`, `width` and
+/// `height` in pixels, or `undefined` if it has none.
+fn scale_9_grid<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    let grid = as_movie_clip(this).and_then(|movie_clip| movie_clip.scaling_grid());
+    if let Some(grid) = grid {
+        let out = ScriptObject::object(context.gc_context, Some(avm.prototypes.object));
+        out.set("x", grid.x_min.to_pixels().into(), avm, context)?;
+        out.set("y", grid.y_min.to_pixels().into(), avm, context)?;
+        let width = grid.x_max - grid.x_min;
+        out.set("width", width.to_pixels().into(), avm, context)?;
+        let height = grid.y_max - grid.y_min;
+        out.set("height", height.to_pixels().into(), avm, context)?;
+        Ok(out.into())
+    } else {
+        Ok(Value::Undefined.into())
+    }
+}
+
+/// Sets the 9-slice scaling grid of the clip from an object with `x`, `y`, `width` and
+/// `height` in pixels. Any other value removes the grid.
+fn set_scale_9_grid<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    let movie_clip = match as_movie_clip(this) {
+        Some(movie_clip) => movie_clip,
+        None => return Ok(Value::Undefined.into()),
+    };
+
+    let grid = if let Ok(rect) = args.get(0).unwrap_or(&Value::Undefined).as_object() {
+        let mut values = [0.0; 4];
+        for (value, name) in values.iter_mut().zip(&["x", "y", "width", "height"]) {
+            *value = rect
+                .get(name, avm, context)?
+                .resolve(avm, context)?
+                .as_number(avm, context)?;
+        }
+        let [x, y, width, height] = values;
+        Some(BoundingBox {
+            x_min: Twips::from_pixels(x),
+            y_min: Twips::from_pixels(y),
+            x_max: Twips::from_pixels(x + width),
+            y_max: Twips::from_pixels(y + height),
+            valid: true,
+        })
+    } else {
+        None
+    };
+    movie_clip.set_scaling_grid(context.gc_context, grid);
+    Ok(Value::Undefined.into())
+}
+
 pub fn swap_depths<'gc>(
     movie_clip: MovieClip<'gc>,
     avm: &mut Avm1<'gc>,
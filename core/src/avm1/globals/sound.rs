@@ -1,11 +1,12 @@
 //! AVM1 Sound object
-//! TODO: Sound position, transform, loadSound
 
 use crate::avm1::function::Executable;
 use crate::avm1::property::Attribute::*;
 use crate::avm1::return_value::ReturnValue;
-use crate::avm1::{Avm1, Error, Object, SoundObject, TObject, UpdateContext, Value};
+use crate::avm1::{Avm1, Error, Object, ScriptObject, SoundObject, TObject, UpdateContext, Value};
+use crate::backend::audio::SoundTransform;
 use crate::character::Character;
+use crate::display_object::TDisplayObject;
 use gc_arena::MutationContext;
 
 /// Implements `Sound`
@@ -84,7 +85,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "get_transform",
+        "getTransform",
         get_transform,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -92,7 +93,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "get_volume",
+        "getVolume",
         get_volume,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -100,7 +101,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "load_sound",
+        "loadSound",
         load_sound,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -116,7 +117,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "set_pan",
+        "setPan",
         set_pan,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -124,7 +125,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "set_transform",
+        "setTransform",
         set_transform,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -132,7 +133,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "set_volume",
+        "setVolume",
         set_volume,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -203,101 +204,159 @@ fn duration<'gc>(
 fn get_bytes_loaded<'gc>(
     avm: &mut Avm1<'gc>,
     _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
-        log::warn!("Sound.getBytesLoaded: Unimplemented");
-        Ok(1.into())
-    } else {
-        Ok(Value::Undefined.into())
+        if let Some(sound_object) = this.as_sound_object() {
+            if let Some(bytes_loaded) = sound_object.bytes_loaded() {
+                return Ok(bytes_loaded.into());
+            }
+        } else {
+            log::warn!("Sound.getBytesLoaded: this is not a Sound");
+        }
     }
+    Ok(Value::Undefined.into())
 }
 
 fn get_bytes_total<'gc>(
     avm: &mut Avm1<'gc>,
     _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
-        log::warn!("Sound.getBytesTotal: Unimplemented");
-        Ok(1.into())
-    } else {
-        Ok(Value::Undefined.into())
+        if let Some(sound_object) = this.as_sound_object() {
+            if let Some(bytes_total) = sound_object.bytes_total() {
+                return Ok(bytes_total.into());
+            }
+        } else {
+            log::warn!("Sound.getBytesTotal: this is not a Sound");
+        }
     }
+    Ok(Value::Undefined.into())
 }
 
 fn get_pan<'gc>(
     _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.getPan: Unimplemented");
-    Ok(0.into())
+    if let Some(sound_object) = this.as_sound_object() {
+        Ok(sound_transform(context, sound_object).pan().into())
+    } else {
+        log::warn!("Sound.getPan: this is not a Sound");
+        Ok(Value::Undefined.into())
+    }
 }
 
 fn get_transform<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.getTransform: Unimplemented");
-    Ok(Value::Undefined.into())
+    if let Some(sound_object) = this.as_sound_object() {
+        let transform = sound_transform(context, sound_object);
+        let out = ScriptObject::object(context.gc_context, Some(avm.prototypes.object));
+        out.set("ll", transform.left_to_left.into(), avm, context)?;
+        out.set("lr", transform.right_to_left.into(), avm, context)?;
+        out.set("rl", transform.left_to_right.into(), avm, context)?;
+        out.set("rr", transform.right_to_right.into(), avm, context)?;
+        Ok(out.into())
+    } else {
+        log::warn!("Sound.getTransform: this is not a Sound");
+        Ok(Value::Undefined.into())
+    }
 }
 
 fn get_volume<'gc>(
     _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.getVolume: Unimplemented");
-    Ok(100.into())
+    if let Some(sound_object) = this.as_sound_object() {
+        Ok(sound_transform(context, sound_object).volume.into())
+    } else {
+        log::warn!("Sound.getVolume: this is not a Sound");
+        Ok(Value::Undefined.into())
+    }
 }
 
 fn id3<'gc>(
     avm: &mut Avm1<'gc>,
     _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
-        log::warn!("Sound.id3: Unimplemented");
+        if let Some(sound_object) = this.as_sound_object() {
+            if let Some(id3) = sound_object.id3() {
+                return Ok(id3.into());
+            }
+        } else {
+            log::warn!("Sound.id3: this is not a Sound");
+        }
     }
     Ok(Value::Undefined.into())
 }
 
 fn load_sound<'gc>(
     avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
-    _args: &[Value<'gc>],
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
-        log::warn!("Sound.loadSound: Unimplemented");
+        if let Some(sound_object) = this.as_sound_object() {
+            let url = args
+                .get(0)
+                .cloned()
+                .unwrap_or(Value::Undefined)
+                .coerce_to_string(avm, context)?;
+            let is_streaming = args
+                .get(1)
+                .unwrap_or(&Value::Undefined)
+                .as_bool(avm.current_swf_version());
+
+            // The previously attached sound is replaced.
+            stop_sound_instance(context, sound_object);
+            sound_object.set_sound(context.gc_context, None);
+            sound_object.set_sound_instance(context.gc_context, None);
+            sound_object.set_duration(context.gc_context, 0);
+            sound_object.set_position(context.gc_context, 0);
+            sound_object.set_bytes_loaded(context.gc_context, Some(0));
+            sound_object.set_bytes_total(context.gc_context, None);
+            sound_object.set_id3(context.gc_context, None);
+
+            context
+                .audio_manager
+                .load_sound(context.navigator, sound_object, &url, is_streaming);
+        } else {
+            log::warn!("Sound.loadSound: this is not a Sound");
+        }
     }
     Ok(Value::Undefined.into())
 }
 
 fn position<'gc>(
     avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
     this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
         if let Some(sound_object) = this.as_sound_object() {
-            // TODO: The position is "sticky"; even if the sound is no longer playing, it should return
-            // the previous valid position.
-            // Needs some audio backend work for this.
-            if sound_object.sound().is_some() {
-                if let Some(_sound_instance) = sound_object.sound_instance() {
-                    log::warn!("Sound.position: Unimplemented");
-                }
-                return Ok(sound_object.position().into());
+            if sound_object.sound().is_some() || sound_object.sound_instance().is_some() {
+                // The position is "sticky"; once the sound stops playing, this returns the
+                // last position of the sound.
+                let position = sound_object
+                    .sound_instance()
+                    .and_then(|sound_instance| context.audio.get_sound_position(sound_instance))
+                    .unwrap_or_else(|| sound_object.position());
+                return Ok(position.into());
             }
         } else {
             log::warn!("Sound.position: this is not a Sound");
@@ -307,32 +366,83 @@ fn position<'gc>(
 }
 
 fn set_pan<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
-    _args: &[Value<'gc>],
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.setPan: Unimplemented");
+    if let Some(sound_object) = this.as_sound_object() {
+        let pan = args
+            .get(0)
+            .unwrap_or(&Value::Undefined)
+            .as_number(avm, context)?;
+        let mut transform = sound_transform(context, sound_object);
+        transform.set_pan(to_percentage(pan).max(-100).min(100));
+        set_sound_transform(context, sound_object, transform);
+    } else {
+        log::warn!("Sound.setPan: this is not a Sound");
+    }
     Ok(Value::Undefined.into())
 }
 
 fn set_transform<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
-    _args: &[Value<'gc>],
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.setTransform: Unimplemented");
+    if let Some(sound_object) = this.as_sound_object() {
+        if let Ok(obj) = args.get(0).unwrap_or(&Value::Undefined).as_object() {
+            let mut transform = sound_transform(context, sound_object);
+            // Only the properties present on the object are changed.
+            let mut get_property = |name: &str| -> Result<Option<i32>, Error> {
+                if obj.has_property(name) {
+                    let value = obj
+                        .get(name, avm, context)?
+                        .resolve(avm, context)?
+                        .as_number(avm, context)?;
+                    Ok(Some(to_percentage(value)))
+                } else {
+                    Ok(None)
+                }
+            };
+            if let Some(ll) = get_property("ll")? {
+                transform.left_to_left = ll;
+            }
+            if let Some(lr) = get_property("lr")? {
+                transform.right_to_left = lr;
+            }
+            if let Some(rl) = get_property("rl")? {
+                transform.left_to_right = rl;
+            }
+            if let Some(rr) = get_property("rr")? {
+                transform.right_to_right = rr;
+            }
+            set_sound_transform(context, sound_object, transform);
+        }
+    } else {
+        log::warn!("Sound.setTransform: this is not a Sound");
+    }
     Ok(Value::Undefined.into())
 }
 
 fn set_volume<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
-    _args: &[Value<'gc>],
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.setVolume: Unimplemented");
+    if let Some(sound_object) = this.as_sound_object() {
+        let volume = args
+            .get(0)
+            .unwrap_or(&Value::Undefined)
+            .as_number(avm, context)?;
+        let mut transform = sound_transform(context, sound_object);
+        transform.volume = to_percentage(volume);
+        set_sound_transform(context, sound_object, transform);
+    } else {
+        log::warn!("Sound.setVolume: this is not a Sound");
+    }
     Ok(Value::Undefined.into())
 }
 
@@ -360,7 +470,12 @@ fn start<'gc>(
     use swf::{SoundEvent, SoundInfo};
     if let Some(sound_object) = this.as_sound_object() {
         if let Some(sound) = sound_object.sound() {
-            let sound_instance = context.audio.start_sound(
+            let owner = sound_object.owner();
+            let owner_transform = owner
+                .map(|owner| owner.global_sound_transform())
+                .unwrap_or_default();
+            let sound_instance = context.audio_manager.start_sound(
+                context.audio,
                 sound,
                 &SoundInfo {
                     event: SoundEvent::Start,
@@ -373,8 +488,12 @@ fn start<'gc>(
                     num_loops: loops,
                     envelope: None,
                 },
+                Some(sound_object),
+                owner,
+                &owner_transform,
             );
             sound_object.set_sound_instance(context.gc_context, Some(sound_instance));
+            sound_object.set_position(context.gc_context, (start_offset.max(0.0) * 1000.0) as u32);
         } else {
             log::warn!("Sound.start: No sound is attached");
         }
@@ -399,19 +518,19 @@ fn stop<'gc>(
                 context.library.get_character_by_export_name(&name)
             {
                 // Stop all sounds with the given name.
-                context.audio.stop_sounds_with_handle(*sound);
+                context
+                    .audio_manager
+                    .stop_sounds_with_handle(context.audio, *sound);
             } else {
                 log::warn!("Sound.stop: Sound '{}' not found", name);
             }
         } else if let Some(_owner) = sound.owner() {
             // Usage 2: Stop all sound running within a given clip.
             // TODO: We just stop the last played sound for now.
-            if let Some(sound_instance) = sound.sound_instance() {
-                context.audio.stop_sound(sound_instance);
-            }
+            stop_sound_instance(context, sound);
         } else {
             // Usage 3: If there is no owner and no name, this call acts like `stopAllSounds()`.
-            context.audio.stop_all_sounds();
+            context.audio_manager.stop_all_sounds(context.audio);
         }
     } else {
         log::warn!("Sound.stop: this is not a Sound");
@@ -419,3 +538,57 @@ fn stop<'gc>(
 
     Ok(Value::Undefined.into())
 }
+
+/// Stops the sound last started by a `Sound` object.
+/// Its position remains where the sound was stopped.
+fn stop_sound_instance<'gc>(
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    sound_object: SoundObject<'gc>,
+) {
+    if let Some(sound_instance) = sound_object.sound_instance() {
+        if let Some(position) = context.audio.get_sound_position(sound_instance) {
+            sound_object.set_position(context.gc_context, position);
+        }
+        context
+            .audio_manager
+            .stop_sound(context.audio, sound_instance);
+    }
+}
+
+/// The sound transform controlled by a `Sound` object.
+/// This is the transform of its owner, or the global sound transform if it has no owner.
+fn sound_transform<'gc>(
+    context: &UpdateContext<'_, 'gc, '_>,
+    sound_object: SoundObject<'gc>,
+) -> SoundTransform {
+    if let Some(owner) = sound_object.owner() {
+        *owner.sound_transform()
+    } else {
+        *context.audio_manager.global_sound_transform()
+    }
+}
+
+/// Sets the sound transform controlled by a `Sound` object, and updates any playing sounds.
+fn set_sound_transform<'gc>(
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    sound_object: SoundObject<'gc>,
+    transform: SoundTransform,
+) {
+    if let Some(mut owner) = sound_object.owner() {
+        owner.set_sound_transform(context.gc_context, &transform);
+        context.audio_manager.update_sound_transforms(context.audio);
+    } else {
+        context
+            .audio_manager
+            .set_global_sound_transform(context.audio, transform);
+    }
+}
+
+/// Converts an AVM1 number to a sound transform percentage.
+fn to_percentage(value: f64) -> i32 {
+    if value.is_finite() {
+        value as i32
+    } else {
+        0
+    }
+}
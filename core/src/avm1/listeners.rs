@@ -138,25 +138,33 @@ impl<'gc> Listeners<'gc> {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SystemListener {
+    Key,
     Mouse,
+    Stage,
 }
 
 #[derive(Clone, Collect, Debug, Copy)]
 #[collect(no_drop)]
 pub struct SystemListeners<'gc> {
+    pub key: Listeners<'gc>,
     pub mouse: Listeners<'gc>,
+    pub stage: Listeners<'gc>,
 }
 
 impl<'gc> SystemListeners<'gc> {
     pub fn new(gc_context: MutationContext<'gc, '_>, array_proto: Option<Object<'gc>>) -> Self {
         Self {
+            key: Listeners::new(gc_context, array_proto),
             mouse: Listeners::new(gc_context, array_proto),
+            stage: Listeners::new(gc_context, array_proto),
         }
     }
 
     pub fn get(&self, listener: SystemListener) -> Listeners<'gc> {
         match listener {
+            SystemListener::Key => self.key,
             SystemListener::Mouse => self.mouse,
+            SystemListener::Stage => self.stage,
         }
     }
 }
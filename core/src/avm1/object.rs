@@ -65,6 +65,21 @@ pub trait TObject<'gc>: 'gc + Collect + Debug + Into<Object<'gc>> + Clone + Copy
         }
     }
 
+    /// Retrieve a named property from the object, or it's prototype, and resolve it.
+    ///
+    /// This is for use outside of ActionScript, where errors can't be thrown; `undefined` is
+    /// returned if the property can't be read.
+    fn get_resolved(
+        &self,
+        name: &str,
+        avm: &mut Avm1<'gc>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+    ) -> Value<'gc> {
+        self.get(name, avm, context)
+            .and_then(|value| value.resolve(avm, context))
+            .unwrap_or(Value::Undefined)
+    }
+
     /// Set a named property on this object, or it's prototype.
     fn set(
         &self,
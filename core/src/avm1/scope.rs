@@ -212,6 +212,16 @@ impl<'gc> Scope<'gc> {
         &mut self.values
     }
 
+    /// Returns what kind of scope this is.
+    pub fn class(&self) -> ScopeClass {
+        self.class
+    }
+
+    /// Returns the parent scope, if any.
+    pub fn parent_cell(&self) -> Option<GcCell<'gc, Scope<'gc>>> {
+        self.parent
+    }
+
     /// Returns a reference to the parent scope object.
     pub fn parent(&self) -> Option<Ref<Scope<'gc>>> {
         match self.parent {
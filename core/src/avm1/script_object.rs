@@ -150,6 +150,12 @@ impl<'gc> ScriptObject<'gc> {
         self.0.write(gc_context).type_of = type_of;
     }
 
+    /// Returns a copy of the property slot with the given name, without calling its getter if
+    /// it's a virtual property.
+    pub fn property(&self, name: &str) -> Option<Property<'gc>> {
+        self.0.read().values.get(name).cloned()
+    }
+
     #[allow(clippy::trivially_copy_pass_by_ref)]
     pub fn sync_native_property(
         &self,
@@ -572,6 +578,7 @@ mod tests {
     use crate::display_object::MovieClip;
     use crate::library::Library;
     use crate::prelude::*;
+    use crate::stage::Stage;
     use gc_arena::rootless_arena;
     use rand::{rngs::SmallRng, SeedableRng};
     use std::sync::Arc;
@@ -594,6 +601,7 @@ mod tests {
                 rng: &mut SmallRng::from_seed([0u8; 16]),
                 action_queue: &mut crate::context::ActionQueue::new(),
                 audio: &mut NullAudioBackend::new(),
+                audio_manager: &mut crate::audio_manager::AudioManager::new(),
                 input: &mut NullInputBackend::new(),
                 background_color: &mut Color {
                     r: 0,
@@ -607,9 +615,12 @@ mod tests {
                 swf_data: &mut Arc::new(vec![]),
                 system_prototypes: avm.prototypes().clone(),
                 mouse_hovered_object: None,
+                mouse_pressed_object: None,
                 mouse_position: &(Twips::new(0), Twips::new(0)),
                 drag_object: &mut None,
-                stage_size: (Twips::from_pixels(550.0), Twips::from_pixels(400.0)),
+                stage: &mut Stage::new(550, 400),
+                current_context_menu: &mut None,
+                focus_tracker: crate::focus::FocusTracker::new(gc_context),
             };
 
             let object = ScriptObject::object(gc_context, Some(avm.prototypes().object)).into();
@@ -38,12 +38,22 @@ pub struct SoundObjectData<'gc> {
 
     /// Duration of the currently attached sound in milliseconds.
     duration: u32,
+
+    /// The number of bytes of the sound loaded by `loadSound`.
+    bytes_loaded: Option<u32>,
+
+    /// The total size of the sound loaded by `loadSound`, if known.
+    bytes_total: Option<u32>,
+
+    /// The ID3 metadata of the sound loaded by `loadSound`.
+    id3: Option<Object<'gc>>,
 }
 
 unsafe impl<'gc> Collect for SoundObjectData<'gc> {
     fn trace(&self, cc: gc_arena::CollectionContext) {
         self.base.trace(cc);
         self.owner.trace(cc);
+        self.id3.trace(cc);
     }
 }
 
@@ -72,6 +82,9 @@ impl<'gc> SoundObject<'gc> {
                 owner: None,
                 position: 0,
                 duration: 0,
+                bytes_loaded: None,
+                bytes_total: None,
+                id3: None,
             },
         ))
     }
@@ -124,6 +137,30 @@ impl<'gc> SoundObject<'gc> {
         self.0.write(gc_context).position = position;
     }
 
+    pub fn bytes_loaded(self) -> Option<u32> {
+        self.0.read().bytes_loaded
+    }
+
+    pub fn set_bytes_loaded(self, gc_context: MutationContext<'gc, '_>, bytes_loaded: Option<u32>) {
+        self.0.write(gc_context).bytes_loaded = bytes_loaded;
+    }
+
+    pub fn bytes_total(self) -> Option<u32> {
+        self.0.read().bytes_total
+    }
+
+    pub fn set_bytes_total(self, gc_context: MutationContext<'gc, '_>, bytes_total: Option<u32>) {
+        self.0.write(gc_context).bytes_total = bytes_total;
+    }
+
+    pub fn id3(self) -> Option<Object<'gc>> {
+        self.0.read().id3
+    }
+
+    pub fn set_id3(self, gc_context: MutationContext<'gc, '_>, id3: Option<Object<'gc>>) {
+        self.0.write(gc_context).id3 = id3;
+    }
+
     fn base(self) -> ScriptObject<'gc> {
         self.0.read().base
     }
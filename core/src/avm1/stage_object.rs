@@ -6,6 +6,7 @@ use crate::avm1::return_value::ReturnValue;
 use crate::avm1::{Avm1, Error, Object, ObjectPtr, ScriptObject, TDisplayObject, TObject, Value};
 use crate::context::UpdateContext;
 use crate::display_object::{DisplayObject, MovieClip};
+use crate::stage::StageQuality;
 use enumset::EnumSet;
 use gc_arena::{Collect, GcCell, MutationContext};
 use std::collections::{HashMap, HashSet};
@@ -679,39 +680,64 @@ fn url<'gc>(
 
 fn high_quality<'gc>(
     _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
     _this: DisplayObject<'gc>,
 ) -> Result<Value<'gc>, Error> {
-    log::warn!("Unimplemented property _highquality");
-    Ok(1.into())
+    let high_quality = match context.stage.quality() {
+        StageQuality::Low => 0,
+        StageQuality::Medium | StageQuality::High => 1,
+        StageQuality::Best => 2,
+    };
+    Ok(high_quality.into())
 }
 
 fn set_high_quality<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
     _this: DisplayObject<'gc>,
-    _val: Value<'gc>,
+    val: Value<'gc>,
 ) -> Result<(), Error> {
-    log::warn!("Unimplemented property _highquality");
+    let quality = match val.as_number(avm, context)? as i32 {
+        0 => StageQuality::Low,
+        1 => StageQuality::High,
+        2 => StageQuality::Best,
+        _ => return Ok(()),
+    };
+    context.stage.set_quality(quality);
     Ok(())
 }
 
 fn focus_rect<'gc>(
     _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: DisplayObject<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: DisplayObject<'gc>,
 ) -> Result<Value<'gc>, Error> {
-    log::warn!("Unimplemented property _focusrect");
-    Ok(Value::Null)
+    // `_focusrect` on the root is the global setting.
+    let focus_rect = if this.parent().is_none() {
+        Some(context.stage.focus_rect())
+    } else {
+        this.focus_rect()
+    };
+    Ok(focus_rect.map(Value::from).unwrap_or(Value::Null))
 }
 
 fn set_focus_rect<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: DisplayObject<'gc>,
-    _val: Value<'gc>,
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    mut this: DisplayObject<'gc>,
+    val: Value<'gc>,
 ) -> Result<(), Error> {
-    log::warn!("Unimplemented property _focusrect");
+    let focus_rect = match val {
+        Value::Undefined | Value::Null => None,
+        val => Some(val.as_bool(avm.current_swf_version())),
+    };
+    if this.parent().is_none() {
+        if let Some(focus_rect) = focus_rect {
+            context.stage.set_focus_rect(focus_rect);
+        }
+    } else {
+        this.set_focus_rect(context.gc_context, focus_rect);
+    }
     Ok(())
 }
 
@@ -736,20 +762,22 @@ fn set_sound_buf_time<'gc>(
 
 fn quality<'gc>(
     _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
     _this: DisplayObject<'gc>,
 ) -> Result<Value<'gc>, Error> {
-    log::warn!("Unimplemented property _quality");
-    Ok("HIGH".into())
+    Ok(context.stage.quality().name().into())
 }
 
 fn set_quality<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
     _this: DisplayObject<'gc>,
-    _val: Value<'gc>,
+    val: Value<'gc>,
 ) -> Result<(), Error> {
-    log::warn!("Unimplemented property _quality");
+    let name = val.coerce_to_string(avm, context)?;
+    if let Some(quality) = StageQuality::from_name(&name) {
+        context.stage.set_quality(quality);
+    }
     Ok(())
 }
 
@@ -1,3 +1,4 @@
+use crate::audio_manager::AudioManager;
 use crate::avm1::activation::Activation;
 use crate::avm1::{Avm1, Object, UpdateContext};
 use crate::backend::audio::NullAudioBackend;
@@ -6,8 +7,10 @@ use crate::backend::navigator::NullNavigatorBackend;
 use crate::backend::render::NullRenderer;
 use crate::context::ActionQueue;
 use crate::display_object::{MovieClip, TDisplayObject};
+use crate::focus::FocusTracker;
 use crate::library::Library;
 use crate::prelude::*;
+use crate::stage::Stage;
 use gc_arena::{rootless_arena, GcCell, MutationContext};
 use rand::{rngs::SmallRng, SeedableRng};
 use std::sync::Arc;
@@ -32,6 +35,7 @@ where
             root,
             rng: &mut SmallRng::from_seed([0u8; 16]),
             audio: &mut NullAudioBackend::new(),
+            audio_manager: &mut AudioManager::new(),
             input: &mut NullInputBackend::new(),
             action_queue: &mut ActionQueue::new(),
             background_color: &mut Color {
@@ -46,9 +50,12 @@ where
             swf_data: &mut Arc::new(vec![]),
             system_prototypes: avm.prototypes().clone(),
             mouse_hovered_object: None,
+            mouse_pressed_object: None,
             mouse_position: &(Twips::new(0), Twips::new(0)),
             drag_object: &mut None,
-            stage_size: (Twips::from_pixels(550.0), Twips::from_pixels(400.0)),
+            stage: &mut Stage::new(550, 400),
+            current_context_menu: &mut None,
+            focus_tracker: FocusTracker::new(gc_context),
         };
 
         let globals = avm.global_object_cell();
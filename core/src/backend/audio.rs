@@ -2,6 +2,8 @@ use generational_arena::{Arena, Index};
 use std::ops::{Deref, DerefMut};
 
 pub mod decoders;
+pub mod mixer;
+pub mod recording;
 pub mod swf {
     pub use swf::{
         read, AudioCompression, CharacterId, Sound, SoundEnvelope, SoundEnvelopePoint, SoundEvent,
@@ -15,6 +17,105 @@ pub type SoundInstanceHandle = Index;
 
 type Error = Box<dyn std::error::Error>;
 
+/// How a sound is mixed into the left and right speakers, as set by `Sound.setTransform`.
+/// All values are percentages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoundTransform {
+    /// How much of the left input is played in the left speaker.
+    pub left_to_left: i32,
+
+    /// How much of the left input is played in the right speaker.
+    pub left_to_right: i32,
+
+    /// How much of the right input is played in the left speaker.
+    pub right_to_left: i32,
+
+    /// How much of the right input is played in the right speaker.
+    pub right_to_right: i32,
+
+    /// The overall volume, applied after the channels are mixed.
+    pub volume: i32,
+}
+
+impl SoundTransform {
+    /// The pan of this transform, from -100 (left speaker only) to 100 (right speaker only).
+    pub fn pan(&self) -> i32 {
+        self.right_to_right - self.left_to_left
+    }
+
+    /// Pans the sound between the speakers, as `Sound.setPan` does.
+    /// This also resets any mixing between the channels.
+    pub fn set_pan(&mut self, pan: i32) {
+        if pan < 0 {
+            self.left_to_left = 100;
+            self.right_to_right = 100 + pan;
+        } else {
+            self.left_to_left = 100 - pan;
+            self.right_to_right = 100;
+        }
+        self.left_to_right = 0;
+        self.right_to_left = 0;
+    }
+
+    /// Combines this transform with the transform of a child, so that the child's sounds
+    /// are affected by both.
+    pub fn concat(&self, child: &SoundTransform) -> SoundTransform {
+        // The channel mixing is a 2x2 matrix; the parent mixes the output of the child.
+        let mix = |a: i32, b: i32, c: i32, d: i32| (a * b + c * d) / 100;
+        SoundTransform {
+            left_to_left: mix(
+                self.left_to_left,
+                child.left_to_left,
+                self.right_to_left,
+                child.left_to_right,
+            ),
+            left_to_right: mix(
+                self.left_to_right,
+                child.left_to_left,
+                self.right_to_right,
+                child.left_to_right,
+            ),
+            right_to_left: mix(
+                self.left_to_left,
+                child.right_to_left,
+                self.right_to_left,
+                child.right_to_right,
+            ),
+            right_to_right: mix(
+                self.left_to_right,
+                child.right_to_left,
+                self.right_to_right,
+                child.right_to_right,
+            ),
+            volume: self.volume * child.volume / 100,
+        }
+    }
+
+    /// Returns the multipliers to apply to each input channel for each speaker, including
+    /// the volume, as `[left_to_left, left_to_right, right_to_left, right_to_right]`.
+    pub fn multipliers(&self) -> [f32; 4] {
+        let volume = self.volume as f32 / 100.0;
+        [
+            self.left_to_left as f32 / 100.0 * volume,
+            self.left_to_right as f32 / 100.0 * volume,
+            self.right_to_left as f32 / 100.0 * volume,
+            self.right_to_right as f32 / 100.0 * volume,
+        ]
+    }
+}
+
+impl Default for SoundTransform {
+    fn default() -> Self {
+        Self {
+            left_to_left: 100,
+            left_to_right: 0,
+            right_to_left: 0,
+            right_to_right: 100,
+            volume: 100,
+        }
+    }
+}
+
 pub trait AudioBackend {
     fn prime_audio(&mut self) {}
     fn register_sound(&mut self, swf_sound: &swf::Sound) -> Result<SoundHandle, Error>;
@@ -34,6 +135,30 @@ pub trait AudioBackend {
     }
     fn preload_sound_stream_end(&mut self, _clip_id: swf::CharacterId) {}
 
+    /// Registers an MP3 file loaded from outside of the SWF, such as with `Sound.loadSound`.
+    fn register_mp3(&mut self, data: &[u8]) -> Result<SoundHandle, Error> {
+        let mut frames = decoders::mp3_frames(data).map(|(_, header)| header);
+        let first_frame = frames.next().ok_or("No MP3 frames found")?;
+        let num_samples = frames.fold(u32::from(first_frame.num_samples), |num_samples, frame| {
+            num_samples + u32::from(frame.num_samples)
+        });
+
+        // MP3 data in a SWF begins with the number of samples of latency to skip.
+        let mut sound_data = vec![0, 0];
+        sound_data.extend_from_slice(data);
+        self.register_sound(&swf::Sound {
+            id: 0,
+            format: swf::SoundFormat {
+                compression: swf::AudioCompression::Mp3,
+                sample_rate: first_frame.sample_rate,
+                is_stereo: first_frame.num_channels == 2,
+                is_16_bit: true,
+            },
+            num_samples,
+            data: sound_data,
+        })
+    }
+
     /// Starts playing a sound instance that is not tied to a MovieClip timeline.
     /// In Flash, this is known as an "Event" sound.
     fn start_sound(&mut self, sound: SoundHandle, settings: &swf::SoundInfo)
@@ -47,6 +172,16 @@ pub trait AudioBackend {
         handle: &swf::SoundStreamHead,
     ) -> AudioStreamHandle;
 
+    /// Starts playing an MP3 file that is still being downloaded, as a streaming
+    /// `Sound.loadSound` does. Playback waits if it catches up to the downloaded data.
+    /// Returns `None` if the backend does not support this, or no audio has been downloaded yet.
+    fn start_mp3_stream(
+        &mut self,
+        _buffer: decoders::Mp3StreamBuffer,
+    ) -> Option<SoundInstanceHandle> {
+        None
+    }
+
     /// Stops a playing sound instance.
     /// No-op if the sound is not playing.
     fn stop_sound(&mut self, sound: SoundInstanceHandle);
@@ -67,15 +202,44 @@ pub trait AudioBackend {
     /// which only plays a sound if that sound is not already playing.
     fn is_sound_playing_with_handle(&mut self, handle: SoundHandle) -> bool;
 
+    /// Returns whether a sound instance is still playing.
+    fn is_sound_playing(&mut self, instance: SoundInstanceHandle) -> bool;
+
     /// Get the duration of a sound in milliseconds.
     /// Returns `None` if sound is not registered.
     fn get_sound_duration(&self, sound: SoundHandle) -> Option<u32>;
 
+    /// Returns the current position of a playing sound instance within its sound, in
+    /// milliseconds. The position returns to the start point of the sound each time it loops.
+    /// Used by `Sound.position`.
+    /// Returns `None` if the sound is not playing, or if the backend can't report its position.
+    fn get_sound_position(&self, _instance: SoundInstanceHandle) -> Option<u32> {
+        None
+    }
+
+    /// Returns how far a stream sound has played, in milliseconds.
+    /// Used to keep the timeline in sync with its stream sound.
+    /// Returns `None` if the stream is not playing, or if the backend can't report its position.
+    fn get_stream_position(&self, _stream: AudioStreamHandle) -> Option<u32> {
+        None
+    }
+
+    /// Changes how a playing sound instance is mixed into the speakers.
+    fn set_sound_transform(&mut self, _instance: SoundInstanceHandle, _transform: &SoundTransform) {
+    }
+
+    /// Changes how a playing stream sound is mixed into the speakers.
+    fn set_stream_transform(&mut self, _stream: AudioStreamHandle, _transform: &SoundTransform) {}
+
     // TODO: Eventually remove this/move it to library.
     fn is_loading_complete(&self) -> bool {
         true
     }
     fn tick(&mut self) {}
+
+    /// Called each time the player runs a frame, with the duration of a frame in milliseconds.
+    /// Used by backends that mix audio using the frames as a clock instead of in real time.
+    fn advance_clock(&mut self, _frame_time: f64) {}
 }
 
 /// Rust does not auto-impl a Trait for Box<Trait> or Deref<Target=Trait>
@@ -111,6 +275,9 @@ impl<T: AudioBackend + ?Sized> AudioBackend for Box<T> {
     fn preload_sound_stream_end(&mut self, clip_id: swf::CharacterId) {
         self.deref_mut().preload_sound_stream_end(clip_id)
     }
+    fn register_mp3(&mut self, data: &[u8]) -> Result<SoundHandle, Error> {
+        self.deref_mut().register_mp3(data)
+    }
     fn start_sound(
         &mut self,
         sound: SoundHandle,
@@ -128,6 +295,12 @@ impl<T: AudioBackend + ?Sized> AudioBackend for Box<T> {
         self.deref_mut()
             .start_stream(clip_id, clip_frame, clip_data, handle)
     }
+    fn start_mp3_stream(
+        &mut self,
+        buffer: decoders::Mp3StreamBuffer,
+    ) -> Option<SoundInstanceHandle> {
+        self.deref_mut().start_mp3_stream(buffer)
+    }
 
     fn stop_sound(&mut self, sound: SoundInstanceHandle) {
         self.deref_mut().stop_sound(sound)
@@ -147,16 +320,40 @@ impl<T: AudioBackend + ?Sized> AudioBackend for Box<T> {
         self.deref_mut().is_sound_playing_with_handle(handle)
     }
 
+    fn is_sound_playing(&mut self, instance: SoundInstanceHandle) -> bool {
+        self.deref_mut().is_sound_playing(instance)
+    }
+
     fn get_sound_duration(&self, sound: SoundHandle) -> Option<u32> {
         self.deref().get_sound_duration(sound)
     }
 
+    fn get_sound_position(&self, instance: SoundInstanceHandle) -> Option<u32> {
+        self.deref().get_sound_position(instance)
+    }
+
+    fn get_stream_position(&self, stream: AudioStreamHandle) -> Option<u32> {
+        self.deref().get_stream_position(stream)
+    }
+
+    fn set_sound_transform(&mut self, instance: SoundInstanceHandle, transform: &SoundTransform) {
+        self.deref_mut().set_sound_transform(instance, transform)
+    }
+
+    fn set_stream_transform(&mut self, stream: AudioStreamHandle, transform: &SoundTransform) {
+        self.deref_mut().set_stream_transform(stream, transform)
+    }
+
     fn is_loading_complete(&self) -> bool {
         self.deref().is_loading_complete()
     }
     fn tick(&mut self) {
         self.deref_mut().tick()
     }
+
+    fn advance_clock(&mut self, frame_time: f64) {
+        self.deref_mut().advance_clock(frame_time)
+    }
 }
 
 /// Audio backend that ignores all audio.
@@ -207,6 +404,9 @@ impl AudioBackend for NullAudioBackend {
     fn is_sound_playing_with_handle(&mut self, _handle: SoundHandle) -> bool {
         false
     }
+    fn is_sound_playing(&mut self, _instance: SoundInstanceHandle) -> bool {
+        false
+    }
 
     fn get_sound_duration(&self, _sound: SoundHandle) -> Option<u32> {
         None
@@ -218,3 +418,46 @@ impl Default for NullAudioBackend {
         NullAudioBackend::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sound_transform_pan() {
+        let mut transform = SoundTransform::default();
+        assert_eq!(transform.pan(), 0);
+
+        transform.set_pan(-25);
+        assert_eq!(transform.left_to_left, 100);
+        assert_eq!(transform.right_to_right, 75);
+        assert_eq!(transform.pan(), -25);
+
+        transform.set_pan(100);
+        assert_eq!(transform.left_to_left, 0);
+        assert_eq!(transform.right_to_right, 100);
+        assert_eq!(transform.pan(), 100);
+    }
+
+    #[test]
+    fn sound_transform_concat() {
+        let default = SoundTransform::default();
+        let swapped = SoundTransform {
+            left_to_left: 0,
+            left_to_right: 100,
+            right_to_left: 100,
+            right_to_right: 0,
+            volume: 50,
+        };
+        assert_eq!(default.concat(&swapped), swapped);
+        assert_eq!(swapped.concat(&default), swapped);
+
+        // Swapping the channels twice restores them.
+        let swapped_twice = swapped.concat(&swapped);
+        assert_eq!(swapped_twice.left_to_left, 100);
+        assert_eq!(swapped_twice.left_to_right, 0);
+        assert_eq!(swapped_twice.right_to_left, 0);
+        assert_eq!(swapped_twice.right_to_right, 100);
+        assert_eq!(swapped_twice.volume, 25);
+    }
+}
@@ -2,16 +2,36 @@
 
 mod adpcm;
 mod mp3;
+mod nellymoser;
 mod pcm;
+#[cfg(feature = "speex")]
+mod speex;
+
+#[cfg(all(feature = "speex", target_arch = "wasm32"))]
+compile_error!("The `speex` feature links the system's libspeex, and isn't supported on wasm32.");
 
 pub use adpcm::AdpcmDecoder;
-pub use mp3::Mp3Decoder;
+pub use mp3::{mp3_frames, Mp3Decoder, Mp3FrameHeader, Mp3StreamBuffer, Mp3StreamDecoder};
+pub use nellymoser::NellymoserDecoder;
 pub use pcm::PcmDecoder;
+#[cfg(feature = "speex")]
+pub use speex::SpeexDecoder;
 
 use crate::tag_utils::SwfSlice;
 use std::io::{self, Cursor, Read};
 use swf::{AudioCompression, SoundFormat, TagCode};
 
+type Error = Box<dyn std::error::Error>;
+
+/// Whether sounds using the given compression can be decoded.
+/// `make_decoder` and `make_seekable_decoder` return an error for any other compression.
+pub fn is_supported(compression: AudioCompression) -> bool {
+    match compression {
+        AudioCompression::Speex => cfg!(feature = "speex"),
+        _ => true,
+    }
+}
+
 /// An audio decoder. Can be used as an `Iterator` to return stero sample frames.
 /// If the sound is mono, the sample is duplicated across both channels.
 pub trait Decoder: Iterator<Item = [i16; 2]> {
@@ -26,8 +46,8 @@ pub trait Decoder: Iterator<Item = [i16; 2]> {
 pub fn make_decoder<'a, R: 'a + Send + Read>(
     format: &SoundFormat,
     data: R,
-) -> Box<dyn 'a + Send + Decoder> {
-    match format.compression {
+) -> Result<Box<dyn 'a + Send + Decoder>, Error> {
+    let decoder: Box<dyn 'a + Send + Decoder> = match format.compression {
         AudioCompression::UncompressedUnknownEndian => {
             // Cross fingers that it's little endian.
             log::warn!("make_decoder: PCM sound is unknown endian; assuming little endian");
@@ -54,14 +74,44 @@ pub fn make_decoder<'a, R: 'a + Send + Read>(
             format.sample_rate.into(),
             data,
         )),
-        _ => {
-            log::error!(
-                "make_decoder: Unhandled audio compression {:?}",
-                format.compression
-            );
-            unimplemented!()
-        }
-    }
+        AudioCompression::Nellymoser16Khz => Box::new(NellymoserDecoder::new(data, 16000)),
+        AudioCompression::Nellymoser8Khz => Box::new(NellymoserDecoder::new(data, 8000)),
+        AudioCompression::Nellymoser => Box::new(NellymoserDecoder::new(data, format.sample_rate)),
+        #[cfg(feature = "speex")]
+        AudioCompression::Speex => Box::new(SpeexDecoder::new(data)),
+        _ => return Err(format!("Unsupported audio compression {:?}", format.compression).into()),
+    };
+    Ok(decoder)
+}
+
+/// Instantiate a seekable decoder for the compression that the sound data uses.
+/// Seekable decoders are used for event sounds that loop or start partway through.
+pub fn make_seekable_decoder<R: 'static + AsRef<[u8]> + Default + Send>(
+    format: &SoundFormat,
+    data: Cursor<R>,
+) -> Result<Box<dyn Send + SeekableDecoder>, Error> {
+    let decoder: Box<dyn Send + SeekableDecoder> = match format.compression {
+        AudioCompression::Uncompressed | AudioCompression::UncompressedUnknownEndian => Box::new(
+            PcmDecoder::new(data, format.is_stereo, format.sample_rate, format.is_16_bit),
+        ),
+        AudioCompression::Adpcm => Box::new(AdpcmDecoder::new(
+            data,
+            format.is_stereo,
+            format.sample_rate,
+        )),
+        AudioCompression::Mp3 => Box::new(Mp3Decoder::new(
+            if format.is_stereo { 2 } else { 1 },
+            format.sample_rate.into(),
+            data,
+        )),
+        AudioCompression::Nellymoser16Khz => Box::new(NellymoserDecoder::new(data, 16000)),
+        AudioCompression::Nellymoser8Khz => Box::new(NellymoserDecoder::new(data, 8000)),
+        AudioCompression::Nellymoser => Box::new(NellymoserDecoder::new(data, format.sample_rate)),
+        #[cfg(feature = "speex")]
+        AudioCompression::Speex => Box::new(SpeexDecoder::new(data)),
+        _ => return Err(format!("Unsupported audio compression {:?}", format.compression).into()),
+    };
+    Ok(decoder)
 }
 
 /// A "stream" sound is a sound that has its data distributed across `SoundStreamBlock` tags,
@@ -84,12 +134,12 @@ struct StandardStreamDecoder {
 impl StandardStreamDecoder {
     /// Constructs a new `StandardStreamDecoder.
     /// `swf_data` should be the tag data of the MovieClip that contains the stream.
-    fn new(format: &SoundFormat, swf_data: SwfSlice, swf_version: u8) -> Self {
+    fn new(format: &SoundFormat, swf_data: SwfSlice, swf_version: u8) -> Result<Self, Error> {
         // Create a tag reader to get the audio data from SoundStreamBlock tags.
         let tag_reader = StreamTagReader::new(format.compression, swf_data, swf_version);
         // Wrap the tag reader in the decoder.
-        let decoder = make_decoder(format, tag_reader);
-        Self { decoder }
+        let decoder = make_decoder(format, tag_reader)?;
+        Ok(Self { decoder })
     }
 }
 
@@ -179,11 +229,19 @@ pub fn make_stream_decoder(
     format: &swf::SoundFormat,
     swf_data: SwfSlice,
     swf_version: u8,
-) -> Box<dyn Decoder + Send> {
+) -> Result<Box<dyn Decoder + Send>, Error> {
     if format.compression == AudioCompression::Adpcm {
-        Box::new(AdpcmStreamDecoder::new(format, swf_data, swf_version))
+        Ok(Box::new(AdpcmStreamDecoder::new(
+            format,
+            swf_data,
+            swf_version,
+        )))
     } else {
-        Box::new(StandardStreamDecoder::new(format, swf_data, swf_version))
+        Ok(Box::new(StandardStreamDecoder::new(
+            format,
+            swf_data,
+            swf_version,
+        )?))
     }
 }
 
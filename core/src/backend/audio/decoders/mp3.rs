@@ -1,5 +1,6 @@
 use super::{Decoder, SeekableDecoder};
 use std::io::{Cursor, Read};
+use std::sync::{Arc, Mutex};
 
 #[cfg(feature = "minimp3")]
 #[allow(dead_code)]
@@ -170,3 +171,324 @@ impl<R: AsRef<[u8]> + Default> SeekableDecoder for Mp3Decoder<Cursor<R>> {
         *self = Mp3Decoder::new(self.num_channels, self.sample_rate, cursor);
     }
 }
+
+impl<R: Read> Mp3Decoder<R> {
+    /// The reader that the MP3 data is read from.
+    pub fn reader_mut(&mut self) -> &mut R {
+        #[cfg(feature = "minimp3")]
+        {
+            self.decoder.reader_mut()
+        }
+        #[cfg(all(feature = "puremp3", not(feature = "minimp3")))]
+        {
+            self.decoder.get_mut()
+        }
+    }
+}
+
+/// The header of an MPEG-1/2/2.5 Layer III audio frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mp3FrameHeader {
+    pub sample_rate: u16,
+    pub num_channels: u8,
+
+    /// The length of the frame in bytes, including the header.
+    pub frame_len: usize,
+
+    /// The number of sample frames that this frame decodes to.
+    pub num_samples: u16,
+}
+
+impl Mp3FrameHeader {
+    /// Parses the frame header at the beginning of `data`.
+    /// Returns `None` if `data` does not begin with a valid Layer III frame header.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 4 || data[0] != 0xff || data[1] & 0xe0 != 0xe0 {
+            return None;
+        }
+
+        // Only Layer III is supported.
+        let version = (data[1] >> 3) & 0b11;
+        let layer = (data[1] >> 1) & 0b11;
+        if version == 0b01 || layer != 0b01 {
+            return None;
+        }
+        let is_mpeg1 = version == 0b11;
+
+        const MPEG1_BIT_RATES: [u16; 15] = [
+            0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+        ];
+        const MPEG2_BIT_RATES: [u16; 15] =
+            [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+        const SAMPLE_RATES: [u16; 3] = [44100, 48000, 32000];
+
+        // Free format bit rates (index 0) are not supported.
+        let bit_rate_index = usize::from(data[2] >> 4);
+        let sample_rate_index = usize::from((data[2] >> 2) & 0b11);
+        if bit_rate_index == 0 || bit_rate_index == 15 || sample_rate_index == 3 {
+            return None;
+        }
+        let bit_rate = if is_mpeg1 {
+            MPEG1_BIT_RATES[bit_rate_index]
+        } else {
+            MPEG2_BIT_RATES[bit_rate_index]
+        };
+        let sample_rate = match version {
+            0b11 => SAMPLE_RATES[sample_rate_index],
+            0b10 => SAMPLE_RATES[sample_rate_index] / 2,
+            _ => SAMPLE_RATES[sample_rate_index] / 4,
+        };
+        let num_samples: u16 = if is_mpeg1 { 1152 } else { 576 };
+        let padding = usize::from((data[2] >> 1) & 1);
+        let frame_len = usize::from(num_samples / 8) * usize::from(bit_rate) * 1000
+            / usize::from(sample_rate)
+            + padding;
+
+        Some(Self {
+            sample_rate,
+            num_channels: if data[3] >> 6 == 0b11 { 1 } else { 2 },
+            frame_len,
+            num_samples,
+        })
+    }
+}
+
+/// Returns an iterator over the offset and header of each whole frame in the MP3 data `data`.
+/// ID3 tags and any other data between frames are skipped.
+pub fn mp3_frames(data: &[u8]) -> Mp3Frames<'_> {
+    Mp3Frames { data, pos: 0 }
+}
+
+/// An iterator over the frames in MP3 data, created by `mp3_frames`.
+pub struct Mp3Frames<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Mp3Frames<'a> {
+    type Item = (usize, Mp3FrameHeader);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos + 4 <= self.data.len() {
+            let data = &self.data[self.pos..];
+            if let Some(header) = Mp3FrameHeader::parse(data) {
+                if header.frame_len > data.len() {
+                    // The frame is incomplete.
+                    return None;
+                }
+                let offset = self.pos;
+                self.pos += header.frame_len;
+                return Some((offset, header));
+            } else if data.len() >= 10 && &data[..3] == b"ID3" {
+                // Skip the ID3v2 tag. The size excludes the header and the footer.
+                let size = data[6..10]
+                    .iter()
+                    .fold(0, |size, &byte| (size << 7) | usize::from(byte & 0x7f));
+                let has_footer = data[5] & 0x10 != 0;
+                self.pos += 10 + size + if has_footer { 10 } else { 0 };
+            } else {
+                self.pos += 1;
+            }
+        }
+        None
+    }
+}
+
+/// The data of an MP3 file that is still being downloaded, such as a streaming
+/// `Sound.loadSound`. This is shared between the loader that appends the data as it arrives,
+/// and the `Mp3StreamDecoder` that plays it.
+#[derive(Clone, Default)]
+pub struct Mp3StreamBuffer(Arc<Mutex<Mp3StreamBufferData>>);
+
+#[derive(Default)]
+struct Mp3StreamBufferData {
+    data: Vec<u8>,
+    is_complete: bool,
+}
+
+impl Mp3StreamBuffer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Appends newly downloaded data to the end of the buffer.
+    pub fn append(&self, data: &[u8]) {
+        self.0.lock().unwrap().data.extend_from_slice(data);
+    }
+
+    /// Marks the download as finished.
+    /// Decoders of this buffer end once they have played all of the data.
+    pub fn finish(&self) {
+        self.0.lock().unwrap().is_complete = true;
+    }
+}
+
+/// Decodes an MP3 file that is still being downloaded.
+///
+/// Playback waits, outputting silence, when it catches up to the end of the downloaded data.
+/// The underlying decoder treats running out of data as the end of the sound, so it is only
+/// given whole frames, and is always kept one frame behind the downloaded data.
+pub struct Mp3StreamDecoder {
+    decoder: Mp3Decoder<Mp3StreamReader>,
+    buffer: Mp3StreamBuffer,
+
+    /// The end of the last whole frame in the buffer.
+    frames_end: usize,
+
+    /// The number of whole frames in the buffer.
+    num_frames: u64,
+
+    samples_per_frame: u64,
+    num_samples_decoded: u64,
+}
+
+impl Mp3StreamDecoder {
+    /// Creates a decoder for `buffer`.
+    /// Returns `None` if the first frame of the MP3 has not been downloaded yet.
+    pub fn new(buffer: Mp3StreamBuffer) -> Option<Self> {
+        let header = {
+            let stream = buffer.0.lock().unwrap();
+            mp3_frames(&stream.data).next()?.1
+        };
+        let reader = Mp3StreamReader {
+            buffer: buffer.clone(),
+            pos: 0,
+            limit: 0,
+        };
+        Some(Self {
+            decoder: Mp3Decoder::new(
+                header.num_channels.into(),
+                header.sample_rate.into(),
+                reader,
+            ),
+            buffer,
+            frames_end: 0,
+            num_frames: 0,
+            samples_per_frame: header.num_samples.into(),
+            num_samples_decoded: 0,
+        })
+    }
+
+    /// Finds any frames that have been downloaded since the last call, and allows the decoder
+    /// to read them. Returns whether the download is complete.
+    fn update_frames(&mut self) -> bool {
+        let stream = self.buffer.0.lock().unwrap();
+        let start = self.frames_end;
+        for (offset, header) in mp3_frames(&stream.data[start..]) {
+            self.frames_end = start + offset + header.frame_len;
+            self.num_frames += 1;
+        }
+        self.decoder.reader_mut().limit = if stream.is_complete {
+            stream.data.len()
+        } else {
+            self.frames_end
+        };
+        stream.is_complete
+    }
+}
+
+impl Iterator for Mp3StreamDecoder {
+    type Item = [i16; 2];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.num_samples_decoded % self.samples_per_frame == 0 {
+            // The decoder is about to decode a new frame.
+            // Wait for it and the following frame to download.
+            let is_complete = self.update_frames();
+            let num_frames_decoded = self.num_samples_decoded / self.samples_per_frame;
+            if !is_complete && self.num_frames <= num_frames_decoded + 1 {
+                return Some([0, 0]);
+            }
+        }
+
+        let sample = self.decoder.next()?;
+        self.num_samples_decoded += 1;
+        Some(sample)
+    }
+}
+
+impl Decoder for Mp3StreamDecoder {
+    #[inline]
+    fn num_channels(&self) -> u8 {
+        self.decoder.num_channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u16 {
+        self.decoder.sample_rate()
+    }
+}
+
+/// Reads the downloaded data of an `Mp3StreamBuffer`, up to the limit set by its
+/// `Mp3StreamDecoder`.
+struct Mp3StreamReader {
+    buffer: Mp3StreamBuffer,
+    pos: usize,
+    limit: usize,
+}
+
+impl Read for Mp3StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let stream = self.buffer.0.lock().unwrap();
+        let len = (self.limit - self.pos).min(buf.len());
+        buf[..len].copy_from_slice(&stream.data[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an MPEG-1 Layer III frame header for a 128 kbps, 44.1 kHz stereo frame.
+    fn frame(padding: bool) -> Vec<u8> {
+        let mut frame = vec![0xff, 0xfb, if padding { 0x92 } else { 0x90 }, 0x00];
+        frame.resize(if padding { 418 } else { 417 }, 0);
+        frame
+    }
+
+    #[test]
+    fn parse_frame_header() {
+        assert_eq!(
+            Mp3FrameHeader::parse(&frame(false)),
+            Some(Mp3FrameHeader {
+                sample_rate: 44100,
+                num_channels: 2,
+                frame_len: 417,
+                num_samples: 1152,
+            })
+        );
+
+        // MPEG-2, 64 kbps, 22.05 kHz, mono.
+        assert_eq!(
+            Mp3FrameHeader::parse(&[0xff, 0xf3, 0x80, 0xc0]),
+            Some(Mp3FrameHeader {
+                sample_rate: 22050,
+                num_channels: 1,
+                frame_len: 208,
+                num_samples: 576,
+            })
+        );
+
+        // Layer II and invalid bit rates aren't Layer III frames.
+        assert_eq!(Mp3FrameHeader::parse(&[0xff, 0xfd, 0x90, 0x00]), None);
+        assert_eq!(Mp3FrameHeader::parse(&[0xff, 0xfb, 0xf0, 0x00]), None);
+    }
+
+    #[test]
+    fn iterate_frames() {
+        let mut data = b"ID3\x04\x00\x00\x00\x00\x00\x04\xff\xfb\x90\x00".to_vec();
+        data.extend(frame(false));
+        data.extend(b"junk");
+        data.extend(frame(true));
+        // A truncated frame at the end is not included.
+        data.extend(&frame(false)[..100]);
+
+        let frames: Vec<_> = mp3_frames(&data)
+            .map(|(offset, header)| (offset, header.frame_len))
+            .collect();
+        assert_eq!(frames, [(14, 417), (435, 418)]);
+    }
+}
@@ -0,0 +1 @@
+#include <speex/speex.h>
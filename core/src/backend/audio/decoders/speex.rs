@@ -0,0 +1,180 @@
+use super::{Decoder, SeekableDecoder};
+use std::io::{Cursor, Read};
+use std::os::raw::{c_char, c_int, c_void};
+
+/// Decoder for Speex audio data in a Flash file, using the system's libspeex.
+/// Flash Player 10 and later can export Speex, and it is also used for microphone audio
+/// recorded to FLV files. Flash always uses wideband Speex, which is mono at 16 kHz.
+///
+/// A Speex packet contains a number of 20ms frames. Each packet is padded to a whole byte,
+/// so the data is read a packet at a time until the decoder reaches the padding.
+pub struct SpeexDecoder<R: Read> {
+    inner: R,
+
+    /// The undecoded Speex data. This is read in full on the first call to `next`.
+    data: Option<Vec<u8>>,
+
+    /// The libspeex decoder state. This is null if libspeex couldn't create a decoder, in
+    /// which case the decoder returns no audio.
+    state: *mut c_void,
+    bits: ffi::SpeexBits,
+
+    /// The decoded samples of the current frame.
+    samples: Vec<i16>,
+    cur_sample: usize,
+}
+
+// The decoder state and bit buffer are owned exclusively by the `SpeexDecoder`.
+unsafe impl<R: Read + Send> Send for SpeexDecoder<R> {}
+
+impl<R: Read> SpeexDecoder<R> {
+    const SAMPLE_RATE: u16 = 16000;
+
+    pub fn new(inner: R) -> Self {
+        unsafe {
+            let state =
+                ffi::speex_decoder_init(ffi::speex_lib_get_mode(ffi::SPEEX_MODEID_WB as c_int));
+            let mut frame_size: c_int = 0;
+            if state.is_null() {
+                log::error!("Unable to create a Speex decoder");
+            } else {
+                ffi::speex_decoder_ctl(
+                    state,
+                    ffi::SPEEX_GET_FRAME_SIZE as c_int,
+                    &mut frame_size as *mut c_int as *mut c_void,
+                );
+            }
+            let mut bits = std::mem::zeroed();
+            ffi::speex_bits_init(&mut bits);
+            Self {
+                inner,
+                data: None,
+                state,
+                bits,
+                samples: vec![0; frame_size as usize],
+                cur_sample: frame_size as usize,
+            }
+        }
+    }
+
+    /// Decodes the next frame of audio.
+    /// Returns `false` when there is no more audio to decode.
+    fn next_frame(&mut self) -> bool {
+        if self.state.is_null() {
+            return false;
+        }
+
+        if self.data.is_none() {
+            let mut data = vec![];
+            if let Err(e) = self.inner.read_to_end(&mut data) {
+                log::warn!("Error reading Speex data: {}", e);
+            }
+            self.read_bits(&data);
+            self.data = Some(data);
+        }
+
+        loop {
+            let result = unsafe {
+                ffi::speex_decode_int(self.state, &mut self.bits, self.samples.as_mut_ptr())
+            };
+            match result {
+                0 => {
+                    self.cur_sample = 0;
+                    return true;
+                }
+                // We've reached the end of a packet; skip its padding and continue with the next.
+                -1 => {
+                    let remaining = unsafe { ffi::speex_bits_remaining(&mut self.bits) };
+                    let padding = remaining % 8;
+                    if remaining - padding <= 0 {
+                        return false;
+                    }
+                    unsafe { ffi::speex_bits_advance(&mut self.bits, padding) };
+                }
+                _ => {
+                    log::warn!("Corrupt Speex data");
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn read_bits(&mut self, data: &[u8]) {
+        unsafe {
+            ffi::speex_bits_read_from(
+                &mut self.bits,
+                data.as_ptr() as *const c_char,
+                data.len() as c_int,
+            );
+        }
+    }
+}
+
+impl<R: Read> Iterator for SpeexDecoder<R> {
+    type Item = [i16; 2];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur_sample >= self.samples.len() && !self.next_frame() {
+            return None;
+        }
+
+        let sample = self.samples[self.cur_sample];
+        self.cur_sample += 1;
+        Some([sample, sample])
+    }
+}
+
+impl<R: Read> Decoder for SpeexDecoder<R> {
+    #[inline]
+    fn num_channels(&self) -> u8 {
+        1
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u16 {
+        Self::SAMPLE_RATE
+    }
+}
+
+impl<R: AsRef<[u8]>> SeekableDecoder for SpeexDecoder<Cursor<R>> {
+    #[inline]
+    fn reset(&mut self) {
+        if !self.state.is_null() {
+            unsafe {
+                ffi::speex_decoder_ctl(
+                    self.state,
+                    ffi::SPEEX_RESET_STATE as c_int,
+                    std::ptr::null_mut(),
+                );
+            }
+        }
+        if let Some(data) = self.data.take() {
+            self.read_bits(&data);
+            self.data = Some(data);
+        }
+        self.cur_sample = self.samples.len();
+    }
+}
+
+impl<R: Read> Drop for SpeexDecoder<R> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::speex_bits_destroy(&mut self.bits);
+            if !self.state.is_null() {
+                ffi::speex_decoder_destroy(self.state);
+            }
+        }
+    }
+}
+
+/// Bindings to libspeex, generated from the system's headers by the build script.
+#[allow(
+    dead_code,
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals
+)]
+mod ffi {
+    include!(concat!(env!("OUT_DIR"), "/speex.rs"));
+}
@@ -0,0 +1,927 @@
+//! A software audio mixer that can be shared between audio backends.
+
+use super::decoders::{self, Decoder, SeekableDecoder};
+use super::{
+    swf, AudioBackend, AudioStreamHandle, SoundHandle, SoundInstanceHandle, SoundTransform,
+};
+use crate::tag_utils::SwfSlice;
+use generational_arena::Arena;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+type Error = Box<dyn std::error::Error>;
+
+/// A source of stereo sample frames at the output sample rate.
+type Signal = Box<dyn Send + Iterator<Item = [i16; 2]>>;
+
+/// A callback that receives every buffer output by an `AudioMixer`, e.g. to record it.
+/// The samples are interleaved for each output channel, and range from -1.0 to 1.0.
+pub type AudioTap = Box<dyn Send + FnMut(&[f32])>;
+
+/// Decodes, resamples and mixes all playing sounds into a buffer of samples.
+///
+/// `AudioMixer` implements `AudioBackend`, so it handles event sounds (including their
+/// in and out points, loops and envelopes), stream sounds and sound transforms. A platform
+/// backend only has to output the buffers from `mix`, usually on an audio thread using
+/// an `AudioMixerProxy`. Without any output, it can also be used as a headless backend.
+pub struct AudioMixer {
+    sounds: Arena<Sound>,
+    sound_instances: Arc<Mutex<Arena<SoundInstance>>>,
+
+    /// Receives the mixed output, wherever it is mixed.
+    tap: Arc<Mutex<Option<AudioTap>>>,
+
+    /// The number of channels in the output buffer.
+    num_output_channels: u8,
+
+    /// The sample rate of the output buffer.
+    output_sample_rate: u32,
+}
+
+/// Contains the data and metadata for a sound in an SWF file.
+/// A `Sound` is defined by the `DefineSound` SWF tags.
+struct Sound {
+    format: swf::SoundFormat,
+    data: Arc<Vec<u8>>,
+
+    /// Number of samples in this audio.
+    /// This does not include the skip_sample_frames.
+    num_sample_frames: u32,
+
+    /// Number of samples to skip encoder delay.
+    skip_sample_frames: u16,
+}
+
+/// An actively playing instance of a sound.
+/// This sound can be either an event sound (`StartSound`) or
+/// a stream sound (`SoundStreamBlock`).
+struct SoundInstance {
+    /// The handle the sound definition inside `sounds`.
+    /// `None` if this is a stream sound.
+    handle: Option<SoundHandle>,
+
+    /// The resampled audio. Call `next()` to yield sample frames.
+    signal: Signal,
+
+    /// How this sound is mixed into the left and right speakers.
+    transform: SoundTransform,
+
+    /// The number of sample frames of this sound that have been mixed, at the output sample rate.
+    num_output_frames: u64,
+
+    /// The position in the sound where playback started, in milliseconds.
+    start_position: u32,
+
+    /// The length of each loop in milliseconds, or `None` if the sound does not loop.
+    loop_duration: Option<u32>,
+
+    /// Flag indicating whether this sound is still playing.
+    /// If this flag is false, the sound will be cleaned up during the
+    /// next call to `mix`.
+    active: bool,
+}
+
+impl AudioMixer {
+    pub fn new(num_output_channels: u8, output_sample_rate: u32) -> Self {
+        Self {
+            sounds: Arena::new(),
+            sound_instances: Arc::new(Mutex::new(Arena::new())),
+            tap: Arc::new(Mutex::new(None)),
+            num_output_channels,
+            output_sample_rate,
+        }
+    }
+
+    /// Returns a handle to the mixer that can be sent to an audio thread to mix the output.
+    pub fn proxy(&self) -> AudioMixerProxy {
+        AudioMixerProxy {
+            sound_instances: Arc::clone(&self.sound_instances),
+            tap: Arc::clone(&self.tap),
+            num_output_channels: self.num_output_channels,
+        }
+    }
+
+    /// The number of channels in the output buffer.
+    pub fn num_output_channels(&self) -> u8 {
+        self.num_output_channels
+    }
+
+    /// The sample rate of the output buffer.
+    pub fn output_sample_rate(&self) -> u32 {
+        self.output_sample_rate
+    }
+
+    /// Sets a tap that receives all of the audio mixed by this mixer or its proxies,
+    /// or removes the tap if `tap` is `None`.
+    pub fn set_tap(&mut self, tap: Option<AudioTap>) {
+        *self.tap.lock().unwrap() = tap;
+    }
+
+    /// Mixes all playing sounds into `output_buffer`.
+    /// The samples are interleaved for each output channel, and range from -1.0 to 1.0.
+    pub fn mix(&self, output_buffer: &mut [f32]) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        Self::mix_audio(
+            &mut sound_instances,
+            self.num_output_channels,
+            output_buffer,
+        );
+        if let Some(tap) = &mut *self.tap.lock().unwrap() {
+            tap(output_buffer);
+        }
+    }
+
+    /// Refills the output buffer by stepping through all active sounds
+    /// and mixing in their output.
+    fn mix_audio(
+        sound_instances: &mut Arena<SoundInstance>,
+        num_output_channels: u8,
+        output_buffer: &mut [f32],
+    ) {
+        let num_output_channels = usize::from(num_output_channels);
+        if num_output_channels == 0 {
+            return;
+        }
+
+        // For each sample, mix the samples from all active sound instances.
+        for buf_frame in output_buffer.chunks_exact_mut(num_output_channels) {
+            let (mut left, mut right) = (0.0, 0.0);
+            for (_, sound) in sound_instances.iter_mut() {
+                if !sound.active {
+                    continue;
+                }
+                if let Some(frame) = sound.signal.next() {
+                    let [l, r] = Self::transform_frame(frame, &sound.transform);
+                    left += l;
+                    right += r;
+                    sound.num_output_frames += 1;
+                } else {
+                    sound.active = false;
+                }
+            }
+
+            let (left, right) = (left.max(-1.0).min(1.0), right.max(-1.0).min(1.0));
+            if num_output_channels == 1 {
+                buf_frame[0] = (left + right) / 2.0;
+            } else {
+                buf_frame[0] = left;
+                buf_frame[1] = right;
+                for sample in &mut buf_frame[2..] {
+                    *sample = 0.0;
+                }
+            }
+        }
+
+        // Remove all dead sounds.
+        sound_instances.retain(|_, sound| sound.active);
+    }
+
+    /// Converts a sample frame to floating point and mixes its channels according to a
+    /// sound transform.
+    fn transform_frame(frame: [i16; 2], transform: &SoundTransform) -> [f32; 2] {
+        let (left, right) = (f32::from(frame[0]) / 32768.0, f32::from(frame[1]) / 32768.0);
+        if *transform == SoundTransform::default() {
+            return [left, right];
+        }
+
+        let [left_to_left, left_to_right, right_to_left, right_to_right] = transform.multipliers();
+        [
+            left * left_to_left + right * right_to_left,
+            left * left_to_right + right * right_to_right,
+        ]
+    }
+
+    /// Resamples a signal from `sample_rate` to the output sample rate.
+    fn make_resampler(&self, sample_rate: u16, signal: Signal) -> Signal {
+        if u32::from(sample_rate) == self.output_sample_rate {
+            signal
+        } else {
+            Box::new(Resampler::new(
+                signal,
+                sample_rate.into(),
+                self.output_sample_rate,
+            ))
+        }
+    }
+
+    /// Creates a signal that decodes and resamples an event sound, applying the given
+    /// sound settings.
+    fn make_signal_from_event_sound(
+        &self,
+        sound: &Sound,
+        settings: &swf::SoundInfo,
+    ) -> Result<Signal, Error> {
+        let data = Cursor::new(VecAsRef(Arc::clone(&sound.data)));
+        if sound.skip_sample_frames == 0
+            && settings.in_sample.is_none()
+            && settings.out_sample.is_none()
+            && settings.num_loops <= 1
+            && settings.envelope.is_none()
+        {
+            // For simple event sounds, just use the same decoder as streams.
+            let decoder = decoders::make_decoder(&sound.format, data)?;
+            let sample_rate = decoder.sample_rate();
+            Ok(self.make_resampler(sample_rate, Box::new(decoder)))
+        } else {
+            // For event sounds with envelopes/other properties, wrap it in `EventSoundSignal`.
+            let decoder = decoders::make_seekable_decoder(&sound.format, data)?;
+            let sample_rate = decoder.sample_rate();
+            let signal = EventSoundSignal::new_with_settings(
+                decoder,
+                settings,
+                sound.num_sample_frames,
+                sound.skip_sample_frames,
+            );
+            Ok(self.make_resampler(sample_rate, Box::new(signal)))
+        }
+    }
+
+    /// Creates a signal that decodes and resamples a "stream" sound.
+    fn make_signal_from_stream(
+        &self,
+        format: &swf::SoundFormat,
+        data_stream: SwfSlice,
+        swf_version: u8,
+    ) -> Result<Signal, Error> {
+        let decoder = decoders::make_stream_decoder(format, data_stream, swf_version)?;
+        let sample_rate = decoder.sample_rate();
+        Ok(self.make_resampler(sample_rate, Box::new(decoder)))
+    }
+
+    fn insert_instance(
+        &mut self,
+        handle: Option<SoundHandle>,
+        signal: Signal,
+        start_position: u32,
+        loop_duration: Option<u32>,
+    ) -> SoundInstanceHandle {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances.insert(SoundInstance {
+            handle,
+            signal,
+            transform: Default::default(),
+            num_output_frames: 0,
+            start_position,
+            loop_duration,
+            active: true,
+        })
+    }
+
+    /// Returns how long a sound instance has played, in milliseconds.
+    fn elapsed_time(&self, instance: &SoundInstance) -> u32 {
+        (instance.num_output_frames * 1000 / u64::from(self.output_sample_rate)) as u32
+    }
+}
+
+impl AudioBackend for AudioMixer {
+    fn register_sound(&mut self, swf_sound: &swf::Sound) -> Result<SoundHandle, Error> {
+        if !decoders::is_supported(swf_sound.format.compression) {
+            return Err(format!(
+                "Unsupported audio compression {:?}",
+                swf_sound.format.compression
+            )
+            .into());
+        }
+
+        // Slice off latency seek for MP3 data.
+        let (skip_sample_frames, data) =
+            if swf_sound.format.compression == swf::AudioCompression::Mp3 {
+                let skip_sample_frames =
+                    u16::from(swf_sound.data[0]) | (u16::from(swf_sound.data[1]) << 8);
+                (skip_sample_frames, &swf_sound.data[2..])
+            } else {
+                (0, &swf_sound.data[..])
+            };
+
+        let sound = Sound {
+            format: swf_sound.format.clone(),
+            data: Arc::new(data.to_vec()),
+            num_sample_frames: swf_sound.num_samples,
+            skip_sample_frames,
+        };
+        Ok(self.sounds.insert(sound))
+    }
+
+    fn start_sound(
+        &mut self,
+        sound_handle: SoundHandle,
+        settings: &swf::SoundInfo,
+    ) -> SoundInstanceHandle {
+        let signal = self
+            .make_signal_from_event_sound(&self.sounds[sound_handle], settings)
+            .unwrap_or_else(|e| {
+                log::warn!("Unable to decode sound: {}", e);
+                Box::new(std::iter::empty())
+            });
+
+        // Positions in sound settings are always given at 44.1 kHz.
+        let to_ms = |sample: u32| (u64::from(sample) * 1000 / 44100) as u32;
+        let start_position = settings.in_sample.map(to_ms).unwrap_or(0);
+        let loop_duration = if settings.num_loops > 1 {
+            let end_position = settings
+                .out_sample
+                .map(to_ms)
+                .or_else(|| self.get_sound_duration(sound_handle))
+                .unwrap_or(0);
+            Some(end_position.saturating_sub(start_position))
+        } else {
+            None
+        };
+        self.insert_instance(Some(sound_handle), signal, start_position, loop_duration)
+    }
+
+    fn start_stream(
+        &mut self,
+        _clip_id: swf::CharacterId,
+        _clip_frame: u16,
+        clip_data: SwfSlice,
+        stream_info: &swf::SoundStreamHead,
+    ) -> AudioStreamHandle {
+        // The audio data for stream sounds is distributed among the frames of a
+        // movie clip. The stream tag reader will parse through the SWF and
+        // feed the decoder audio data on the fly.
+        // TODO: Use actual SWF version here (would only matter for SWF <3...)
+        let signal = self
+            .make_signal_from_stream(&stream_info.stream_format, clip_data, 8)
+            .unwrap_or_else(|e| {
+                log::warn!("Unable to decode stream sound: {}", e);
+                Box::new(std::iter::empty())
+            });
+        self.insert_instance(None, signal, 0, None)
+    }
+
+    fn start_mp3_stream(
+        &mut self,
+        buffer: decoders::Mp3StreamBuffer,
+    ) -> Option<SoundInstanceHandle> {
+        let decoder = decoders::Mp3StreamDecoder::new(buffer)?;
+        let signal = self.make_resampler(decoder.sample_rate(), Box::new(decoder));
+        Some(self.insert_instance(None, signal, 0, None))
+    }
+
+    fn stop_sound(&mut self, sound: SoundInstanceHandle) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances.remove(sound);
+    }
+
+    fn stop_stream(&mut self, stream: AudioStreamHandle) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances.remove(stream);
+    }
+
+    fn stop_all_sounds(&mut self) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances.clear();
+    }
+
+    fn stop_sounds_with_handle(&mut self, handle: SoundHandle) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        let handle = Some(handle);
+        sound_instances.retain(|_, instance| instance.handle != handle);
+    }
+
+    fn is_sound_playing_with_handle(&mut self, handle: SoundHandle) -> bool {
+        let sound_instances = self.sound_instances.lock().unwrap();
+        let handle = Some(handle);
+        sound_instances
+            .iter()
+            .any(|(_, instance)| instance.handle == handle && instance.active)
+    }
+
+    fn is_sound_playing(&mut self, instance: SoundInstanceHandle) -> bool {
+        let sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances
+            .get(instance)
+            .map(|instance| instance.active)
+            .unwrap_or(false)
+    }
+
+    fn get_sound_duration(&self, sound: SoundHandle) -> Option<u32> {
+        if let Some(sound) = self.sounds.get(sound) {
+            // AS duration does not subtract skip_sample_frames.
+            let num_sample_frames = u64::from(sound.num_sample_frames);
+            let ms = num_sample_frames * 1000 / u64::from(sound.format.sample_rate);
+            Some(ms as u32)
+        } else {
+            None
+        }
+    }
+
+    fn get_sound_position(&self, instance: SoundInstanceHandle) -> Option<u32> {
+        let sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances
+            .get(instance)
+            .filter(|instance| instance.active)
+            .map(|instance| {
+                let elapsed_time = self.elapsed_time(instance);
+                match instance.loop_duration {
+                    Some(loop_duration) if loop_duration > 0 => {
+                        instance.start_position + elapsed_time % loop_duration
+                    }
+                    _ => instance.start_position + elapsed_time,
+                }
+            })
+    }
+
+    fn get_stream_position(&self, stream: AudioStreamHandle) -> Option<u32> {
+        let sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances
+            .get(stream)
+            .filter(|instance| instance.active)
+            .map(|instance| self.elapsed_time(instance))
+    }
+
+    fn set_sound_transform(&mut self, instance: SoundInstanceHandle, transform: &SoundTransform) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        if let Some(instance) = sound_instances.get_mut(instance) {
+            instance.transform = *transform;
+        }
+    }
+
+    fn set_stream_transform(&mut self, stream: AudioStreamHandle, transform: &SoundTransform) {
+        // Streams are sound instances in the mixer.
+        self.set_sound_transform(stream, transform);
+    }
+}
+
+/// A handle to an `AudioMixer` that can be sent to another thread to mix the output.
+#[derive(Clone)]
+pub struct AudioMixerProxy {
+    sound_instances: Arc<Mutex<Arena<SoundInstance>>>,
+    tap: Arc<Mutex<Option<AudioTap>>>,
+    num_output_channels: u8,
+}
+
+impl AudioMixerProxy {
+    /// Mixes all playing sounds into `output_buffer`.
+    /// See `AudioMixer::mix`.
+    pub fn mix(&self, output_buffer: &mut [f32]) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        AudioMixer::mix_audio(
+            &mut sound_instances,
+            self.num_output_channels,
+            output_buffer,
+        );
+        if let Some(tap) = &mut *self.tap.lock().unwrap() {
+            tap(output_buffer);
+        }
+    }
+}
+
+/// Implements `AudioBackend` for a type by forwarding to an `AudioMixer` in the given field.
+/// Any additional methods, such as `prime_audio`, can be added after the macro.
+#[macro_export]
+macro_rules! impl_audio_mixer_backend {
+    ($mixer:ident) => {
+        fn register_sound(
+            &mut self,
+            swf_sound: &$crate::backend::audio::swf::Sound,
+        ) -> Result<$crate::backend::audio::SoundHandle, Box<dyn std::error::Error>> {
+            self.$mixer.register_sound(swf_sound)
+        }
+
+        fn start_sound(
+            &mut self,
+            sound_handle: $crate::backend::audio::SoundHandle,
+            settings: &$crate::backend::audio::swf::SoundInfo,
+        ) -> $crate::backend::audio::SoundInstanceHandle {
+            self.$mixer.start_sound(sound_handle, settings)
+        }
+
+        fn start_stream(
+            &mut self,
+            clip_id: $crate::backend::audio::swf::CharacterId,
+            clip_frame: u16,
+            clip_data: $crate::tag_utils::SwfSlice,
+            stream_info: &$crate::backend::audio::swf::SoundStreamHead,
+        ) -> $crate::backend::audio::AudioStreamHandle {
+            self.$mixer
+                .start_stream(clip_id, clip_frame, clip_data, stream_info)
+        }
+
+        fn start_mp3_stream(
+            &mut self,
+            buffer: $crate::backend::audio::decoders::Mp3StreamBuffer,
+        ) -> Option<$crate::backend::audio::SoundInstanceHandle> {
+            self.$mixer.start_mp3_stream(buffer)
+        }
+
+        fn stop_sound(&mut self, sound: $crate::backend::audio::SoundInstanceHandle) {
+            self.$mixer.stop_sound(sound)
+        }
+
+        fn stop_stream(&mut self, stream: $crate::backend::audio::AudioStreamHandle) {
+            self.$mixer.stop_stream(stream)
+        }
+
+        fn stop_all_sounds(&mut self) {
+            self.$mixer.stop_all_sounds()
+        }
+
+        fn stop_sounds_with_handle(&mut self, handle: $crate::backend::audio::SoundHandle) {
+            self.$mixer.stop_sounds_with_handle(handle)
+        }
+
+        fn is_sound_playing_with_handle(
+            &mut self,
+            handle: $crate::backend::audio::SoundHandle,
+        ) -> bool {
+            self.$mixer.is_sound_playing_with_handle(handle)
+        }
+
+        fn is_sound_playing(
+            &mut self,
+            instance: $crate::backend::audio::SoundInstanceHandle,
+        ) -> bool {
+            self.$mixer.is_sound_playing(instance)
+        }
+
+        fn get_sound_duration(&self, sound: $crate::backend::audio::SoundHandle) -> Option<u32> {
+            self.$mixer.get_sound_duration(sound)
+        }
+
+        fn get_sound_position(
+            &self,
+            instance: $crate::backend::audio::SoundInstanceHandle,
+        ) -> Option<u32> {
+            self.$mixer.get_sound_position(instance)
+        }
+
+        fn get_stream_position(
+            &self,
+            stream: $crate::backend::audio::AudioStreamHandle,
+        ) -> Option<u32> {
+            self.$mixer.get_stream_position(stream)
+        }
+
+        fn set_sound_transform(
+            &mut self,
+            instance: $crate::backend::audio::SoundInstanceHandle,
+            transform: &$crate::backend::audio::SoundTransform,
+        ) {
+            self.$mixer.set_sound_transform(instance, transform)
+        }
+
+        fn set_stream_transform(
+            &mut self,
+            stream: $crate::backend::audio::AudioStreamHandle,
+            transform: &$crate::backend::audio::SoundTransform,
+        ) {
+            self.$mixer.set_stream_transform(stream, transform)
+        }
+    };
+}
+
+/// A dummy wrapper struct to implement `AsRef<[u8]>` for `Arc<Vec<u8>`.
+/// Not having this trait causes problems when trying to use `Cursor<Vec<u8>>`.
+struct VecAsRef(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for VecAsRef {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Default for VecAsRef {
+    fn default() -> Self {
+        VecAsRef(Arc::new(vec![]))
+    }
+}
+
+/// Resamples a signal using linear interpolation.
+struct Resampler {
+    signal: Signal,
+
+    /// The number of input frames that pass per output frame.
+    step: f64,
+
+    /// The position between `prev_frame` and `next_frame`, from 0.0 to 1.0.
+    position: f64,
+
+    prev_frame: [i16; 2],
+    next_frame: Option<[i16; 2]>,
+}
+
+impl Resampler {
+    fn new(mut signal: Signal, input_sample_rate: u32, output_sample_rate: u32) -> Self {
+        let prev_frame = signal.next();
+        let next_frame = if prev_frame.is_some() {
+            signal.next()
+        } else {
+            None
+        };
+        Self {
+            signal,
+            step: f64::from(input_sample_rate) / f64::from(output_sample_rate),
+            position: 0.0,
+            prev_frame: prev_frame.unwrap_or([0, 0]),
+            next_frame,
+        }
+    }
+}
+
+impl Iterator for Resampler {
+    type Item = [i16; 2];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position >= 1.0 {
+            self.position -= 1.0;
+            self.prev_frame = self.next_frame?;
+            self.next_frame = self.signal.next();
+        }
+
+        let next_frame = self.next_frame?;
+        let lerp = |a: i16, b: i16| {
+            let (a, b) = (f64::from(a), f64::from(b));
+            (a + (b - a) * self.position) as i16
+        };
+        let frame = [
+            lerp(self.prev_frame[0], next_frame[0]),
+            lerp(self.prev_frame[1], next_frame[1]),
+        ];
+        self.position += self.step;
+        Some(frame)
+    }
+}
+
+/// A signal for event sound instances using sound settings (looping, start/end point, envelope).
+struct EventSoundSignal {
+    decoder: Box<dyn SeekableDecoder + Send>,
+    num_loops: u16,
+    envelope_signal: Option<EnvelopeSignal>,
+
+    /// The number of 44.1 kHz samples that pass per sample frame of the sound.
+    /// Positions in sound settings are always given at 44.1 kHz.
+    sample_divisor: u32,
+
+    start_sample_frame: u32,
+    end_sample_frame: u32,
+    cur_sample_frame: u32,
+    is_exhausted: bool,
+}
+
+impl EventSoundSignal {
+    fn new_with_settings(
+        decoder: Box<dyn SeekableDecoder + Send>,
+        settings: &swf::SoundInfo,
+        num_sample_frames: u32,
+        skip_sample_frames: u16,
+    ) -> Self {
+        let skip_sample_frames = u32::from(skip_sample_frames);
+        let sample_divisor = std::cmp::max(44100 / u32::from(decoder.sample_rate()), 1);
+        let start_sample_frame =
+            settings.in_sample.unwrap_or(0) / sample_divisor + skip_sample_frames;
+        let end_sample_frame = settings
+            .out_sample
+            .map(|n| n / sample_divisor)
+            .unwrap_or(num_sample_frames)
+            + skip_sample_frames;
+
+        let envelope_signal = settings
+            .envelope
+            .as_ref()
+            .map(|envelope| EnvelopeSignal::new(envelope.clone()));
+
+        let mut signal = Self {
+            decoder,
+            num_loops: settings.num_loops,
+            envelope_signal,
+            sample_divisor,
+            start_sample_frame,
+            end_sample_frame,
+            cur_sample_frame: start_sample_frame,
+            is_exhausted: false,
+        };
+        signal.next_loop();
+        signal
+    }
+
+    /// Resets the decoder to the start point of the loop.
+    fn next_loop(&mut self) {
+        if self.num_loops > 0 {
+            self.num_loops -= 1;
+            self.decoder.seek_to_sample_frame(self.start_sample_frame);
+            self.cur_sample_frame = self.start_sample_frame;
+        } else {
+            self.is_exhausted = true;
+        }
+    }
+}
+
+impl Iterator for EventSoundSignal {
+    type Item = [i16; 2];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Loop the sound if necessary, and get the next frame.
+        let mut frame = None;
+        while frame.is_none() && !self.is_exhausted {
+            frame = self.decoder.next();
+            if frame.is_some() {
+                self.cur_sample_frame += 1;
+                if self.cur_sample_frame > self.end_sample_frame {
+                    self.next_loop();
+                }
+            } else {
+                self.next_loop();
+            }
+        }
+
+        let frame = frame?;
+        if let Some(envelope) = &mut self.envelope_signal {
+            let [left_volume, right_volume] = envelope.next(self.sample_divisor);
+            Some([
+                (f32::from(frame[0]) * left_volume) as i16,
+                (f32::from(frame[1]) * right_volume) as i16,
+            ])
+        } else {
+            Some(frame)
+        }
+    }
+}
+
+/// The sound envelope for an event sound.
+/// The sound signal gets multiplied by the envelope for volume/panning effects.
+struct EnvelopeSignal {
+    /// Iterator through the envelope points specified in the SWF file.
+    envelope: std::vec::IntoIter<swf::SoundEnvelopePoint>,
+
+    /// The starting envelope point.
+    prev_point: swf::SoundEnvelopePoint,
+
+    /// The ending envelope point.
+    next_point: swf::SoundEnvelopePoint,
+
+    /// The current position in 44.1 kHz samples.
+    cur_sample: u32,
+}
+
+impl EnvelopeSignal {
+    fn new(envelope: swf::SoundEnvelope) -> Self {
+        let mut envelope = envelope.into_iter();
+        let first_point = envelope.next().unwrap_or_else(|| swf::SoundEnvelopePoint {
+            sample: 0,
+            left_volume: 1.0,
+            right_volume: 1.0,
+        });
+        Self {
+            // The initial volume is the first point's volume.
+            prev_point: swf::SoundEnvelopePoint {
+                sample: 0,
+                left_volume: first_point.left_volume,
+                right_volume: first_point.right_volume,
+            },
+            next_point: first_point,
+            cur_sample: 0,
+            envelope,
+        }
+    }
+
+    /// Returns the left and right volume at the current position, and advances the
+    /// envelope by `num_samples` 44.1 kHz samples.
+    fn next(&mut self, num_samples: u32) -> [f32; 2] {
+        // Calculate interpolated volume.
+        let out = if self.prev_point.sample < self.next_point.sample {
+            let a = (self.cur_sample - self.prev_point.sample) as f32;
+            let b = (self.next_point.sample - self.prev_point.sample) as f32;
+            let lerp = a / b;
+            [
+                self.prev_point.left_volume
+                    + (self.next_point.left_volume - self.prev_point.left_volume) * lerp,
+                self.prev_point.right_volume
+                    + (self.next_point.right_volume - self.prev_point.right_volume) * lerp,
+            ]
+        } else {
+            [self.next_point.left_volume, self.next_point.right_volume]
+        };
+
+        // Update envelope endpoints.
+        self.cur_sample = self.cur_sample.saturating_add(num_samples);
+        while self.cur_sample > self.next_point.sample {
+            self.prev_point = self.next_point.clone();
+            self.next_point = self
+                .envelope
+                .next()
+                .unwrap_or_else(|| swf::SoundEnvelopePoint {
+                    sample: std::u32::MAX,
+                    left_volume: self.prev_point.left_volume,
+                    right_volume: self.prev_point.right_volume,
+                });
+
+            if self.prev_point.sample > self.next_point.sample {
+                self.next_point.sample = self.prev_point.sample;
+                log::error!("Invalid sound envelope; sample indices are out of order");
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Registers a mono 16-bit PCM sound containing `samples`.
+    fn register_pcm(mixer: &mut AudioMixer, sample_rate: u16, samples: &[i16]) -> SoundHandle {
+        let data = samples
+            .iter()
+            .flat_map(|s| s.to_le_bytes().to_vec())
+            .collect();
+        mixer
+            .register_sound(&swf::Sound {
+                id: 1,
+                format: swf::SoundFormat {
+                    compression: swf::AudioCompression::Uncompressed,
+                    sample_rate,
+                    is_16_bit: true,
+                    is_stereo: false,
+                },
+                num_samples: samples.len() as u32,
+                data,
+            })
+            .unwrap()
+    }
+
+    fn sound_info(num_loops: u16) -> swf::SoundInfo {
+        swf::SoundInfo {
+            event: swf::SoundEvent::Event,
+            in_sample: None,
+            out_sample: None,
+            num_loops,
+            envelope: None,
+        }
+    }
+
+    #[test]
+    fn mix_event_sound() {
+        let mut mixer = AudioMixer::new(2, 44100);
+        let sound = register_pcm(&mut mixer, 44100, &[16384, -16384, 8192]);
+        let instance = mixer.start_sound(sound, &sound_info(1));
+
+        let mut output = [1.0; 8];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.5, 0.5, -0.5, -0.5, 0.25, 0.25, 0.0, 0.0]);
+        assert!(!mixer.is_sound_playing(instance));
+    }
+
+    #[test]
+    #[cfg(not(feature = "speex"))]
+    fn unsupported_sound_is_not_registered() {
+        let mut mixer = AudioMixer::new(2, 44100);
+        let result = mixer.register_sound(&swf::Sound {
+            id: 1,
+            format: swf::SoundFormat {
+                compression: swf::AudioCompression::Speex,
+                sample_rate: 16000,
+                is_16_bit: true,
+                is_stereo: false,
+            },
+            num_samples: 320,
+            data: vec![0; 40],
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn mix_looping_sound_with_transform() {
+        let mut mixer = AudioMixer::new(2, 44100);
+        let sound = register_pcm(&mut mixer, 44100, &[16384, 8192]);
+        let instance = mixer.start_sound(sound, &sound_info(2));
+        let mut transform = SoundTransform::default();
+        transform.set_pan(-100);
+        mixer.set_sound_transform(instance, &transform);
+
+        let mut output = [1.0; 10];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.5, 0.0, 0.25, 0.0, 0.5, 0.0, 0.25, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn mix_resampled_sound() {
+        let mut mixer = AudioMixer::new(1, 44100);
+        let sound = register_pcm(&mut mixer, 22050, &[0, 16384, 0]);
+        mixer.start_sound(sound, &sound_info(1));
+
+        let mut output = [1.0; 6];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.0, 0.25, 0.5, 0.25, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn looping_sound_position() {
+        let mut mixer = AudioMixer::new(1, 44100);
+        // A 100 ms sound.
+        let sound = register_pcm(&mut mixer, 44100, &[0; 4410]);
+        let instance = mixer.start_sound(sound, &sound_info(3));
+        assert_eq!(mixer.get_sound_position(instance), Some(0));
+
+        let mut output = vec![0.0; 6615];
+        mixer.mix(&mut output);
+        assert_eq!(mixer.get_sound_position(instance), Some(50));
+
+        let mut output = vec![0.0; 8820];
+        mixer.mix(&mut output);
+        assert_eq!(mixer.get_sound_position(instance), None);
+    }
+}
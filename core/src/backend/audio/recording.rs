@@ -0,0 +1,256 @@
+//! Recording of the audio output by the player, e.g. for automated testing.
+
+use super::mixer::{AudioMixer, AudioTap};
+use super::AudioBackend;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::sync::mpsc;
+use std::thread;
+
+/// Writes audio to a 16-bit PCM WAV file.
+///
+/// The chunk lengths in the header are only filled in by `update_header` and `finish`.
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+
+    /// The number of bytes of sample data written so far.
+    data_len: u32,
+
+    /// The number of bytes of sample data in one second of audio.
+    bytes_per_second: u32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    /// The length of the header written before the sample data.
+    const HEADER_LEN: u32 = 44;
+
+    pub fn new(mut writer: W, num_channels: u8, sample_rate: u32) -> io::Result<Self> {
+        let block_align = u16::from(num_channels) * 2;
+        let bytes_per_second = sample_rate * u32::from(block_align);
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(Self::HEADER_LEN - 8).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; // PCM
+        writer.write_all(&u16::from(num_channels).to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&bytes_per_second.to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&16u16.to_le_bytes())?; // Bits per sample
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            data_len: 0,
+            bytes_per_second,
+        })
+    }
+
+    /// Appends interleaved samples ranging from -1.0 to 1.0, as output by `AudioMixer`.
+    pub fn write_samples(&mut self, samples: &[f32]) -> io::Result<()> {
+        let data: Vec<u8> = samples
+            .iter()
+            .flat_map(|&sample| {
+                let sample = (sample.max(-1.0).min(1.0) * 32767.0) as i16;
+                sample.to_le_bytes().to_vec()
+            })
+            .collect();
+        self.writer.write_all(&data)?;
+        self.data_len += data.len() as u32;
+        Ok(())
+    }
+
+    /// Fills in the chunk lengths in the header for the samples written so far, so that the
+    /// file is valid even if nothing more is written to it.
+    pub fn update_header(&mut self) -> io::Result<()> {
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer
+            .write_all(&(Self::HEADER_LEN - 8 + self.data_len).to_le_bytes())?;
+        self.writer
+            .seek(SeekFrom::Start(u64::from(Self::HEADER_LEN) - 4))?;
+        self.writer.write_all(&self.data_len.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()
+    }
+
+    /// Updates the header and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.update_header()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: 'static + Send + Write + Seek> WavWriter<W> {
+    /// Converts this writer into a tap for `AudioMixer::set_tap`, so that it records
+    /// everything the mixer outputs.
+    ///
+    /// The mixer may run on the audio thread, so the samples are written by a separate thread.
+    /// The header is updated after every second of audio, and when the tap is dropped.
+    pub fn into_tap(mut self) -> AudioTap {
+        let (sender, receiver) = mpsc::channel::<Vec<f32>>();
+        thread::spawn(move || {
+            let mut header_data_len = 0;
+            for samples in receiver {
+                if let Err(e) = self.write_samples(&samples) {
+                    log::error!("Unable to record audio: {}", e);
+                    return;
+                }
+                if self.data_len - header_data_len >= self.bytes_per_second {
+                    header_data_len = self.data_len;
+                    if let Err(e) = self.update_header() {
+                        log::error!("Unable to record audio: {}", e);
+                        return;
+                    }
+                }
+            }
+            if let Err(e) = self.finish() {
+                log::error!("Unable to finish audio recording: {}", e);
+            }
+        });
+        Box::new(move |samples| {
+            // The receiver only goes away if writing failed, which has already been logged.
+            let _ = sender.send(samples.to_vec());
+        })
+    }
+}
+
+/// An audio backend that mixes audio as the movie runs frames, instead of outputting it to
+/// an audio device in real time.
+///
+/// The frames act as a fake clock: each frame mixes one frame's worth of audio. The output
+/// therefore only depends on the frames that were run, which makes recordings of it
+/// deterministic. Set a tap on the mixer to receive the output.
+///
+/// The timeline can't be synced to stream sounds with this backend, because the timeline
+/// drives the audio.
+pub struct FrameClockAudioBackend {
+    mixer: AudioMixer,
+
+    /// The fraction of a sample frame that was not mixed by the previous frame.
+    sample_frame_remainder: f64,
+
+    /// The buffer that the audio for each frame is mixed into.
+    buffer: Vec<f32>,
+}
+
+impl FrameClockAudioBackend {
+    pub fn new(num_output_channels: u8, output_sample_rate: u32) -> Self {
+        Self {
+            mixer: AudioMixer::new(num_output_channels, output_sample_rate),
+            sample_frame_remainder: 0.0,
+            buffer: vec![],
+        }
+    }
+
+    pub fn mixer(&self) -> &AudioMixer {
+        &self.mixer
+    }
+
+    pub fn mixer_mut(&mut self) -> &mut AudioMixer {
+        &mut self.mixer
+    }
+}
+
+impl AudioBackend for FrameClockAudioBackend {
+    crate::impl_audio_mixer_backend!(mixer);
+
+    fn advance_clock(&mut self, frame_time: f64) {
+        let num_sample_frames = self.sample_frame_remainder
+            + frame_time * f64::from(self.mixer.output_sample_rate()) / 1000.0;
+        self.sample_frame_remainder = num_sample_frames.fract();
+
+        let num_samples =
+            num_sample_frames as usize * usize::from(self.mixer.num_output_channels());
+        self.buffer.resize(num_samples, 0.0);
+        self.mixer.mix(&mut self.buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn write_wav() {
+        let mut writer = WavWriter::new(Cursor::new(vec![]), 2, 44100).unwrap();
+        writer.write_samples(&[0.5, -0.5]).unwrap();
+        writer.write_samples(&[1.0, -2.0]).unwrap();
+        let wav = writer.finish().unwrap().into_inner();
+
+        assert_eq!(wav.len(), 44 + 8);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[4..8], &44u32.to_le_bytes());
+        assert_eq!(&wav[22..24], &2u16.to_le_bytes());
+        assert_eq!(&wav[24..28], &44100u32.to_le_bytes());
+        assert_eq!(&wav[40..44], &8u32.to_le_bytes());
+        assert_eq!(
+            &wav[44..],
+            &[0xff, 0x3f, 0x01, 0xc0, 0xff, 0x7f, 0x01, 0x80]
+        );
+    }
+
+    /// A `Cursor` that can be read while a tap is writing to it on another thread.
+    #[derive(Clone, Default)]
+    struct SharedCursor(std::sync::Arc<std::sync::Mutex<Cursor<Vec<u8>>>>);
+
+    impl Write for SharedCursor {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for SharedCursor {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.0.lock().unwrap().seek(pos)
+        }
+    }
+
+    #[test]
+    fn record_with_tap() {
+        let cursor = SharedCursor::default();
+        let writer = WavWriter::new(cursor.clone(), 1, 100).unwrap();
+        let mut tap = writer.into_tap();
+        tap(&[0.5; 60]);
+        tap(&[0.5; 60]);
+        drop(tap);
+
+        // The header is filled in by the writer thread once the tap is dropped.
+        let mut data_len = 0;
+        for _ in 0..100 {
+            let wav = cursor.0.lock().unwrap().get_ref().clone();
+            data_len = u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]);
+            if data_len == 240 {
+                assert_eq!(wav.len(), 44 + 240);
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(data_len, 240);
+    }
+
+    #[test]
+    fn frame_clock() {
+        use std::sync::{Arc, Mutex};
+
+        let mut audio = FrameClockAudioBackend::new(1, 44100);
+        let num_samples = Arc::new(Mutex::new(0));
+        let tap_num_samples = Arc::clone(&num_samples);
+        audio
+            .mixer_mut()
+            .set_tap(Some(Box::new(move |samples: &[f32]| {
+                *tap_num_samples.lock().unwrap() += samples.len();
+            })));
+
+        // A frame at 24 frames per second is 1837.5 samples long.
+        for _ in 0..24 {
+            audio.advance_clock(1000.0 / 24.0);
+        }
+        let num_samples = *num_samples.lock().unwrap();
+        assert!(num_samples == 44099 || num_samples == 44100);
+    }
+}
@@ -3,13 +3,35 @@ use crate::events::KeyCode;
 pub trait InputBackend {
     fn is_key_down(&self, key: KeyCode) -> bool;
 
+    /// Whether a lock key, such as caps lock, is toggled on.
+    fn is_key_toggled(&self, key: KeyCode) -> bool;
+
     fn get_last_key_code(&self) -> KeyCode;
 
+    /// The character typed by the last key that was pressed or released, if any.
+    fn get_last_key_char(&self) -> Option<char>;
+
     fn mouse_visible(&self) -> bool;
 
     fn hide_mouse(&mut self);
 
     fn show_mouse(&mut self);
+
+    /// Changes the shape of the mouse cursor over the player.
+    fn set_mouse_cursor(&mut self, cursor: MouseCursor);
+}
+
+/// The shapes of the mouse cursor that the player shows.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MouseCursor {
+    /// The default arrow.
+    Arrow,
+
+    /// The pointing hand shown over buttons.
+    Hand,
+
+    /// The text cursor shown over selectable text.
+    IBeam,
 }
 
 /// Input backend that does nothing
@@ -26,10 +48,18 @@ impl InputBackend for NullInputBackend {
         false
     }
 
+    fn is_key_toggled(&self, _key: KeyCode) -> bool {
+        false
+    }
+
     fn get_last_key_code(&self) -> KeyCode {
         KeyCode::Unknown
     }
 
+    fn get_last_key_char(&self) -> Option<char> {
+        None
+    }
+
     fn mouse_visible(&self) -> bool {
         true
     }
@@ -37,6 +67,8 @@ impl InputBackend for NullInputBackend {
     fn hide_mouse(&mut self) {}
 
     fn show_mouse(&mut self) {}
+
+    fn set_mouse_cursor(&mut self, _cursor: MouseCursor) {}
 }
 
 impl Default for NullInputBackend {
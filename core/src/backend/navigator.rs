@@ -1,8 +1,12 @@
 //! Browser-related platform functions
 
+use generational_arena::Index;
 use std::collections::HashMap;
 use swf::avm1::types::SendVarsMethod;
 
+/// A handle to a request started by `NavigatorBackend::fetch`.
+pub type RequestHandle = Index;
+
 /// Enumerates all possible navigation methods.
 pub enum NavigationMethod {
     /// Indicates that navigation should generate a GET request.
@@ -23,6 +27,20 @@ impl NavigationMethod {
     }
 }
 
+/// The status of a request started by `NavigatorBackend::fetch`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestStatus {
+    /// The response is still downloading.
+    /// `bytes_total` is the size of the response, if it is known.
+    Loading { bytes_total: Option<u32> },
+
+    /// The whole response has been received.
+    Complete,
+
+    /// The request failed, for example because the file was not found.
+    Failed,
+}
+
 /// A backend interacting with a browser environment.
 pub trait NavigatorBackend {
     /// Cause a browser navigation to a given URL.
@@ -53,6 +71,22 @@ pub trait NavigatorBackend {
         window: Option<String>,
         vars_method: Option<(NavigationMethod, HashMap<String, String>)>,
     );
+
+    /// Starts downloading the file at `url`, such as an MP3 loaded by `Sound.loadSound`.
+    ///
+    /// The download happens in the background; call `poll_request` to receive its data.
+    /// Returns `None` if this backend is unable to fetch files.
+    fn fetch(&mut self, _url: &str) -> Option<RequestHandle> {
+        None
+    }
+
+    /// Appends any data received for `request` since the last call to `data`,
+    /// and returns the status of the request.
+    ///
+    /// Once a request is complete or has failed, its handle is no longer valid.
+    fn poll_request(&mut self, _request: RequestHandle, _data: &mut Vec<u8>) -> RequestStatus {
+        RequestStatus::Failed
+    }
 }
 
 /// A null implementation for platforms that do not live in a web browser.
@@ -2,6 +2,9 @@ pub use crate::{transform::Transform, Color};
 use std::io::Read;
 pub use swf;
 
+pub mod glyph_atlas;
+pub mod overlay;
+
 pub trait RenderBackend {
     fn set_viewport_dimensions(&mut self, width: u32, height: u32);
     fn register_shape(&mut self, shape: &swf::Shape) -> ShapeHandle;
@@ -25,6 +28,19 @@ pub trait RenderBackend {
     fn clear(&mut self, color: Color);
     fn render_bitmap(&mut self, bitmap: BitmapHandle, transform: &Transform);
     fn render_shape(&mut self, shape: ShapeHandle, transform: &Transform);
+
+    /// Renders a glyph shape of a text field with advanced anti-aliasing.
+    ///
+    /// Backends that don't support the text rendering settings render the glyph as a shape.
+    fn render_glyph(
+        &mut self,
+        shape: ShapeHandle,
+        transform: &Transform,
+        _settings: &TextRenderSettings,
+    ) {
+        self.render_shape(shape, transform)
+    }
+
     fn end_frame(&mut self);
     fn draw_pause_overlay(&mut self);
     fn draw_letterbox(&mut self, letterbox: Letterbox);
@@ -33,7 +49,7 @@ pub trait RenderBackend {
     fn pop_mask(&mut self);
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ShapeHandle(pub usize);
 
 #[derive(Copy, Clone, Debug)]
@@ -47,11 +63,26 @@ pub struct BitmapInfo {
     pub height: u16,
 }
 
+/// The settings of a `CSMTextSettings` tag for rendering text with advanced anti-aliasing.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TextRenderSettings {
+    /// The thickness of glyph edges, from -200 to 200.
+    pub thickness: f32,
+
+    /// The sharpness of glyph edges, from -400 to 400.
+    pub sharpness: f32,
+}
+
+/// The margins to mask around the movie when it doesn't fill the viewport, in pixels.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Letterbox {
     None,
-    Letterbox(f32),
-    Pillarbox(f32),
+
+    /// Bars above and below the movie, with the given heights.
+    Letterbox(f32, f32),
+
+    /// Bars left and right of the movie, with the given widths.
+    Pillarbox(f32, f32),
 }
 
 pub struct NullRenderer;
@@ -0,0 +1,644 @@
+//! A cache of rasterized glyphs, shared by the render backends to draw small text quickly.
+//!
+//! Glyphs are rasterized in software into pages of alpha coverage, applying the thickness
+//! and sharpness of `CSMTextSettings`. Backends upload the pages as textures and draw each
+//! glyph as a pixel-aligned quad.
+
+use super::{ShapeHandle, TextRenderSettings};
+use crate::matrix::Matrix;
+use swf::ShapeRecord;
+
+/// The maximum number of pages. When they are all full, the atlas is cleared.
+const MAX_PAGES: usize = 4;
+
+/// The largest glyph bitmap that is cached, in pixels.
+const MAX_GLYPH_SIZE: u32 = 128;
+
+/// The number of horizontal and vertical sub-pixel positions that glyphs are rasterized at.
+const SUBPIXEL_STEPS: f32 = 4.0;
+
+pub struct GlyphAtlas {
+    /// The outlines of the registered glyphs.
+    outlines: fnv::FnvHashMap<ShapeHandle, GlyphOutline>,
+
+    /// The rasterized glyphs. `None` if the glyph can't be cached, e.g. it's empty.
+    glyphs: fnv::FnvHashMap<GlyphKey, Option<AtlasGlyph>>,
+
+    pages: Vec<AtlasPage>,
+}
+
+impl GlyphAtlas {
+    /// The width and height of each page, in pixels.
+    pub const PAGE_SIZE: u32 = 512;
+
+    pub fn new() -> Self {
+        Self {
+            outlines: Default::default(),
+            glyphs: Default::default(),
+            pages: vec![],
+        }
+    }
+
+    /// Registers the outline of a glyph, so that it can be rasterized into the atlas.
+    pub fn register_glyph(&mut self, shape: ShapeHandle, glyph: &swf::Glyph) {
+        self.outlines.insert(
+            shape,
+            GlyphOutline::from_shape_records(&glyph.shape_records),
+        );
+    }
+
+    /// Returns the rasterized glyph to draw the given glyph shape with.
+    ///
+    /// `matrix` transforms the glyph to the viewport in twips. Returns `None` if the glyph
+    /// can't be drawn from the atlas, because it's rotated, skewed, flipped, too large or
+    /// empty; the glyph should be rendered as a shape instead.
+    pub fn get(
+        &mut self,
+        shape: ShapeHandle,
+        matrix: &Matrix,
+        settings: &TextRenderSettings,
+    ) -> Option<AtlasGlyph> {
+        if matrix.b != 0.0 || matrix.c != 0.0 || matrix.a <= 0.0 || matrix.d <= 0.0 {
+            return None;
+        }
+
+        let (x, offset_x) = split_subpixel(matrix.tx / 20.0);
+        let (y, offset_y) = split_subpixel(matrix.ty / 20.0);
+        let key = GlyphKey {
+            shape,
+            scale: (matrix.a.to_bits(), matrix.d.to_bits()),
+            offset: (offset_x, offset_y),
+            thickness: settings.thickness.to_bits(),
+            sharpness: settings.sharpness.to_bits(),
+        };
+
+        let glyph = if let Some(glyph) = self.glyphs.get(&key) {
+            *glyph
+        } else {
+            let glyph = self.rasterize(shape, matrix, (offset_x, offset_y), settings);
+            self.glyphs.insert(key, glyph);
+            glyph
+        };
+
+        glyph.map(|glyph| AtlasGlyph {
+            left: glyph.left + x,
+            top: glyph.top + y,
+            ..glyph
+        })
+    }
+
+    /// Returns the number of pages in the atlas.
+    pub fn num_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Calls `f` with each page that has changed since the last call, so that it can be
+    /// uploaded to a texture.
+    ///
+    /// Pages are removed when the atlas is cleared, so textures of pages past `num_pages`
+    /// should be dropped.
+    pub fn update_pages(&mut self, mut f: impl FnMut(usize, &AtlasPage)) {
+        for (i, page) in self.pages.iter_mut().enumerate() {
+            if page.is_dirty {
+                f(i, page);
+                page.is_dirty = false;
+            }
+        }
+    }
+
+    /// Rasterizes a glyph into the atlas, positioned relative to the pixel containing its
+    /// origin.
+    fn rasterize(
+        &mut self,
+        shape: ShapeHandle,
+        matrix: &Matrix,
+        (offset_x, offset_y): (u8, u8),
+        settings: &TextRenderSettings,
+    ) -> Option<AtlasGlyph> {
+        let outline = self.outlines.get(&shape)?;
+        if outline.edges.is_empty() {
+            return None;
+        }
+
+        let scale = (matrix.a / 20.0, matrix.d / 20.0);
+        let offset = (
+            f32::from(offset_x) / SUBPIXEL_STEPS,
+            f32::from(offset_y) / SUBPIXEL_STEPS,
+        );
+        // Leave a pixel of space around the glyph for the anti-aliased edges.
+        let (x_min, y_min, x_max, y_max) = outline.bounds;
+        let left = (x_min * scale.0 + offset.0).floor() as i32 - 1;
+        let top = (y_min * scale.1 + offset.1).floor() as i32 - 1;
+        let right = (x_max * scale.0 + offset.0).ceil() as i32 + 1;
+        let bottom = (y_max * scale.1 + offset.1).ceil() as i32 + 1;
+        let (width, height) = ((right - left) as u32, (bottom - top) as u32);
+        if width > MAX_GLYPH_SIZE || height > MAX_GLYPH_SIZE {
+            return None;
+        }
+
+        let to_bitmap = |(x, y): (f32, f32)| {
+            (
+                x * scale.0 + offset.0 - left as f32,
+                y * scale.1 + offset.1 - top as f32,
+            )
+        };
+        let mut rasterizer = Rasterizer::new(width as usize, height as usize);
+        for edge in &outline.edges {
+            match *edge {
+                Edge::Line(from, to) => rasterizer.line(to_bitmap(from), to_bitmap(to)),
+                Edge::Curve(from, control, to) => {
+                    rasterizer.curve(to_bitmap(from), to_bitmap(control), to_bitmap(to))
+                }
+            }
+        }
+        let alpha: Vec<u8> = rasterizer
+            .into_coverage()
+            .map(|coverage| (adjust_coverage(coverage, settings) * 255.0).round() as u8)
+            .collect();
+
+        let (page, x, y) = self.allocate(width, height);
+        self.pages[page].write(x, y, width, height, &alpha);
+        Some(AtlasGlyph {
+            page,
+            x,
+            y,
+            width,
+            height,
+            left,
+            top,
+        })
+    }
+
+    /// Finds space for a glyph bitmap, adding a page or clearing the atlas if needed.
+    fn allocate(&mut self, width: u32, height: u32) -> (usize, u32, u32) {
+        for (i, page) in self.pages.iter_mut().enumerate() {
+            if let Some((x, y)) = page.allocate(width, height) {
+                return (i, x, y);
+            }
+        }
+
+        if self.pages.len() >= MAX_PAGES {
+            self.glyphs.clear();
+            self.pages.clear();
+        }
+        let mut page = AtlasPage::new();
+        let (x, y) = page
+            .allocate(width, height)
+            .expect("Glyph is larger than an atlas page");
+        self.pages.push(page);
+        (self.pages.len() - 1, x, y)
+    }
+}
+
+impl Default for GlyphAtlas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A glyph bitmap in the atlas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtlasGlyph {
+    /// The index of the page containing the glyph.
+    pub page: usize,
+
+    /// The position and size of the glyph bitmap in the page, in pixels.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+
+    /// The position in the viewport to draw the top-left corner of the glyph bitmap at,
+    /// in pixels.
+    pub left: i32,
+    pub top: i32,
+}
+
+/// A page of glyph bitmaps in the atlas.
+pub struct AtlasPage {
+    /// The coverage of each pixel.
+    alpha: Vec<u8>,
+
+    /// The rows of glyphs in the page.
+    shelves: Vec<Shelf>,
+
+    /// Whether the page has changed since it was last uploaded.
+    is_dirty: bool,
+}
+
+impl AtlasPage {
+    fn new() -> Self {
+        let size = GlyphAtlas::PAGE_SIZE as usize;
+        Self {
+            alpha: vec![0; size * size],
+            shelves: vec![],
+            is_dirty: true,
+        }
+    }
+
+    /// Returns the coverage of each pixel of the page, row by row.
+    pub fn alpha(&self) -> &[u8] {
+        &self.alpha
+    }
+
+    /// Returns the page as white RGBA pixels with premultiplied alpha.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.alpha.iter().flat_map(|&a| vec![a, a, a, a]).collect()
+    }
+
+    /// Finds space for a bitmap, placing it in the first row of glyphs that fits it.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        for shelf in &mut self.shelves {
+            if height <= shelf.height && shelf.width + width <= GlyphAtlas::PAGE_SIZE {
+                let x = shelf.width;
+                shelf.width += width;
+                return Some((x, shelf.y));
+            }
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height);
+        if y + height > GlyphAtlas::PAGE_SIZE || width > GlyphAtlas::PAGE_SIZE {
+            return None;
+        }
+        self.shelves.push(Shelf { y, height, width });
+        Some((0, y))
+    }
+
+    fn write(&mut self, x: u32, y: u32, width: u32, height: u32, alpha: &[u8]) {
+        let (x, width) = (x as usize, width as usize);
+        for row in 0..height as usize {
+            let start = (y as usize + row) * GlyphAtlas::PAGE_SIZE as usize + x;
+            self.alpha[start..start + width]
+                .copy_from_slice(&alpha[row * width..(row + 1) * width]);
+        }
+        self.is_dirty = true;
+    }
+}
+
+/// A row of glyphs in an atlas page.
+struct Shelf {
+    y: u32,
+    height: u32,
+
+    /// The width used by glyphs so far.
+    width: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    shape: ShapeHandle,
+
+    /// The bits of the horizontal and vertical scale of the glyph.
+    scale: (u32, u32),
+
+    /// The sub-pixel position of the glyph origin.
+    offset: (u8, u8),
+
+    /// The bits of the thickness and sharpness settings.
+    thickness: u32,
+    sharpness: u32,
+}
+
+/// The outline of a glyph, in glyph units.
+struct GlyphOutline {
+    edges: Vec<Edge>,
+
+    /// The minimum and maximum x and y coordinates of the outline.
+    bounds: (f32, f32, f32, f32),
+}
+
+enum Edge {
+    Line((f32, f32), (f32, f32)),
+    Curve((f32, f32), (f32, f32), (f32, f32)),
+}
+
+impl GlyphOutline {
+    fn from_shape_records(records: &[ShapeRecord]) -> Self {
+        let bounds = crate::shape_utils::calculate_shape_bounds(records);
+        let mut edges = vec![];
+        let mut position = (0.0, 0.0);
+        for record in records {
+            match record {
+                ShapeRecord::StyleChange(style_change) => {
+                    if let Some((x, y)) = style_change.move_to {
+                        position = (x.get() as f32, y.get() as f32);
+                    }
+                }
+                ShapeRecord::StraightEdge { delta_x, delta_y } => {
+                    let to = (
+                        position.0 + delta_x.get() as f32,
+                        position.1 + delta_y.get() as f32,
+                    );
+                    edges.push(Edge::Line(position, to));
+                    position = to;
+                }
+                ShapeRecord::CurvedEdge {
+                    control_delta_x,
+                    control_delta_y,
+                    anchor_delta_x,
+                    anchor_delta_y,
+                } => {
+                    let control = (
+                        position.0 + control_delta_x.get() as f32,
+                        position.1 + control_delta_y.get() as f32,
+                    );
+                    let to = (
+                        control.0 + anchor_delta_x.get() as f32,
+                        control.1 + anchor_delta_y.get() as f32,
+                    );
+                    edges.push(Edge::Curve(position, control, to));
+                    position = to;
+                }
+            }
+        }
+
+        Self {
+            edges,
+            bounds: (
+                bounds.x_min.get() as f32,
+                bounds.y_min.get() as f32,
+                bounds.x_max.get() as f32,
+                bounds.y_max.get() as f32,
+            ),
+        }
+    }
+}
+
+/// Splits a position in pixels into a whole pixel and a sub-pixel step.
+fn split_subpixel(position: f32) -> (i32, u8) {
+    let steps = (position * SUBPIXEL_STEPS).round();
+    let pixel = (steps / SUBPIXEL_STEPS).floor();
+    (pixel as i32, (steps - pixel * SUBPIXEL_STEPS) as u8)
+}
+
+/// Applies the thickness and sharpness of `CSMTextSettings` to the coverage of a pixel.
+///
+/// Thickness ranges from -200 to 200, and thickens the glyph by raising its coverage to a
+/// power. Sharpness ranges from -400 to 400, and blends the coverage towards a steeper or
+/// shallower curve. Both leave fully covered and uncovered pixels unchanged.
+fn adjust_coverage(coverage: f32, settings: &TextRenderSettings) -> f32 {
+    let mut coverage = coverage.min(1.0);
+    let thickness = settings.thickness.max(-200.0).min(200.0);
+    if thickness != 0.0 {
+        coverage = coverage.powf(2f32.powf(-thickness / 200.0));
+    }
+
+    let sharpness = settings.sharpness.max(-400.0).min(400.0) / 400.0;
+    if sharpness > 0.0 {
+        let sharp = coverage * coverage * (3.0 - 2.0 * coverage);
+        coverage += (sharp - coverage) * sharpness;
+    } else if sharpness < 0.0 {
+        let soft = 0.5 - ((1.0 - 2.0 * coverage).asin() / 3.0).sin();
+        coverage += (soft - coverage) * -sharpness;
+    }
+    coverage
+}
+
+/// Rasterizes outlines into anti-aliased coverage, by accumulating the signed area that
+/// each edge covers in each pixel.
+struct Rasterizer {
+    width: usize,
+    height: usize,
+    accumulation: Vec<f32>,
+}
+
+impl Rasterizer {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            accumulation: vec![0.0; width * height + 4],
+        }
+    }
+
+    fn line(&mut self, from: (f32, f32), to: (f32, f32)) {
+        if (from.1 - to.1).abs() <= std::f32::EPSILON {
+            return;
+        }
+        let (direction, from, to) = if from.1 < to.1 {
+            (1.0, from, to)
+        } else {
+            (-1.0, to, from)
+        };
+        let dxdy = (to.0 - from.0) / (to.1 - from.1);
+        let mut x = from.0;
+        if from.1 < 0.0 {
+            x -= from.1 * dxdy;
+        }
+
+        let y_start = from.1.max(0.0) as usize;
+        let y_end = self.height.min(to.1.ceil() as usize);
+        for y in y_start..y_end {
+            let row = y * self.width;
+            let dy = ((y + 1) as f32).min(to.1) - (y as f32).max(from.1);
+            let x_next = x + dxdy * dy;
+            let d = dy * direction;
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let x0_floor = x0.floor();
+            let x0_index = x0_floor.max(0.0) as usize;
+            let x1_ceil = x1.ceil();
+            let x1_index = x1_ceil.max(0.0) as usize;
+            if x1_index <= x0_index + 1 {
+                // The edge is within a single pixel of this row.
+                let x_mid = 0.5 * (x + x_next) - x0_floor;
+                self.accumulation[row + x0_index] += d - d * x_mid;
+                self.accumulation[row + x0_index + 1] += d * x_mid;
+            } else {
+                let s = (x1 - x0).recip();
+                let x0_fract = x0 - x0_floor;
+                let a0 = 0.5 * s * (1.0 - x0_fract) * (1.0 - x0_fract);
+                let x1_fract = x1 - x1_ceil + 1.0;
+                let a_end = 0.5 * s * x1_fract * x1_fract;
+                self.accumulation[row + x0_index] += d * a0;
+                if x1_index == x0_index + 2 {
+                    self.accumulation[row + x0_index + 1] += d * (1.0 - a0 - a_end);
+                } else {
+                    let a1 = s * (1.5 - x0_fract);
+                    self.accumulation[row + x0_index + 1] += d * (a1 - a0);
+                    for x in x0_index + 2..x1_index - 1 {
+                        self.accumulation[row + x] += d * s;
+                    }
+                    let a2 = a1 + (x1_index - x0_index - 3) as f32 * s;
+                    self.accumulation[row + x1_index - 1] += d * (1.0 - a2 - a_end);
+                }
+                self.accumulation[row + x1_index] += d * a_end;
+            }
+            x = x_next;
+        }
+    }
+
+    /// Draws a quadratic curve by splitting it into lines.
+    fn curve(&mut self, from: (f32, f32), control: (f32, f32), to: (f32, f32)) {
+        let deviation = (
+            from.0 - 2.0 * control.0 + to.0,
+            from.1 - 2.0 * control.1 + to.1,
+        );
+        let deviation_squared = deviation.0 * deviation.0 + deviation.1 * deviation.1;
+        if deviation_squared < 0.333 {
+            self.line(from, to);
+            return;
+        }
+
+        let num_lines = 1 + (3.0 * deviation_squared).sqrt().sqrt().floor() as usize;
+        let lerp =
+            |t: f32, a: (f32, f32), b: (f32, f32)| (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+        let mut point = from;
+        for i in 1..num_lines {
+            let t = i as f32 / num_lines as f32;
+            let next = lerp(t, lerp(t, from, control), lerp(t, control, to));
+            self.line(point, next);
+            point = next;
+        }
+        self.line(point, to);
+    }
+
+    /// Returns the coverage of each pixel, from 0.0 to 1.0.
+    fn into_coverage(self) -> impl Iterator<Item = f32> {
+        let num_pixels = self.width * self.height;
+        self.accumulation
+            .into_iter()
+            .take(num_pixels)
+            .scan(0.0, |sum, area| {
+                *sum += area;
+                Some(f32::abs(*sum).min(1.0))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use swf::Twips;
+
+    /// A glyph of a 200 unit square.
+    fn square_glyph() -> swf::Glyph {
+        let edge = |x, y| ShapeRecord::StraightEdge {
+            delta_x: Twips::new(x),
+            delta_y: Twips::new(y),
+        };
+        swf::Glyph {
+            shape_records: vec![
+                ShapeRecord::StyleChange(swf::StyleChangeData {
+                    move_to: Some((Twips::new(0), Twips::new(-200))),
+                    fill_style_0: None,
+                    fill_style_1: Some(1),
+                    line_style: None,
+                    new_styles: None,
+                }),
+                edge(200, 0),
+                edge(0, 200),
+                edge(-200, 0),
+                edge(0, -200),
+            ],
+            code: 0,
+            advance: Some(200),
+            bounds: None,
+        }
+    }
+
+    fn alpha_at(atlas: &GlyphAtlas, glyph: &AtlasGlyph, x: u32, y: u32) -> u8 {
+        let page = &atlas.pages[glyph.page];
+        page.alpha[((glyph.y + y) * GlyphAtlas::PAGE_SIZE + glyph.x + x) as usize]
+    }
+
+    #[test]
+    fn rasterize_glyph() {
+        let mut atlas = GlyphAtlas::new();
+        atlas.register_glyph(ShapeHandle(1), &square_glyph());
+
+        // Draw the square 10 pixels wide, with its origin at (5.5, 20).
+        let matrix = Matrix {
+            a: 1.0,
+            d: 1.0,
+            tx: 5.5 * 20.0,
+            ty: 20.0 * 20.0,
+            ..Default::default()
+        };
+        let settings = TextRenderSettings::default();
+        let glyph = atlas.get(ShapeHandle(1), &matrix, &settings).unwrap();
+        assert_eq!((glyph.width, glyph.height), (13, 12));
+        assert_eq!((glyph.left, glyph.top), (4, 9));
+
+        // The square covers half of the pixels at its left and right edges.
+        assert_eq!(alpha_at(&atlas, &glyph, 0, 5), 0);
+        assert_eq!(alpha_at(&atlas, &glyph, 1, 5), 128);
+        assert_eq!(alpha_at(&atlas, &glyph, 2, 5), 255);
+        assert_eq!(alpha_at(&atlas, &glyph, 11, 5), 128);
+        assert_eq!(alpha_at(&atlas, &glyph, 12, 5), 0);
+        assert_eq!(alpha_at(&atlas, &glyph, 5, 0), 0);
+        assert_eq!(alpha_at(&atlas, &glyph, 5, 1), 255);
+        assert_eq!(alpha_at(&atlas, &glyph, 5, 10), 255);
+        assert_eq!(alpha_at(&atlas, &glyph, 5, 11), 0);
+
+        // The glyph is reused at other positions with the same sub-pixel offset.
+        let moved = Matrix {
+            tx: matrix.tx + 100.0,
+            ..matrix
+        };
+        let moved_glyph = atlas.get(ShapeHandle(1), &moved, &settings).unwrap();
+        assert_eq!((moved_glyph.x, moved_glyph.y), (glyph.x, glyph.y));
+        assert_eq!(moved_glyph.left, glyph.left + 5);
+        assert_eq!(atlas.glyphs.len(), 1);
+
+        // Rotated, large and unknown glyphs can't be drawn from the atlas.
+        let rotated = Matrix { b: 1.0, ..matrix };
+        assert_eq!(atlas.get(ShapeHandle(1), &rotated, &settings), None);
+        let large = Matrix { a: 20.0, ..matrix };
+        assert_eq!(atlas.get(ShapeHandle(1), &large, &settings), None);
+        assert_eq!(atlas.get(ShapeHandle(2), &matrix, &settings), None);
+    }
+
+    #[test]
+    fn full_atlas_is_cleared() {
+        let mut atlas = GlyphAtlas::new();
+        atlas.register_glyph(ShapeHandle(1), &square_glyph());
+        let settings = TextRenderSettings::default();
+
+        // Each 100 pixel square takes up a 102x102 area, so 25 fit in each page.
+        // Shrink the squares very slightly to rasterize a new glyph each time.
+        let matrix = Matrix {
+            a: 10.0,
+            d: 10.0,
+            ..Default::default()
+        };
+        let mut num_updated = 0;
+        for i in 0..25 * MAX_PAGES {
+            let matrix = Matrix {
+                a: matrix.a - i as f32 * 0.0001,
+                ..matrix
+            };
+            assert!(atlas.get(ShapeHandle(1), &matrix, &settings).is_some());
+            atlas.update_pages(|_, _| num_updated += 1);
+        }
+        assert_eq!(atlas.num_pages(), MAX_PAGES);
+        assert_eq!(num_updated, 25 * MAX_PAGES);
+
+        let matrix = Matrix { a: 11.0, ..matrix };
+        let glyph = atlas.get(ShapeHandle(1), &matrix, &settings).unwrap();
+        assert_eq!((glyph.page, glyph.x, glyph.y), (0, 0, 0));
+        assert_eq!(atlas.num_pages(), 1);
+        assert_eq!(atlas.glyphs.len(), 1);
+    }
+
+    #[test]
+    fn coverage_settings() {
+        let settings = |thickness, sharpness| TextRenderSettings {
+            thickness,
+            sharpness,
+        };
+        for &(thickness, sharpness) in &[(200.0, 0.0), (-200.0, 0.0), (0.0, 400.0), (0.0, -400.0)] {
+            let settings = settings(thickness, sharpness);
+            assert_abs_diff_eq!(adjust_coverage(0.0, &settings), 0.0);
+            assert_abs_diff_eq!(adjust_coverage(1.0, &settings), 1.0, epsilon = 0.0001);
+        }
+        assert!(adjust_coverage(0.25, &settings(200.0, 0.0)) > 0.25);
+        assert!(adjust_coverage(0.25, &settings(-200.0, 0.0)) < 0.25);
+        assert!(adjust_coverage(0.25, &settings(0.0, 400.0)) < 0.25);
+        assert!(adjust_coverage(0.25, &settings(0.0, -400.0)) > 0.25);
+        assert_abs_diff_eq!(adjust_coverage(0.25, &settings(0.0, 0.0)), 0.25);
+    }
+}
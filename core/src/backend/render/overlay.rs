@@ -0,0 +1,87 @@
+//! Helpers for the overlays that the player draws over the movie, such as the context menu
+//! and the focus rectangle.
+//!
+//! Overlays are built from rectangles, which are drawn by scaling and tinting a single shape.
+
+use super::{RenderBackend, ShapeHandle};
+use crate::prelude::*;
+use crate::transform::Transform;
+
+/// Fills a rectangle given in pixels as `(x, y, width, height)` with a `0xRRGGBB` color,
+/// with `rect` being a one pixel square filled with white.
+pub fn draw_rect(
+    renderer: &mut dyn RenderBackend,
+    rect: ShapeHandle,
+    (x, y, width, height): (f32, f32, f32, f32),
+    color: u32,
+) {
+    let matrix = Matrix {
+        a: width,
+        d: height,
+        tx: x * 20.0,
+        ty: y * 20.0,
+        ..Default::default()
+    };
+    renderer.render_shape(
+        rect,
+        &Transform {
+            matrix,
+            color_transform: color_transform(color),
+        },
+    );
+}
+
+/// Returns a color transform that tints white to the given `0xRRGGBB` color.
+pub fn color_transform(color: u32) -> ColorTransform {
+    ColorTransform {
+        r_mult: ((color >> 16) & 0xff) as f32 / 255.0,
+        g_mult: ((color >> 8) & 0xff) as f32 / 255.0,
+        b_mult: (color & 0xff) as f32 / 255.0,
+        ..Default::default()
+    }
+}
+
+/// Returns a shape filling a one pixel square with white, which `draw_rect` is drawn with.
+pub fn rect_shape() -> swf::Shape {
+    let edge = |delta_x, delta_y| swf::ShapeRecord::StraightEdge {
+        delta_x: Twips::new(delta_x),
+        delta_y: Twips::new(delta_y),
+    };
+    let bounds = swf::Rectangle {
+        x_min: Twips::new(0),
+        x_max: Twips::new(20),
+        y_min: Twips::new(0),
+        y_max: Twips::new(20),
+    };
+    swf::Shape {
+        version: 1,
+        id: 0,
+        shape_bounds: bounds.clone(),
+        edge_bounds: bounds,
+        has_fill_winding_rule: false,
+        has_non_scaling_strokes: false,
+        has_scaling_strokes: false,
+        styles: swf::ShapeStyles {
+            fill_styles: vec![swf::FillStyle::Color(Color {
+                r: 255,
+                g: 255,
+                b: 255,
+                a: 255,
+            })],
+            line_styles: vec![],
+        },
+        shape: vec![
+            swf::ShapeRecord::StyleChange(swf::StyleChangeData {
+                move_to: Some((Twips::new(0), Twips::new(0))),
+                fill_style_0: None,
+                fill_style_1: Some(1),
+                line_style: None,
+                new_styles: None,
+            }),
+            edge(20, 0),
+            edge(0, 20),
+            edge(-20, 0),
+            edge(0, -20),
+        ],
+    }
+}
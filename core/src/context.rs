@@ -1,12 +1,17 @@
 //! Contexts and helper types passed between functions.
+use crate::audio_manager::AudioManager;
 use crate::avm1;
 
 use crate::avm1::listeners::SystemListener;
 use crate::avm1::Value;
 use crate::backend::input::InputBackend;
 use crate::backend::{audio::AudioBackend, navigator::NavigatorBackend, render::RenderBackend};
+use crate::context_menu::ContextMenuState;
+use crate::focus::FocusTracker;
 use crate::library::Library;
 use crate::prelude::*;
+use crate::scaling_grid::ScalingGridTransform;
+use crate::stage::Stage;
 use crate::tag_utils::SwfSlice;
 use crate::transform::TransformStack;
 use core::fmt;
@@ -53,6 +58,9 @@ pub struct UpdateContext<'a, 'gc, 'gc_context> {
     /// The audio backend, used by display objects and AVM to play audio.
     pub audio: &'a mut dyn AudioBackend,
 
+    /// The sounds that are currently playing, used to apply sound transforms.
+    pub audio_manager: &'a mut AudioManager<'gc>,
+
     /// The navigator backend, used by the AVM to make HTTP requests and visit webpages.
     pub navigator: &'a mut dyn NavigatorBackend,
 
@@ -76,14 +84,23 @@ pub struct UpdateContext<'a, 'gc, 'gc_context> {
     /// The display object that the mouse is currently hovering over.
     pub mouse_hovered_object: Option<DisplayObject<'gc>>,
 
+    /// The button that the mouse pressed, while the mouse button is held down.
+    pub mouse_pressed_object: Option<DisplayObject<'gc>>,
+
     /// The location of the mouse when it was last over the player.
     pub mouse_position: &'a (Twips, Twips),
 
     /// The object being dragged via a `startDrag` action.
     pub drag_object: &'a mut Option<crate::player::DragObject<'gc>>,
 
-    /// The dimensions of the stage.
-    pub stage_size: (Twips, Twips),
+    /// The stage, which places the movie in the viewport.
+    pub stage: &'a mut Stage,
+
+    /// The context menu that is currently shown, if any.
+    pub current_context_menu: &'a mut Option<ContextMenuState<'gc>>,
+
+    /// The object with keyboard focus, which is moved with Tab.
+    pub focus_tracker: FocusTracker<'gc>,
 }
 
 /// A queued ActionScript call.
@@ -172,6 +189,9 @@ pub struct RenderContext<'a, 'gc> {
 
     /// The stack of clip depths, used in masking.
     pub clip_depth_stack: Vec<Depth>,
+
+    /// The scaling grid of the clip whose children are being rendered, if it has one.
+    pub scaling_grid: Option<ScalingGridTransform>,
 }
 
 /// The type of action being run.
@@ -186,6 +206,13 @@ pub enum ActionType<'gc> {
     /// An event handler method, e.g. `onEnterFrame`.
     Method { name: &'static str },
 
+    /// An event handler method on an object, e.g. `Sound.onLoad`.
+    ObjectMethod {
+        object: avm1::Object<'gc>,
+        name: &'static str,
+        args: Vec<Value<'gc>>,
+    },
+
     /// A system listener method,
     NotifyListeners {
         listener: SystemListener,
@@ -209,6 +236,12 @@ impl fmt::Debug for ActionType<'_> {
                 .debug_struct("ActionType::Method")
                 .field("name", name)
                 .finish(),
+            ActionType::ObjectMethod { object, name, args } => f
+                .debug_struct("ActionType::ObjectMethod")
+                .field("object", object)
+                .field("name", name)
+                .field("args", args)
+                .finish(),
             ActionType::NotifyListeners {
                 listener,
                 method,
@@ -226,8 +259,13 @@ impl fmt::Debug for ActionType<'_> {
 unsafe impl<'gc> Collect for ActionType<'gc> {
     #[inline]
     fn trace(&self, cc: gc_arena::CollectionContext) {
-        if let ActionType::NotifyListeners { args, .. } = self {
-            args.trace(cc);
+        match self {
+            ActionType::ObjectMethod { object, args, .. } => {
+                object.trace(cc);
+                args.trace(cc);
+            }
+            ActionType::NotifyListeners { args, .. } => args.trace(cc),
+            _ => (),
         }
     }
 }
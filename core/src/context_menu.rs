@@ -0,0 +1,424 @@
+//! The context menu shown when the user right-clicks the player.
+//!
+//! The player builds the items of the menu, which are shown by the frontend. On desktop,
+//! the menu is drawn by the renderer as a `ContextMenuPopup`.
+
+use crate::avm1::{Avm1, Object, TObject, Value};
+use crate::backend::render::overlay::{color_transform, draw_rect};
+use crate::backend::render::{RenderBackend, ShapeHandle};
+use crate::context::UpdateContext;
+use crate::font::{Glyph, TextFont};
+use crate::prelude::*;
+use crate::stage::StageQuality;
+use crate::transform::Transform;
+use gc_arena::Collect;
+
+/// The most custom items that a menu can contain, as in Flash.
+pub const MAX_CUSTOM_ITEMS: usize = 15;
+
+/// An item in the context menu.
+#[derive(Clone, Debug, PartialEq, Collect)]
+#[collect(require_static)]
+pub struct ContextMenuItem {
+    pub caption: String,
+
+    /// Disabled items are shown greyed out, and can't be selected.
+    pub enabled: bool,
+
+    /// Whether a separator line is drawn above this item.
+    pub separator_before: bool,
+
+    /// Whether a check mark is shown next to this item, such as for the current quality.
+    pub checked: bool,
+}
+
+impl ContextMenuItem {
+    pub fn new(caption: &str) -> Self {
+        Self {
+            caption: caption.to_string(),
+            enabled: true,
+            separator_before: false,
+            checked: false,
+        }
+    }
+}
+
+/// What happens when a context menu item is selected.
+#[derive(Clone, Collect)]
+#[collect(no_drop)]
+pub enum ContextMenuCallback<'gc> {
+    ZoomIn,
+    ZoomOut,
+    ShowAll,
+    Quality(StageQuality),
+    Play,
+    Loop,
+    Rewind,
+    Forward,
+    Back,
+
+    /// A custom `ContextMenuItem`, whose `onSelect` handler is called with the object that
+    /// was right-clicked.
+    Avm1 {
+        item: Object<'gc>,
+        target: DisplayObject<'gc>,
+    },
+}
+
+/// The items of the current context menu, and the callback of each item.
+#[derive(Collect, Default)]
+#[collect(no_drop)]
+pub struct ContextMenuState<'gc> {
+    items: Vec<ContextMenuItem>,
+    callbacks: Vec<ContextMenuCallback<'gc>>,
+}
+
+impl<'gc> ContextMenuState<'gc> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, item: ContextMenuItem, callback: ContextMenuCallback<'gc>) {
+        self.items.push(item);
+        self.callbacks.push(callback);
+    }
+
+    /// Adds a group of items, which is separated from the items above it.
+    pub fn push_group(&mut self, group: Vec<(ContextMenuItem, ContextMenuCallback<'gc>)>) {
+        for (i, (mut item, callback)) in group.into_iter().enumerate() {
+            item.separator_before = i == 0;
+            self.push(item, callback);
+        }
+    }
+
+    pub fn items(&self) -> &[ContextMenuItem] {
+        &self.items
+    }
+
+    /// Adds the visible `customItems` of an AVM1 `ContextMenu`, whose `onSelect` handlers are
+    /// called with `target`. Must be called with a stack frame.
+    pub fn push_avm1_items(
+        &mut self,
+        avm: &mut Avm1<'gc>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        menu: Object<'gc>,
+        target: DisplayObject<'gc>,
+    ) {
+        let custom_items = match menu.get_resolved("customItems", avm, context) {
+            Value::Object(custom_items) => custom_items,
+            _ => return,
+        };
+
+        let swf_version = avm.current_swf_version();
+        let mut num_items = 0;
+        for i in 0..custom_items.length() {
+            let item = match custom_items.array_element(i) {
+                Value::Object(item) => item,
+                _ => continue,
+            };
+            if !item
+                .get_resolved("visible", avm, context)
+                .as_bool(swf_version)
+            {
+                continue;
+            }
+
+            let caption = item
+                .get_resolved("caption", avm, context)
+                .coerce_to_string(avm, context)
+                .unwrap_or_default();
+            let menu_item = ContextMenuItem {
+                caption,
+                enabled: item
+                    .get_resolved("enabled", avm, context)
+                    .as_bool(swf_version),
+                separator_before: item
+                    .get_resolved("separatorBefore", avm, context)
+                    .as_bool(swf_version),
+                checked: false,
+            };
+            self.push(menu_item, ContextMenuCallback::Avm1 { item, target });
+
+            num_items += 1;
+            if num_items >= MAX_CUSTOM_ITEMS {
+                break;
+            }
+        }
+    }
+
+    /// Returns the callback of the item with the given index, if it can be selected.
+    pub fn callback(&self, index: usize) -> Option<&ContextMenuCallback<'gc>> {
+        match self.items.get(index) {
+            Some(item) if item.enabled => self.callbacks.get(index),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the `menu` of `object`, or of its nearest ancestor that has one. Returns the menu and
+/// the object it belongs to. Must be called with a stack frame.
+pub fn find_avm1_menu<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    object: DisplayObject<'gc>,
+) -> Option<(Object<'gc>, DisplayObject<'gc>)> {
+    let mut node = Some(object);
+    while let Some(display_object) = node {
+        if let Value::Object(object) = display_object.object() {
+            if let Value::Object(menu) = object.get_resolved("menu", avm, context) {
+                return Some((menu, display_object));
+            }
+        }
+        node = display_object.parent();
+    }
+    None
+}
+
+/// Returns whether a group of built-in items, such as `zoom`, is enabled by the `builtInItems`
+/// of an AVM1 `ContextMenu`. Must be called with a stack frame.
+pub fn avm1_built_in_items_enabled<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    menu: Object<'gc>,
+    name: &str,
+) -> bool {
+    match menu.get_resolved("builtInItems", avm, context) {
+        Value::Object(built_in_items) => built_in_items
+            .get_resolved(name, avm, context)
+            .as_bool(avm.current_swf_version()),
+        _ => true,
+    }
+}
+
+/// The size of the text of the items, in pixels.
+const FONT_SIZE: f32 = 12.0;
+
+/// The height of each item.
+const ITEM_HEIGHT: f32 = 22.0;
+
+/// The height of the gap that separator lines are drawn in.
+const SEPARATOR_HEIGHT: f32 = 9.0;
+
+/// The space above the first item and below the last item.
+const PADDING: f32 = 3.0;
+
+/// The space to the left of the captions, where check marks are drawn.
+const CAPTION_LEFT: f32 = 24.0;
+
+/// The space to the right of the captions.
+const CAPTION_RIGHT: f32 = 16.0;
+
+const MIN_WIDTH: f32 = 120.0;
+
+const BORDER_COLOR: u32 = 0x808080;
+const BACKGROUND_COLOR: u32 = 0xf0f0f0;
+const SEPARATOR_COLOR: u32 = 0xc8c8c8;
+const HIGHLIGHT_COLOR: u32 = 0x3399ff;
+const TEXT_COLOR: u32 = 0x000000;
+const HIGHLIGHTED_TEXT_COLOR: u32 = 0xffffff;
+const DISABLED_TEXT_COLOR: u32 = 0x8c8c8c;
+
+/// A context menu drawn over the movie by the renderer.
+///
+/// Positions are in pixels, relative to the top-left corner of the viewport.
+#[derive(Debug)]
+pub struct ContextMenuPopup {
+    items: Vec<ContextMenuItem>,
+
+    /// The top-left corner of the popup.
+    position: (f32, f32),
+
+    size: (f32, f32),
+
+    /// The top edge of each item, relative to the popup.
+    rows: Vec<f32>,
+
+    /// The item under the mouse, if it can be selected.
+    hovered: Option<usize>,
+}
+
+impl ContextMenuPopup {
+    /// Lays out a popup at the given mouse position, moving it so that it fits in the
+    /// viewport. `measure` returns the width of a caption at the given font size.
+    pub fn new(
+        items: Vec<ContextMenuItem>,
+        (x, y): (f32, f32),
+        (viewport_width, viewport_height): (f32, f32),
+        measure: impl Fn(&str, f32) -> f32,
+    ) -> Self {
+        let mut rows = Vec::with_capacity(items.len());
+        let mut width = MIN_WIDTH;
+        let mut bottom = PADDING;
+        for (i, item) in items.iter().enumerate() {
+            if item.separator_before && i > 0 {
+                bottom += SEPARATOR_HEIGHT;
+            }
+            rows.push(bottom);
+            bottom += ITEM_HEIGHT;
+            width = width.max(CAPTION_LEFT + measure(&item.caption, FONT_SIZE) + CAPTION_RIGHT);
+        }
+        let size = (width.ceil(), bottom + PADDING);
+
+        // Open towards the top-left if the popup doesn't fit below and to the right.
+        let x = if x + size.0 > viewport_width {
+            x - size.0
+        } else {
+            x
+        };
+        let y = if y + size.1 > viewport_height {
+            y - size.1
+        } else {
+            y
+        };
+
+        Self {
+            items,
+            position: (x.max(0.0), y.max(0.0)),
+            size,
+            rows,
+            hovered: None,
+        }
+    }
+
+    pub fn contains(&self, (x, y): (f32, f32)) -> bool {
+        let (left, top) = self.position;
+        x >= left && x < left + self.size.0 && y >= top && y < top + self.size.1
+    }
+
+    /// Returns the index of the selectable item at the given position.
+    pub fn item_at(&self, (x, y): (f32, f32)) -> Option<usize> {
+        if !self.contains((x, y)) {
+            return None;
+        }
+        let y = y - self.position.1;
+        self.rows
+            .iter()
+            .position(|&top| y >= top && y < top + ITEM_HEIGHT)
+            .filter(|&i| self.items[i].enabled)
+    }
+
+    /// Highlights the item under the mouse, returning whether the highlight changed.
+    pub fn update_hovered(&mut self, position: (f32, f32)) -> bool {
+        let hovered = self.item_at(position);
+        let changed = hovered != self.hovered;
+        self.hovered = hovered;
+        changed
+    }
+
+    /// Draws the popup, with `rect` being a one pixel square filled with white.
+    pub fn render(&self, renderer: &mut dyn RenderBackend, rect: ShapeHandle, font: &dyn TextFont) {
+        let (left, top) = self.position;
+        let (width, height) = self.size;
+        let draw_rect = |renderer: &mut dyn RenderBackend, x, y, width, height, color| {
+            draw_rect(renderer, rect, (x, y, width, height), color);
+        };
+
+        draw_rect(renderer, left, top, width, height, BORDER_COLOR);
+        let (inner_width, inner_height) = (width - 2.0, height - 2.0);
+        draw_rect(
+            renderer,
+            left + 1.0,
+            top + 1.0,
+            inner_width,
+            inner_height,
+            BACKGROUND_COLOR,
+        );
+        for (i, (item, &row)) in self.items.iter().zip(&self.rows).enumerate() {
+            let row = top + row;
+            if item.separator_before && i > 0 {
+                let line_y = row - (SEPARATOR_HEIGHT / 2.0).ceil();
+                draw_rect(
+                    renderer,
+                    left + 2.0,
+                    line_y,
+                    width - 4.0,
+                    1.0,
+                    SEPARATOR_COLOR,
+                );
+            }
+
+            let text_color = if self.hovered == Some(i) {
+                draw_rect(
+                    renderer,
+                    left + 2.0,
+                    row,
+                    width - 4.0,
+                    ITEM_HEIGHT,
+                    HIGHLIGHT_COLOR,
+                );
+                HIGHLIGHTED_TEXT_COLOR
+            } else if item.enabled {
+                TEXT_COLOR
+            } else {
+                DISABLED_TEXT_COLOR
+            };
+
+            if item.checked {
+                let size = 6.0;
+                let x = left + ((CAPTION_LEFT - size) / 2.0).floor();
+                let y = row + ((ITEM_HEIGHT - size) / 2.0).floor();
+                draw_rect(renderer, x, y, size, size, text_color);
+            }
+
+            let text_top = row + ((ITEM_HEIGHT - FONT_SIZE) / 2.0).floor() - 1.0;
+            let transform = Transform {
+                matrix: Matrix {
+                    tx: (left + CAPTION_LEFT) * 20.0,
+                    ty: text_top * 20.0,
+                    ..Default::default()
+                },
+                color_transform: color_transform(text_color),
+            };
+            let mut draw_glyph = |transform: &Transform, glyph: &Glyph| {
+                renderer.render_shape(glyph.shape, transform);
+            };
+            font.evaluate(&item.caption, transform, FONT_SIZE, &mut draw_glyph);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_popup(position: (f32, f32)) -> ContextMenuPopup {
+        let mut items = vec![
+            ContextMenuItem::new("Zoom In"),
+            ContextMenuItem::new("Zoom Out"),
+            ContextMenuItem::new("A very long caption"),
+        ];
+        items[1].enabled = false;
+        items[2].separator_before = true;
+        // Each character is 10 pixels wide.
+        ContextMenuPopup::new(items, position, (400.0, 300.0), |caption, _| {
+            caption.len() as f32 * 10.0
+        })
+    }
+
+    #[test]
+    fn layout() {
+        let popup = test_popup((100.0, 50.0));
+        assert_eq!(popup.position, (100.0, 50.0));
+        assert_eq!(popup.size, (230.0, 3.0 * 22.0 + 9.0 + 6.0));
+        assert_eq!(popup.rows, vec![3.0, 25.0, 56.0]);
+
+        // The popup opens towards the top-left when it would leave the viewport.
+        let popup = test_popup((300.0, 290.0));
+        assert_eq!(popup.position, (70.0, 209.0));
+    }
+
+    #[test]
+    fn hit_testing() {
+        let mut popup = test_popup((100.0, 50.0));
+        assert_eq!(popup.item_at((110.0, 60.0)), Some(0));
+        // Disabled items and separators can't be selected.
+        assert_eq!(popup.item_at((110.0, 80.0)), None);
+        assert_eq!(popup.item_at((110.0, 100.0)), None);
+        assert_eq!(popup.item_at((110.0, 110.0)), Some(2));
+        assert_eq!(popup.item_at((90.0, 60.0)), None);
+
+        assert!(popup.update_hovered((110.0, 60.0)));
+        assert!(!popup.update_hovered((120.0, 61.0)));
+        assert!(popup.update_hovered((0.0, 0.0)));
+    }
+}
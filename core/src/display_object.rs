@@ -1,4 +1,5 @@
 use crate::avm1::{Object, Value};
+use crate::backend::audio::SoundTransform;
 use crate::context::{RenderContext, UpdateContext};
 use crate::player::NEWEST_PLAYER_VERSION;
 use crate::prelude::*;
@@ -32,6 +33,7 @@ pub struct DisplayObjectBase<'gc> {
     place_frame: u16,
     depth: Depth,
     transform: Transform,
+    sound_transform: SoundTransform,
     name: String,
     clip_depth: Depth,
 
@@ -53,6 +55,16 @@ pub struct DisplayObjectBase<'gc> {
     /// The next sibling of this display object in order of execution.
     next_sibling: Option<DisplayObject<'gc>>,
 
+    /// Whether a focus rectangle is drawn around this object when it has keyboard focus,
+    /// set by `_focusrect`. If unset, the global `_focusrect` is used.
+    focus_rect: Option<bool>,
+
+    /// The object that masks this object, set with `setMask`.
+    masker: Option<DisplayObject<'gc>>,
+
+    /// The object that this object masks, if it was passed to `setMask`.
+    maskee: Option<DisplayObject<'gc>>,
+
     /// Bit flags for various display object properites.
     flags: EnumSet<DisplayObjectFlags>,
 }
@@ -64,6 +76,7 @@ impl<'gc> Default for DisplayObjectBase<'gc> {
             place_frame: Default::default(),
             depth: Default::default(),
             transform: Default::default(),
+            sound_transform: Default::default(),
             name: Default::default(),
             clip_depth: Default::default(),
             rotation: 0.0,
@@ -73,6 +86,9 @@ impl<'gc> Default for DisplayObjectBase<'gc> {
             first_child: None,
             prev_sibling: None,
             next_sibling: None,
+            focus_rect: None,
+            masker: None,
+            maskee: None,
             flags: DisplayObjectFlags::Visible.into(),
         }
     }
@@ -85,6 +101,8 @@ unsafe impl<'gc> Collect for DisplayObjectBase<'gc> {
         self.first_child.trace(cc);
         self.prev_sibling.trace(cc);
         self.next_sibling.trace(cc);
+        self.masker.trace(cc);
+        self.maskee.trace(cc);
     }
 }
 
@@ -132,6 +150,27 @@ impl<'gc> DisplayObjectBase<'gc> {
     ) {
         self.transform.color_transform = *color_transform;
     }
+    fn sound_transform(&self) -> &SoundTransform {
+        &self.sound_transform
+    }
+    fn set_sound_transform(
+        &mut self,
+        _context: MutationContext<'gc, '_>,
+        sound_transform: &SoundTransform,
+    ) {
+        self.sound_transform = *sound_transform;
+    }
+    /// The sound transform of this object combined with those of its ancestors.
+    /// Display objects use this while they are borrowed, such as when starting sounds from tags.
+    fn global_sound_transform(&self) -> SoundTransform {
+        if let Some(parent) = self.parent {
+            parent
+                .global_sound_transform()
+                .concat(&self.sound_transform)
+        } else {
+            self.sound_transform
+        }
+    }
     fn x(&self) -> f64 {
         f64::from(self.transform.matrix.tx) / Twips::TWIPS_PER_PIXEL
     }
@@ -328,6 +367,30 @@ impl<'gc> DisplayObjectBase<'gc> {
         }
     }
 
+    fn focus_rect(&self) -> Option<bool> {
+        self.focus_rect
+    }
+
+    fn set_focus_rect(&mut self, value: Option<bool>) {
+        self.focus_rect = value;
+    }
+
+    fn masker(&self) -> Option<DisplayObject<'gc>> {
+        self.masker
+    }
+
+    fn set_masker(&mut self, masker: Option<DisplayObject<'gc>>) {
+        self.masker = masker;
+    }
+
+    fn maskee(&self) -> Option<DisplayObject<'gc>> {
+        self.maskee
+    }
+
+    fn set_maskee(&mut self, maskee: Option<DisplayObject<'gc>>) {
+        self.maskee = maskee;
+    }
+
     fn transformed_by_script(&self) -> bool {
         self.flags.contains(DisplayObjectFlags::TransformedByScript)
     }
@@ -405,29 +468,46 @@ pub trait TDisplayObject<'gc>: 'gc + Collect + Debug {
         color_transform: &ColorTransform,
     );
 
-    /// Converts a local position to a global stage position
-    fn local_to_global(&self, local: (Twips, Twips)) -> (Twips, Twips) {
-        let mut node = self.parent();
-        let mut matrix = *self.matrix();
-        while let Some(display_object) = node {
-            matrix = *display_object.matrix() * matrix;
-            node = display_object.parent();
+    /// The sound transform applied to sounds owned by this object and its children.
+    /// This is set by `Sound.setVolume`, `Sound.setPan` and `Sound.setTransform`.
+    fn sound_transform(&self) -> Ref<SoundTransform>;
+    fn set_sound_transform(
+        &mut self,
+        context: MutationContext<'gc, '_>,
+        sound_transform: &SoundTransform,
+    );
+
+    /// The sound transform of this object combined with those of all of its ancestors.
+    fn global_sound_transform(&self) -> SoundTransform {
+        let sound_transform = *self.sound_transform();
+        if let Some(parent) = self.parent() {
+            parent.global_sound_transform().concat(&sound_transform)
+        } else {
+            sound_transform
         }
+    }
 
-        matrix * local
+    /// Converts a local position to a global stage position
+    fn local_to_global(&self, local: (Twips, Twips)) -> (Twips, Twips) {
+        self.local_to_global_matrix() * local
     }
 
     /// Converts a local position on the stage to a local position on this display object
     fn global_to_local(&self, global: (Twips, Twips)) -> (Twips, Twips) {
+        let mut matrix = self.local_to_global_matrix();
+        matrix.invert();
+        matrix * global
+    }
+
+    /// The matrix that transforms from the local space of this display object to the stage.
+    fn local_to_global_matrix(&self) -> Matrix {
         let mut node = self.parent();
         let mut matrix = *self.matrix();
         while let Some(display_object) = node {
             matrix = *display_object.matrix() * matrix;
             node = display_object.parent();
         }
-
-        matrix.invert();
-        matrix * global
+        matrix
     }
 
     /// The `x` position in pixels of this display object in local space.
@@ -641,6 +721,29 @@ pub trait TDisplayObject<'gc>: 'gc + Collect + Debug {
     /// Returned by the `_visible`/`visible` ActionScript properties.
     fn set_visible(&mut self, context: MutationContext<'gc, '_>, value: bool);
 
+    /// Whether a focus rectangle is drawn around this object when it has keyboard focus.
+    /// `None` means that the global setting is used.
+    /// Returned by the `_focusrect` ActionScript property.
+    fn focus_rect(&self) -> Option<bool>;
+
+    /// Sets whether a focus rectangle is drawn around this object when it has keyboard focus.
+    /// Set by the `_focusrect` ActionScript property.
+    fn set_focus_rect(&mut self, context: MutationContext<'gc, '_>, value: Option<bool>);
+
+    /// The object that masks this object, set with `setMask`.
+    fn masker(&self) -> Option<DisplayObject<'gc>>;
+
+    /// Sets the object that masks this object. `DisplayObject::set_mask` also updates the mask.
+    fn set_masker(&mut self, context: MutationContext<'gc, '_>, masker: Option<DisplayObject<'gc>>);
+
+    /// The object that this object masks. Objects used as masks are not rendered normally,
+    /// and can't be hit by the mouse.
+    fn maskee(&self) -> Option<DisplayObject<'gc>>;
+
+    /// Sets the object that this object masks. `DisplayObject::set_mask` also updates the
+    /// masked object.
+    fn set_maskee(&mut self, context: MutationContext<'gc, '_>, maskee: Option<DisplayObject<'gc>>);
+
     /// Whether this display object has been transformed by ActionScript.
     /// When this flag is set, changes from SWF `PlaceObject` tags are ignored.
     fn transformed_by_script(&self) -> bool;
@@ -761,6 +864,12 @@ pub trait TDisplayObject<'gc>: 'gc + Collect + Debug {
         false
     }
 
+    /// Tests if a given stage position point intersects with the shapes that this object draws,
+    /// rather than with its bounding box.
+    fn hit_test_shape(&self, _pos: (Twips, Twips)) -> bool {
+        false
+    }
+
     fn mouse_pick(
         &self,
         _self_node: DisplayObject<'gc>,
@@ -838,6 +947,13 @@ macro_rules! impl_display_object {
             context: gc_arena::MutationContext<'gc, '_>, color_transform: &crate::color_transform::ColorTransform) {
             self.0.write(context).$field.set_color_transform(context, color_transform)
         }
+        fn sound_transform(&self) -> std::cell::Ref<crate::backend::audio::SoundTransform> {
+            std::cell::Ref::map(self.0.read(), |o| o.$field.sound_transform())
+        }
+        fn set_sound_transform(&mut self,
+            context: gc_arena::MutationContext<'gc, '_>, sound_transform: &crate::backend::audio::SoundTransform) {
+            self.0.write(context).$field.set_sound_transform(context, sound_transform)
+        }
         fn x(&self) -> f64 {
             self.0.read().$field.x()
         }
@@ -930,6 +1046,27 @@ macro_rules! impl_display_object {
             context: gc_arena::MutationContext<'gc, '_>, value: bool) {
             self.0.write(context).$field.set_visible(value);
         }
+        fn focus_rect(&self) -> Option<bool> {
+            self.0.read().$field.focus_rect()
+        }
+        fn set_focus_rect(&mut self,
+            context: gc_arena::MutationContext<'gc, '_>, value: Option<bool>) {
+            self.0.write(context).$field.set_focus_rect(value);
+        }
+        fn masker(&self) -> Option<DisplayObject<'gc>> {
+            self.0.read().$field.masker()
+        }
+        fn set_masker(&mut self,
+            context: gc_arena::MutationContext<'gc, '_>, masker: Option<DisplayObject<'gc>>) {
+            self.0.write(context).$field.set_masker(masker);
+        }
+        fn maskee(&self) -> Option<DisplayObject<'gc>> {
+            self.0.read().$field.maskee()
+        }
+        fn set_maskee(&mut self,
+            context: gc_arena::MutationContext<'gc, '_>, maskee: Option<DisplayObject<'gc>>) {
+            self.0.write(context).$field.set_maskee(maskee);
+        }
         fn transformed_by_script(&self) -> bool {
             self.0.read().$field.transformed_by_script()
         }
@@ -965,7 +1102,9 @@ pub fn render_children<'gc>(
             context.renderer.pop_mask();
             clip_depth = clip_depth_stack.pop().unwrap();
         }
-        if child.clip_depth() > 0 && child.allow_as_mask() {
+        if child.maskee().filter(|maskee| !maskee.removed()).is_some() {
+            // Objects used as masks by `setMask` are only drawn into the mask of their maskee.
+        } else if child.clip_depth() > 0 && child.allow_as_mask() {
             // Push and render the mask.
             clip_depth_stack.push(clip_depth);
             clip_depth = child.clip_depth();
@@ -974,7 +1113,7 @@ pub fn render_children<'gc>(
             context.renderer.activate_mask();
         } else if child.visible() {
             // Normal child.
-            child.render(context);
+            render_masked(context, child);
         }
     }
 
@@ -984,10 +1123,63 @@ pub fn render_children<'gc>(
     }
 }
 
+/// Renders a child, masked by the object that was set with `setMask`, if any.
+///
+/// The mask can be anywhere on the display list, so it's drawn with its own transform to the
+/// stage, relative to the transform of the child's parent that is on top of the stack.
+fn render_masked<'gc>(context: &mut RenderContext<'_, 'gc>, child: DisplayObject<'gc>) {
+    let mask = match child.masker().filter(|mask| !mask.removed()) {
+        Some(mask) => mask,
+        None => {
+            child.render(context);
+            return;
+        }
+    };
+
+    let mut matrix = child
+        .parent()
+        .map_or_else(Matrix::default, |parent| parent.local_to_global_matrix());
+    matrix.invert();
+    if let Some(mask_parent) = mask.parent() {
+        matrix = matrix * mask_parent.local_to_global_matrix();
+    }
+
+    context.renderer.push_mask();
+    context.transform_stack.push(&Transform {
+        matrix,
+        color_transform: Default::default(),
+    });
+    mask.render(context);
+    context.transform_stack.pop();
+    context.renderer.activate_mask();
+    child.render(context);
+    context.renderer.pop_mask();
+}
+
 impl<'gc> DisplayObject<'gc> {
     pub fn ptr_eq(a: DisplayObject<'gc>, b: DisplayObject<'gc>) -> bool {
         a.as_ptr() == b.as_ptr()
     }
+
+    /// Masks this object with `mask`, or removes its mask if `None`, as `setMask` does.
+    /// An object can only mask one other object, so `mask` stops masking any object that it
+    /// masked before.
+    pub fn set_mask(
+        mut self,
+        gc_context: MutationContext<'gc, '_>,
+        mask: Option<DisplayObject<'gc>>,
+    ) {
+        if let Some(mut old_mask) = self.masker() {
+            old_mask.set_maskee(gc_context, None);
+        }
+        if let Some(mut mask) = mask {
+            if let Some(mut old_maskee) = mask.maskee() {
+                old_maskee.set_masker(gc_context, None);
+            }
+            mask.set_maskee(gc_context, Some(self));
+        }
+        self.set_masker(gc_context, mask);
+    }
 }
 
 /// Bit flags used by `DisplayObject`.
@@ -79,6 +79,10 @@ impl<'gc> TDisplayObject<'gc> for Bitmap<'gc> {
         }
     }
 
+    fn hit_test_shape(&self, point: (Twips, Twips)) -> bool {
+        self.world_bounds().contains(point)
+    }
+
     fn run_frame(&mut self, _context: &mut UpdateContext) {
         // Noop
     }
@@ -1,8 +1,10 @@
+use crate::avm1::globals::button::attach_virtual_properties;
 use crate::avm1::{Object, StageObject, Value};
 use crate::context::{ActionType, RenderContext, UpdateContext};
 use crate::display_object::{DisplayObjectBase, TDisplayObject};
 use crate::events::{ButtonEvent, ButtonEventResult, ButtonKeyCode};
 use crate::prelude::*;
+use crate::scaling_grid::ScalingGridTransform;
 use gc_arena::{Collect, GcCell, MutationContext};
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
@@ -21,6 +23,12 @@ pub struct ButtonData<'gc> {
     tracking: ButtonTracking,
     object: Option<Object<'gc>>,
     initialized: bool,
+
+    /// Whether the button responds to the mouse, set with `enabled`.
+    enabled: bool,
+
+    /// Whether the hand cursor is shown over the button, set with `useHandCursor`.
+    use_hand_cursor: bool,
 }
 
 impl<'gc> Button<'gc> {
@@ -56,6 +64,7 @@ impl<'gc> Button<'gc> {
             over_to_down_sound: None,
             down_to_over_sound: None,
             over_to_up_sound: None,
+            scaling_grid: None,
         };
 
         Button(GcCell::allocate(
@@ -68,6 +77,8 @@ impl<'gc> Button<'gc> {
                 state: self::ButtonState::Up,
                 initialized: false,
                 object: None,
+                enabled: true,
+                use_hand_cursor: true,
                 tracking: if button.is_track_as_menu {
                     ButtonTracking::Menu
                 } else {
@@ -87,6 +98,36 @@ impl<'gc> Button<'gc> {
             .handle_button_event((*self).into(), context, event)
     }
 
+    pub fn enabled(self) -> bool {
+        self.0.read().enabled
+    }
+
+    pub fn set_enabled(self, gc_context: MutationContext<'gc, '_>, enabled: bool) {
+        self.0.write(gc_context).enabled = enabled;
+    }
+
+    pub fn use_hand_cursor(self) -> bool {
+        self.0.read().use_hand_cursor
+    }
+
+    pub fn set_use_hand_cursor(self, gc_context: MutationContext<'gc, '_>, use_hand_cursor: bool) {
+        self.0.write(gc_context).use_hand_cursor = use_hand_cursor;
+    }
+
+    /// Whether the button is tracked as a menu item, which lets it be pressed by dragging
+    /// onto it with the mouse button held down, even if it wasn't the button that was pressed.
+    pub fn track_as_menu(self) -> bool {
+        self.0.read().tracking == ButtonTracking::Menu
+    }
+
+    pub fn set_track_as_menu(self, gc_context: MutationContext<'gc, '_>, track_as_menu: bool) {
+        self.0.write(gc_context).tracking = if track_as_menu {
+            ButtonTracking::Menu
+        } else {
+            ButtonTracking::Push
+        };
+    }
+
     pub fn set_sounds(self, gc_context: MutationContext<'gc, '_>, sounds: swf::ButtonSounds) {
         let button = self.0.write(gc_context);
         let mut static_data = button.static_data.write(gc_context);
@@ -96,6 +137,12 @@ impl<'gc> Button<'gc> {
         static_data.over_to_up_sound = sounds.over_to_up_sound;
     }
 
+    /// Sets the 9-slice scaling grid of this button, in its local space.
+    pub fn set_scaling_grid(self, gc_context: MutationContext<'gc, '_>, grid: Option<BoundingBox>) {
+        let button = self.0.write(gc_context);
+        button.static_data.write(gc_context).scaling_grid = grid;
+    }
+
     /// Handles the ancient DefineButtonCxform SWF tag.
     /// Set the color transform for all children of each state.
     pub fn set_colors(
@@ -130,8 +177,10 @@ impl<'gc> TDisplayObject<'gc> for Button<'gc> {
     ) {
         let mut mc = self.0.write(gc_context);
         if mc.object.is_none() {
-            let object = StageObject::for_display_object(gc_context, display_object, Some(proto));
-            mc.object = Some(object.into());
+            let object: Object<'gc> =
+                StageObject::for_display_object(gc_context, display_object, Some(proto)).into();
+            attach_virtual_properties(gc_context, object);
+            mc.object = Some(object);
         }
     }
 
@@ -144,11 +193,29 @@ impl<'gc> TDisplayObject<'gc> for Button<'gc> {
     fn render(&self, context: &mut RenderContext<'_, 'gc>) {
         context.transform_stack.push(&*self.transform());
 
+        let grid = self.0.read().static_data.read().scaling_grid.clone();
+        let scaling_grid = grid.and_then(|grid| {
+            ScalingGridTransform::new(
+                &grid,
+                &self.self_bounds(),
+                &context.transform_stack.transform().matrix,
+            )
+        });
+        let parent_scaling_grid = std::mem::replace(&mut context.scaling_grid, scaling_grid);
         crate::display_object::render_children(context, &self.0.read().children);
+        context.scaling_grid = parent_scaling_grid;
 
         context.transform_stack.pop();
     }
 
+    fn self_bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::default();
+        for child in self.0.read().children.values() {
+            bounds.union(&child.local_bounds());
+        }
+        bounds
+    }
+
     fn hit_test(&self, point: (Twips, Twips)) -> bool {
         for child in self.0.read().hit_area.values().rev() {
             if child.world_bounds().contains(point) {
@@ -159,6 +226,14 @@ impl<'gc> TDisplayObject<'gc> for Button<'gc> {
         false
     }
 
+    fn hit_test_shape(&self, point: (Twips, Twips)) -> bool {
+        self.0
+            .read()
+            .children
+            .values()
+            .any(|child| child.hit_test_shape(point))
+    }
+
     fn mouse_pick(
         &self,
         self_node: DisplayObject<'gc>,
@@ -292,53 +367,90 @@ impl<'gc> ButtonData<'gc> {
         context: &mut crate::context::UpdateContext<'_, 'gc, '_>,
         event: ButtonEvent,
     ) {
-        let cur_state = self.state;
-        let new_state = match event {
-            ButtonEvent::RollOut => ButtonState::Up,
-            ButtonEvent::RollOver => ButtonState::Over,
-            ButtonEvent::Press => ButtonState::Down,
-            ButtonEvent::Release => ButtonState::Over,
+        // The button shows its over state while the mouse is dragged off it, unless it's
+        // tracked as a menu item, which returns to its up state instead.
+        let is_menu = self.tracking == ButtonTracking::Menu;
+        let (condition, new_state) = match event {
+            ButtonEvent::RollOver => (swf::ButtonActionCondition::IdleToOverUp, ButtonState::Over),
+            ButtonEvent::RollOut => (swf::ButtonActionCondition::OverUpToIdle, ButtonState::Up),
+            ButtonEvent::Press => (
+                swf::ButtonActionCondition::OverUpToOverDown,
+                ButtonState::Down,
+            ),
+            ButtonEvent::Release => (
+                swf::ButtonActionCondition::OverDownToOverUp,
+                ButtonState::Over,
+            ),
+            ButtonEvent::ReleaseOutside => {
+                (swf::ButtonActionCondition::OutDownToIdle, ButtonState::Up)
+            }
+            ButtonEvent::DragOut if is_menu => {
+                (swf::ButtonActionCondition::OverDownToIdle, ButtonState::Up)
+            }
+            ButtonEvent::DragOut => (
+                swf::ButtonActionCondition::OverDownToOutDown,
+                ButtonState::Over,
+            ),
+            ButtonEvent::DragOver if is_menu => (
+                swf::ButtonActionCondition::IdleToOverDown,
+                ButtonState::Down,
+            ),
+            ButtonEvent::DragOver => (
+                swf::ButtonActionCondition::OutDownToOverDown,
+                ButtonState::Down,
+            ),
             ButtonEvent::KeyPress { key_code } => {
                 self.run_actions(
                     context,
                     swf::ButtonActionCondition::KeyPress,
                     Some(key_code),
                 );
-                cur_state
+                return;
             }
         };
-
-        match (cur_state, new_state) {
-            (ButtonState::Up, ButtonState::Over) => {
-                self.run_actions(context, swf::ButtonActionCondition::IdleToOverUp, None);
-                self.play_sound(context, self.static_data.read().up_to_over_sound.as_ref());
-            }
-            (ButtonState::Over, ButtonState::Up) => {
-                self.run_actions(context, swf::ButtonActionCondition::OverUpToIdle, None);
-                self.play_sound(context, self.static_data.read().over_to_up_sound.as_ref());
-            }
-            (ButtonState::Over, ButtonState::Down) => {
-                self.run_actions(context, swf::ButtonActionCondition::OverUpToOverDown, None);
-                self.play_sound(context, self.static_data.read().over_to_down_sound.as_ref());
-            }
-            (ButtonState::Down, ButtonState::Over) => {
-                self.run_actions(context, swf::ButtonActionCondition::OverDownToOverUp, None);
-                self.play_sound(context, self.static_data.read().down_to_over_sound.as_ref());
+        self.run_actions(context, condition, None);
+
+        // Queue ActionScript-defined event handlers after the SWF defined ones.
+        if context.swf_version >= 6 {
+            if let Some(name) = event.method_name() {
+                context.action_queue.queue_actions(
+                    self_display_object,
+                    ActionType::Method { name },
+                    false,
+                );
             }
-            _ => (),
         }
 
+        let static_data = self.static_data;
+        let static_data = static_data.read();
+        let sound = match condition {
+            swf::ButtonActionCondition::IdleToOverUp => static_data.up_to_over_sound.as_ref(),
+            swf::ButtonActionCondition::OverUpToIdle => static_data.over_to_up_sound.as_ref(),
+            swf::ButtonActionCondition::OverUpToOverDown => static_data.over_to_down_sound.as_ref(),
+            swf::ButtonActionCondition::OverDownToOverUp => static_data.down_to_over_sound.as_ref(),
+            _ => None,
+        };
+        self.play_sound(self_display_object, context, sound);
+
         self.set_state(self_display_object, context, new_state);
     }
 
     fn play_sound(
         &self,
+        self_display_object: DisplayObject<'gc>,
         context: &mut UpdateContext<'_, 'gc, '_>,
         sound: Option<&swf::ButtonSound>,
     ) {
         if let Some((id, sound_info)) = sound {
             if let Some(sound_handle) = context.library.get_sound(*id) {
-                context.audio.start_sound(sound_handle, sound_info);
+                context.audio_manager.start_sound(
+                    context.audio,
+                    sound_handle,
+                    sound_info,
+                    None,
+                    Some(self_display_object),
+                    &self.base.global_sound_transform(),
+                );
             }
         }
     }
@@ -420,6 +532,9 @@ struct ButtonStatic {
     over_to_down_sound: Option<swf::ButtonSound>,
     down_to_over_sound: Option<swf::ButtonSound>,
     over_to_up_sound: Option<swf::ButtonSound>,
+
+    /// The 9-slice scaling grid, set by `DefineScalingGrid`.
+    scaling_grid: Option<BoundingBox>,
 }
 
 unsafe impl gc_arena::Collect for ButtonStatic {
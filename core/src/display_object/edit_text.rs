@@ -3,7 +3,7 @@ use crate::avm1::globals::text_field::attach_virtual_properties;
 use crate::avm1::{Object, StageObject, Value};
 use crate::context::{RenderContext, UpdateContext};
 use crate::display_object::{DisplayObjectBase, TDisplayObject};
-use crate::font::{Glyph, TextFormat};
+use crate::font::{self, Glyph, TextFont, TextFormat};
 use crate::library::Library;
 use crate::prelude::*;
 use crate::transform::Transform;
@@ -180,6 +180,16 @@ impl<'gc> EditText<'gc> {
         self.0.write(gc_context).is_word_wrap = is_word_wrap;
     }
 
+    /// Whether the text can be edited by the user, making this text field reachable with Tab.
+    pub fn is_editable(self) -> bool {
+        !self.0.read().static_data.0.is_read_only
+    }
+
+    /// Whether the text can be selected with the mouse, which shows the text cursor over it.
+    pub fn is_selectable(self) -> bool {
+        self.0.read().static_data.0.is_selectable
+    }
+
     /// Construct a base text transform for this `EditText`, to be used for
     /// evaluating fonts.
     ///
@@ -252,6 +262,19 @@ impl<'gc> EditText<'gc> {
         base_width
     }
 
+    /// Returns the font to lay out and render this text field with.
+    ///
+    /// If the font can't be found or has no glyph information, the text is rendered with the
+    /// device font of the same name instead.
+    fn font<'a>(self, library: &'a Library<'gc>) -> Box<dyn TextFont + 'a> {
+        let font_id = self.0.read().static_data.0.font_id.unwrap_or(0);
+        match library.get_font(font_id) {
+            Some(font) if font.has_glyphs() => Box::new(font),
+            Some(font) => Box::new(library.get_device_font(font.name())),
+            None => Box::new(library.get_device_font("_sans")),
+        }
+    }
+
     /// Compute all "break points" between lines.
     ///
     /// The breakpoints are the character indicies of every point in the string
@@ -274,39 +297,30 @@ impl<'gc> EditText<'gc> {
     fn line_breaks(self, library: &Library<'gc>) -> Vec<usize> {
         let edit_text = self.0.read();
         let static_data = &edit_text.static_data.0;
-        let font_id = static_data.font_id.unwrap_or(0);
 
         if edit_text.is_multiline {
-            if let Some(font) = library
-                .get_font(font_id)
-                .filter(|font| font.has_glyphs())
-                .or_else(|| library.device_font())
-            {
-                let mut breakpoints = vec![];
-                let mut break_base = 0;
-                let height = static_data
-                    .height
-                    .map(|v| v.to_pixels() as f32)
-                    .unwrap_or_else(|| font.scale());
-
-                for natural_line in edit_text.text.split('\n') {
-                    if break_base != 0 {
-                        breakpoints.push(break_base);
-                    }
-
-                    for breakpoint in
-                        font.split_wrapped_lines(natural_line, height, self.line_width())
-                    {
-                        breakpoints.push(break_base + breakpoint);
-                    }
-
-                    break_base += natural_line.len() + 1;
+            let font = self.font(library);
+            let mut breakpoints = vec![];
+            let mut break_base = 0;
+            let height = static_data
+                .height
+                .map(|v| v.to_pixels() as f32)
+                .unwrap_or_else(|| font.scale());
+
+            for natural_line in edit_text.text.split('\n') {
+                if break_base != 0 {
+                    breakpoints.push(break_base);
+                }
+
+                for breakpoint in font.split_wrapped_lines(natural_line, height, self.line_width())
+                {
+                    breakpoints.push(break_base + breakpoint);
                 }
 
-                breakpoints
-            } else {
-                vec![]
+                break_base += natural_line.len() + 1;
             }
+
+            breakpoints
         } else {
             vec![]
         }
@@ -340,43 +354,37 @@ impl<'gc> EditText<'gc> {
     ///
     /// The returned tuple should be interpreted as width, then height.
     pub fn measure_text(self, context: &mut UpdateContext<'_, 'gc, '_>) -> (f32, f32) {
+        let font = self.font(context.library);
+        font.register_glyphs(context.renderer, &self.0.read().text);
         let breakpoints = self.line_breaks_cached(context.gc_context, context.library);
 
         let edit_text = self.0.read();
         let static_data = &edit_text.static_data.0;
-        let font_id = static_data.font_id.unwrap_or(0);
 
         let mut size: (f32, f32) = (0.0, 0.0);
 
-        if let Some(font) = context
-            .library
-            .get_font(font_id)
-            .filter(|font| font.has_glyphs())
-            .or_else(|| context.library.device_font())
-        {
-            let mut start = 0;
-            let mut chunks = vec![];
-            for breakpoint in breakpoints {
-                chunks.push(&edit_text.text[start..breakpoint]);
-                start = breakpoint;
-            }
+        let mut start = 0;
+        let mut chunks = vec![];
+        for breakpoint in breakpoints {
+            chunks.push(&edit_text.text[start..breakpoint]);
+            start = breakpoint;
+        }
 
-            chunks.push(&edit_text.text[start..]);
+        chunks.push(&edit_text.text[start..]);
 
-            let height = static_data
-                .height
-                .map(|v| v.to_pixels() as f32)
-                .unwrap_or_else(|| font.scale());
+        let height = static_data
+            .height
+            .map(|v| v.to_pixels() as f32)
+            .unwrap_or_else(|| font.scale());
 
-            for chunk in chunks {
-                let chunk_size = font.measure(chunk, height);
+        for chunk in chunks {
+            let chunk_size = font.measure(chunk, height);
 
-                size.0 = size.0.max(chunk_size.0);
-                if let Some(layout) = &static_data.layout {
-                    size.1 += layout.leading.to_pixels() as f32;
-                }
-                size.1 += chunk_size.1;
+            size.0 = size.0.max(chunk_size.0);
+            if let Some(layout) = &static_data.layout {
+                size.1 += layout.leading.to_pixels() as f32;
             }
+            size.1 += chunk_size.1;
         }
 
         size
@@ -427,60 +435,74 @@ impl<'gc> TDisplayObject<'gc> for EditText<'gc> {
         self.0.read().static_data.0.bounds.clone().into()
     }
 
+    fn hit_test_shape(&self, point: (Twips, Twips)) -> bool {
+        self.world_bounds().contains(point)
+    }
+
+    fn mouse_pick(
+        &self,
+        self_node: DisplayObject<'gc>,
+        point: (Twips, Twips),
+    ) -> Option<DisplayObject<'gc>> {
+        // Text that can be selected or edited takes the mouse, like a button.
+        if (self.is_selectable() || self.is_editable())
+            && self.visible()
+            && self.world_bounds().contains(point)
+        {
+            Some(self_node)
+        } else {
+            None
+        }
+    }
+
     fn render(&self, context: &mut RenderContext<'_, 'gc>) {
         context.transform_stack.push(&*self.transform());
 
         let mut text_transform = self.text_transform();
 
+        // If the font can't be found or has no glyph information, we use a "device font" instead.
+        // We're cheating a bit and not actually rendering text using the OS/web.
+        // Instead, device fonts are drawn from font files provided to the player, and the
+        // glyph outlines are rendered the same as any other SWF outline text.
+        let library = context.library;
+        let font = self.font(library);
         let edit_text = self.0.read();
         let static_data = &edit_text.static_data.0;
-        let font_id = static_data.font_id.unwrap_or(0);
-
-        // If the font can't be found or has no glyph information, use the "device font" instead.
-        // We're cheating a bit and not actually rendering text using the OS/web.
-        // Instead, we embed an SWF version of Noto Sans to use as the "device font", and render
-        // it the same as any other SWF outline text.
-        if let Some(font) = context
-            .library
-            .get_font(font_id)
-            .filter(|font| font.has_glyphs())
-            .or_else(|| context.library.device_font())
-        {
-            let height = static_data
-                .height
-                .map(|v| v.to_pixels() as f32)
-                .unwrap_or_else(|| font.scale());
-
-            let breakpoints = edit_text
-                .cached_break_points
-                .clone()
-                .unwrap_or_else(|| self.line_breaks(context.library));
-            let mut start = 0;
-            let mut chunks = vec![];
-            for breakpoint in breakpoints {
-                chunks.push(&edit_text.text[start..breakpoint]);
-                start = breakpoint;
-            }
+        font.register_glyphs(context.renderer, &edit_text.text);
+        let settings = library.text_settings(static_data.id);
+
+        let height = static_data
+            .height
+            .map(|v| v.to_pixels() as f32)
+            .unwrap_or_else(|| font.scale());
+
+        let breakpoints = edit_text
+            .cached_break_points
+            .clone()
+            .unwrap_or_else(|| self.line_breaks(library));
+        let mut start = 0;
+        let mut chunks = vec![];
+        for breakpoint in breakpoints {
+            chunks.push(&edit_text.text[start..breakpoint]);
+            start = breakpoint;
+        }
 
-            chunks.push(&edit_text.text[start..]);
-
-            for chunk in chunks {
-                font.evaluate(
-                    chunk,
-                    text_transform.clone(),
-                    height,
-                    |transform, glyph: &Glyph| {
-                        // Render glyph.
-                        context.transform_stack.push(transform);
-                        context
-                            .renderer
-                            .render_shape(glyph.shape, context.transform_stack.transform());
-                        context.transform_stack.pop();
-                    },
-                );
-
-                text_transform = self.newline(height, text_transform);
-            }
+        chunks.push(&edit_text.text[start..]);
+
+        for chunk in chunks {
+            font.evaluate(
+                chunk,
+                text_transform.clone(),
+                height,
+                &mut |transform, glyph: &Glyph| {
+                    // Render glyph.
+                    context.transform_stack.push(transform);
+                    font::render_glyph(context, glyph, font.scale(), settings);
+                    context.transform_stack.pop();
+                },
+            );
+
+            text_transform = self.newline(height, text_transform);
         }
 
         context.transform_stack.pop();
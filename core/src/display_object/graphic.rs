@@ -2,7 +2,12 @@ use crate::backend::render::ShapeHandle;
 use crate::context::{RenderContext, UpdateContext};
 use crate::display_object::{DisplayObjectBase, TDisplayObject};
 use crate::prelude::*;
+use crate::scaling_grid::slice_shape;
+use crate::shape_utils::shape_hit_test;
+use crate::tag_utils::SwfSlice;
+use crate::transform::Transform;
 use gc_arena::{Collect, GcCell};
+use std::cell::RefCell;
 
 #[derive(Clone, Debug, Collect, Copy)]
 #[collect(no_drop)]
@@ -15,11 +20,22 @@ pub struct GraphicData<'gc> {
 }
 
 impl<'gc> Graphic<'gc> {
-    pub fn from_swf_tag(context: &mut UpdateContext<'_, 'gc, '_>, swf_shape: &swf::Shape) -> Self {
+    /// Creates a graphic from a `DefineShape` tag, given the data of the tag and the version
+    /// of `DefineShape` it uses.
+    pub fn from_swf_tag(
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        swf_shape: &swf::Shape,
+        tag_data: SwfSlice,
+        shape_version: u8,
+    ) -> Self {
         let static_data = GraphicStatic {
             id: swf_shape.id,
             render_handle: context.renderer.register_shape(swf_shape),
             bounds: swf_shape.shape_bounds.clone().into(),
+            tag_data,
+            swf_version: context.swf_version,
+            shape_version,
+            sliced_shapes: RefCell::new(vec![]),
         };
         Graphic(GcCell::allocate(
             context.gc_context,
@@ -29,6 +45,17 @@ impl<'gc> Graphic<'gc> {
             },
         ))
     }
+
+    /// Transforms the scaling grid of the parent clip into the space of this shape. Returns
+    /// `None` if the shape is rotated or skewed, in which case it can't be sliced.
+    fn shape_scaling_grid(&self, grid: &BoundingBox) -> Option<BoundingBox> {
+        let mut matrix = *self.matrix();
+        if matrix.b != 0.0 || matrix.c != 0.0 || matrix.a == 0.0 || matrix.d == 0.0 {
+            return None;
+        }
+        matrix.invert();
+        Some(grid.transform(&matrix))
+    }
 }
 
 impl<'gc> TDisplayObject<'gc> for Graphic<'gc> {
@@ -53,6 +80,15 @@ impl<'gc> TDisplayObject<'gc> for Graphic<'gc> {
         bounds
     }
 
+    fn hit_test_shape(&self, point: (Twips, Twips)) -> bool {
+        self.world_bounds().contains(point)
+            && self
+                .0
+                .read()
+                .static_data
+                .hit_test(self.global_to_local(point))
+    }
+
     fn run_frame(&mut self, _context: &mut UpdateContext) {
         // Noop
     }
@@ -63,6 +99,28 @@ impl<'gc> TDisplayObject<'gc> for Graphic<'gc> {
             return;
         }
 
+        if let Some(scaling_grid) = context.scaling_grid.clone() {
+            if let Some(grid) = self.shape_scaling_grid(&scaling_grid.grid) {
+                let static_data = self.0.read().static_data;
+                if let Some(handles) = static_data.sliced_shape_handles(context, &grid) {
+                    let transform = self.transform();
+                    for (region, handle) in scaling_grid.regions.iter().zip(handles) {
+                        if let Some(handle) = handle {
+                            context.transform_stack.push(&Transform {
+                                matrix: *region * transform.matrix,
+                                color_transform: transform.color_transform,
+                            });
+                            context
+                                .renderer
+                                .render_shape(handle, context.transform_stack.transform());
+                            context.transform_stack.pop();
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+
         context.transform_stack.push(&*self.transform());
 
         context.renderer.render_shape(
@@ -87,6 +145,71 @@ struct GraphicStatic {
     id: CharacterId,
     render_handle: ShapeHandle,
     bounds: BoundingBox,
+
+    /// The `DefineShape` tag, which is only read again if the shape is sliced or hit tested, so
+    /// that every shape doesn't have to keep a copy of its records.
+    tag_data: SwfSlice,
+    swf_version: u8,
+    shape_version: u8,
+
+    /// The shape split along each scaling grid it has been rendered with, keyed by the grid in
+    /// the space of the shape.
+    sliced_shapes: RefCell<Vec<(BoundingBox, Vec<Option<ShapeHandle>>)>>,
+}
+
+impl GraphicStatic {
+    /// Gets the handles of the nine parts of this shape split along the given grid, registering
+    /// them with the renderer the first time. Returns `None` if the shape can't be read.
+    fn sliced_shape_handles(
+        &self,
+        context: &mut RenderContext,
+        grid: &BoundingBox,
+    ) -> Option<Vec<Option<ShapeHandle>>> {
+        let mut sliced_shapes = self.sliced_shapes.borrow_mut();
+        if let Some((_, handles)) = sliced_shapes.iter().find(|(sliced_grid, _)| {
+            sliced_grid.x_min == grid.x_min
+                && sliced_grid.y_min == grid.y_min
+                && sliced_grid.x_max == grid.x_max
+                && sliced_grid.y_max == grid.y_max
+        }) {
+            return Some(handles.clone());
+        }
+
+        let shape = match self.read_shape() {
+            Ok(shape) => shape,
+            Err(e) => {
+                log::error!("Unable to read shape {} to slice it: {}", self.id, e);
+                return None;
+            }
+        };
+        let handles: Vec<_> = slice_shape(&shape, grid)
+            .iter()
+            .map(|shape| {
+                shape
+                    .as_ref()
+                    .map(|shape| context.renderer.register_shape(shape))
+            })
+            .collect();
+        sliced_shapes.push((grid.clone(), handles.clone()));
+        Some(handles)
+    }
+
+    /// Tests whether a point in the space of the shape is inside its fills or strokes.
+    fn hit_test(&self, point: (Twips, Twips)) -> bool {
+        match self.read_shape() {
+            Ok(shape) => shape_hit_test(&shape, point),
+            Err(e) => {
+                log::error!("Unable to read shape {} to hit test it: {}", self.id, e);
+                false
+            }
+        }
+    }
+
+    /// Reads the shape from its `DefineShape` tag again.
+    fn read_shape(&self) -> swf::error::Result<swf::Shape> {
+        swf::read::Reader::new(self.tag_data.as_ref(), self.swf_version)
+            .read_define_shape(self.shape_version)
+    }
 }
 
 unsafe impl<'gc> gc_arena::Collect for GraphicStatic {
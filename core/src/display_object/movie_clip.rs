@@ -1,14 +1,16 @@
 //! `MovieClip` display object and support code.
-use crate::avm1::{Object, StageObject, Value};
+use crate::avm1::globals::movie_clip::attach_virtual_properties;
+use crate::avm1::{Object, StageObject, TObject, Value};
 use crate::backend::audio::AudioStreamHandle;
 use crate::character::Character;
 use crate::context::{ActionType, RenderContext, UpdateContext};
 use crate::display_object::{
     Bitmap, Button, DisplayObjectBase, EditText, Graphic, MorphShapeStatic, TDisplayObject, Text,
 };
-use crate::events::{ButtonKeyCode, ClipEvent};
+use crate::events::{ButtonEvent, ButtonKeyCode, ClipEvent};
 use crate::font::Font;
 use crate::prelude::*;
+use crate::scaling_grid::ScalingGridTransform;
 use crate::tag_utils::{self, DecodeResult, SwfSlice, SwfStream};
 use enumset::{EnumSet, EnumSetType};
 use gc_arena::{Collect, Gc, GcCell, MutationContext};
@@ -20,6 +22,29 @@ use swf::read::SwfRead;
 
 type FrameNumber = u16;
 
+/// The event handlers that make a movie clip act as a button.
+const BUTTON_HANDLERS: [&str; 7] = [
+    "onPress",
+    "onRelease",
+    "onReleaseOutside",
+    "onRollOver",
+    "onRollOut",
+    "onDragOver",
+    "onDragOut",
+];
+
+/// The clip events that make a movie clip act as a button, created with `on(press)` and
+/// similar handlers on the clip instance.
+const BUTTON_CLIP_EVENTS: [ClipEvent; 7] = [
+    ClipEvent::Press,
+    ClipEvent::Release,
+    ClipEvent::ReleaseOutside,
+    ClipEvent::RollOver,
+    ClipEvent::RollOut,
+    ClipEvent::DragOver,
+    ClipEvent::DragOut,
+];
+
 /// A movie clip is a display object with its own timeline that runs independently of the root timeline.
 /// The SWF19 spec calls this "Sprite" and the SWF tag defines it is "DefineSprite".
 /// However, in AVM2, Sprite is a separate display object, and MovieClip is a subclass of Sprite.
@@ -37,10 +62,12 @@ pub struct MovieClipData<'gc> {
     tag_stream_pos: u64,
     current_frame: FrameNumber,
     audio_stream: Option<AudioStreamHandle>,
+    audio_stream_start_frame: FrameNumber,
     children: BTreeMap<Depth, DisplayObject<'gc>>,
     object: Option<Object<'gc>>,
     clip_actions: SmallVec<[ClipAction; 2]>,
     flags: EnumSet<MovieClipFlags>,
+    scaling_grid: Option<BoundingBox>,
 }
 
 impl<'gc> MovieClip<'gc> {
@@ -55,10 +82,12 @@ impl<'gc> MovieClip<'gc> {
                 tag_stream_pos: 0,
                 current_frame: 0,
                 audio_stream: None,
+                audio_stream_start_frame: 0,
                 children: BTreeMap::new(),
                 object: None,
                 clip_actions: SmallVec::new(),
-                flags: EnumSet::empty(),
+                flags: MovieClipFlags::Enabled | MovieClipFlags::UseHandCursor,
+                scaling_grid: None,
             },
         ))
     }
@@ -90,10 +119,14 @@ impl<'gc> MovieClip<'gc> {
                 tag_stream_pos: 0,
                 current_frame: 0,
                 audio_stream: None,
+                audio_stream_start_frame: 0,
                 children: BTreeMap::new(),
                 object: None,
                 clip_actions: SmallVec::new(),
-                flags: MovieClipFlags::Playing.into(),
+                flags: MovieClipFlags::Playing
+                    | MovieClipFlags::Enabled
+                    | MovieClipFlags::UseHandCursor,
+                scaling_grid: None,
             },
         ))
     }
@@ -108,7 +141,6 @@ impl<'gc> MovieClip<'gc> {
             .preload(context, morph_shapes, self.into())
     }
 
-    #[allow(dead_code)]
     pub fn playing(self) -> bool {
         self.0.read().playing()
     }
@@ -154,6 +186,13 @@ impl<'gc> MovieClip<'gc> {
         self.0.read().static_data.total_frames
     }
 
+    /// The stream sound playing on this clip's timeline, and the frame that the stream started on.
+    pub fn audio_stream(self) -> Option<(AudioStreamHandle, FrameNumber)> {
+        let clip = self.0.read();
+        clip.audio_stream
+            .map(|stream| (stream, clip.audio_stream_start_frame))
+    }
+
     pub fn frames_loaded(self) -> FrameNumber {
         // TODO(Herschel): root needs to progressively stream in frames.
         self.0.read().static_data.total_frames
@@ -186,6 +225,73 @@ impl<'gc> MovieClip<'gc> {
         self.0.write(gc_context).set_clip_actions(actions);
     }
 
+    /// The 9-slice scaling grid of this clip, in its local space.
+    pub fn scaling_grid(self) -> Option<BoundingBox> {
+        self.0.read().scaling_grid.clone()
+    }
+
+    /// Sets the 9-slice scaling grid of this clip, set by `DefineScalingGrid` or `scale9Grid`.
+    pub fn set_scaling_grid(self, gc_context: MutationContext<'gc, '_>, grid: Option<BoundingBox>) {
+        self.0.write(gc_context).scaling_grid = grid;
+    }
+
+    /// Whether this clip acts as a button, because it has button event handlers. Such clips
+    /// receive mouse events instead of their children, and show the hand cursor.
+    pub fn is_button_mode(self) -> bool {
+        let mc = self.0.read();
+        let has_clip_actions = mc.clip_actions.iter().any(|action| {
+            action
+                .events
+                .iter()
+                .any(|event| BUTTON_CLIP_EVENTS.contains(event))
+        });
+        has_clip_actions
+            || mc.object.map_or(false, |object| {
+                BUTTON_HANDLERS.iter().any(|name| object.has_property(name))
+            })
+    }
+
+    /// Whether this clip responds to the mouse when it acts as a button.
+    pub fn enabled(self) -> bool {
+        self.0.read().flags.contains(MovieClipFlags::Enabled)
+    }
+
+    pub fn set_enabled(self, gc_context: MutationContext<'gc, '_>, enabled: bool) {
+        self.0
+            .write(gc_context)
+            .set_flag(MovieClipFlags::Enabled, enabled);
+    }
+
+    /// Whether this clip is tracked as a menu item when it acts as a button, which lets it be
+    /// pressed by dragging onto it with the mouse button held down.
+    pub fn track_as_menu(self) -> bool {
+        self.0.read().flags.contains(MovieClipFlags::TrackAsMenu)
+    }
+
+    pub fn set_track_as_menu(self, gc_context: MutationContext<'gc, '_>, track_as_menu: bool) {
+        self.0
+            .write(gc_context)
+            .set_flag(MovieClipFlags::TrackAsMenu, track_as_menu);
+    }
+
+    /// Whether the hand cursor is shown over this clip when it acts as a button.
+    pub fn use_hand_cursor(self) -> bool {
+        self.0.read().flags.contains(MovieClipFlags::UseHandCursor)
+    }
+
+    pub fn set_use_hand_cursor(self, gc_context: MutationContext<'gc, '_>, use_hand_cursor: bool) {
+        self.0
+            .write(gc_context)
+            .set_flag(MovieClipFlags::UseHandCursor, use_hand_cursor);
+    }
+
+    /// Runs the handlers for a mouse event on a clip that acts as a button.
+    pub fn handle_button_event(self, context: &mut UpdateContext<'_, 'gc, '_>, event: ButtonEvent) {
+        self.0
+            .read()
+            .run_clip_action(self.into(), context, event.clip_event());
+    }
+
     /// Adds a script-created display object as a child to this clip.
     pub fn add_child_from_avm(
         &mut self,
@@ -322,7 +428,16 @@ impl<'gc> TDisplayObject<'gc> for MovieClip<'gc> {
 
     fn render(&self, context: &mut RenderContext<'_, 'gc>) {
         context.transform_stack.push(&*self.transform());
+        let scaling_grid = self.scaling_grid().and_then(|grid| {
+            ScalingGridTransform::new(
+                &grid,
+                &self.self_bounds(),
+                &context.transform_stack.transform().matrix,
+            )
+        });
+        let parent_scaling_grid = std::mem::replace(&mut context.scaling_grid, scaling_grid);
         crate::display_object::render_children(context, &self.0.read().children);
+        context.scaling_grid = parent_scaling_grid;
         context.transform_stack.pop();
     }
 
@@ -330,12 +445,33 @@ impl<'gc> TDisplayObject<'gc> for MovieClip<'gc> {
         self.world_bounds().contains(point)
     }
 
+    fn hit_test_shape(&self, point: (Twips, Twips)) -> bool {
+        self.0
+            .read()
+            .children
+            .values()
+            .any(|child| can_hit_child(*child, point) && child.hit_test_shape(point))
+    }
+
     fn mouse_pick(
         &self,
-        _self_node: DisplayObject<'gc>,
+        self_node: DisplayObject<'gc>,
         point: (Twips, Twips),
     ) -> Option<DisplayObject<'gc>> {
+        // A clip that acts as a button takes the mouse from its children, wherever they're drawn.
+        if self.is_button_mode() {
+            return if self.visible() && self.hit_test_shape(point) {
+                Some(self_node)
+            } else {
+                None
+            };
+        }
+
         for child in self.0.read().children.values().rev() {
+            if !can_hit_child(*child, point) {
+                continue;
+            }
+
             let result = child.mouse_pick(*child, point);
             if result.is_some() {
                 return result;
@@ -366,8 +502,12 @@ impl<'gc> TDisplayObject<'gc> for MovieClip<'gc> {
     ) {
         let mut mc = self.0.write(gc_context);
         if mc.object.is_none() {
-            let object = StageObject::for_display_object(gc_context, display_object, Some(proto));
-            mc.object = Some(object.into());
+            let object =
+                StageObject::for_display_object(gc_context, display_object, Some(proto)).into();
+
+            attach_virtual_properties(gc_context, object);
+
+            mc.object = Some(object);
         }
     }
 
@@ -430,6 +570,14 @@ impl<'gc> MovieClipData<'gc> {
         }
     }
 
+    fn set_flag(&mut self, flag: MovieClipFlags, value: bool) {
+        if value {
+            self.flags.insert(flag);
+        } else {
+            self.flags.remove(flag);
+        }
+    }
+
     fn first_child(&self) -> Option<DisplayObject<'gc>> {
         self.base.first_child()
     }
@@ -452,7 +600,9 @@ impl<'gc> MovieClipData<'gc> {
         self.set_playing(false);
         // Stop audio stream if one is playing.
         if let Some(audio_stream) = self.audio_stream.take() {
-            context.audio.stop_stream(audio_stream);
+            context
+                .audio_manager
+                .stop_stream(context.audio, audio_stream);
         }
     }
 
@@ -544,10 +694,11 @@ impl<'gc> MovieClipData<'gc> {
             TagCode::RemoveObject if run_display_actions => self.remove_object(context, reader, 1),
             TagCode::RemoveObject2 if run_display_actions => self.remove_object(context, reader, 2),
             TagCode::SetBackgroundColor => self.set_background_color(context, reader),
-            TagCode::StartSound => self.start_sound_1(context, reader),
+            TagCode::SetTabIndex if run_display_actions => self.set_tab_index(context, reader),
+            TagCode::StartSound => self.start_sound_1(self_display_object, context, reader),
             TagCode::SoundStreamBlock => {
                 has_stream_block = true;
-                self.sound_stream_block(context, reader)
+                self.sound_stream_block(self_display_object, context, reader)
             }
             _ => Ok(()),
         };
@@ -558,7 +709,9 @@ impl<'gc> MovieClipData<'gc> {
         // If we are playing a streaming sound, there should(?) be a `SoundStreamBlock` on each frame.
         if let Some(audio_stream) = self.audio_stream {
             if !has_stream_block {
-                context.audio.stop_stream(audio_stream);
+                context
+                    .audio_manager
+                    .stop_stream(context.audio, audio_stream);
                 self.audio_stream = None;
             }
         }
@@ -649,6 +802,13 @@ impl<'gc> MovieClipData<'gc> {
         context: &mut UpdateContext<'_, 'gc, '_>,
         frame: FrameNumber,
     ) {
+        // The stream sound restarts from the destination frame.
+        if let Some(audio_stream) = self.audio_stream.take() {
+            context
+                .audio_manager
+                .stop_stream(context.audio, audio_stream);
+        }
+
         // Flash gotos are tricky:
         // 1) Conceptually, a goto should act like the playhead is advancing forward or
         //    backward to a frame.
@@ -966,6 +1126,7 @@ impl<'gc, 'a> MovieClipData<'gc> {
         let mut cur_frame = 1;
         let mut ids = fnv::FnvHashMap::default();
         let tag_callback = |reader: &mut _, tag_code, tag_len| match tag_code {
+            TagCode::CsmTextSettings => self.csm_text_settings(context, reader),
             TagCode::DefineBits => self.define_bits(context, reader, tag_len),
             TagCode::DefineBitsJpeg2 => self.define_bits_jpeg_2(context, reader, tag_len),
             TagCode::DefineBitsJpeg3 => self.define_bits_jpeg_3(context, reader, tag_len),
@@ -981,12 +1142,14 @@ impl<'gc, 'a> MovieClipData<'gc> {
             TagCode::DefineFont2 => self.define_font_2(context, reader),
             TagCode::DefineFont3 => self.define_font_3(context, reader),
             TagCode::DefineFont4 => unimplemented!(),
+            TagCode::DefineFontAlignZones => self.define_font_align_zones(context, reader, tag_len),
+            TagCode::DefineScalingGrid => self.define_scaling_grid(context, reader),
             TagCode::DefineMorphShape => self.define_morph_shape(context, reader, morph_shapes, 1),
             TagCode::DefineMorphShape2 => self.define_morph_shape(context, reader, morph_shapes, 2),
-            TagCode::DefineShape => self.define_shape(context, reader, 1),
-            TagCode::DefineShape2 => self.define_shape(context, reader, 2),
-            TagCode::DefineShape3 => self.define_shape(context, reader, 3),
-            TagCode::DefineShape4 => self.define_shape(context, reader, 4),
+            TagCode::DefineShape => self.define_shape(context, reader, tag_len, 1),
+            TagCode::DefineShape2 => self.define_shape(context, reader, tag_len, 2),
+            TagCode::DefineShape3 => self.define_shape(context, reader, tag_len, 3),
+            TagCode::DefineShape4 => self.define_shape(context, reader, tag_len, 4),
             TagCode::DefineSound => self.define_sound(context, reader, tag_len),
             TagCode::DefineSprite => self.define_sprite(context, reader, tag_len, morph_shapes),
             TagCode::DefineText => self.define_text(context, reader, 1),
@@ -1080,10 +1243,18 @@ impl<'gc, 'a> MovieClipData<'gc> {
         &mut self,
         context: &mut UpdateContext<'_, 'gc, '_>,
         reader: &mut SwfStream<&'a [u8]>,
+        tag_len: usize,
         version: u8,
     ) -> DecodeResult {
+        // The shape is read again from the tag if it's sliced by a scaling grid.
+        let start = (self.tag_stream_start() + reader.get_ref().position()) as usize;
+        let tag_data = SwfSlice {
+            data: std::sync::Arc::clone(context.swf_data),
+            start,
+            end: start + tag_len,
+        };
         let swf_shape = reader.read_define_shape(version)?;
-        let graphic = Graphic::from_swf_tag(context, &swf_shape);
+        let graphic = Graphic::from_swf_tag(context, &swf_shape, tag_data, version);
         context
             .library
             .register_character(swf_shape.id, Character::Graphic(graphic));
@@ -1178,6 +1349,18 @@ impl<'gc, 'a> MovieClipData<'gc> {
         Ok(())
     }
 
+    #[inline]
+    fn csm_text_settings(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        reader: &mut SwfStream<&'a [u8]>,
+    ) -> DecodeResult {
+        if let swf::Tag::CsmTextSettings(settings) = reader.read_csm_text_settings()? {
+            context.library.set_text_settings(settings);
+        }
+        Ok(())
+    }
+
     #[inline]
     fn define_bits(
         &mut self,
@@ -1475,6 +1658,46 @@ impl<'gc, 'a> MovieClipData<'gc> {
         Ok(())
     }
 
+    #[inline]
+    fn define_font_align_zones(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        reader: &mut SwfStream<&'a [u8]>,
+        tag_len: usize,
+    ) -> DecodeResult {
+        // The zones run to the end of the tag.
+        use std::io::Read;
+        let mut reader =
+            swf::read::Reader::new(reader.get_mut().take(tag_len as u64), context.swf_version);
+        if let swf::Tag::DefineFontAlignZones { id, zones, .. } =
+            reader.read_define_font_align_zones()?
+        {
+            context.library.set_font_align_zones(id, &zones);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn define_scaling_grid(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        reader: &mut SwfStream<&'a [u8]>,
+    ) -> DecodeResult {
+        let id = reader.read_u16()?;
+        let grid: BoundingBox = reader.read_rectangle()?.into();
+        match context.library.get_character_by_id(id) {
+            Some(Character::MovieClip(movie_clip)) => {
+                movie_clip.set_scaling_grid(context.gc_context, Some(grid))
+            }
+            Some(Character::Button(button)) => {
+                button.set_scaling_grid(context.gc_context, Some(grid))
+            }
+            Some(_) => log::warn!("DefineScalingGrid: Tried to apply on non-sprite ID {}", id),
+            None => log::warn!("DefineScalingGrid: Character ID {} doesn't exist", id),
+        }
+        Ok(())
+    }
+
     #[inline]
     fn define_sound(
         &mut self,
@@ -1487,10 +1710,16 @@ impl<'gc, 'a> MovieClipData<'gc> {
         let mut reader =
             swf::read::Reader::new(reader.get_mut().take(tag_len as u64), context.swf_version);
         let sound = reader.read_define_sound()?;
-        let handle = context.audio.register_sound(&sound).unwrap();
-        context
-            .library
-            .register_character(sound.id, Character::Sound(handle));
+        match context.audio.register_sound(&sound) {
+            Ok(handle) => context
+                .library
+                .register_character(sound.id, Character::Sound(handle)),
+            Err(e) => log::warn!(
+                "DefineSound: Unable to register sound ID {}: {}",
+                sound.id,
+                e
+            ),
+        }
         Ok(())
     }
 
@@ -1754,12 +1983,40 @@ impl<'gc, 'a> MovieClipData<'gc> {
         Ok(())
     }
 
+    /// Handles the `SetTabIndex` tag, which sets the `tabIndex` of a child as if by
+    /// ActionScript.
+    #[inline]
+    fn set_tab_index(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        reader: &mut SwfStream<&'a [u8]>,
+    ) -> DecodeResult {
+        let depth: Depth = reader.read_u16()?.into();
+        let tab_index = reader.read_u16()?;
+        if let Some(child) = self.children.get(&depth) {
+            if let Value::Object(object) = child.object() {
+                object.define_value(
+                    context.gc_context,
+                    "tabIndex",
+                    tab_index.into(),
+                    EnumSet::empty(),
+                );
+            }
+        }
+        Ok(())
+    }
+
     #[inline]
     fn sound_stream_block(
         &mut self,
+        self_display_object: DisplayObject<'gc>,
         context: &mut UpdateContext<'_, 'gc, '_>,
         _reader: &mut SwfStream<&'a [u8]>,
     ) -> DecodeResult {
+        if !self.playing() {
+            return Ok(());
+        }
+
         if let (Some(stream_info), None) = (&self.static_data.audio_stream_info, self.audio_stream)
         {
             let pos = self.tag_stream_start() + self.tag_stream_pos;
@@ -1768,12 +2025,20 @@ impl<'gc, 'a> MovieClipData<'gc> {
                 start: pos as usize,
                 end: self.tag_stream_start() as usize + self.tag_stream_len(),
             };
-            self.audio_stream = Some(context.audio.start_stream(
+            let audio_stream = context.audio.start_stream(
                 self.id(),
                 self.current_frame() + 1,
                 slice,
                 &stream_info,
-            ));
+            );
+            context.audio_manager.add_stream(
+                context.audio,
+                audio_stream,
+                self_display_object,
+                &self.base.global_sound_transform(),
+            );
+            self.audio_stream = Some(audio_stream);
+            self.audio_stream_start_frame = self.current_frame();
         }
 
         Ok(())
@@ -1782,6 +2047,7 @@ impl<'gc, 'a> MovieClipData<'gc> {
     #[inline]
     fn start_sound_1(
         &mut self,
+        self_display_object: DisplayObject<'gc>,
         context: &mut UpdateContext<'_, 'gc, '_>,
         reader: &mut SwfStream<&'a [u8]>,
     ) -> DecodeResult {
@@ -1792,18 +2058,34 @@ impl<'gc, 'a> MovieClipData<'gc> {
             match start_sound.sound_info.event {
                 // "Event" sounds always play, independent of the timeline.
                 SoundEvent::Event => {
-                    context.audio.start_sound(handle, &start_sound.sound_info);
+                    context.audio_manager.start_sound(
+                        context.audio,
+                        handle,
+                        &start_sound.sound_info,
+                        None,
+                        Some(self_display_object),
+                        &self.base.global_sound_transform(),
+                    );
                 }
 
                 // "Start" sounds only play if an instance of the same sound is not already playing.
                 SoundEvent::Start => {
                     if !context.audio.is_sound_playing_with_handle(handle) {
-                        context.audio.start_sound(handle, &start_sound.sound_info);
+                        context.audio_manager.start_sound(
+                            context.audio,
+                            handle,
+                            &start_sound.sound_info,
+                            None,
+                            Some(self_display_object),
+                            &self.base.global_sound_transform(),
+                        );
                     }
                 }
 
                 // "Stop" stops any active instances of a given sound.
-                SoundEvent::Stop => context.audio.stop_sounds_with_handle(handle),
+                SoundEvent::Stop => context
+                    .audio_manager
+                    .stop_sounds_with_handle(context.audio, handle),
             }
         }
         Ok(())
@@ -1959,6 +2241,30 @@ enum MovieClipFlags {
 
     /// Whether this `MovieClip` is playing or stopped.
     Playing,
+
+    /// Whether this `MovieClip` responds to the mouse when it acts as a button, set with
+    /// `enabled`.
+    Enabled,
+
+    /// Whether this `MovieClip` is tracked as a menu item when it acts as a button, set with
+    /// `trackAsMenu`.
+    TrackAsMenu,
+
+    /// Whether the hand cursor is shown over this `MovieClip` when it acts as a button, set
+    /// with `useHandCursor`.
+    UseHandCursor,
+}
+
+/// Whether a child can be hit at a point. Masks can't be hit, and masked objects can only be hit
+/// inside the shapes of their mask.
+fn can_hit_child(child: DisplayObject<'_>, point: (Twips, Twips)) -> bool {
+    if child.maskee().filter(|maskee| !maskee.removed()).is_some() {
+        return false;
+    }
+    child
+        .masker()
+        .filter(|mask| !mask.removed())
+        .map_or(true, |mask| mask.hit_test_shape(point))
 }
 
 /// Actions that are attached to a `MovieClip` event in
@@ -1,5 +1,6 @@
 use crate::context::{RenderContext, UpdateContext};
 use crate::display_object::{DisplayObjectBase, TDisplayObject};
+use crate::font;
 use crate::prelude::*;
 use crate::transform::Transform;
 use gc_arena::{Collect, GcCell};
@@ -61,6 +62,8 @@ impl<'gc> TDisplayObject<'gc> for Text<'gc> {
         let mut font_id = 0;
         let mut height = 0.0;
         let mut transform: Transform = Default::default();
+        let library = context.library;
+        let settings = library.text_settings(tf.static_data.id);
         for block in &tf.static_data.text_blocks {
             if let Some(x) = block.x_offset {
                 transform.matrix.tx = x.get() as f32;
@@ -71,7 +74,7 @@ impl<'gc> TDisplayObject<'gc> for Text<'gc> {
             color = block.color.as_ref().unwrap_or(&color).clone();
             font_id = block.font_id.unwrap_or(font_id);
             height = block.height.map(|h| h.get() as f32).unwrap_or(height);
-            if let Some(font) = context.library.get_font(font_id) {
+            if let Some(font) = library.get_font(font_id) {
                 let scale = height / font.scale();
                 transform.matrix.a = scale;
                 transform.matrix.d = scale;
@@ -82,9 +85,7 @@ impl<'gc> TDisplayObject<'gc> for Text<'gc> {
                 for c in &block.glyphs {
                     if let Some(glyph) = font.get_glyph(c.index as usize) {
                         context.transform_stack.push(&transform);
-                        context
-                            .renderer
-                            .render_shape(glyph.shape, context.transform_stack.transform());
+                        font::render_glyph(context, &glyph, font.scale(), settings);
                         context.transform_stack.pop();
                         transform.matrix.tx += c.advance as f32;
                     }
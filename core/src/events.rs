@@ -1,16 +1,44 @@
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
+/// An input event sent to the player by the frontend.
+///
+/// `MouseWheel` deltas are in lines, where one notch of a mouse wheel scrolls 3 lines, as on
+/// Windows. Positive deltas scroll up.
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum PlayerEvent {
     KeyDown { key_code: KeyCode },
+    KeyUp { key_code: KeyCode },
     MouseMove { x: f64, y: f64 },
-    MouseUp { x: f64, y: f64 },
-    MouseDown { x: f64, y: f64 },
+    MouseUp { x: f64, y: f64, button: MouseButton },
+    MouseDown { x: f64, y: f64, button: MouseButton },
     MouseLeft,
+    MouseWheel { delta: f64 },
     TextInput { codepoint: char },
 }
 
+/// The mouse buttons that Flash content can detect.
+///
+/// Only the left button presses buttons and fires `onMouseDown`; the right button opens the
+/// context menu. All of them can be polled with `Key.isDown`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+impl MouseButton {
+    /// The virtual key code of this button, as used by `Key.isDown`.
+    pub fn key_code(self) -> KeyCode {
+        match self {
+            MouseButton::Left => KeyCode::MouseLeft,
+            MouseButton::Right => KeyCode::MouseRight,
+            MouseButton::Middle => KeyCode::MouseMiddle,
+        }
+    }
+}
+
 /// The events that an AVM1 button can fire.
 ///
 /// In Flash, these are created using `on` code on the button instance:
@@ -19,16 +47,52 @@ pub enum PlayerEvent {
 ///     trace("Button clicked");
 /// }
 /// ```
+///
+/// `DragOut` and `DragOver` are sent while the mouse button is held down, when the mouse
+/// leaves or enters the button. `ReleaseOutside` is sent when the mouse button is released
+/// after the mouse was dragged off the button that it pressed.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-#[allow(dead_code)]
 pub enum ButtonEvent {
     Press,
     Release,
+    ReleaseOutside,
     RollOut,
     RollOver,
+    DragOut,
+    DragOver,
     KeyPress { key_code: ButtonKeyCode },
 }
 
+impl ButtonEvent {
+    /// The name of the ActionScript handler for this event, such as `onPress`.
+    pub fn method_name(self) -> Option<&'static str> {
+        match self {
+            ButtonEvent::Press => Some("onPress"),
+            ButtonEvent::Release => Some("onRelease"),
+            ButtonEvent::ReleaseOutside => Some("onReleaseOutside"),
+            ButtonEvent::RollOut => Some("onRollOut"),
+            ButtonEvent::RollOver => Some("onRollOver"),
+            ButtonEvent::DragOut => Some("onDragOut"),
+            ButtonEvent::DragOver => Some("onDragOver"),
+            ButtonEvent::KeyPress { .. } => None,
+        }
+    }
+
+    /// The clip event that this event fires on a movie clip that acts as a button.
+    pub fn clip_event(self) -> ClipEvent {
+        match self {
+            ButtonEvent::Press => ClipEvent::Press,
+            ButtonEvent::Release => ClipEvent::Release,
+            ButtonEvent::ReleaseOutside => ClipEvent::ReleaseOutside,
+            ButtonEvent::RollOut => ClipEvent::RollOut,
+            ButtonEvent::RollOver => ClipEvent::RollOver,
+            ButtonEvent::DragOut => ClipEvent::DragOut,
+            ButtonEvent::DragOver => ClipEvent::DragOver,
+            ButtonEvent::KeyPress { key_code } => ClipEvent::KeyPress { key_code },
+        }
+    }
+}
+
 /// Whether this button event was handled by some child.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ButtonEventResult {
@@ -67,7 +131,11 @@ pub enum ClipEvent {
 #[repr(u8)]
 pub enum KeyCode {
     Unknown = 0,
+    MouseLeft = 1,
+    MouseRight = 2,
+    MouseMiddle = 4,
     Backspace = 8,
+    Tab = 9,
     Return = 13,
     Shift = 16,
     Control = 17,
@@ -148,6 +216,7 @@ pub enum KeyCode {
     Insert = 45,
     Delete = 46,
     Pause = 19,
+    NumLock = 144,
     ScrollLock = 145,
     F1 = 112,
     F2 = 113,
@@ -298,7 +367,78 @@ pub fn key_code_to_button_key_code(key_code: KeyCode) -> Option<ButtonKeyCode> {
         KeyCode::PgUp => ButtonKeyCode::PgUp,
         KeyCode::PgDown => ButtonKeyCode::PgDown,
         KeyCode::Escape => ButtonKeyCode::Escape,
+        KeyCode::Tab => ButtonKeyCode::Tab,
         _ => return None,
     };
     Some(out)
 }
+
+/// Returns the character that a key types on a US keyboard layout, as reported by
+/// `Key.getAscii`. Control keys return their ASCII control character.
+///
+/// This is used when a frontend can't tell which character a key event typed, e.g. because
+/// the text input event only arrives after the key event.
+pub fn key_code_to_char(key_code: KeyCode, shift: bool) -> Option<char> {
+    let code: u8 = key_code.into();
+    let (lower, upper) = match key_code {
+        KeyCode::Backspace => ('\u{8}', '\u{8}'),
+        KeyCode::Tab => ('\t', '\t'),
+        KeyCode::Return => ('\r', '\r'),
+        KeyCode::Escape => ('\u{1b}', '\u{1b}'),
+        KeyCode::Delete => ('\u{7f}', '\u{7f}'),
+        KeyCode::Space => (' ', ' '),
+        KeyCode::Key0 => ('0', ')'),
+        KeyCode::Key1 => ('1', '!'),
+        KeyCode::Key2 => ('2', '@'),
+        KeyCode::Key3 => ('3', '#'),
+        KeyCode::Key4 => ('4', '$'),
+        KeyCode::Key5 => ('5', '%'),
+        KeyCode::Key6 => ('6', '^'),
+        KeyCode::Key7 => ('7', '&'),
+        KeyCode::Key8 => ('8', '*'),
+        KeyCode::Key9 => ('9', '('),
+        _ if code >= KeyCode::A.into() && code <= KeyCode::Z.into() => {
+            let upper = char::from(code);
+            (upper.to_ascii_lowercase(), upper)
+        }
+        _ if code >= KeyCode::Numpad0.into() && code <= KeyCode::Numpad9.into() => {
+            let digit = char::from(b'0' + code - u8::from(KeyCode::Numpad0));
+            (digit, digit)
+        }
+        KeyCode::Semicolon => (';', ':'),
+        KeyCode::Equals => ('=', '+'),
+        KeyCode::Comma => (',', '<'),
+        KeyCode::Minus => ('-', '_'),
+        KeyCode::Period => ('.', '>'),
+        KeyCode::Slash => ('/', '?'),
+        KeyCode::Grave => ('`', '~'),
+        KeyCode::LBracket => ('[', '{'),
+        KeyCode::Backslash => ('\\', '|'),
+        KeyCode::RBracket => (']', '}'),
+        KeyCode::Apostrophe => ('\'', '"'),
+        KeyCode::Multiply => ('*', '*'),
+        KeyCode::Plus => ('+', '+'),
+        KeyCode::NumpadMinus => ('-', '-'),
+        KeyCode::NumpadPeriod => ('.', '.'),
+        KeyCode::NumpadSlash => ('/', '/'),
+        _ => return None,
+    };
+    Some(if shift { upper } else { lower })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_chars() {
+        assert_eq!(key_code_to_char(KeyCode::A, false), Some('a'));
+        assert_eq!(key_code_to_char(KeyCode::Z, true), Some('Z'));
+        assert_eq!(key_code_to_char(KeyCode::Key2, true), Some('@'));
+        assert_eq!(key_code_to_char(KeyCode::Numpad7, true), Some('7'));
+        assert_eq!(key_code_to_char(KeyCode::Return, false), Some('\r'));
+        assert_eq!(key_code_to_char(KeyCode::Apostrophe, true), Some('"'));
+        assert_eq!(key_code_to_char(KeyCode::Left, false), None);
+        assert_eq!(key_code_to_char(KeyCode::Shift, true), None);
+    }
+}
@@ -0,0 +1,356 @@
+//! Keyboard focus, which is moved between buttons, clips and text fields with Tab.
+
+use crate::avm1::{Avm1, TObject, Value};
+use crate::backend::render::overlay::draw_rect;
+use crate::backend::render::{RenderBackend, ShapeHandle};
+use crate::context::{ActionType, UpdateContext};
+use crate::events::ButtonEvent;
+use crate::prelude::*;
+use gc_arena::{Collect, GcCell, MutationContext};
+use std::cmp::Ordering;
+
+/// The color of the focus rectangle.
+const FOCUS_RECT_COLOR: u32 = 0xffff00;
+
+/// The thickness of the edges of the focus rectangle, in pixels.
+const FOCUS_RECT_THICKNESS: f32 = 2.0;
+
+/// Keeps track of the object with keyboard focus.
+#[derive(Clone, Copy, Collect)]
+#[collect(no_drop)]
+pub struct FocusTracker<'gc>(GcCell<'gc, Option<DisplayObject<'gc>>>);
+
+impl<'gc> FocusTracker<'gc> {
+    pub fn new(gc_context: MutationContext<'gc, '_>) -> Self {
+        Self(GcCell::allocate(gc_context, None))
+    }
+
+    /// The object with focus, if it's still on the stage.
+    pub fn get(self) -> Option<DisplayObject<'gc>> {
+        let focused = *self.0.read();
+        focused.filter(|object| !object.removed())
+    }
+
+    /// Moves the focus to `focused`, calling `onKillFocus` on the object that loses it and
+    /// `onSetFocus` on the object that receives it. The queued actions must be run afterwards.
+    pub fn set(
+        self,
+        focused: Option<DisplayObject<'gc>>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+    ) {
+        let old = self.get();
+        if old.map(|o| o.as_ptr()) == focused.map(|o| o.as_ptr()) {
+            return;
+        }
+        *self.0.write(context.gc_context) = focused;
+
+        // Buttons show their over state while they have focus, as if the mouse was over them.
+        let hovered = context.mouse_hovered_object;
+        let is_hovered = |object: DisplayObject<'gc>| {
+            hovered.map_or(false, |hovered| DisplayObject::ptr_eq(hovered, object))
+        };
+        if let Some(mut button) = old.filter(|&o| !is_hovered(o)).and_then(|o| o.as_button()) {
+            button.handle_button_event(context, ButtonEvent::RollOut);
+        }
+        if let Some(mut button) = focused
+            .filter(|&o| !is_hovered(o))
+            .and_then(|o| o.as_button())
+        {
+            button.handle_button_event(context, ButtonEvent::RollOver);
+        }
+
+        let object_value = |object: Option<DisplayObject<'gc>>| {
+            object.map_or(Value::Null, |object| object.object())
+        };
+        if let Some(old) = old {
+            queue_handler(context, old, "onKillFocus", object_value(focused));
+        }
+        if let Some(focused) = focused {
+            queue_handler(context, focused, "onSetFocus", object_value(old));
+        }
+    }
+}
+
+fn queue_handler<'gc>(
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    display_object: DisplayObject<'gc>,
+    name: &'static str,
+    arg: Value<'gc>,
+) {
+    if let Value::Object(object) = display_object.object() {
+        context.action_queue.queue_actions(
+            display_object,
+            ActionType::ObjectMethod {
+                object,
+                name,
+                args: vec![arg],
+            },
+            false,
+        );
+    }
+}
+
+/// Returns the object that Tab moves the focus to from `focused`, or Shift+Tab if `reverse`
+/// is set. The focus wraps around at either end. Must be called with a stack frame.
+pub fn next_tab_object<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    focused: Option<DisplayObject<'gc>>,
+    reverse: bool,
+) -> Option<DisplayObject<'gc>> {
+    let root = context.root;
+    let order = tab_order(avm, context, root);
+    if order.is_empty() {
+        return None;
+    }
+
+    let position = focused.and_then(|focused| {
+        order
+            .iter()
+            .position(|&object| DisplayObject::ptr_eq(object, focused))
+    });
+    let index = match (position, reverse) {
+        (Some(i), false) => (i + 1) % order.len(),
+        (Some(i), true) => (i + order.len() - 1) % order.len(),
+        (None, false) => 0,
+        (None, true) => order.len() - 1,
+    };
+    Some(order[index])
+}
+
+/// Returns the objects in `root` that can receive focus, in the order that Tab visits them.
+///
+/// If any of them has a `tabIndex`, only those objects are visited, in order of `tabIndex`.
+/// Otherwise, they're visited from top to bottom, then left to right.
+/// Must be called with a stack frame.
+pub fn tab_order<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    root: DisplayObject<'gc>,
+) -> Vec<DisplayObject<'gc>> {
+    let mut objects = vec![];
+    collect_tab_objects(avm, context, root, &mut objects);
+
+    let mut indexed: Vec<_> = objects
+        .iter()
+        .filter_map(
+            |&object| match get_property(avm, context, object, "tabIndex") {
+                Value::Number(index) if !index.is_nan() => Some((index, object)),
+                _ => None,
+            },
+        )
+        .collect();
+    if !indexed.is_empty() {
+        indexed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        return indexed.into_iter().map(|(_, object)| object).collect();
+    }
+
+    objects.sort_by_key(|object| {
+        let bounds = object.world_bounds();
+        (bounds.y_min, bounds.x_min)
+    });
+    objects
+}
+
+/// Adds the visible children of `parent` that can receive focus, and their own children,
+/// unless `tabChildren` is set to false.
+fn collect_tab_objects<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    parent: DisplayObject<'gc>,
+    objects: &mut Vec<DisplayObject<'gc>>,
+) {
+    let swf_version = avm.current_swf_version();
+    let tab_children = get_property(avm, context, parent, "tabChildren");
+    if tab_children != Value::Undefined && !tab_children.as_bool(swf_version) {
+        return;
+    }
+
+    for child in parent.children() {
+        if !child.visible() {
+            continue;
+        }
+
+        let is_tab_enabled = match get_property(avm, context, child, "tabEnabled") {
+            Value::Undefined => is_tab_enabled_by_default(child),
+            tab_enabled => tab_enabled.as_bool(swf_version),
+        };
+        if is_tab_enabled {
+            objects.push(child);
+        }
+
+        // The children of a button are only its graphics.
+        if child.as_button().is_none() {
+            collect_tab_objects(avm, context, child, objects);
+        }
+    }
+}
+
+/// Whether an object is in the tab order when its `tabEnabled` isn't set. This is the case
+/// for buttons, editable text fields and clips with button event handlers.
+fn is_tab_enabled_by_default(object: DisplayObject<'_>) -> bool {
+    if object.as_button().is_some() {
+        true
+    } else if let Some(edit_text) = object.as_edit_text() {
+        edit_text.is_editable()
+    } else if let Some(clip) = object.as_movie_clip() {
+        clip.is_button_mode()
+    } else {
+        false
+    }
+}
+
+/// Reads a property of the AVM1 object of `display_object`.
+fn get_property<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    display_object: DisplayObject<'gc>,
+    name: &str,
+) -> Value<'gc> {
+    match display_object.object() {
+        Value::Object(object) => object.get_resolved(name, avm, context),
+        _ => Value::Undefined,
+    }
+}
+
+/// Draws the focus rectangle around `bounds`, which are in viewport space, with `rect` being a
+/// one pixel square filled with white.
+pub fn render_focus_rect(
+    renderer: &mut dyn RenderBackend,
+    rect: ShapeHandle,
+    bounds: &BoundingBox,
+) {
+    let (left, top) = (
+        bounds.x_min.to_pixels() as f32,
+        bounds.y_min.to_pixels() as f32,
+    );
+    let (right, bottom) = (
+        bounds.x_max.to_pixels() as f32,
+        bounds.y_max.to_pixels() as f32,
+    );
+    let (width, height) = (right - left, bottom - top);
+    let thickness = FOCUS_RECT_THICKNESS;
+    let edges = [
+        (left, top, width, thickness),
+        (left, bottom - thickness, width, thickness),
+        (left, top, thickness, height),
+        (right - thickness, top, thickness, height),
+    ];
+    for &edge in &edges {
+        draw_rect(renderer, rect, edge, FOCUS_RECT_COLOR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avm1::test_utils::with_avm;
+    use crate::display_object::MovieClip;
+
+    /// Adds a clip to `parent`, with the given properties set on it.
+    fn add_clip<'gc>(
+        avm: &mut Avm1<'gc>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        parent: DisplayObject<'gc>,
+        properties: &[(&str, Value<'gc>)],
+    ) -> DisplayObject<'gc> {
+        let mut clip: DisplayObject<'gc> =
+            MovieClip::new(avm.current_swf_version(), context.gc_context).into();
+        clip.post_instantiation(context.gc_context, clip, avm.prototypes().movie_clip);
+        let depth = parent.children().count() as Depth;
+        parent
+            .as_movie_clip()
+            .unwrap()
+            .add_child_from_avm(context, clip, depth);
+
+        let object = clip.object().as_object().unwrap();
+        for (name, value) in properties {
+            object.set(name, value.clone(), avm, context).unwrap();
+        }
+        clip
+    }
+
+    fn assert_order<'gc>(actual: &[DisplayObject<'gc>], expected: &[DisplayObject<'gc>]) {
+        assert_eq!(actual.len(), expected.len());
+        for (&actual, &expected) in actual.iter().zip(expected) {
+            assert!(DisplayObject::ptr_eq(actual, expected));
+        }
+    }
+
+    #[test]
+    fn tab_index_order() {
+        with_avm(8, |avm, context, _this| {
+            let root = context.root;
+            let enabled: (&str, Value) = ("tabEnabled", true.into());
+            let a = add_clip(
+                avm,
+                context,
+                root,
+                &[enabled.clone(), ("tabIndex", 3.into())],
+            );
+            let b = add_clip(
+                avm,
+                context,
+                root,
+                &[enabled.clone(), ("tabIndex", 1.into())],
+            );
+            let c = add_clip(
+                avm,
+                context,
+                root,
+                &[enabled.clone(), ("tabIndex", 2.into())],
+            );
+            // Without a `tabIndex`, this clip is left out.
+            add_clip(avm, context, root, &[enabled]);
+
+            assert_order(&tab_order(avm, context, root), &[b, c, a]);
+        });
+    }
+
+    #[test]
+    fn tab_enabled_and_tab_children() {
+        with_avm(8, |avm, context, _this| {
+            let root = context.root;
+            let enabled: (&str, Value) = ("tabEnabled", true.into());
+            add_clip(avm, context, root, &[("tabEnabled", false.into())]);
+            let a = add_clip(avm, context, root, &[("onRelease", 1.into())]);
+            let b = add_clip(avm, context, root, &[]);
+            let c = add_clip(avm, context, b, &[enabled.clone()]);
+            let d = add_clip(avm, context, root, &[("tabChildren", false.into())]);
+            add_clip(avm, context, d, &[enabled]);
+
+            let order = tab_order(avm, context, root);
+            assert_eq!(order.len(), 2);
+            for &object in &[a, c] {
+                assert!(order.iter().any(|&o| DisplayObject::ptr_eq(o, object)));
+            }
+        });
+    }
+
+    #[test]
+    fn tab_wraps_around() {
+        with_avm(8, |avm, context, _this| {
+            let root = context.root;
+            let enabled: (&str, Value) = ("tabEnabled", true.into());
+            let a = add_clip(
+                avm,
+                context,
+                root,
+                &[enabled.clone(), ("tabIndex", 1.into())],
+            );
+            let b = add_clip(avm, context, root, &[enabled, ("tabIndex", 2.into())]);
+
+            let cases = [
+                (None, false, a),
+                (Some(a), false, b),
+                (Some(b), false, a),
+                (None, true, b),
+                (Some(a), true, b),
+            ];
+            for &(focused, reverse, expected) in &cases {
+                let next = next_tab_object(avm, context, focused, reverse).unwrap();
+                assert!(DisplayObject::ptr_eq(next, expected));
+            }
+        });
+    }
+}
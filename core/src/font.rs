@@ -1,12 +1,17 @@
-use crate::backend::render::{RenderBackend, ShapeHandle};
+use crate::backend::render::{RenderBackend, ShapeHandle, TextRenderSettings};
+use crate::context::RenderContext;
 use crate::prelude::*;
 use crate::transform::Transform;
 use gc_arena::{Collect, Gc, MutationContext};
 
 type Error = Box<dyn std::error::Error>;
 
+mod device_font;
+mod grid_fit;
 mod text_format;
 
+pub use device_font::{DeviceFont, DeviceFonts};
+pub use grid_fit::{grid_fit, GlyphAlignZone};
 pub use text_format::TextFormat;
 
 #[derive(Debug, Clone, Collect, Copy)]
@@ -16,6 +21,10 @@ pub struct Font<'gc>(Gc<'gc, FontData>);
 #[derive(Debug, Clone, Collect)]
 #[collect(require_static)]
 struct FontData {
+    /// The name of the font.
+    /// Used to look up a device font if this font has no glyphs.
+    name: String,
+
     /// The list of glyphs defined in the font.
     /// Used directly by `DefineText` tags.
     glyphs: Vec<Glyph>,
@@ -62,6 +71,7 @@ impl<'gc> Font<'gc> {
         Ok(Font(Gc::allocate(
             gc_context,
             FontData {
+                name: tag.name.clone(),
                 glyphs,
                 code_point_to_glyph,
 
@@ -73,6 +83,11 @@ impl<'gc> Font<'gc> {
         )))
     }
 
+    /// Returns the name of this font.
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
     /// Returns whether this font contains glyph shapes.
     /// If not, this font should be rendered as a device font.
     pub fn has_glyphs(self) -> bool {
@@ -119,6 +134,32 @@ impl<'gc> Font<'gc> {
     pub fn scale(self) -> f32 {
         self.0.scale
     }
+}
+
+/// A font that text can be laid out and rendered with.
+///
+/// This is implemented by fonts embedded in the movie, and by device fonts.
+pub trait TextFont {
+    /// The EM square size that glyph coordinates and advances are relative to.
+    fn scale(&self) -> f32;
+
+    /// The distance from the top of a line of text to its baseline, relative to `scale`.
+    fn ascent(&self) -> f32 {
+        self.scale()
+    }
+
+    /// Returns a glyph entry by character.
+    fn get_glyph_for_char(&self, c: char) -> Option<Glyph>;
+
+    /// Returns whether this font contains kerning information.
+    fn has_kerning_info(&self) -> bool;
+
+    /// Given a pair of characters, returns the offset that should be applied
+    /// to the advance value between these two characters.
+    fn get_kerning_offset(&self, left: char, right: char) -> Twips;
+
+    /// Prepares the glyphs needed to draw the given text, before it is laid out.
+    fn register_glyphs(&self, _renderer: &mut dyn RenderBackend, _text: &str) {}
 
     /// Evaluate this font against a particular string on a glyph-by-glyph
     /// basis.
@@ -128,16 +169,15 @@ impl<'gc> Font<'gc> {
     /// of transforms and glyphs which will be consumed by the `glyph_func`
     /// closure. This corresponds to the series of drawing operations necessary
     /// to render the text on a single horizontal line.
-    pub fn evaluate<FGlyph>(
-        self,
+    fn evaluate(
+        &self,
         text: &str,
         mut transform: Transform,
         height: f32,
-        mut glyph_func: FGlyph,
-    ) where
-        FGlyph: FnMut(&Transform, &Glyph),
-    {
-        transform.matrix.ty += height * Twips::TWIPS_PER_PIXEL as f32;
+        glyph_func: &mut dyn FnMut(&Transform, &Glyph),
+    ) {
+        transform.matrix.ty +=
+            height * Twips::TWIPS_PER_PIXEL as f32 * (self.ascent() / self.scale());
         let scale = (height * Twips::TWIPS_PER_PIXEL as f32) / self.scale();
 
         transform.matrix.a = scale;
@@ -160,15 +200,20 @@ impl<'gc> Font<'gc> {
     }
 
     /// Measure a particular string's metrics (width and height).
-    pub fn measure(self, text: &str, height: f32) -> (f32, f32) {
+    fn measure(&self, text: &str, height: f32) -> (f32, f32) {
         let mut size = (0.0, 0.0);
 
-        self.evaluate(text, Default::default(), height, |transform, _glyph| {
-            let tx = transform.matrix.tx / Twips::TWIPS_PER_PIXEL as f32;
-            let ty = transform.matrix.ty / Twips::TWIPS_PER_PIXEL as f32;
-            size.0 = f32::max(size.0, tx);
-            size.1 = f32::max(size.1, ty);
-        });
+        self.evaluate(
+            text,
+            Default::default(),
+            height,
+            &mut |transform, _glyph| {
+                let tx = transform.matrix.tx / Twips::TWIPS_PER_PIXEL as f32;
+                let ty = transform.matrix.ty / Twips::TWIPS_PER_PIXEL as f32;
+                size.0 = f32::max(size.0, tx);
+                size.1 = f32::max(size.1, ty);
+            },
+        );
 
         size
     }
@@ -178,7 +223,7 @@ impl<'gc> Font<'gc> {
     ///
     /// This function assumes only `" "` is valid whitespace to split words on,
     /// and will not attempt to break words that are longer than `width`.
-    pub fn split_wrapped_lines(self, text: &str, height: f32, width: f32) -> Vec<usize> {
+    fn split_wrapped_lines(&self, text: &str, height: f32, width: f32) -> Vec<usize> {
         let mut result = vec![];
         let mut current_width = width;
         let mut current_word = &text[0..0];
@@ -223,8 +268,64 @@ impl<'gc> Font<'gc> {
     }
 }
 
+impl<'gc> TextFont for Font<'gc> {
+    fn scale(&self) -> f32 {
+        Font::scale(*self)
+    }
+
+    fn get_glyph_for_char(&self, c: char) -> Option<Glyph> {
+        Font::get_glyph_for_char(*self, c)
+    }
+
+    fn has_kerning_info(&self) -> bool {
+        Font::has_kerning_info(*self)
+    }
+
+    fn get_kerning_offset(&self, left: char, right: char) -> Twips {
+        Font::get_kerning_offset(*self, left, right)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Glyph {
     pub shape: ShapeHandle,
     pub advance: i16,
 }
+
+/// Renders a glyph of a text field with the transform at the top of the transform stack.
+///
+/// `scale` is the EM square size of the glyph's font. If the text field has advanced
+/// anti-aliasing enabled by a `CSMTextSettings` tag, the glyph is grid fitted to its
+/// alignment zones and rendered with the thickness and sharpness of the settings.
+pub fn render_glyph(
+    context: &mut RenderContext<'_, '_>,
+    glyph: &Glyph,
+    scale: f32,
+    settings: Option<&swf::CsmTextSettings>,
+) {
+    let settings = match settings {
+        Some(settings) if settings.use_advanced_rendering => settings,
+        _ => {
+            context
+                .renderer
+                .render_shape(glyph.shape, context.transform_stack.transform());
+            return;
+        }
+    };
+
+    let mut transform = context.transform_stack.transform().clone();
+    grid_fit(
+        &mut transform.matrix,
+        context.library.glyph_align_zone(glyph.shape),
+        scale,
+        settings.grid_fit,
+    );
+    context.renderer.render_glyph(
+        glyph.shape,
+        &transform,
+        &TextRenderSettings {
+            thickness: settings.thickness,
+            sharpness: settings.sharpness,
+        },
+    );
+}
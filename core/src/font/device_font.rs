@@ -0,0 +1,490 @@
+//! Device fonts, rendered from TrueType/OpenType font files provided to the player.
+
+use super::{Font, Glyph, TextFont};
+use crate::backend::render::RenderBackend;
+use crate::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use swf::ShapeRecord;
+
+type Error = Box<dyn std::error::Error>;
+
+/// The font files used to render text in fonts that aren't embedded in the movie.
+///
+/// Files are registered under their family name, and further names (such as the generic
+/// `_sans`, `_serif` and `_typewriter` families) can be mapped to them. Each name maps to a
+/// list of files in order of preference; a character missing from one file is drawn from
+/// the next one instead.
+///
+/// Glyph outlines are converted to shapes lazily, the first time a character is drawn in
+/// a particular file.
+#[derive(Default)]
+pub struct DeviceFonts {
+    files: Vec<FontFile>,
+
+    /// Maps lowercase font names to indices into `files`.
+    names: HashMap<String, Vec<usize>>,
+
+    /// The glyphs converted so far, keyed by file index and character.
+    /// `None` if the file contains no glyph for the character.
+    glyphs: RefCell<fnv::FnvHashMap<(usize, char), Option<Glyph>>>,
+}
+
+impl DeviceFonts {
+    /// The EM square size that device glyphs are scaled to.
+    /// This matches `DefineFont3` fonts, such as the built-in device font.
+    const EM_SCALE: f32 = 20480.0;
+
+    /// The generic font family used when a font name isn't known.
+    const DEFAULT_FAMILY: &'static str = "_sans";
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a TrueType or OpenType font file, or each font in a font collection.
+    ///
+    /// Returns the family names of the registered fonts.
+    pub fn add_font(&mut self, data: Vec<u8>) -> Result<Vec<String>, Error> {
+        let num_fonts = ttf_parser::fonts_in_collection(&data).unwrap_or(1);
+        let data = std::sync::Arc::new(data);
+        let mut families = vec![];
+        for index in 0..num_fonts {
+            let file = FontFile::new(data.clone(), index)?;
+            let family = file.family.clone();
+            self.names
+                .entry(family.to_lowercase())
+                .or_default()
+                .push(self.files.len());
+            self.files.push(file);
+            families.push(family);
+        }
+        Ok(families)
+    }
+
+    /// Maps a font name to the files of a registered font family.
+    ///
+    /// If the name is already mapped, the family is added as a fallback for characters
+    /// missing from the previously mapped fonts.
+    pub fn map_name(&mut self, name: &str, family: &str) -> Result<(), Error> {
+        let files = self
+            .names
+            .get(&family.to_lowercase())
+            .cloned()
+            .ok_or_else(|| format!("Unknown font family {}", family))?;
+        let mapped = self.names.entry(name.to_lowercase()).or_default();
+        for file in files {
+            if !mapped.contains(&file) {
+                mapped.push(file);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether any font files have been registered.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the device font used to render text in the font with the given name.
+    ///
+    /// Characters that none of the mapped files contain are drawn using the files of the
+    /// default `_sans` family, or all registered files if `_sans` is not mapped, and finally
+    /// using `fallback`.
+    pub fn get<'gc>(&self, name: &str, fallback: Option<Font<'gc>>) -> DeviceFont<'_, 'gc> {
+        let mut chain = self
+            .names
+            .get(&name.to_lowercase())
+            .cloned()
+            .unwrap_or_default();
+        let default_files: Vec<usize> = self
+            .names
+            .get(Self::DEFAULT_FAMILY)
+            .cloned()
+            .unwrap_or_else(|| (0..self.files.len()).collect());
+        for file in default_files {
+            if !chain.contains(&file) {
+                chain.push(file);
+            }
+        }
+
+        DeviceFont {
+            fonts: self,
+            chain,
+            fallback,
+        }
+    }
+}
+
+/// A font rendered from device font files, with a fallback font for missing characters.
+///
+/// Glyphs must be converted with `TextFont::register_glyphs` before they can be used to lay out
+/// text; unconverted characters are skipped, as if the font lacked them.
+pub struct DeviceFont<'a, 'gc> {
+    fonts: &'a DeviceFonts,
+
+    /// The indices of the files to draw glyphs from, in order of preference.
+    chain: Vec<usize>,
+
+    /// The font to draw characters that none of the files contain.
+    fallback: Option<Font<'gc>>,
+}
+
+impl<'a, 'gc> DeviceFont<'a, 'gc> {
+    /// Returns the index of the file that draws the given character, and its glyph.
+    /// The file index is `None` for glyphs from the fallback font.
+    fn find_glyph(&self, c: char) -> Option<(Option<usize>, Glyph)> {
+        let glyphs = self.fonts.glyphs.borrow();
+        for &file in &self.chain {
+            if let Some(Some(glyph)) = glyphs.get(&(file, c)) {
+                return Some((Some(file), glyph.clone()));
+            }
+        }
+        self.fallback
+            .and_then(|font| font.get_glyph_for_char(c))
+            .map(|glyph| (None, glyph))
+    }
+}
+
+impl<'a, 'gc> TextFont for DeviceFont<'a, 'gc> {
+    fn scale(&self) -> f32 {
+        DeviceFonts::EM_SCALE
+    }
+
+    fn ascent(&self) -> f32 {
+        if let Some(&file) = self.chain.first() {
+            let file = &self.fonts.files[file];
+            f32::from(file.ascent) * DeviceFonts::EM_SCALE / file.units_per_em
+        } else {
+            DeviceFonts::EM_SCALE
+        }
+    }
+
+    /// Converts the glyphs needed to draw `text` into shapes, if they haven't been already.
+    fn register_glyphs(&self, renderer: &mut dyn RenderBackend, text: &str) {
+        let mut glyphs = self.fonts.glyphs.borrow_mut();
+        for c in text.chars() {
+            for &file in &self.chain {
+                let glyph = glyphs
+                    .entry((file, c))
+                    .or_insert_with(|| self.fonts.files[file].convert_glyph(renderer, c));
+                if glyph.is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn get_glyph_for_char(&self, c: char) -> Option<Glyph> {
+        self.find_glyph(c).map(|(_, glyph)| glyph)
+    }
+
+    fn has_kerning_info(&self) -> bool {
+        self.chain
+            .iter()
+            .any(|&file| self.fonts.files[file].has_kerning)
+            || self.fallback.map_or(false, |font| font.has_kerning_info())
+    }
+
+    fn get_kerning_offset(&self, left: char, right: char) -> Twips {
+        // Only apply kerning between two characters drawn from the same font.
+        match (self.find_glyph(left), self.find_glyph(right)) {
+            (Some((Some(left_file), _)), Some((Some(right_file), _)))
+                if left_file == right_file =>
+            {
+                self.fonts.files[left_file].kerning_offset(left, right)
+            }
+            (Some((None, _)), Some((None, _))) => self
+                .fallback
+                .map(|font| font.get_kerning_offset(left, right))
+                .unwrap_or_default(),
+            _ => Default::default(),
+        }
+    }
+}
+
+/// A font in a TrueType/OpenType font file or collection.
+struct FontFile {
+    data: std::sync::Arc<Vec<u8>>,
+
+    /// The index of the font in a font collection.
+    index: u32,
+
+    family: String,
+    units_per_em: f32,
+    ascent: i16,
+    has_kerning: bool,
+}
+
+impl FontFile {
+    fn new(data: std::sync::Arc<Vec<u8>>, index: u32) -> Result<Self, Error> {
+        let font = ttf_parser::Font::from_data(&data, index).ok_or("Invalid font file")?;
+        let family = font.family_name().ok_or("Font has no family name")?;
+        let units_per_em = f32::from(font.units_per_em().ok_or("Font has no EM size")?);
+        let ascent = font.ascender();
+        let has_kerning = font.kerning_subtables().next().is_some();
+        Ok(Self {
+            data,
+            index,
+            family,
+            units_per_em,
+            ascent,
+            has_kerning,
+        })
+    }
+
+    fn parse(&self) -> Option<ttf_parser::Font<'_>> {
+        ttf_parser::Font::from_data(&self.data, self.index)
+    }
+
+    /// Converts the outline of the glyph for the given character into a shape.
+    fn convert_glyph(&self, renderer: &mut dyn RenderBackend, c: char) -> Option<Glyph> {
+        let font = self.parse()?;
+        let glyph_id = font.glyph_index(c)?;
+        let scale = DeviceFonts::EM_SCALE / self.units_per_em;
+
+        let mut builder = ShapeRecordBuilder::new(scale);
+        // Glyphs without an outline, such as spaces, result in an empty shape.
+        font.outline_glyph(glyph_id, &mut builder);
+
+        let advance = f32::from(font.glyph_hor_advance(glyph_id).unwrap_or(0)) * scale;
+        let advance = advance.round().min(f32::from(std::i16::MAX)) as i16;
+        let swf_glyph = swf::Glyph {
+            shape_records: builder.records,
+            code: c as u16,
+            advance: Some(advance),
+            bounds: None,
+        };
+        Some(Glyph {
+            shape: renderer.register_glyph_shape(&swf_glyph),
+            advance,
+        })
+    }
+
+    /// Returns the horizontal kerning between two characters, in device font units.
+    fn kerning_offset(&self, left: char, right: char) -> Twips {
+        let font = match self.parse() {
+            Some(font) => font,
+            None => return Default::default(),
+        };
+        let (left, right) = match (font.glyph_index(left), font.glyph_index(right)) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return Default::default(),
+        };
+        let kerning = font
+            .kerning_subtables()
+            .filter(|subtable| subtable.is_horizontal() && !subtable.has_cross_stream())
+            .filter_map(|subtable| subtable.glyphs_kerning(left, right))
+            .next()
+            .unwrap_or(0);
+        let scale = DeviceFonts::EM_SCALE / self.units_per_em;
+        Twips::new((f32::from(kerning) * scale).round() as i32)
+    }
+}
+
+/// Builds SWF shape records from a TrueType/OpenType glyph outline.
+///
+/// Font outlines are y-up, while SWF shapes are y-down. Cubic curves, which SWF shapes
+/// lack, are approximated by quadratic curves.
+struct ShapeRecordBuilder {
+    /// The scale from font units to shape units.
+    scale: f32,
+
+    records: Vec<ShapeRecord>,
+
+    /// The current point in font units.
+    point: (f32, f32),
+
+    /// The start of the current contour in font units.
+    contour_start: (f32, f32),
+
+    /// The current point in shape units.
+    /// Edges are relative to this rounded position, so rounding errors don't accumulate.
+    position: (i32, i32),
+}
+
+impl ShapeRecordBuilder {
+    fn new(scale: f32) -> Self {
+        Self {
+            scale,
+            records: vec![],
+            point: (0.0, 0.0),
+            contour_start: (0.0, 0.0),
+            position: (0, 0),
+        }
+    }
+
+    fn to_shape_units(&self, x: f32, y: f32) -> (i32, i32) {
+        (
+            (x * self.scale).round() as i32,
+            (-y * self.scale).round() as i32,
+        )
+    }
+}
+
+impl ttf_parser::OutlineBuilder for ShapeRecordBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let position = self.to_shape_units(x, y);
+        // The first style change sets the fill, which is kept for the rest of the glyph.
+        let fill_style_1 = if self.records.is_empty() {
+            Some(1)
+        } else {
+            None
+        };
+        self.records
+            .push(ShapeRecord::StyleChange(swf::StyleChangeData {
+                move_to: Some((Twips::new(position.0), Twips::new(position.1))),
+                fill_style_0: None,
+                fill_style_1,
+                line_style: None,
+                new_styles: None,
+            }));
+        self.point = (x, y);
+        self.contour_start = (x, y);
+        self.position = position;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let position = self.to_shape_units(x, y);
+        self.records.push(ShapeRecord::StraightEdge {
+            delta_x: Twips::new(position.0 - self.position.0),
+            delta_y: Twips::new(position.1 - self.position.1),
+        });
+        self.point = (x, y);
+        self.position = position;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let control = self.to_shape_units(x1, y1);
+        let anchor = self.to_shape_units(x, y);
+        self.records.push(ShapeRecord::CurvedEdge {
+            control_delta_x: Twips::new(control.0 - self.position.0),
+            control_delta_y: Twips::new(control.1 - self.position.1),
+            anchor_delta_x: Twips::new(anchor.0 - control.0),
+            anchor_delta_y: Twips::new(anchor.1 - control.1),
+        });
+        self.point = (x, y);
+        self.position = anchor;
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        // Split the cubic curve in half, and approximate each half with a quadratic curve
+        // through the same end points.
+        let lerp = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+        let p0 = self.point;
+        let (p1, p2, p3) = ((x1, y1), (x2, y2), (x, y));
+        let p01 = lerp(p0, p1);
+        let p12 = lerp(p1, p2);
+        let p23 = lerp(p2, p3);
+        let p012 = lerp(p01, p12);
+        let p123 = lerp(p12, p23);
+        let mid = lerp(p012, p123);
+
+        let control = |a: (f32, f32), b: (f32, f32), c: (f32, f32), d: (f32, f32)| {
+            (
+                (3.0 * (b.0 + c.0) - a.0 - d.0) / 4.0,
+                (3.0 * (b.1 + c.1) - a.1 - d.1) / 4.0,
+            )
+        };
+        let control1 = control(p0, p01, p012, mid);
+        let control2 = control(mid, p123, p23, p3);
+        self.quad_to(control1.0, control1.1, mid.0, mid.1);
+        self.quad_to(control2.0, control2.1, p3.0, p3.1);
+    }
+
+    fn close(&mut self) {
+        let start = self.contour_start;
+        if self.to_shape_units(start.0, start.1) != self.position {
+            self.line_to(start.0, start.1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ttf_parser::OutlineBuilder;
+
+    #[test]
+    fn outline_to_shape_records() {
+        let mut builder = ShapeRecordBuilder::new(2.0);
+        builder.move_to(0.0, 0.0);
+        builder.line_to(10.0, 0.0);
+        builder.quad_to(10.0, 10.0, 0.0, 10.0);
+        builder.close();
+        builder.move_to(2.25, 2.25);
+        builder.line_to(2.5, 2.5);
+        builder.close();
+
+        assert_eq!(
+            builder.records,
+            vec![
+                ShapeRecord::StyleChange(swf::StyleChangeData {
+                    move_to: Some((Twips::new(0), Twips::new(0))),
+                    fill_style_0: None,
+                    fill_style_1: Some(1),
+                    line_style: None,
+                    new_styles: None,
+                }),
+                ShapeRecord::StraightEdge {
+                    delta_x: Twips::new(20),
+                    delta_y: Twips::new(0),
+                },
+                ShapeRecord::CurvedEdge {
+                    control_delta_x: Twips::new(0),
+                    control_delta_y: Twips::new(-20),
+                    anchor_delta_x: Twips::new(-20),
+                    anchor_delta_y: Twips::new(0),
+                },
+                ShapeRecord::StraightEdge {
+                    delta_x: Twips::new(0),
+                    delta_y: Twips::new(20),
+                },
+                ShapeRecord::StyleChange(swf::StyleChangeData {
+                    move_to: Some((Twips::new(5), Twips::new(-5))),
+                    fill_style_0: None,
+                    fill_style_1: None,
+                    line_style: None,
+                    new_styles: None,
+                }),
+                ShapeRecord::StraightEdge {
+                    delta_x: Twips::new(0),
+                    delta_y: Twips::new(0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cubic_curve_approximation() {
+        let mut builder = ShapeRecordBuilder::new(1.0);
+        builder.move_to(0.0, 0.0);
+        builder.curve_to(0.0, 100.0, 100.0, 100.0, 100.0, 0.0);
+
+        let mut end = (0, 0);
+        let mut num_curves = 0;
+        for record in &builder.records {
+            if let ShapeRecord::CurvedEdge {
+                control_delta_x,
+                control_delta_y,
+                anchor_delta_x,
+                anchor_delta_y,
+            } = record
+            {
+                end.0 += control_delta_x.get() + anchor_delta_x.get();
+                end.1 += control_delta_y.get() + anchor_delta_y.get();
+                num_curves += 1;
+            }
+        }
+        assert_eq!(num_curves, 2);
+        assert_eq!(end, (100, 0));
+    }
+
+    #[test]
+    fn invalid_font_file() {
+        let mut fonts = DeviceFonts::new();
+        assert!(fonts.add_font(vec![0; 64]).is_err());
+        assert!(fonts.map_name("_sans", "Missing").is_err());
+        assert!(fonts.is_empty());
+    }
+}
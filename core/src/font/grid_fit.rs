@@ -0,0 +1,170 @@
+//! Grid fitting of glyphs for advanced anti-aliasing, using `DefineFontAlignZones`.
+
+use crate::matrix::Matrix;
+use swf::TextGridFit;
+
+/// The largest EM square size, in pixels, that glyphs are grid fitted at.
+/// Hinting is only useful for small text; larger text keeps its exact outlines.
+const MAX_GRID_FIT_SIZE: f32 = 48.0;
+
+/// The alignment zones of a glyph in glyph space, defined by a `DefineFontAlignZones` tag.
+///
+/// Grid fitting aligns the edges of each zone to pixel boundaries, such as the stems of a
+/// glyph horizontally, or the baseline and x-height vertically.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlyphAlignZone {
+    /// The left and right edges of the horizontal zone.
+    pub x: (f32, f32),
+
+    /// The top and bottom edges of the vertical zone.
+    pub y: (f32, f32),
+}
+
+impl GlyphAlignZone {
+    /// Converts an alignment zone from a `DefineFontAlignZones` tag for a font with the given
+    /// EM square size.
+    ///
+    /// The zone coordinates are relative to a 1024 unit EM square, like `DefineFont2` glyphs,
+    /// and the vertical zone is measured upwards from its bottom edge.
+    pub fn from_swf_zone(zone: &swf::FontAlignZone, scale: f32) -> Self {
+        let scale = scale / 1024.0;
+        let left = f16_to_f32(zone.left as u16) * scale;
+        let width = f16_to_f32(zone.width as u16) * scale;
+        let bottom = f16_to_f32(zone.bottom as u16) * scale;
+        let height = f16_to_f32(zone.height as u16) * scale;
+        Self {
+            x: (left, left + width),
+            y: (-bottom - height, -bottom),
+        }
+    }
+}
+
+/// Adjusts the matrix of a glyph so that its alignment zones fall on pixel boundaries.
+///
+/// `matrix` transforms the glyph to the viewport in twips, and `scale` is the EM square
+/// size of the font. Without alignment zones, only the origin of the glyph is aligned.
+/// `TextGridFit::SubPixel` only aligns the glyph vertically. Rotated, skewed and large
+/// glyphs are not grid fitted.
+pub fn grid_fit(
+    matrix: &mut Matrix,
+    zone: Option<&GlyphAlignZone>,
+    scale: f32,
+    grid_fit: TextGridFit,
+) {
+    if grid_fit == TextGridFit::None
+        || matrix.b != 0.0
+        || matrix.c != 0.0
+        || matrix.d.abs() * scale / 20.0 > MAX_GRID_FIT_SIZE
+    {
+        return;
+    }
+
+    let (x, y) = zone.map_or(((0.0, 0.0), (0.0, 0.0)), |zone| (zone.x, zone.y));
+    fit_axis(&mut matrix.d, &mut matrix.ty, y);
+    if grid_fit == TextGridFit::Pixel {
+        fit_axis(&mut matrix.a, &mut matrix.tx, x);
+    }
+}
+
+/// Scales and translates one axis of a glyph, so that both edges of a zone are rounded to
+/// whole pixels. A zone is never collapsed to less than a pixel.
+#[allow(clippy::float_cmp)]
+fn fit_axis(scale: &mut f32, translate: &mut f32, (start, end): (f32, f32)) {
+    let to_pixels = |coord: f32| (*scale * coord + *translate) / 20.0;
+    let (start_pixels, end_pixels) = (to_pixels(start), to_pixels(end));
+    let fitted_start = start_pixels.round();
+    if start == end {
+        *translate += (fitted_start - start_pixels) * 20.0;
+        return;
+    }
+
+    let mut fitted_end = end_pixels.round();
+    if fitted_end == fitted_start {
+        fitted_end = fitted_start + (end_pixels - start_pixels).signum();
+    }
+    *scale = (fitted_end - fitted_start) * 20.0 / (end - start);
+    *translate = fitted_start * 20.0 - *scale * start;
+}
+
+/// Converts a half-precision float, as stored in `DefineFontAlignZones`, to an `f32`.
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * std::f32::INFINITY,
+        0x1f => std::f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn half_floats() {
+        assert_abs_diff_eq!(f16_to_f32(0x0000), 0.0);
+        assert_abs_diff_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_abs_diff_eq!(f16_to_f32(0xc000), -2.0);
+        assert_abs_diff_eq!(f16_to_f32(0x5a00), 192.0);
+        assert_abs_diff_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+    }
+
+    #[test]
+    fn fit_to_pixels() {
+        // A 1024 unit EM square at 12 pixels, positioned at a fractional pixel.
+        let mut matrix = Matrix {
+            a: 12.0 * 20.0 / 1024.0,
+            d: 12.0 * 20.0 / 1024.0,
+            tx: 10.3 * 20.0,
+            ty: 20.6 * 20.0,
+            ..Default::default()
+        };
+        let zone = GlyphAlignZone {
+            x: (100.0, 200.0),
+            y: (-500.0, 0.0),
+        };
+        grid_fit(&mut matrix, Some(&zone), 1024.0, TextGridFit::Pixel);
+
+        let to_pixels = |x: f32, y: f32| {
+            (
+                (matrix.a * x + matrix.tx) / 20.0,
+                (matrix.d * y + matrix.ty) / 20.0,
+            )
+        };
+        let (left, top) = to_pixels(zone.x.0, zone.y.0);
+        let (right, bottom) = to_pixels(zone.x.1, zone.y.1);
+        assert_abs_diff_eq!(left, 11.0, epsilon = 0.001);
+        assert_abs_diff_eq!(right, 13.0, epsilon = 0.001);
+        assert_abs_diff_eq!(top, 15.0, epsilon = 0.001);
+        assert_abs_diff_eq!(bottom, 21.0, epsilon = 0.001);
+    }
+
+    #[test]
+    fn fit_origin() {
+        let mut matrix = Matrix {
+            a: 0.5,
+            d: 0.5,
+            tx: 10.3 * 20.0,
+            ty: 20.6 * 20.0,
+            ..Default::default()
+        };
+        grid_fit(&mut matrix, None, 1024.0, TextGridFit::SubPixel);
+        assert_abs_diff_eq!(matrix.tx, 10.3 * 20.0);
+        assert_abs_diff_eq!(matrix.ty, 21.0 * 20.0, epsilon = 0.001);
+        assert_abs_diff_eq!(matrix.d, 0.5);
+
+        // Large or rotated glyphs are left alone.
+        let mut large = Matrix {
+            a: 2.0,
+            d: 2.0,
+            ty: 0.5 * 20.0,
+            ..Default::default()
+        };
+        grid_fit(&mut large, None, 1024.0, TextGridFit::Pixel);
+        assert_abs_diff_eq!(large.ty, 0.5 * 20.0);
+    }
+}
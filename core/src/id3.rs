@@ -0,0 +1,330 @@
+//! Parsing of ID3 tags, the metadata embedded in MP3 files.
+//!
+//! The parsed properties are named as they are in the AS2 `Sound.id3` object.
+//! ID3v2 frames use their four character frame ID (e.g. `TIT2`). The common frames are
+//! also copied to the ID3v1 property names (e.g. `songname`), as Flash Player does.
+
+/// The ID3v2 frames that have an equivalent ID3v1 property.
+const ID3V1_PROPERTY_NAMES: [(&str, &str); 7] = [
+    ("COMM", "comment"),
+    ("TALB", "album"),
+    ("TCON", "genre"),
+    ("TIT2", "songname"),
+    ("TPE1", "artist"),
+    ("TRCK", "track"),
+    ("TYER", "year"),
+];
+
+/// The three character frame IDs used by ID3v2.2, and their ID3v2.3 equivalents.
+const ID3V22_FRAME_IDS: [(&[u8], &str); 7] = [
+    (b"COM", "COMM"),
+    (b"TAL", "TALB"),
+    (b"TCO", "TCON"),
+    (b"TT2", "TIT2"),
+    (b"TP1", "TPE1"),
+    (b"TRK", "TRCK"),
+    (b"TYE", "TYER"),
+];
+
+/// Returns the length of the ID3v2 tag at the beginning of `data`, including its header.
+/// Returns `None` if `data` does not begin with an ID3v2 tag header.
+pub fn id3v2_tag_len(data: &[u8]) -> Option<usize> {
+    if data.len() < 10 || &data[..3] != b"ID3" {
+        return None;
+    }
+    let has_footer = data[5] & 0x10 != 0;
+    Some(10 + read_syncsafe_int(&data[6..10]) + if has_footer { 10 } else { 0 })
+}
+
+/// Parses the ID3v2 tag at the beginning of `data`, which must contain the whole tag.
+/// Returns `None` if there is no ID3v2 tag.
+pub fn parse_id3v2(data: &[u8]) -> Option<Vec<(String, String)>> {
+    let tag_len = id3v2_tag_len(data)?;
+    if tag_len > data.len() {
+        return None;
+    }
+
+    let version = data[3];
+    let flags = data[5];
+    let mut tag: Vec<u8> = data[10..tag_len].to_vec();
+    if flags & 0x80 != 0 {
+        tag = remove_unsynchronization(&tag);
+    }
+
+    // Skip the extended header.
+    let mut pos = 0;
+    if flags & 0x40 != 0 && version >= 3 && tag.len() >= 4 {
+        pos = if version == 3 {
+            read_int(&tag[..4]).saturating_add(4)
+        } else {
+            read_syncsafe_int(&tag[..4])
+        };
+    }
+
+    let mut properties = vec![];
+    let header_len = if version == 2 { 6 } else { 10 };
+    // Frame lengths come from the file, so compare them against the remaining length
+    // rather than adding them to `pos`, which could overflow a 32-bit `usize`.
+    while pos <= tag.len() && header_len <= tag.len() - pos {
+        let header = &tag[pos..pos + header_len];
+        // The rest of the tag is padding.
+        if header[0] == 0 {
+            break;
+        }
+
+        let (id, frame_len) = match version {
+            2 => {
+                let id = ID3V22_FRAME_IDS
+                    .iter()
+                    .find(|(id, _)| *id == &header[..3])
+                    .map(|(_, id)| id.to_string());
+                (id, read_int(&header[3..6]))
+            }
+            3 => (frame_id(&header[..4]), read_int(&header[4..8])),
+            _ => (frame_id(&header[..4]), read_syncsafe_int(&header[4..8])),
+        };
+        pos += header_len;
+        if frame_len > tag.len() - pos {
+            break;
+        }
+        let frame = &tag[pos..pos + frame_len];
+        pos += frame_len;
+
+        if let Some(id) = id {
+            if let Some(value) = parse_frame(&id, frame) {
+                properties.push((id, value));
+            }
+        }
+    }
+
+    // Flash Player also exposes the common frames with their ID3v1 names.
+    for (frame_id, name) in ID3V1_PROPERTY_NAMES.iter() {
+        if let Some((_, value)) = properties.iter().find(|(id, _)| id == frame_id) {
+            let value = value.clone();
+            properties.push((name.to_string(), value));
+        }
+    }
+
+    Some(properties)
+}
+
+/// Parses the ID3v1 tag at the end of `data`.
+/// Returns `None` if there is no ID3v1 tag.
+pub fn parse_id3v1(data: &[u8]) -> Option<Vec<(String, String)>> {
+    if data.len() < 128 {
+        return None;
+    }
+    let tag = &data[data.len() - 128..];
+    if &tag[..3] != b"TAG" {
+        return None;
+    }
+
+    let field = |start: usize, len: usize| -> String {
+        let text = decode_latin1(&tag[start..start + len]);
+        text.trim_end_matches(&['\0', ' '][..]).to_string()
+    };
+    // ID3v1.1 stores the track number in the last byte of the comment.
+    let has_track = tag[125] == 0 && tag[126] != 0;
+    let comment_len = if has_track { 28 } else { 30 };
+    let mut properties = vec![
+        ("songname".to_string(), field(3, 30)),
+        ("artist".to_string(), field(33, 30)),
+        ("album".to_string(), field(63, 30)),
+        ("year".to_string(), field(93, 4)),
+        ("comment".to_string(), field(97, comment_len)),
+        ("genre".to_string(), tag[127].to_string()),
+    ];
+    if has_track {
+        properties.push(("track".to_string(), tag[126].to_string()));
+    }
+    Some(properties)
+}
+
+/// Returns the frame ID of an ID3v2.3 or ID3v2.4 frame, if it is a valid frame ID.
+fn frame_id(id: &[u8]) -> Option<String> {
+    if id
+        .iter()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        Some(id.iter().map(|&c| char::from(c)).collect())
+    } else {
+        None
+    }
+}
+
+/// Decodes the value of a text (`T???`) or comment (`COMM`) frame.
+/// Returns `None` for other kinds of frames.
+fn parse_frame(id: &str, frame: &[u8]) -> Option<String> {
+    let (&encoding, data) = frame.split_first()?;
+    if id == "COMM" {
+        // The comment is preceded by a language code and a short description.
+        if data.len() < 3 {
+            return None;
+        }
+        let data = &data[3..];
+        let description_len = if encoding == 1 || encoding == 2 {
+            data.chunks_exact(2)
+                .position(|c| c == [0, 0])
+                .map(|i| i * 2 + 2)
+        } else {
+            data.iter().position(|&c| c == 0).map(|i| i + 1)
+        }
+        .unwrap_or(data.len());
+        Some(decode_text(encoding, &data[description_len..]))
+    } else if id.starts_with('T') && id != "TXXX" {
+        Some(decode_text(encoding, data))
+    } else {
+        None
+    }
+}
+
+/// Decodes the text in an ID3v2 frame using the frame's text encoding.
+fn decode_text(encoding: u8, data: &[u8]) -> String {
+    let text = match encoding {
+        // UTF-16 with a byte order mark.
+        1 => {
+            if data.len() >= 2 && data[..2] == [0xff, 0xfe] {
+                decode_utf16(&data[2..], u16::from_le_bytes)
+            } else if data.len() >= 2 && data[..2] == [0xfe, 0xff] {
+                decode_utf16(&data[2..], u16::from_be_bytes)
+            } else {
+                decode_utf16(data, u16::from_be_bytes)
+            }
+        }
+        // UTF-16BE.
+        2 => decode_utf16(data, u16::from_be_bytes),
+        // UTF-8.
+        3 => String::from_utf8_lossy(data).into_owned(),
+        // ISO-8859-1.
+        _ => decode_latin1(data),
+    };
+    text.trim_end_matches('\0').to_string()
+}
+
+fn decode_latin1(data: &[u8]) -> String {
+    data.iter().map(|&c| char::from(c)).collect()
+}
+
+fn decode_utf16(data: &[u8], read_u16: fn([u8; 2]) -> u16) -> String {
+    let units = data.chunks_exact(2).map(|c| read_u16([c[0], c[1]]));
+    std::char::decode_utf16(units)
+        .map(|c| c.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Reverses the "unsynchronization" scheme, which inserts a zero byte after each 0xFF byte
+/// so that the tag does not contain MP3 frame sync patterns.
+fn remove_unsynchronization(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut prev = 0;
+    for &byte in data {
+        if !(prev == 0xff && byte == 0) {
+            out.push(byte);
+        }
+        prev = byte;
+    }
+    out
+}
+
+/// Reads a big-endian integer.
+fn read_int(data: &[u8]) -> usize {
+    data.iter()
+        .fold(0, |value, &byte| (value << 8) | usize::from(byte))
+}
+
+/// Reads a big-endian "syncsafe" integer, which stores 7 bits in each byte.
+fn read_syncsafe_int(data: &[u8]) -> usize {
+    data.iter()
+        .fold(0, |value, &byte| (value << 7) | usize::from(byte & 0x7f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property<'a>(properties: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        properties
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn id3v23_frame(id: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut frame = id.to_vec();
+        frame.extend(&(data.len() as u32).to_be_bytes());
+        frame.extend(&[0, 0]);
+        frame.extend(data);
+        frame
+    }
+
+    #[test]
+    fn parse_id3v23_tag() {
+        let mut frames = id3v23_frame(b"TIT2", b"\x00Song\x00");
+        frames.extend(id3v23_frame(b"TPE1", b"\x01\xff\xfeA\x00r\x00t\x00"));
+        frames.extend(id3v23_frame(b"COMM", b"\x00engdesc\x00Comment"));
+        frames.extend(id3v23_frame(b"APIC", b"\x00image"));
+        frames.extend(&[0; 16]);
+
+        let mut data = b"ID3\x03\x00\x00\x00\x00".to_vec();
+        data.extend(&[(frames.len() >> 7) as u8, (frames.len() & 0x7f) as u8]);
+        data.extend(&frames);
+        data.extend(&[0xff, 0xfb, 0x90, 0x00]);
+
+        assert_eq!(id3v2_tag_len(&data), Some(data.len() - 4));
+        let properties = parse_id3v2(&data).unwrap();
+        assert_eq!(property(&properties, "TIT2"), Some("Song"));
+        assert_eq!(property(&properties, "songname"), Some("Song"));
+        assert_eq!(property(&properties, "TPE1"), Some("Art"));
+        assert_eq!(property(&properties, "artist"), Some("Art"));
+        assert_eq!(property(&properties, "COMM"), Some("Comment"));
+        assert_eq!(property(&properties, "APIC"), None);
+
+        // The whole tag must be present.
+        assert_eq!(parse_id3v2(&data[..20]), None);
+    }
+
+    #[test]
+    fn parse_id3v23_tag_with_invalid_lengths() {
+        let mut frames = id3v23_frame(b"TIT2", b"\x00Song\x00");
+        frames.extend(b"TPE1\xff\xff\xff\xff\x00\x00Artist");
+
+        let mut data = b"ID3\x03\x00\x00\x00\x00".to_vec();
+        data.extend(&[(frames.len() >> 7) as u8, (frames.len() & 0x7f) as u8]);
+        data.extend(&frames);
+        let properties = parse_id3v2(&data).unwrap();
+        assert_eq!(property(&properties, "TIT2"), Some("Song"));
+        assert_eq!(property(&properties, "TPE1"), None);
+
+        // An extended header that claims to be longer than the tag.
+        let mut data = b"ID3\x03\x00\x40\x00\x00".to_vec();
+        data.extend(&[0, 8, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+        assert_eq!(parse_id3v2(&data), Some(vec![]));
+    }
+
+    #[test]
+    fn parse_id3v11_tag() {
+        let mut tag = b"TAG".to_vec();
+        for (text, len) in &[("Title", 30), ("Artist", 30), ("Album", 30), ("1999", 4)] {
+            let mut field = text.as_bytes().to_vec();
+            field.resize(*len, 0);
+            tag.extend(field);
+        }
+        let mut comment = b"Comment".to_vec();
+        comment.resize(28, b' ');
+        tag.extend(comment);
+        tag.extend(&[0, 7, 17]);
+
+        let mut data = vec![0xff, 0xfb, 0x90, 0x00];
+        data.extend(&tag);
+        let properties = parse_id3v1(&data).unwrap();
+        assert_eq!(property(&properties, "songname"), Some("Title"));
+        assert_eq!(property(&properties, "artist"), Some("Artist"));
+        assert_eq!(property(&properties, "album"), Some("Album"));
+        assert_eq!(property(&properties, "year"), Some("1999"));
+        assert_eq!(property(&properties, "comment"), Some("Comment"));
+        assert_eq!(property(&properties, "track"), Some("7"));
+        assert_eq!(property(&properties, "genre"), Some("17"));
+
+        assert_eq!(parse_id3v1(&data[4..100]), None);
+    }
+}
@@ -6,18 +6,24 @@ mod display_object;
 #[macro_use]
 extern crate smallvec;
 
+mod audio_manager;
 mod avm1;
 mod bounding_box;
 mod character;
 pub mod color_transform;
 mod context;
+pub mod context_menu;
 pub mod events;
+mod focus;
 mod font;
+mod id3;
 mod library;
 pub mod matrix;
 mod player;
 mod prelude;
+mod scaling_grid;
 pub mod shape_utils;
+mod stage;
 pub mod string_utils;
 pub mod tag_utils;
 mod transform;
@@ -25,6 +31,7 @@ mod xml;
 
 pub mod backend;
 
+pub use avm1::debugger;
 pub use events::PlayerEvent;
 pub use player::Player;
 pub use swf;
@@ -1,9 +1,10 @@
 use crate::avm1::globals::SystemPrototypes;
 use crate::avm1::Object;
 use crate::backend::audio::SoundHandle;
+use crate::backend::render::ShapeHandle;
 use crate::character::Character;
 use crate::display_object::TDisplayObject;
-use crate::font::Font;
+use crate::font::{DeviceFont, DeviceFonts, Font, GlyphAlignZone};
 use crate::prelude::*;
 use gc_arena::MutationContext;
 use std::collections::HashMap;
@@ -14,6 +15,9 @@ pub struct Library<'gc> {
     export_characters: HashMap<String, Character<'gc>>,
     jpeg_tables: Option<Vec<u8>>,
     device_font: Option<Font<'gc>>,
+    device_fonts: DeviceFonts,
+    glyph_align_zones: HashMap<ShapeHandle, GlyphAlignZone>,
+    text_settings: HashMap<CharacterId, swf::CsmTextSettings>,
 }
 
 impl<'gc> Library<'gc> {
@@ -23,6 +27,9 @@ impl<'gc> Library<'gc> {
             export_characters: HashMap::new(),
             jpeg_tables: None,
             device_font: None,
+            device_fonts: DeviceFonts::new(),
+            glyph_align_zones: HashMap::new(),
+            text_settings: HashMap::new(),
         }
     }
 
@@ -179,6 +186,54 @@ impl<'gc> Library<'gc> {
     pub fn set_device_font(&mut self, font: Option<Font<'gc>>) {
         self.device_font = font;
     }
+
+    /// Returns the font files used to render device fonts.
+    pub fn device_fonts(&self) -> &DeviceFonts {
+        &self.device_fonts
+    }
+
+    pub fn device_fonts_mut(&mut self) -> &mut DeviceFonts {
+        &mut self.device_fonts
+    }
+
+    /// Returns the device font used to render text in the font with the given name.
+    /// Characters missing from the device font files are drawn with the built-in device font.
+    pub fn get_device_font(&self, name: &str) -> DeviceFont<'_, 'gc> {
+        self.device_fonts.get(name, self.device_font)
+    }
+
+    /// Sets the alignment zones of the glyphs of a font, from a `DefineFontAlignZones` tag.
+    pub fn set_font_align_zones(&mut self, font_id: CharacterId, zones: &[swf::FontAlignZone]) {
+        let font = if let Some(font) = self.get_font(font_id) {
+            font
+        } else {
+            log::warn!("DefineFontAlignZones: Font ID {} doesn't exist", font_id);
+            return;
+        };
+        for (i, zone) in zones.iter().enumerate() {
+            if let Some(glyph) = font.get_glyph(i) {
+                self.glyph_align_zones.insert(
+                    glyph.shape,
+                    GlyphAlignZone::from_swf_zone(zone, font.scale()),
+                );
+            }
+        }
+    }
+
+    /// Returns the alignment zone used to grid fit the given glyph shape.
+    pub fn glyph_align_zone(&self, shape: ShapeHandle) -> Option<&GlyphAlignZone> {
+        self.glyph_align_zones.get(&shape)
+    }
+
+    /// Sets the advanced anti-aliasing settings of a text character, from a `CSMTextSettings` tag.
+    pub fn set_text_settings(&mut self, settings: swf::CsmTextSettings) {
+        self.text_settings.insert(settings.id, settings);
+    }
+
+    /// Returns the advanced anti-aliasing settings of a text character.
+    pub fn text_settings(&self, id: CharacterId) -> Option<&swf::CsmTextSettings> {
+        self.text_settings.get(&id)
+    }
 }
 
 unsafe impl<'gc> gc_arena::Collect for Library<'gc> {
@@ -1,14 +1,23 @@
-use crate::avm1::listeners::SystemListener;
-use crate::avm1::Avm1;
-use crate::backend::input::InputBackend;
-use crate::backend::{
-    audio::AudioBackend, navigator::NavigatorBackend, render::Letterbox, render::RenderBackend,
+use crate::audio_manager::AudioManager;
+use crate::avm1::debugger::{
+    Breakpoint, DebugFrame, DebugLocation, Debugger, FrameInspection, StepMode, ValueInspection,
 };
+use crate::avm1::listeners::SystemListener;
+use crate::avm1::{Avm1, Value};
+use crate::backend::input::{InputBackend, MouseCursor};
+use crate::backend::render::{overlay, ShapeHandle};
+use crate::backend::{audio::AudioBackend, navigator::NavigatorBackend, render::RenderBackend};
 use crate::context::{ActionQueue, ActionType, RenderContext, UpdateContext};
+use crate::context_menu::{
+    self, ContextMenuCallback, ContextMenuItem, ContextMenuPopup, ContextMenuState,
+};
 use crate::display_object::{MorphShape, MovieClip};
-use crate::events::{ButtonEvent, ButtonKeyCode, ClipEvent, PlayerEvent};
+use crate::events::{ButtonEvent, ButtonKeyCode, ClipEvent, KeyCode, MouseButton, PlayerEvent};
+use crate::focus::{self, FocusTracker};
+use crate::font::TextFont;
 use crate::library::Library;
 use crate::prelude::*;
+use crate::stage::{Stage, StageQuality};
 use crate::transform::TransformStack;
 use gc_arena::{make_arena, ArenaParameters, Collect, GcCell};
 use log::info;
@@ -33,11 +42,21 @@ struct GcRootData<'gc> {
     root: DisplayObject<'gc>,
     mouse_hovered_object: Option<DisplayObject<'gc>>, // TODO: Remove GcCell wrapped inside GcCell.
 
+    /// The button that the mouse pressed, while the mouse button is held down.
+    mouse_pressed_object: Option<DisplayObject<'gc>>,
+
     /// The object being dragged via a `startDrag` action.
     drag_object: Option<DragObject<'gc>>,
 
     avm: Avm1<'gc>,
     action_queue: ActionQueue<'gc>,
+    audio_manager: AudioManager<'gc>,
+
+    /// The context menu that is currently shown, if any.
+    current_context_menu: Option<ContextMenuState<'gc>>,
+
+    /// The object with keyboard focus.
+    focus_tracker: FocusTracker<'gc>,
 }
 
 impl<'gc> GcRootData<'gc> {
@@ -51,6 +70,8 @@ impl<'gc> GcRootData<'gc> {
         &mut ActionQueue<'gc>,
         &mut Avm1<'gc>,
         &mut Option<DragObject<'gc>>,
+        &mut AudioManager<'gc>,
+        &mut Option<ContextMenuState<'gc>>,
     ) {
         (
             self.root,
@@ -58,6 +79,8 @@ impl<'gc> GcRootData<'gc> {
             &mut self.action_queue,
             &mut self.avm,
             &mut self.drag_object,
+            &mut self.audio_manager,
+            &mut self.current_context_menu,
         )
     }
 }
@@ -93,8 +116,6 @@ pub struct Player<
     navigator: Navigator,
     input: Input,
     transform_stack: TransformStack,
-    view_matrix: Matrix,
-    inverse_view_matrix: Matrix,
 
     rng: SmallRng,
 
@@ -105,14 +126,28 @@ pub struct Player<
     frame_accumulator: f64,
     global_time: u64,
 
-    viewport_width: u32,
-    viewport_height: u32,
-    movie_width: u32,
-    movie_height: u32,
-    letterbox: Letterbox,
+    /// Whether the root timeline is kept in sync with its stream sound, as Flash does.
+    sync_to_stream_sound: bool,
+
+    /// Places the movie in the viewport.
+    stage: Stage,
+
+    /// Whether the root timeline loops, toggled from the context menu. When disabled, the
+    /// movie stops on its last frame.
+    looping: bool,
+
+    /// The context menu popup drawn over the movie, shown with `show_context_menu`.
+    context_menu_popup: Option<ContextMenuPopup>,
+
+    /// A one pixel white square, used to draw the context menu popup and the focus
+    /// rectangle. It's registered with the renderer when it's first drawn.
+    rect_shape: Option<ShapeHandle>,
 
     mouse_pos: (Twips, Twips),
     is_mouse_down: bool,
+
+    /// The mouse cursor that was last set on the input backend.
+    mouse_cursor: MouseCursor,
 }
 
 impl<
@@ -175,8 +210,6 @@ impl<
                 a: 255,
             },
             transform_stack: TransformStack::new(),
-            view_matrix: Default::default(),
-            inverse_view_matrix: Default::default(),
 
             rng: SmallRng::from_seed([0u8; 16]), // TODO(Herschel): Get a proper seed on all platforms.
 
@@ -207,9 +240,13 @@ impl<
                         )
                         .into(),
                         mouse_hovered_object: None,
+                        mouse_pressed_object: None,
                         drag_object: None,
                         avm: Avm1::new(gc_context, NEWEST_PLAYER_VERSION),
                         action_queue: ActionQueue::new(),
+                        audio_manager: AudioManager::new(),
+                        current_context_menu: None,
+                        focus_tracker: FocusTracker::new(gc_context),
                     },
                 ))
             }),
@@ -217,15 +254,16 @@ impl<
             frame_rate: header.frame_rate.into(),
             frame_accumulator: 0.0,
             global_time: 0,
+            sync_to_stream_sound: true,
 
-            movie_width,
-            movie_height,
-            viewport_width: movie_width,
-            viewport_height: movie_height,
-            letterbox: Letterbox::None,
+            stage: Stage::new(movie_width, movie_height),
+            looping: true,
+            context_menu_popup: None,
+            rect_shape: None,
 
             mouse_pos: (Twips::new(0), Twips::new(0)),
             is_mouse_down: false,
+            mouse_cursor: MouseCursor::Arrow,
 
             renderer,
             audio,
@@ -239,7 +277,6 @@ impl<
             root.post_instantiation(gc_context, root, root_data.avm.prototypes().movie_clip);
         });
 
-        player.build_matrices();
         player.preload();
 
         Ok(player)
@@ -252,26 +289,51 @@ impl<
             return;
         }
 
+        // The timeline is frozen while the debugger has paused execution.
+        if self.debugger_is_paused() {
+            return;
+        }
+
         if self.is_playing() {
             self.frame_accumulator += dt;
             self.global_time += dt as u64;
             let frame_time = 1000.0 / self.frame_rate;
 
-            let needs_render = self.frame_accumulator >= frame_time;
-
             const MAX_FRAMES_PER_TICK: u32 = 5; // Sanity cap on frame tick.
-            let mut frame = 0;
-            while frame < MAX_FRAMES_PER_TICK && self.frame_accumulator >= frame_time {
-                self.frame_accumulator -= frame_time;
-                self.run_frame();
-                frame += 1;
-            }
 
-            // Sanity: If we had too many frames to tick, just reset the accumulator
-            // to prevent running at turbo speed.
-            if self.frame_accumulator >= frame_time {
-                self.frame_accumulator = 0.0;
-            }
+            // If the timeline is more than a second ahead of its stream sound, the audio probably
+            // isn't being output (e.g. the audio context is suspended), so play by the clock instead.
+            let max_stream_lead = -(self.frame_rate.ceil() as i32);
+            let needs_render = match self.root_stream_frames_behind() {
+                Some(frames_behind) if frames_behind >= max_stream_lead => {
+                    // Follow the stream sound: hold the timeline if it's ahead of the audio,
+                    // or run frames without rendering them to catch up if it's behind.
+                    self.frame_accumulator = 0.0;
+                    let num_frames = frames_behind.max(0).min(MAX_FRAMES_PER_TICK as i32);
+                    for _ in 0..num_frames {
+                        self.run_frame();
+                    }
+                    num_frames > 0
+                }
+                _ => {
+                    let needs_render = self.frame_accumulator >= frame_time;
+
+                    let mut frame = 0;
+                    while frame < MAX_FRAMES_PER_TICK && self.frame_accumulator >= frame_time {
+                        self.frame_accumulator -= frame_time;
+                        self.run_frame();
+                        frame += 1;
+                    }
+
+                    // Sanity: If we had too many frames to tick, just reset the accumulator
+                    // to prevent running at turbo speed.
+                    if self.frame_accumulator >= frame_time {
+                        self.frame_accumulator = 0.0;
+                    }
+
+                    needs_render
+                }
+            };
 
             if needs_render {
                 self.render();
@@ -307,39 +369,172 @@ impl<
         self.is_playing = v;
     }
 
+    /// Whether the root timeline follows its stream sound, dropping or holding frames
+    /// to stay in sync with the audio.
+    pub fn sync_to_stream_sound(&self) -> bool {
+        self.sync_to_stream_sound
+    }
+
+    /// Sets whether the root timeline follows its stream sound.
+    /// When disabled, frames always run at the movie's frame rate.
+    pub fn set_sync_to_stream_sound(&mut self, v: bool) {
+        self.sync_to_stream_sound = v;
+    }
+
+    /// Returns how many frames the root timeline is behind its stream sound, or `None` if the
+    /// root timeline isn't playing a stream sound. This is negative if the timeline is ahead.
+    fn root_stream_frames_behind(&mut self) -> Option<i32> {
+        if !self.sync_to_stream_sound {
+            return None;
+        }
+
+        let (audio, frame_rate) = (&self.audio, self.frame_rate);
+        self.gc_arena.mutate(|_gc_context, gc_root| {
+            let root = gc_root.0.read().root.as_movie_clip()?;
+            let (stream, start_frame) = root.audio_stream()?;
+            let position = audio.get_stream_position(stream)?;
+            let expected_frame = f64::from(start_frame) + f64::from(position) * frame_rate / 1000.0;
+            Some(expected_frame as i32 - i32::from(root.current_frame()))
+        })
+    }
+
     pub fn movie_width(&self) -> u32 {
-        self.movie_width
+        self.stage.movie_size().0
     }
 
     pub fn movie_height(&self) -> u32 {
-        self.movie_height
+        self.stage.movie_size().1
     }
 
     pub fn viewport_dimensions(&self) -> (u32, u32) {
-        (self.viewport_width, self.viewport_height)
+        self.stage.viewport_size()
     }
 
     pub fn set_viewport_dimensions(&mut self, width: u32, height: u32) {
-        self.viewport_width = width;
-        self.viewport_height = height;
-        self.build_matrices();
+        if self.stage.set_viewport_size(width, height) {
+            // The stage was resized to the viewport in `noScale` mode.
+            self.mutate_with_update_context(|avm, context| {
+                let root = context.root;
+                context.action_queue.queue_actions(
+                    root,
+                    ActionType::NotifyListeners {
+                        listener: SystemListener::Stage,
+                        method: "onResize",
+                        args: vec![],
+                    },
+                    false,
+                );
+                Self::run_actions(avm, context);
+            });
+        }
+    }
+
+    /// Whether the margins around the movie are masked when it doesn't fill the viewport.
+    pub fn letterbox_enabled(&self) -> bool {
+        self.stage.letterbox_enabled()
+    }
+
+    /// Sets whether the margins around the movie are masked when it doesn't fill the viewport.
+    /// When disabled, content outside the movie's bounds is visible in the margins, as in Flash.
+    pub fn set_letterbox_enabled(&mut self, letterbox_enabled: bool) {
+        self.stage.set_letterbox_enabled(letterbox_enabled);
     }
 
     pub fn handle_event(&mut self, event: PlayerEvent) {
+        // The context menu popup takes all input while it's open.
+        if self.context_menu_popup.is_some() {
+            self.handle_context_menu_event(event);
+            return;
+        }
+
         let mut needs_render = false;
 
         // Update mouse position from mouse events.
         if let PlayerEvent::MouseMove { x, y }
-        | PlayerEvent::MouseDown { x, y }
-        | PlayerEvent::MouseUp { x, y } = event
+        | PlayerEvent::MouseDown { x, y, .. }
+        | PlayerEvent::MouseUp { x, y, .. } = event
         {
             self.mouse_pos =
-                self.inverse_view_matrix * (Twips::from_pixels(x), Twips::from_pixels(y));
+                self.stage.inverse_view_matrix() * (Twips::from_pixels(x), Twips::from_pixels(y));
             if self.update_roll_over() {
                 needs_render = true;
             }
         }
 
+        // Propagate clip events, and notify the `Key` and `Mouse` listeners.
+        // Key events run before the `keyPress` events that they trigger, as in Flash.
+        let (clip_event, listener) = match event {
+            PlayerEvent::KeyDown { .. } => (
+                Some(ClipEvent::KeyDown),
+                Some((SystemListener::Key, "onKeyDown")),
+            ),
+            PlayerEvent::KeyUp { .. } => (
+                Some(ClipEvent::KeyUp),
+                Some((SystemListener::Key, "onKeyUp")),
+            ),
+            PlayerEvent::MouseMove { .. } => (
+                Some(ClipEvent::MouseMove),
+                Some((SystemListener::Mouse, "onMouseMove")),
+            ),
+            // Only the left mouse button is visible to AVM1 mouse events.
+            PlayerEvent::MouseUp {
+                button: MouseButton::Left,
+                ..
+            } => (
+                Some(ClipEvent::MouseUp),
+                Some((SystemListener::Mouse, "onMouseUp")),
+            ),
+            PlayerEvent::MouseDown {
+                button: MouseButton::Left,
+                ..
+            } => (
+                Some(ClipEvent::MouseDown),
+                Some((SystemListener::Mouse, "onMouseDown")),
+            ),
+            _ => (None, None),
+        };
+
+        if clip_event.is_some() || listener.is_some() {
+            self.mutate_with_update_context(|_avm, context| {
+                let root = context.root;
+
+                if let Some(clip_event) = clip_event {
+                    root.propagate_clip_event(context, clip_event);
+                }
+
+                if let Some((listener, method)) = listener {
+                    context.action_queue.queue_actions(
+                        root,
+                        ActionType::NotifyListeners {
+                            listener,
+                            method,
+                            args: vec![],
+                        },
+                        false,
+                    );
+                }
+            });
+        }
+
+        if let PlayerEvent::MouseWheel { delta } = event {
+            self.mutate_with_update_context(|_avm, context| {
+                let root = context.root;
+                // `onMouseWheel` listeners receive the object under the mouse as the scroll target.
+                let scroll_target = context
+                    .mouse_hovered_object
+                    .map_or(Value::Undefined, |target| target.object());
+                context.action_queue.queue_actions(
+                    root,
+                    ActionType::NotifyListeners {
+                        listener: SystemListener::Mouse,
+                        method: "onMouseWheel",
+                        args: vec![delta.into(), scroll_target],
+                    },
+                    false,
+                );
+            });
+        }
+
         // Propagate button events.
         let button_event = match event {
             // ASCII characters convert directly to keyPress button events.
@@ -362,66 +557,106 @@ impl<
             _ => None,
         };
 
-        if button_event.is_some() {
+        if let Some(ButtonEvent::KeyPress { key_code }) = button_event {
             self.mutate_with_update_context(|_avm, context| {
                 let root = context.root;
-                if let Some(button_event) = button_event {
-                    root.propagate_button_event(context, button_event);
-                }
+                root.propagate_button_event(context, ButtonEvent::KeyPress { key_code });
+                // `onClipEvent(keyPress)` handlers use the same key codes as buttons.
+                root.propagate_clip_event(context, ClipEvent::KeyPress { key_code });
             });
         }
 
-        // Propagte clip events.
-        let (clip_event, mouse_event_name) = match event {
-            PlayerEvent::KeyDown { .. } => (Some(ClipEvent::KeyDown), Some("onKeyDown")),
-            PlayerEvent::MouseMove { .. } => (Some(ClipEvent::MouseMove), Some("onMouseMove")),
-            PlayerEvent::MouseUp { .. } => (Some(ClipEvent::MouseUp), Some("onMouseUp")),
-            PlayerEvent::MouseDown { .. } => (Some(ClipEvent::MouseDown), Some("onMouseDown")),
-            _ => (None, None),
-        };
-
-        if clip_event.is_some() || mouse_event_name.is_some() {
-            self.mutate_with_update_context(|_avm, context| {
-                let root = context.root;
-
-                if let Some(clip_event) = clip_event {
-                    root.propagate_clip_event(context, clip_event);
+        // Tab moves the keyboard focus, and Enter or Space presses the focused object.
+        if let PlayerEvent::KeyDown { key_code } = event {
+            match key_code {
+                KeyCode::Tab => {
+                    let reverse = self.input.is_key_down(KeyCode::Shift);
+                    self.mutate_with_update_context(|avm, context| {
+                        let root = context.root;
+                        let focus_tracker = context.focus_tracker;
+                        let next = avm.with_dummy_frame(root, context, |avm, context| {
+                            focus::next_tab_object(avm, context, focus_tracker.get(), reverse)
+                        });
+                        focus_tracker.set(next, context);
+                        Self::run_actions(avm, context);
+                    });
+                    needs_render = true;
                 }
-
-                if let Some(mouse_event_name) = mouse_event_name {
-                    context.action_queue.queue_actions(
-                        root,
-                        ActionType::NotifyListeners {
-                            listener: SystemListener::Mouse,
-                            method: mouse_event_name,
-                            args: vec![],
-                        },
-                        false,
-                    );
+                KeyCode::Return | KeyCode::Space => {
+                    self.mutate_with_update_context(|avm, context| {
+                        if let Some(focused) = context.focus_tracker.get() {
+                            Self::send_button_event(context, focused, ButtonEvent::Press);
+                            Self::send_button_event(context, focused, ButtonEvent::Release);
+                            Self::run_actions(avm, context);
+                        }
+                    });
+                    needs_render = true;
                 }
-            });
+                _ => (),
+            }
         }
 
         let mut is_mouse_down = self.is_mouse_down;
         self.mutate_with_update_context(|avm, context| {
-            if let Some(node) = context.mouse_hovered_object {
-                if let Some(mut button) = node.clone().as_button() {
-                    match event {
-                        PlayerEvent::MouseDown { .. } => {
-                            is_mouse_down = true;
-                            needs_render = true;
-                            button.handle_button_event(context, ButtonEvent::Press);
-                        }
+            // Clicking takes the keyboard focus away.
+            if let PlayerEvent::MouseDown {
+                button: MouseButton::Left,
+                ..
+            } = event
+            {
+                let focus_tracker = context.focus_tracker;
+                focus_tracker.set(None, context);
+            }
 
-                        PlayerEvent::MouseUp { .. } => {
-                            is_mouse_down = false;
-                            needs_render = true;
-                            button.handle_button_event(context, ButtonEvent::Release);
+            match event {
+                PlayerEvent::MouseDown {
+                    button: MouseButton::Left,
+                    ..
+                } => {
+                    is_mouse_down = true;
+                    needs_render = true;
+                    let pressed = context
+                        .mouse_hovered_object
+                        .filter(|&object| Self::button_settings(object).is_some());
+                    context.mouse_pressed_object = pressed;
+                    if let Some(pressed) = pressed {
+                        Self::send_button_event(context, pressed, ButtonEvent::Press);
+                    }
+                }
+
+                PlayerEvent::MouseUp {
+                    button: MouseButton::Left,
+                    ..
+                } => {
+                    is_mouse_down = false;
+                    needs_render = true;
+                    let pressed = context.mouse_pressed_object;
+                    let hovered = context.mouse_hovered_object;
+
+                    // A push button that was dragged off is released outside.
+                    if let Some(pressed) = pressed.filter(|&pressed| {
+                        !hovered.map_or(false, |hovered| DisplayObject::ptr_eq(hovered, pressed))
+                    }) {
+                        let is_menu = Self::button_settings(pressed)
+                            .map_or(false, |settings| settings.track_as_menu);
+                        if !is_menu {
+                            Self::send_button_event(context, pressed, ButtonEvent::ReleaseOutside);
                         }
+                    }
 
-                        _ => (),
+                    // Menu buttons are released even if they weren't the button that was
+                    // pressed. Other buttons ignored the mouse while it was held down.
+                    if let Some(hovered) = hovered {
+                        if Self::is_tracking_mouse(context, hovered) {
+                            Self::send_button_event(context, hovered, ButtonEvent::Release);
+                        } else {
+                            Self::send_button_event(context, hovered, ButtonEvent::RollOver);
+                        }
                     }
+                    context.mouse_pressed_object = None;
                 }
+
+                _ => (),
             }
 
             Self::run_actions(avm, context);
@@ -462,39 +697,107 @@ impl<
     }
 
     fn update_roll_over(&mut self) -> bool {
-        // TODO: While the mouse is down, maintain the hovered node.
-        if self.is_mouse_down {
-            return false;
-        }
         let mouse_pos = self.mouse_pos;
+        let is_mouse_down = self.is_mouse_down;
         // Check hovered object.
-        self.mutate_with_update_context(|avm, context| {
+        let (changed, cursor) = self.mutate_with_update_context(|avm, context| {
             let root = context.root;
-            let new_hovered = root.mouse_pick(root, (mouse_pos.0, mouse_pos.1));
+            // Disabled buttons don't respond to the mouse.
+            let new_hovered = root
+                .mouse_pick(root, (mouse_pos.0, mouse_pos.1))
+                .filter(|&object| {
+                    object.as_edit_text().is_some() || Self::button_settings(object).is_some()
+                });
             let cur_hovered = context.mouse_hovered_object;
-            if cur_hovered.map(|d| d.as_ptr()) != new_hovered.map(|d| d.as_ptr()) {
+            let changed = cur_hovered.map(|d| d.as_ptr()) != new_hovered.map(|d| d.as_ptr());
+            if changed {
                 // RollOut of previous node.
                 if let Some(node) = cur_hovered {
-                    if let Some(mut button) = node.as_button() {
-                        button.handle_button_event(context, ButtonEvent::RollOut);
+                    if !is_mouse_down {
+                        Self::send_button_event(context, node, ButtonEvent::RollOut);
+                    } else if Self::is_tracking_mouse(context, node) {
+                        Self::send_button_event(context, node, ButtonEvent::DragOut);
                     }
                 }
 
                 // RollOver on new node.
                 if let Some(node) = new_hovered {
-                    if let Some(mut button) = node.as_button() {
-                        button.handle_button_event(context, ButtonEvent::RollOver);
+                    if !is_mouse_down {
+                        Self::send_button_event(context, node, ButtonEvent::RollOver);
+                    } else if Self::is_tracking_mouse(context, node) {
+                        Self::send_button_event(context, node, ButtonEvent::DragOver);
                     }
                 }
 
                 context.mouse_hovered_object = new_hovered;
 
                 Self::run_actions(avm, context);
-                true
-            } else {
-                false
             }
-        })
+
+            // Show the hand cursor over buttons, and the text cursor over selectable text.
+            let cursor = match new_hovered {
+                Some(node) if node.as_edit_text().is_some() => MouseCursor::IBeam,
+                Some(node) => match Self::button_settings(node) {
+                    Some(settings) if settings.use_hand_cursor => MouseCursor::Hand,
+                    _ => MouseCursor::Arrow,
+                },
+                None => MouseCursor::Arrow,
+            };
+            (changed, cursor)
+        });
+
+        if cursor != self.mouse_cursor {
+            self.mouse_cursor = cursor;
+            self.input.set_mouse_cursor(cursor);
+        }
+        changed
+    }
+
+    /// Sends a mouse event to a button, or to a clip that acts as a button.
+    /// The queued actions must be run afterwards.
+    fn send_button_event<'gc>(
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        object: DisplayObject<'gc>,
+        event: ButtonEvent,
+    ) {
+        if let Some(mut button) = object.as_button() {
+            button.handle_button_event(context, event);
+        } else if let Some(clip) = object.as_movie_clip() {
+            clip.handle_button_event(context, event);
+        }
+    }
+
+    /// Whether a button follows the mouse while the mouse button is held down. This is the
+    /// case for the button that was pressed, and for buttons tracked as menu items.
+    fn is_tracking_mouse<'gc>(
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        object: DisplayObject<'gc>,
+    ) -> bool {
+        let is_pressed = context
+            .mouse_pressed_object
+            .map_or(false, |pressed| DisplayObject::ptr_eq(pressed, object));
+        is_pressed || Self::button_settings(object).map_or(false, |settings| settings.track_as_menu)
+    }
+
+    /// How a button, or a clip that acts as a button, responds to the mouse. Returns `None`
+    /// for objects that aren't buttons and for disabled buttons.
+    fn button_settings(object: DisplayObject<'_>) -> Option<ButtonSettings> {
+        if let Some(button) = object.as_button() {
+            if button.enabled() {
+                return Some(ButtonSettings {
+                    track_as_menu: button.track_as_menu(),
+                    use_hand_cursor: button.use_hand_cursor(),
+                });
+            }
+        } else if let Some(clip) = object.as_movie_clip() {
+            if clip.is_button_mode() && clip.enabled() {
+                return Some(ButtonSettings {
+                    track_as_menu: clip.track_as_menu(),
+                    use_hand_cursor: clip.use_hand_cursor(),
+                });
+            }
+        }
+        None
     }
 
     fn preload(&mut self) {
@@ -516,12 +819,28 @@ impl<
     }
 
     pub fn run_frame(&mut self) {
+        if self.debugger_is_paused() {
+            return;
+        }
+
+        let looping = self.looping;
         self.mutate_with_update_context(|avm, context| {
+            AudioManager::update_sounds(context);
+            AudioManager::update_loading_sounds(context);
             let mut root = context.root;
+            if !looping {
+                if let Some(clip) = root.as_movie_clip() {
+                    if clip.playing() && clip.current_frame() >= clip.total_frames() {
+                        clip.stop(context);
+                    }
+                }
+            }
             root.run_frame(context);
             Self::run_actions(avm, context);
         });
 
+        self.audio.advance_clock(1000.0 / self.frame_rate);
+
         // Update mouse state (check for new hovered button, etc.)
         self.update_drag();
         self.update_roll_over();
@@ -531,13 +850,7 @@ impl<
     }
 
     pub fn render(&mut self) {
-        let view_bounds = BoundingBox {
-            x_min: Twips::new(0),
-            y_min: Twips::new(0),
-            x_max: Twips::from_pixels(self.movie_width.into()),
-            y_max: Twips::from_pixels(self.movie_height.into()),
-            valid: true,
-        };
+        let view_bounds = self.stage.view_bounds();
 
         self.renderer.begin_frame();
 
@@ -546,7 +859,7 @@ impl<
         let (renderer, transform_stack) = (&mut self.renderer, &mut self.transform_stack);
 
         transform_stack.push(&crate::transform::Transform {
-            matrix: self.view_matrix,
+            matrix: self.stage.view_matrix(),
             ..Default::default()
         });
         self.gc_arena.mutate(|_gc_context, gc_root| {
@@ -557,19 +870,339 @@ impl<
                 transform_stack,
                 view_bounds,
                 clip_depth_stack: vec![],
+                scaling_grid: None,
             };
             root_data.root.render(&mut render_context);
         });
         transform_stack.pop();
 
+        // The focus rectangle is drawn in viewport space, so that its edges aren't scaled.
+        let view_matrix = self.stage.view_matrix();
+        let global_focus_rect = self.stage.focus_rect();
+        let focus_rect_bounds = self.gc_arena.mutate(|_gc_context, gc_root| {
+            let root_data = gc_root.0.read();
+            let focused = root_data.focus_tracker.get()?;
+            // Text fields are focused without a rectangle.
+            let has_focus_rect = focused.focus_rect().unwrap_or(global_focus_rect);
+            if !has_focus_rect || focused.as_edit_text().is_some() {
+                return None;
+            }
+            Some(focused.world_bounds().transform(&view_matrix))
+        });
+        if let Some(bounds) = focus_rect_bounds {
+            let rect = self.rect_shape();
+            focus::render_focus_rect(&mut self.renderer, rect, &bounds);
+        }
+
         if !self.is_playing() {
             self.renderer.draw_pause_overlay();
         }
 
-        self.renderer.draw_letterbox(self.letterbox);
+        self.renderer.draw_letterbox(self.stage.letterbox());
+
+        // The context menu is drawn in viewport space, over the letterbox.
+        if self.context_menu_popup.is_some() {
+            let rect = self.rect_shape();
+            let popup = &self.context_menu_popup;
+            let renderer = &mut self.renderer;
+            self.gc_arena.mutate(|_gc_context, gc_root| {
+                let root_data = gc_root.0.read();
+                let font = root_data.library.get_device_font("_sans");
+                if let Some(popup) = popup {
+                    popup.render(renderer, rect, &font);
+                }
+            });
+        }
+
         self.renderer.end_frame();
     }
 
+    /// Returns the one pixel white square that overlays are drawn with, registering it with
+    /// the renderer if needed.
+    fn rect_shape(&mut self) -> ShapeHandle {
+        match self.rect_shape {
+            Some(rect) => rect,
+            None => {
+                let rect = self.renderer.register_shape(&overlay::rect_shape());
+                self.rect_shape = Some(rect);
+                rect
+            }
+        }
+    }
+
+    /// Whether the root timeline loops.
+    pub fn looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Sets whether the root timeline loops. When disabled, the movie stops on its last frame.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Builds the context menu for the object under the mouse, returning its items for the
+    /// frontend to show.
+    ///
+    /// The custom items come from the `menu` of the object, or of its nearest ancestor, whose
+    /// `onSelect` handler runs first so that it can update them. The built-in items follow,
+    /// unless they're hidden by `Stage.showMenu` or `ContextMenu.builtInItems`. When an item
+    /// is picked, the frontend calls `run_context_menu_callback` with its index.
+    pub fn prepare_context_menu(&mut self) -> Vec<ContextMenuItem> {
+        let looping = self.looping;
+        self.mutate_with_update_context(|avm, context| {
+            let mut menu_state = ContextMenuState::new();
+            let root = context.root;
+            let target = context.mouse_hovered_object.unwrap_or(root);
+
+            let menu = avm.with_dummy_frame(root, context, |avm, context| {
+                context_menu::find_avm1_menu(avm, context, target)
+            });
+            if let Some((menu, target)) = menu {
+                context.action_queue.queue_actions(
+                    target,
+                    ActionType::ObjectMethod {
+                        object: menu,
+                        name: "onSelect",
+                        args: vec![target.object(), menu.into()],
+                    },
+                    false,
+                );
+                Self::run_actions(avm, context);
+                avm.with_dummy_frame(root, context, |avm, context| {
+                    menu_state.push_avm1_items(avm, context, menu, target);
+                });
+            }
+
+            if context.stage.show_menu() {
+                // Groups of built-in items can be hidden with `ContextMenu.builtInItems`.
+                let mut shown_items = vec![];
+                for &name in &["zoom", "quality", "play", "loop", "rewind", "forward_back"] {
+                    let is_shown = menu.map_or(true, |(menu, _)| {
+                        avm.with_dummy_frame(root, context, |avm, context| {
+                            context_menu::avm1_built_in_items_enabled(avm, context, menu, name)
+                        })
+                    });
+                    if is_shown {
+                        shown_items.push(name);
+                    }
+                }
+                Self::push_built_in_items(&mut menu_state, context, looping, &shown_items);
+            }
+
+            let items = menu_state.items().to_vec();
+            *context.current_context_menu = Some(menu_state);
+            items
+        })
+    }
+
+    /// Adds the built-in context menu items, for the groups of items named in `shown_items`.
+    fn push_built_in_items<'gc>(
+        menu_state: &mut ContextMenuState<'gc>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        looping: bool,
+        shown_items: &[&str],
+    ) {
+        let is_shown = |name| shown_items.contains(&name);
+        let item = |caption: &str, enabled, checked| ContextMenuItem {
+            caption: String::from(caption),
+            enabled,
+            separator_before: false,
+            checked,
+        };
+
+        if is_shown("zoom") {
+            let is_zoomed = context.stage.zoom() > 1.0;
+            menu_state.push_group(vec![
+                (item("Zoom In", true, false), ContextMenuCallback::ZoomIn),
+                (
+                    item("Zoom Out", is_zoomed, false),
+                    ContextMenuCallback::ZoomOut,
+                ),
+                (
+                    item("Show All", is_zoomed, false),
+                    ContextMenuCallback::ShowAll,
+                ),
+            ]);
+        }
+
+        if is_shown("quality") {
+            let current = context.stage.quality();
+            let quality_item = |caption, quality| {
+                let item = item(caption, true, current == quality);
+                (item, ContextMenuCallback::Quality(quality))
+            };
+            menu_state.push_group(vec![
+                quality_item("Low Quality", StageQuality::Low),
+                quality_item("Medium Quality", StageQuality::Medium),
+                quality_item("High Quality", StageQuality::High),
+            ]);
+        }
+
+        // Timeline controls are only shown for movies with more than one frame.
+        let root = match context.root.as_movie_clip() {
+            Some(root) if root.total_frames() > 1 => root,
+            _ => return,
+        };
+
+        let mut group = vec![];
+        if is_shown("play") {
+            group.push((
+                item("Play", true, root.playing()),
+                ContextMenuCallback::Play,
+            ));
+        }
+        if is_shown("loop") {
+            group.push((item("Loop", true, looping), ContextMenuCallback::Loop));
+        }
+        menu_state.push_group(group);
+
+        let (current_frame, total_frames) = (root.current_frame(), root.total_frames());
+        let mut group = vec![];
+        if is_shown("rewind") {
+            let rewind = item("Rewind", current_frame > 1, false);
+            group.push((rewind, ContextMenuCallback::Rewind));
+        }
+        if is_shown("forward_back") {
+            let forward = item("Forward", current_frame < total_frames, false);
+            group.push((forward, ContextMenuCallback::Forward));
+            let back = item("Back", current_frame > 1, false);
+            group.push((back, ContextMenuCallback::Back));
+        }
+        menu_state.push_group(group);
+    }
+
+    /// Runs the callback of the context menu item with the given index, and closes the menu.
+    pub fn run_context_menu_callback(&mut self, index: usize) {
+        let toggle_loop = self.mutate_with_update_context(|avm, context| {
+            let callback = context
+                .current_context_menu
+                .take()
+                .and_then(|menu_state| menu_state.callback(index).cloned());
+            let root = context.root.as_movie_clip();
+            let mut toggle_loop = false;
+            match callback {
+                Some(ContextMenuCallback::ZoomIn) => context.stage.zoom_in(),
+                Some(ContextMenuCallback::ZoomOut) => context.stage.zoom_out(),
+                Some(ContextMenuCallback::ShowAll) => context.stage.reset_zoom(),
+                Some(ContextMenuCallback::Quality(quality)) => context.stage.set_quality(quality),
+                Some(ContextMenuCallback::Loop) => toggle_loop = true,
+                Some(ContextMenuCallback::Avm1 { item, target }) => {
+                    context.action_queue.queue_actions(
+                        target,
+                        ActionType::ObjectMethod {
+                            object: item,
+                            name: "onSelect",
+                            args: vec![target.object(), item.into()],
+                        },
+                        false,
+                    );
+                }
+                Some(callback) => {
+                    if let Some(root) = root {
+                        match callback {
+                            ContextMenuCallback::Play if root.playing() => root.stop(context),
+                            ContextMenuCallback::Play => root.play(context),
+                            ContextMenuCallback::Rewind => root.goto_frame(context, 1, true),
+                            ContextMenuCallback::Forward => root.next_frame(context),
+                            ContextMenuCallback::Back => root.prev_frame(context),
+                            _ => (),
+                        }
+                    }
+                }
+                None => (),
+            }
+            Self::run_actions(avm, context);
+            toggle_loop
+        });
+
+        if toggle_loop {
+            self.looping = !self.looping;
+        }
+        self.render();
+    }
+
+    /// Closes the context menu without picking an item.
+    pub fn clear_context_menu(&mut self) {
+        self.context_menu_popup = None;
+        self.gc_arena.mutate(|gc_context, gc_root| {
+            gc_root.0.write(gc_context).current_context_menu = None;
+        });
+    }
+
+    /// Shows the context menu for the object under the mouse as a popup drawn over the movie,
+    /// at the given position in the viewport. While it's open, the popup takes all input.
+    ///
+    /// This is used by frontends that don't have native menus to show the items of
+    /// `prepare_context_menu` with.
+    pub fn show_context_menu(&mut self, x: f64, y: f64) {
+        let items = self.prepare_context_menu();
+        if items.is_empty() {
+            self.clear_context_menu();
+            return;
+        }
+
+        let (viewport_width, viewport_height) = self.stage.viewport_size();
+        let renderer = &mut self.renderer;
+        let popup = self.gc_arena.mutate(|_gc_context, gc_root| {
+            let root_data = gc_root.0.read();
+            let font = root_data.library.get_device_font("_sans");
+            for item in &items {
+                font.register_glyphs(renderer, &item.caption);
+            }
+            ContextMenuPopup::new(
+                items,
+                (x as f32, y as f32),
+                (viewport_width as f32, viewport_height as f32),
+                |caption, height| font.measure(caption, height).0,
+            )
+        });
+        self.context_menu_popup = Some(popup);
+        self.render();
+    }
+
+    /// Whether the context menu popup is open.
+    pub fn is_context_menu_open(&self) -> bool {
+        self.context_menu_popup.is_some()
+    }
+
+    /// Sends an event to the open context menu popup. Clicking an item picks it, and clicking
+    /// outside of the popup or pressing Escape closes it.
+    fn handle_context_menu_event(&mut self, event: PlayerEvent) {
+        let popup = match &mut self.context_menu_popup {
+            Some(popup) => popup,
+            None => return,
+        };
+
+        match event {
+            PlayerEvent::MouseMove { x, y } => {
+                if popup.update_hovered((x as f32, y as f32)) {
+                    self.render();
+                }
+            }
+            PlayerEvent::MouseDown { x, y, .. } if !popup.contains((x as f32, y as f32)) => {
+                self.clear_context_menu();
+                self.render();
+            }
+            PlayerEvent::MouseUp {
+                x,
+                y,
+                button: MouseButton::Left,
+            } => {
+                if let Some(index) = popup.item_at((x as f32, y as f32)) {
+                    self.context_menu_popup = None;
+                    self.run_context_menu_callback(index);
+                }
+            }
+            PlayerEvent::KeyDown {
+                key_code: KeyCode::Escape,
+            } => {
+                self.clear_context_menu();
+                self.render();
+            }
+            _ => (),
+        }
+    }
+
     pub fn audio(&self) -> &Audio {
         &self.audio
     }
@@ -600,6 +1233,11 @@ impl<
     }
 
     fn run_actions<'gc>(avm: &mut Avm1<'gc>, context: &mut UpdateContext<'_, 'gc, '_>) {
+        // Queued actions wait until the debugger resumes the code it has paused.
+        if avm.debugger().is_paused() {
+            return;
+        }
+
         while let Some(actions) = context.action_queue.pop() {
             // We don't run frame actions if the clip was removed after it queued the action.
             if !actions.is_unload && actions.clip.removed() {
@@ -635,6 +1273,18 @@ impl<
                     );
                 }
 
+                // Event handler method call on an object (e.g. Sound.onLoad)
+                ActionType::ObjectMethod { object, name, args } => {
+                    avm.insert_stack_frame_for_method(
+                        actions.clip,
+                        object,
+                        context.swf_version,
+                        context,
+                        name,
+                        &args,
+                    );
+                }
+
                 // Event handler method call (e.g. onEnterFrame)
                 ActionType::NotifyListeners {
                     listener,
@@ -655,44 +1305,110 @@ impl<
             }
             // Execute the stack frame (if any).
             let _ = avm.run_stack_till_empty(context);
+
+            if avm.debugger().is_paused() {
+                return;
+            }
         }
     }
 
-    fn build_matrices(&mut self) {
-        // Create  view matrix to scale stage into viewport area.
-        let (movie_width, movie_height) = (self.movie_width as f32, self.movie_height as f32);
-        let (viewport_width, viewport_height) =
-            (self.viewport_width as f32, self.viewport_height as f32);
-        let movie_aspect = movie_width / movie_height;
-        let viewport_aspect = viewport_width / viewport_height;
-        let (scale, margin_width, margin_height) = if viewport_aspect > movie_aspect {
-            let scale = viewport_height / movie_height;
-            (scale, (viewport_width - movie_width * scale) / 2.0, 0.0)
-        } else {
-            let scale = viewport_width / movie_width;
-            (scale, 0.0, (viewport_height - movie_height * scale) / 2.0)
-        };
-        self.view_matrix = Matrix {
-            a: scale,
-            b: 0.0,
-            c: 0.0,
-            d: scale,
-            tx: margin_width * 20.0,
-            ty: margin_height * 20.0,
-        };
-        self.inverse_view_matrix = self.view_matrix;
-        self.inverse_view_matrix.invert();
-
-        // Calculate letterbox dimensions.
-        // TODO: Letterbox should be an option; the original Flash Player defaults to showing content
-        // in the extra margins.
-        self.letterbox = if margin_width > 0.0 {
-            Letterbox::Pillarbox(margin_width)
-        } else if margin_height > 0.0 {
-            Letterbox::Letterbox(margin_height)
-        } else {
-            Letterbox::None
-        };
+    /// Registers a TrueType or OpenType font file to render device fonts with, returning the
+    /// family names of the fonts in the file.
+    ///
+    /// Text in fonts that aren't embedded in the movie is drawn from the font files with a
+    /// matching name. Characters missing from them are drawn from the `_sans` family, and
+    /// finally from the built-in device font.
+    pub fn add_device_font(&mut self, data: Vec<u8>) -> Result<Vec<String>, Error> {
+        self.gc_arena.mutate(|gc_context, gc_root| {
+            let mut root_data = gc_root.0.write(gc_context);
+            root_data.library.device_fonts_mut().add_font(data)
+        })
+    }
+
+    /// Maps a font name, such as the generic `_sans`, `_serif` and `_typewriter` families, to
+    /// a registered device font family.
+    ///
+    /// A name can be mapped to several families, which are used in order for each character.
+    pub fn map_device_font(&mut self, name: &str, family: &str) -> Result<(), Error> {
+        self.gc_arena.mutate(|gc_context, gc_root| {
+            let mut root_data = gc_root.0.write(gc_context);
+            root_data.library.device_fonts_mut().map_name(name, family)
+        })
+    }
+
+    /// Runs `f` with the AVM1 debugger.
+    fn with_debugger<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Debugger) -> R,
+    {
+        self.gc_arena.mutate(|gc_context, gc_root| {
+            let mut root_data = gc_root.0.write(gc_context);
+            f(root_data.avm.debugger_mut())
+        })
+    }
+
+    /// Adds an AVM1 breakpoint.
+    pub fn debugger_add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.with_debugger(|debugger| debugger.add_breakpoint(breakpoint));
+    }
+
+    /// Removes an AVM1 breakpoint, returning whether it existed.
+    pub fn debugger_remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        self.with_debugger(|debugger| debugger.remove_breakpoint(breakpoint))
+    }
+
+    /// Returns all AVM1 breakpoints.
+    pub fn debugger_breakpoints(&mut self) -> Vec<Breakpoint> {
+        self.with_debugger(|debugger| debugger.breakpoints().to_vec())
+    }
+
+    /// Pauses AVM1 execution as soon as the next action runs.
+    pub fn debugger_pause(&mut self) {
+        self.with_debugger(|debugger| debugger.pause());
+    }
+
+    /// Returns whether the debugger has paused AVM1 execution.
+    /// While paused, the player does not advance frames or run any other code.
+    pub fn debugger_is_paused(&mut self) -> bool {
+        self.with_debugger(|debugger| debugger.is_paused())
+    }
+
+    /// Returns the location AVM1 execution is paused at, if any.
+    pub fn debugger_paused_at(&mut self) -> Option<DebugLocation> {
+        self.with_debugger(|debugger| debugger.paused_at().cloned())
+    }
+
+    /// Resumes paused AVM1 execution.
+    /// If `step` is given, execution will pause again once the step completes.
+    pub fn debugger_resume(&mut self, step: Option<StepMode>) {
+        self.mutate_with_update_context(|avm, context| {
+            avm.debugger_resume(step);
+            let _ = avm.run_stack_till_empty(context);
+            Self::run_actions(avm, context);
+        });
+    }
+
+    /// Returns the activations on the AVM1 stack, from innermost to outermost.
+    pub fn debugger_stack_trace(&mut self) -> Vec<DebugFrame> {
+        self.mutate_with_update_context(|avm, _context| avm.debug_stack_trace())
+    }
+
+    /// Inspects the scope chain, registers, operand stack, locals and `this` of an activation.
+    /// Activations are indexed from the innermost, as in `debugger_stack_trace`.
+    pub fn debugger_inspect_frame(&mut self, index: usize) -> Option<FrameInspection> {
+        self.mutate_with_update_context(|avm, _context| avm.debug_inspect_frame(index))
+    }
+
+    /// Evaluates a dotted path (e.g. `this.clip._x`) in the scope of an activation,
+    /// and inspects the value along with the properties of its prototype chain.
+    pub fn debugger_inspect_value(
+        &mut self,
+        index: usize,
+        path: &str,
+    ) -> Result<ValueInspection, Error> {
+        self.mutate_with_update_context(|avm, context| {
+            avm.debug_inspect_value(context, index, path)
+        })
     }
 
     /// Runs the closure `f` with an `UpdateContext`.
@@ -715,8 +1431,7 @@ impl<
             input,
             rng,
             mouse_position,
-            stage_width,
-            stage_height,
+            stage,
         ) = (
             self.player_version,
             self.global_time,
@@ -729,14 +1444,23 @@ impl<
             &mut self.input,
             &mut self.rng,
             &self.mouse_pos,
-            Twips::from_pixels(self.movie_width.into()),
-            Twips::from_pixels(self.movie_height.into()),
+            &mut self.stage,
         );
 
         self.gc_arena.mutate(|gc_context, gc_root| {
             let mut root_data = gc_root.0.write(gc_context);
             let mouse_hovered_object = root_data.mouse_hovered_object;
-            let (root, library, action_queue, avm, drag_object) = root_data.update_context_params();
+            let mouse_pressed_object = root_data.mouse_pressed_object;
+            let focus_tracker = root_data.focus_tracker;
+            let (
+                root,
+                library,
+                action_queue,
+                avm,
+                drag_object,
+                audio_manager,
+                current_context_menu,
+            ) = root_data.update_context_params();
             let mut update_context = UpdateContext {
                 player_version,
                 global_time,
@@ -747,6 +1471,7 @@ impl<
                 rng,
                 renderer,
                 audio,
+                audio_manager,
                 navigator,
                 input,
                 action_queue,
@@ -754,15 +1479,24 @@ impl<
                 root,
                 system_prototypes: avm.prototypes().clone(),
                 mouse_hovered_object,
+                mouse_pressed_object,
                 mouse_position,
                 drag_object,
-                stage_size: (stage_width, stage_height),
+                stage,
+                current_context_menu,
+                focus_tracker,
             };
 
             let ret = f(avm, &mut update_context);
 
-            // Hovered object may have been updated; copy it back to the GC root.
-            root_data.mouse_hovered_object = update_context.mouse_hovered_object;
+            // Hovered and pressed objects may have been updated; copy them back to the GC root.
+            let UpdateContext {
+                mouse_hovered_object,
+                mouse_pressed_object,
+                ..
+            } = update_context;
+            root_data.mouse_hovered_object = mouse_hovered_object;
+            root_data.mouse_pressed_object = mouse_pressed_object;
             ret
         })
     }
@@ -782,6 +1516,16 @@ impl<
     }
 }
 
+/// The settings of a button, or a clip that acts as a button, that change how it follows
+/// the mouse.
+struct ButtonSettings {
+    /// Whether the button can be pressed by dragging onto it with the mouse button held down.
+    track_as_menu: bool,
+
+    /// Whether the hand cursor is shown over the button.
+    use_hand_cursor: bool,
+}
+
 pub struct DragObject<'gc> {
     /// The display object being dragged.
     pub display_object: DisplayObject<'gc>,
@@ -0,0 +1,675 @@
+//! 9-slice scaling, which keeps the corners of a clip at their size when the clip is stretched.
+//!
+//! A scaling grid, defined by `DefineScalingGrid` or `scale9Grid`, splits a clip into nine
+//! regions. When the clip is scaled, its corners keep their size on the stage, its edges only
+//! stretch along their length, and its center stretches to fill the rest. The shapes directly
+//! inside the clip are split along the grid lines, and each part is drawn with the transform
+//! of its region.
+use crate::prelude::*;
+use crate::shape_utils::{calculate_shape_bounds, swf_shape_to_paths, DrawCommand, DrawPath};
+use swf::{ShapeRecord, ShapeStyles, StyleChangeData};
+
+/// The transforms of the nine regions of a clip with a scaling grid.
+#[derive(Clone, Debug)]
+pub struct ScalingGridTransform {
+    /// The scaling grid, in the local space of the clip.
+    pub grid: BoundingBox,
+
+    /// The transform of each region in the local space of the clip, row by row from the
+    /// top left.
+    pub regions: [Matrix; 9],
+}
+
+impl ScalingGridTransform {
+    /// Computes the transforms of the regions of a clip with the given grid, whose contents
+    /// have the given bounds, and which is transformed to the stage by `matrix`.
+    ///
+    /// Returns `None` if the grid doesn't apply, in which case the clip is scaled normally.
+    /// This is the case when the clip is rotated or skewed, or the grid isn't inside the
+    /// bounds of the clip.
+    pub fn new(grid: &BoundingBox, bounds: &BoundingBox, matrix: &Matrix) -> Option<Self> {
+        if !grid.valid || !bounds.valid || matrix.b != 0.0 || matrix.c != 0.0 {
+            return None;
+        }
+
+        let columns = axis_transforms(
+            (bounds.x_min, bounds.x_max),
+            (grid.x_min, grid.x_max),
+            matrix.a,
+        )?;
+        let rows = axis_transforms(
+            (bounds.y_min, bounds.y_max),
+            (grid.y_min, grid.y_max),
+            matrix.d,
+        )?;
+        let mut regions = [Matrix::default(); 9];
+        for (i, region) in regions.iter_mut().enumerate() {
+            let (a, tx) = columns[i % 3];
+            let (d, ty) = rows[i / 3];
+            *region = Matrix {
+                a,
+                b: 0.0,
+                c: 0.0,
+                d,
+                tx,
+                ty,
+            };
+        }
+        Some(Self {
+            grid: grid.clone(),
+            regions,
+        })
+    }
+}
+
+/// Computes the scale and offset of the three columns or rows of a scaling grid along one axis,
+/// given the bounds of the clip and the position of the grid lines on that axis, and the scale
+/// of the clip on the stage.
+///
+/// The outer parts keep their size on the stage, unless the clip is too small to fit them, in
+/// which case they shrink and the middle part disappears.
+fn axis_transforms(
+    (bounds_min, bounds_max): (Twips, Twips),
+    (grid_min, grid_max): (Twips, Twips),
+    scale: f32,
+) -> Option<[(f32, f32); 3]> {
+    if grid_min < bounds_min || grid_max > bounds_max || grid_min >= grid_max || scale == 0.0 {
+        return None;
+    }
+
+    let (bounds_min, bounds_max) = (bounds_min.get() as f32, bounds_max.get() as f32);
+    let (grid_min, grid_max) = (grid_min.get() as f32, grid_max.get() as f32);
+    let scale = scale.abs();
+    let (start, center, end) = (
+        grid_min - bounds_min,
+        grid_max - grid_min,
+        bounds_max - grid_max,
+    );
+    let size = (bounds_max - bounds_min) * scale;
+    let (outer_scale, center_scale) = if size >= start + end {
+        (1.0, (size - start - end) / center)
+    } else {
+        (size / (start + end), 0.0)
+    };
+
+    // The scales are relative to the clip, which is scaled by `scale` on the stage.
+    let (outer_scale, center_scale) = (outer_scale / scale, center_scale / scale);
+    let center_start = bounds_min + start * outer_scale;
+    let end_start = center_start + center * center_scale;
+    Some([
+        (outer_scale, bounds_min - bounds_min * outer_scale),
+        (center_scale, center_start - grid_min * center_scale),
+        (outer_scale, end_start - grid_max * outer_scale),
+    ])
+}
+
+/// Splits a shape along the lines of a scaling grid into nine shapes, row by row from the top
+/// left. The grid is in the space of the shape. Regions that the shape doesn't cover are `None`.
+pub fn slice_shape(shape: &swf::Shape, grid: &BoundingBox) -> Vec<Option<swf::Shape>> {
+    let paths = swf_shape_to_paths(shape);
+    let (x_min, x_max) = (grid.x_min.get() as f64, grid.x_max.get() as f64);
+    let (y_min, y_max) = (grid.y_min.get() as f64, grid.y_max.get() as f64);
+    let columns = [
+        vec![HalfPlane::below(0, x_min)],
+        vec![HalfPlane::above(0, x_min), HalfPlane::below(0, x_max)],
+        vec![HalfPlane::above(0, x_max)],
+    ];
+    let rows = [
+        vec![HalfPlane::below(1, y_min)],
+        vec![HalfPlane::above(1, y_min), HalfPlane::below(1, y_max)],
+        vec![HalfPlane::above(1, y_max)],
+    ];
+
+    (0..9)
+        .map(|i| {
+            let planes: Vec<HalfPlane> = rows[i / 3]
+                .iter()
+                .chain(columns[i % 3].iter())
+                .copied()
+                .collect();
+            region_shape(shape, &paths, &planes)
+        })
+        .collect()
+}
+
+/// Builds a shape from the parts of `paths` that are inside all of `planes`.
+fn region_shape(
+    shape: &swf::Shape,
+    paths: &[DrawPath<'_>],
+    planes: &[HalfPlane],
+) -> Option<swf::Shape> {
+    let mut styles = ShapeStyles {
+        fill_styles: vec![],
+        line_styles: vec![],
+    };
+    let mut records = vec![];
+    for path in paths {
+        match path {
+            DrawPath::Fill { style, commands } => {
+                let contours = clip_contours(to_contours(commands), planes, true);
+                if !contours.is_empty() {
+                    styles.fill_styles.push((*style).clone());
+                    let fill_style = styles.fill_styles.len() as u32;
+                    write_contours(&mut records, &contours, fill_style, 0);
+                }
+            }
+            DrawPath::Stroke {
+                style, commands, ..
+            } => {
+                let contours = clip_contours(to_contours(commands), planes, false);
+                if !contours.is_empty() {
+                    styles.line_styles.push((*style).clone());
+                    let line_style = styles.line_styles.len() as u32;
+                    write_contours(&mut records, &contours, 0, line_style);
+                }
+            }
+        }
+    }
+
+    if records.is_empty() {
+        return None;
+    }
+    let bounds = calculate_shape_bounds(&records);
+    Some(swf::Shape {
+        version: shape.version,
+        id: shape.id,
+        shape_bounds: bounds.clone(),
+        edge_bounds: bounds,
+        has_fill_winding_rule: false,
+        has_non_scaling_strokes: shape.has_non_scaling_strokes,
+        has_scaling_strokes: shape.has_scaling_strokes,
+        styles,
+        shape: records,
+    })
+}
+
+/// A point of a path, in twips.
+type Point = (f64, f64);
+
+#[derive(Copy, Clone, Debug)]
+enum Segment {
+    Line(Point),
+    /// A quadratic curve, with its control point and end point.
+    Curve(Point, Point),
+}
+
+impl Segment {
+    fn end(self) -> Point {
+        match self {
+            Segment::Line(end) | Segment::Curve(_, end) => end,
+        }
+    }
+}
+
+/// A series of connected segments.
+#[derive(Clone, Debug)]
+struct Contour {
+    start: Point,
+    segments: Vec<Segment>,
+}
+
+impl Contour {
+    fn end(&self) -> Point {
+        self.segments
+            .last()
+            .map_or(self.start, |segment| segment.end())
+    }
+}
+
+/// The side of a grid line that is kept, either `coordinate <= value` or `coordinate >= value`
+/// on the given axis, where 0 is x and 1 is y.
+#[derive(Copy, Clone, Debug)]
+struct HalfPlane {
+    axis: usize,
+    value: f64,
+    below: bool,
+}
+
+impl HalfPlane {
+    fn below(axis: usize, value: f64) -> Self {
+        Self {
+            axis,
+            value,
+            below: true,
+        }
+    }
+
+    fn above(axis: usize, value: f64) -> Self {
+        Self {
+            axis,
+            value,
+            below: false,
+        }
+    }
+
+    fn coordinate(self, point: Point) -> f64 {
+        if self.axis == 0 {
+            point.0
+        } else {
+            point.1
+        }
+    }
+
+    fn contains(self, point: Point) -> bool {
+        if self.below {
+            self.coordinate(point) <= self.value
+        } else {
+            self.coordinate(point) >= self.value
+        }
+    }
+
+    /// Moves a point that was computed to be on the line exactly onto it.
+    fn snap(self, point: Point) -> Point {
+        if self.axis == 0 {
+            (self.value, point.1)
+        } else {
+            (point.0, self.value)
+        }
+    }
+}
+
+fn to_contours(commands: &[DrawCommand]) -> Vec<Contour> {
+    let mut contours: Vec<Contour> = vec![];
+    let twips_point = |x: Twips, y: Twips| (x.get() as f64, y.get() as f64);
+    for command in commands {
+        let segment = match *command {
+            DrawCommand::MoveTo { x, y } => {
+                contours.push(Contour {
+                    start: twips_point(x, y),
+                    segments: vec![],
+                });
+                continue;
+            }
+            DrawCommand::LineTo { x, y } => Segment::Line(twips_point(x, y)),
+            DrawCommand::CurveTo { x1, y1, x2, y2 } => {
+                Segment::Curve(twips_point(x1, y1), twips_point(x2, y2))
+            }
+        };
+        if contours.is_empty() {
+            contours.push(Contour {
+                start: (0.0, 0.0),
+                segments: vec![],
+            });
+        }
+        contours.last_mut().unwrap().segments.push(segment);
+    }
+    contours
+}
+
+fn clip_contours(mut contours: Vec<Contour>, planes: &[HalfPlane], closed: bool) -> Vec<Contour> {
+    for &plane in planes {
+        contours = contours
+            .iter()
+            .flat_map(|contour| clip_contour(contour, plane, closed))
+            .collect();
+    }
+    contours
+}
+
+/// Clips a contour to a half plane. A closed contour stays closed, by joining the points where
+/// it leaves and reenters the half plane along the grid line, so that it can still be filled.
+/// An open contour is split into the parts inside the half plane.
+fn clip_contour(contour: &Contour, plane: HalfPlane, closed: bool) -> Vec<Contour> {
+    let mut clipped = vec![];
+    let mut current: Option<Contour> = None;
+    let mut position = contour.start;
+    for &segment in &contour.segments {
+        for (start, piece) in split_segment(position, segment, plane) {
+            if !plane.contains(midpoint(start, piece)) {
+                if !closed {
+                    clipped.extend(current.take());
+                }
+                continue;
+            }
+
+            match current {
+                Some(ref mut current) if points_equal(current.end(), start) => {
+                    current.segments.push(piece)
+                }
+                Some(ref mut current) if closed => {
+                    current.segments.push(Segment::Line(start));
+                    current.segments.push(piece);
+                }
+                _ => {
+                    clipped.extend(current.take());
+                    current = Some(Contour {
+                        start,
+                        segments: vec![piece],
+                    });
+                }
+            }
+        }
+        position = segment.end();
+    }
+
+    if let Some(mut current) = current {
+        if closed && !points_equal(current.end(), current.start) {
+            current.segments.push(Segment::Line(current.start));
+        }
+        clipped.push(current);
+    }
+    clipped
+}
+
+/// Splits a segment starting at `start` where it crosses the line of a half plane, returning
+/// each piece with its start point.
+fn split_segment(start: Point, segment: Segment, plane: HalfPlane) -> Vec<(Point, Segment)> {
+    let value = plane.value;
+    let mut crossings = match segment {
+        Segment::Line(end) => {
+            let (a, b) = (plane.coordinate(start), plane.coordinate(end));
+            if (a - value) * (b - value) < 0.0 {
+                vec![(value - a) / (b - a)]
+            } else {
+                vec![]
+            }
+        }
+        Segment::Curve(control, end) => {
+            let (p0, p1, p2) = (
+                plane.coordinate(start),
+                plane.coordinate(control),
+                plane.coordinate(end),
+            );
+            quadratic_roots(p0 - 2.0 * p1 + p2, 2.0 * (p1 - p0), p0 - value)
+        }
+    };
+    crossings.retain(|&t| t > 0.0 && t < 1.0);
+    crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mut pieces = vec![];
+    let mut piece_start = (0.0, start);
+    for t in crossings.into_iter().map(Some).chain(std::iter::once(None)) {
+        let (t0, p0) = piece_start;
+        let (t1, p1) = match t {
+            Some(t) => (t, plane.snap(segment_point(start, segment, t))),
+            None => (1.0, segment.end()),
+        };
+        let piece = match segment {
+            Segment::Line(_) => Segment::Line(p1),
+            Segment::Curve(control, end) => {
+                // The control point of the part of the curve from `t0` to `t1`.
+                let (dx, dy) = curve_derivative(start, control, end, t0);
+                let half = (t1 - t0) / 2.0;
+                Segment::Curve((p0.0 + dx * half, p0.1 + dy * half), p1)
+            }
+        };
+        pieces.push((p0, piece));
+        piece_start = (t1, p1);
+    }
+    pieces
+}
+
+/// The real roots of `a * t^2 + b * t + c`.
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < 1e-9 {
+        return if b.abs() < 1e-9 { vec![] } else { vec![-c / b] };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return vec![];
+    }
+    let root = discriminant.sqrt();
+    vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+}
+
+fn segment_point(start: Point, segment: Segment, t: f64) -> Point {
+    match segment {
+        Segment::Line(end) => (
+            start.0 + (end.0 - start.0) * t,
+            start.1 + (end.1 - start.1) * t,
+        ),
+        Segment::Curve(control, end) => {
+            let u = 1.0 - t;
+            (
+                u * u * start.0 + 2.0 * u * t * control.0 + t * t * end.0,
+                u * u * start.1 + 2.0 * u * t * control.1 + t * t * end.1,
+            )
+        }
+    }
+}
+
+fn curve_derivative(start: Point, control: Point, end: Point, t: f64) -> (f64, f64) {
+    let u = 1.0 - t;
+    (
+        2.0 * (u * (control.0 - start.0) + t * (end.0 - control.0)),
+        2.0 * (u * (control.1 - start.1) + t * (end.1 - control.1)),
+    )
+}
+
+fn midpoint(start: Point, segment: Segment) -> Point {
+    segment_point(start, segment, 0.5)
+}
+
+fn points_equal(a: Point, b: Point) -> bool {
+    (a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6
+}
+
+/// Writes contours as shape records, drawn with the given fill and line styles.
+fn write_contours(
+    records: &mut Vec<ShapeRecord>,
+    contours: &[Contour],
+    fill_style: u32,
+    line_style: u32,
+) {
+    let round = |(x, y): Point| (x.round() as i32, y.round() as i32);
+    for contour in contours {
+        let mut position = round(contour.start);
+        records.push(ShapeRecord::StyleChange(StyleChangeData {
+            move_to: Some((Twips::new(position.0), Twips::new(position.1))),
+            fill_style_0: Some(0),
+            fill_style_1: Some(fill_style),
+            line_style: Some(line_style),
+            new_styles: None,
+        }));
+        for &segment in &contour.segments {
+            match segment {
+                Segment::Line(end) => {
+                    let end = round(end);
+                    if end != position {
+                        records.push(ShapeRecord::StraightEdge {
+                            delta_x: Twips::new(end.0 - position.0),
+                            delta_y: Twips::new(end.1 - position.1),
+                        });
+                        position = end;
+                    }
+                }
+                Segment::Curve(control, end) => {
+                    let (control, end) = (round(control), round(end));
+                    records.push(ShapeRecord::CurvedEdge {
+                        control_delta_x: Twips::new(control.0 - position.0),
+                        control_delta_y: Twips::new(control.1 - position.1),
+                        anchor_delta_x: Twips::new(end.0 - control.0),
+                        anchor_delta_y: Twips::new(end.1 - control.1),
+                    });
+                    position = end;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(x_min: f64, y_min: f64, x_max: f64, y_max: f64) -> BoundingBox {
+        BoundingBox {
+            x_min: Twips::from_pixels(x_min),
+            y_min: Twips::from_pixels(y_min),
+            x_max: Twips::from_pixels(x_max),
+            y_max: Twips::from_pixels(y_max),
+            valid: true,
+        }
+    }
+
+    /// A red square with its top left corner at the origin.
+    fn square(size: f64) -> swf::Shape {
+        let size = Twips::from_pixels(size);
+        let zero = Twips::new(0);
+        let records = vec![
+            ShapeRecord::StyleChange(StyleChangeData {
+                move_to: Some((zero, zero)),
+                fill_style_0: None,
+                fill_style_1: Some(1),
+                line_style: None,
+                new_styles: None,
+            }),
+            ShapeRecord::StraightEdge {
+                delta_x: size,
+                delta_y: zero,
+            },
+            ShapeRecord::StraightEdge {
+                delta_x: zero,
+                delta_y: size,
+            },
+            ShapeRecord::StraightEdge {
+                delta_x: Twips::new(-size.get()),
+                delta_y: zero,
+            },
+            ShapeRecord::StraightEdge {
+                delta_x: zero,
+                delta_y: Twips::new(-size.get()),
+            },
+        ];
+        let bounds = calculate_shape_bounds(&records);
+        swf::Shape {
+            version: 1,
+            id: 1,
+            shape_bounds: bounds.clone(),
+            edge_bounds: bounds,
+            has_fill_winding_rule: false,
+            has_non_scaling_strokes: false,
+            has_scaling_strokes: true,
+            styles: ShapeStyles {
+                fill_styles: vec![swf::FillStyle::Color(Color {
+                    r: 255,
+                    g: 0,
+                    b: 0,
+                    a: 255,
+                })],
+                line_styles: vec![],
+            },
+            shape: records,
+        }
+    }
+
+    fn x_on_stage(transform: &ScalingGridTransform, region: usize, x: f64, scale: f32) -> Twips {
+        let matrix = Matrix {
+            a: scale,
+            ..Default::default()
+        } * transform.regions[region];
+        let (x, _) = matrix * (Twips::from_pixels(x), Twips::new(0));
+        x
+    }
+
+    #[test]
+    fn corners_keep_their_size() {
+        let grid = bounds(10.0, 10.0, 90.0, 90.0);
+        let clip_bounds = bounds(0.0, 0.0, 100.0, 100.0);
+        let matrix = Matrix {
+            a: 2.0,
+            ..Default::default()
+        };
+        let transform = ScalingGridTransform::new(&grid, &clip_bounds, &matrix).unwrap();
+
+        assert_eq!(x_on_stage(&transform, 0, 0.0, 2.0), Twips::from_pixels(0.0));
+        assert_eq!(
+            x_on_stage(&transform, 0, 10.0, 2.0),
+            Twips::from_pixels(10.0)
+        );
+        assert_eq!(
+            x_on_stage(&transform, 1, 90.0, 2.0),
+            Twips::from_pixels(190.0)
+        );
+        assert_eq!(
+            x_on_stage(&transform, 2, 100.0, 2.0),
+            Twips::from_pixels(200.0)
+        );
+    }
+
+    #[test]
+    fn corners_shrink_in_small_clips() {
+        let grid = bounds(10.0, 10.0, 90.0, 90.0);
+        let clip_bounds = bounds(0.0, 0.0, 100.0, 100.0);
+        let matrix = Matrix {
+            a: 0.125,
+            ..Default::default()
+        };
+        let transform = ScalingGridTransform::new(&grid, &clip_bounds, &matrix).unwrap();
+
+        assert_eq!(
+            x_on_stage(&transform, 0, 10.0, 0.125),
+            Twips::from_pixels(6.25)
+        );
+        assert_eq!(
+            x_on_stage(&transform, 1, 90.0, 0.125),
+            Twips::from_pixels(6.25)
+        );
+        assert_eq!(
+            x_on_stage(&transform, 2, 100.0, 0.125),
+            Twips::from_pixels(12.5)
+        );
+    }
+
+    #[test]
+    fn grid_is_ignored_when_rotated_or_outside() {
+        let grid = bounds(10.0, 10.0, 90.0, 90.0);
+        let clip_bounds = bounds(0.0, 0.0, 100.0, 100.0);
+        let rotated = Matrix {
+            b: 1.0,
+            c: -1.0,
+            ..Default::default()
+        };
+        assert!(ScalingGridTransform::new(&grid, &clip_bounds, &rotated).is_none());
+
+        let outside = bounds(-10.0, 10.0, 90.0, 90.0);
+        assert!(ScalingGridTransform::new(&outside, &clip_bounds, &Matrix::default()).is_none());
+    }
+
+    #[test]
+    fn slice_square() {
+        let grid = bounds(25.0, 25.0, 75.0, 75.0);
+        let regions = slice_shape(&square(100.0), &grid);
+        assert_eq!(regions.len(), 9);
+
+        let expected = [
+            (0.0, 25.0, 0.0, 25.0),
+            (25.0, 75.0, 0.0, 25.0),
+            (75.0, 100.0, 0.0, 25.0),
+            (0.0, 25.0, 25.0, 75.0),
+            (25.0, 75.0, 25.0, 75.0),
+            (75.0, 100.0, 25.0, 75.0),
+            (0.0, 25.0, 75.0, 100.0),
+            (25.0, 75.0, 75.0, 100.0),
+            (75.0, 100.0, 75.0, 100.0),
+        ];
+        for (region, &(x_min, x_max, y_min, y_max)) in regions.iter().zip(expected.iter()) {
+            let bounds = &region.as_ref().unwrap().shape_bounds;
+            assert_eq!(bounds.x_min, Twips::from_pixels(x_min));
+            assert_eq!(bounds.x_max, Twips::from_pixels(x_max));
+            assert_eq!(bounds.y_min, Twips::from_pixels(y_min));
+            assert_eq!(bounds.y_max, Twips::from_pixels(y_max));
+        }
+    }
+
+    #[test]
+    fn slice_leaves_out_empty_regions() {
+        let grid = bounds(-10.0, -10.0, 110.0, 110.0);
+        let regions = slice_shape(&square(100.0), &grid);
+        for (i, region) in regions.iter().enumerate() {
+            assert_eq!(region.is_some(), i == 4);
+        }
+    }
+
+    #[test]
+    fn split_curve() {
+        // A curve from (0, 0) to (100, 0) that peaks at y = 50.
+        let segment = Segment::Curve((50.0, 100.0), (100.0, 0.0));
+        let pieces = split_segment((0.0, 0.0), segment, HalfPlane::below(0, 50.0));
+        assert_eq!(pieces.len(), 2);
+        let (start, piece) = pieces[1];
+        assert!(points_equal(start, (50.0, 50.0)));
+        assert!(points_equal(segment_point(start, piece, 1.0), (100.0, 0.0)));
+        assert!(points_equal(segment_point(start, piece, 0.5), (75.0, 37.5)));
+    }
+}
@@ -64,6 +64,84 @@ pub fn swf_shape_to_paths<'a>(shape: &'a swf::Shape) -> Vec<DrawPath<'a>> {
     ShapeConverter::from_shape(shape).into_commands()
 }
 
+/// Tests whether a point, in the coordinate space of the shape, is inside one of the fills or
+/// on one of the strokes of the shape.
+pub fn shape_hit_test(shape: &swf::Shape, (point_x, point_y): (Twips, Twips)) -> bool {
+    let point = (point_x.get() as f64, point_y.get() as f64);
+    swf_shape_to_paths(shape).iter().any(|path| match path {
+        DrawPath::Fill { commands, .. } => {
+            // Even-odd rule: count the edges crossed by a ray to the right of the point.
+            let mut is_inside = false;
+            for_each_edge(commands, |(x1, y1), (x2, y2)| {
+                if (y1 > point.1) != (y2 > point.1)
+                    && point.0 < x1 + (point.1 - y1) * (x2 - x1) / (y2 - y1)
+                {
+                    is_inside = !is_inside;
+                }
+            });
+            is_inside
+        }
+        DrawPath::Stroke {
+            style, commands, ..
+        } => {
+            // Hairlines are still a pixel wide.
+            let half_width = f64::from(style.width.get().max(20)) / 2.0;
+            let mut is_hit = false;
+            for_each_edge(commands, |start, end| {
+                is_hit |= distance_to_edge(point, start, end) <= half_width;
+            });
+            is_hit
+        }
+    })
+}
+
+/// Calls `f` with the start and end points of each edge of a path, in twips. Curves are split
+/// into straight edges.
+fn for_each_edge(commands: &[DrawCommand], mut f: impl FnMut((f64, f64), (f64, f64))) {
+    const CURVE_EDGES: u32 = 16;
+    let to_f64 = |x: Twips, y: Twips| (x.get() as f64, y.get() as f64);
+    let mut cursor = (0.0, 0.0);
+    for command in commands {
+        match *command {
+            DrawCommand::MoveTo { x, y } => cursor = to_f64(x, y),
+            DrawCommand::LineTo { x, y } => {
+                let end = to_f64(x, y);
+                f(cursor, end);
+                cursor = end;
+            }
+            DrawCommand::CurveTo { x1, y1, x2, y2 } => {
+                let start = cursor;
+                let control = to_f64(x1, y1);
+                let end = to_f64(x2, y2);
+                for i in 1..=CURVE_EDGES {
+                    let t = f64::from(i) / f64::from(CURVE_EDGES);
+                    let s = 1.0 - t;
+                    let point = (
+                        s * s * start.0 + 2.0 * s * t * control.0 + t * t * end.0,
+                        s * s * start.1 + 2.0 * s * t * control.1 + t * t * end.1,
+                    );
+                    f(cursor, point);
+                    cursor = point;
+                }
+            }
+        }
+    }
+}
+
+/// The distance from a point to the closest point on an edge.
+fn distance_to_edge(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let edge = (end.0 - start.0, end.1 - start.1);
+    let length_squared = edge.0 * edge.0 + edge.1 * edge.1;
+    let t = if length_squared > 0.0 {
+        let t = ((point.0 - start.0) * edge.0 + (point.1 - start.1) * edge.1) / length_squared;
+        t.max(0.0).min(1.0)
+    } else {
+        0.0
+    };
+    let closest = (start.0 + t * edge.0, start.1 + t * edge.1);
+    ((point.0 - closest.0).powi(2) + (point.1 - closest.1).powi(2)).sqrt()
+}
+
 /// `DrawPath` represents a solid fill or a stroke.
 /// Fills are always closed paths, while strokes may be open or closed.
 /// Closed paths will have the first point equal to the last point.
@@ -684,4 +762,41 @@ mod tests {
         }];
         assert_eq!(commands, expected);
     }
+
+    /// A square with a square hole in the middle, which is outside the shape.
+    #[test]
+    fn hit_test() {
+        let edge = |x, y| ShapeRecord::StraightEdge {
+            delta_x: Twips::from_pixels(x),
+            delta_y: Twips::from_pixels(y),
+        };
+        let move_to = |x, y| {
+            ShapeRecord::StyleChange(swf::StyleChangeData {
+                move_to: Some((Twips::from_pixels(x), Twips::from_pixels(y))),
+                fill_style_0: None,
+                fill_style_1: Some(1),
+                line_style: None,
+                new_styles: None,
+            })
+        };
+        let shape = build_shape(vec![
+            move_to(0.0, 0.0),
+            edge(100.0, 0.0),
+            edge(0.0, 100.0),
+            edge(-100.0, 0.0),
+            edge(0.0, -100.0),
+            move_to(25.0, 25.0),
+            edge(50.0, 0.0),
+            edge(0.0, 50.0),
+            edge(-50.0, 0.0),
+            edge(0.0, -50.0),
+        ]);
+        let hit_test =
+            |x, y| shape_hit_test(&shape, (Twips::from_pixels(x), Twips::from_pixels(y)));
+        assert!(hit_test(10.0, 10.0));
+        assert!(hit_test(90.0, 50.0));
+        assert!(!hit_test(50.0, 50.0));
+        assert!(!hit_test(150.0, 50.0));
+        assert!(!hit_test(-10.0, 50.0));
+    }
 }
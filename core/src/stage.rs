@@ -0,0 +1,505 @@
+//! The stage, which places the movie in the viewport according to `Stage.scaleMode` and
+//! `Stage.align`.
+
+use crate::backend::render::Letterbox;
+use crate::prelude::*;
+use gc_arena::Collect;
+
+/// The largest zoom factor that the context menu can zoom in to.
+const MAX_ZOOM: f32 = 16.0;
+
+/// How the movie is scaled to fit the viewport, set by `Stage.scaleMode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StageScaleMode {
+    /// The whole movie is visible, keeping its aspect ratio. This is the default.
+    ShowAll,
+
+    /// The movie fills the viewport, keeping its aspect ratio and cropping its edges.
+    NoBorder,
+
+    /// The movie is stretched to fill the viewport.
+    ExactFit,
+
+    /// The movie is not scaled, and the stage is resized to the viewport.
+    NoScale,
+}
+
+impl StageScaleMode {
+    /// Parses a scale mode name, as assigned to `Stage.scaleMode`. Names are case-insensitive.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "showall" => Some(StageScaleMode::ShowAll),
+            "noborder" => Some(StageScaleMode::NoBorder),
+            "exactfit" => Some(StageScaleMode::ExactFit),
+            "noscale" => Some(StageScaleMode::NoScale),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the scale mode, as returned by `Stage.scaleMode`.
+    pub fn name(self) -> &'static str {
+        match self {
+            StageScaleMode::ShowAll => "showAll",
+            StageScaleMode::NoBorder => "noBorder",
+            StageScaleMode::ExactFit => "exactFit",
+            StageScaleMode::NoScale => "noScale",
+        }
+    }
+}
+
+impl Default for StageScaleMode {
+    fn default() -> Self {
+        StageScaleMode::ShowAll
+    }
+}
+
+/// The rendering quality, set by `_quality` or from the context menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Collect)]
+#[collect(require_static)]
+pub enum StageQuality {
+    Low,
+    Medium,
+    High,
+    Best,
+}
+
+impl StageQuality {
+    /// Parses a quality name, as assigned to `_quality`. Names are case-insensitive.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "low" => Some(StageQuality::Low),
+            "medium" => Some(StageQuality::Medium),
+            "high" => Some(StageQuality::High),
+            "best" => Some(StageQuality::Best),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the quality, as returned by `_quality`.
+    pub fn name(self) -> &'static str {
+        match self {
+            StageQuality::Low => "LOW",
+            StageQuality::Medium => "MEDIUM",
+            StageQuality::High => "HIGH",
+            StageQuality::Best => "BEST",
+        }
+    }
+}
+
+impl Default for StageQuality {
+    fn default() -> Self {
+        StageQuality::High
+    }
+}
+
+/// Where the movie is placed along one axis of the viewport.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    /// The left or top edge.
+    Start,
+    Center,
+    /// The right or bottom edge.
+    End,
+}
+
+impl Alignment {
+    /// Returns the offset of the movie along this axis, given the space left over in the
+    /// viewport after it is scaled.
+    fn offset(self, extra_space: f32) -> f32 {
+        match self {
+            Alignment::Start => 0.0,
+            Alignment::Center => extra_space / 2.0,
+            Alignment::End => extra_space,
+        }
+    }
+}
+
+impl Default for Alignment {
+    fn default() -> Self {
+        Alignment::Center
+    }
+}
+
+/// Where the movie is placed in the viewport, set by `Stage.align`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StageAlign {
+    pub horizontal: Alignment,
+    pub vertical: Alignment,
+}
+
+impl StageAlign {
+    /// Parses an alignment, as assigned to `Stage.align`.
+    ///
+    /// The alignment is made up of the letters `T`, `B`, `L` and `R` for the top, bottom, left
+    /// and right edges, in any order and case. Other characters are ignored, and an axis
+    /// without an edge is centered. If both edges of an axis are given, the top or left wins.
+    pub fn from_name(name: &str) -> Self {
+        let has_edge = |edge| name.chars().any(|c| c.to_ascii_uppercase() == edge);
+        let axis = |start, end| {
+            if has_edge(start) {
+                Alignment::Start
+            } else if has_edge(end) {
+                Alignment::End
+            } else {
+                Alignment::Center
+            }
+        };
+        Self {
+            horizontal: axis('L', 'R'),
+            vertical: axis('T', 'B'),
+        }
+    }
+
+    /// Returns the name of the alignment, as returned by `Stage.align`, e.g. `"TL"`.
+    pub fn name(self) -> String {
+        let mut name = String::with_capacity(2);
+        match self.vertical {
+            Alignment::Start => name.push('T'),
+            Alignment::End => name.push('B'),
+            Alignment::Center => (),
+        }
+        match self.horizontal {
+            Alignment::Start => name.push('L'),
+            Alignment::End => name.push('R'),
+            Alignment::Center => (),
+        }
+        name
+    }
+}
+
+/// Places the movie in the viewport.
+pub struct Stage {
+    /// The size of the movie, as defined in its header, in pixels.
+    movie_size: (u32, u32),
+
+    /// The size of the viewport the player renders to, in pixels.
+    viewport_size: (u32, u32),
+
+    scale_mode: StageScaleMode,
+    align: StageAlign,
+
+    /// Whether the margins around the movie are masked when its aspect ratio doesn't match
+    /// the viewport's in `showAll` mode.
+    letterbox_enabled: bool,
+
+    /// Whether the built-in items of the context menu are shown, set by `Stage.showMenu`.
+    show_menu: bool,
+
+    quality: StageQuality,
+
+    /// Whether a yellow rectangle is drawn around the object with keyboard focus, set by the
+    /// global `_focusrect`. Buttons and clips can override it with their own `_focusrect`.
+    focus_rect: bool,
+
+    /// The zoom factor chosen from the context menu. The view is zoomed around the center
+    /// of the viewport.
+    zoom: f32,
+}
+
+impl Stage {
+    pub fn new(movie_width: u32, movie_height: u32) -> Self {
+        Self {
+            movie_size: (movie_width, movie_height),
+            viewport_size: (movie_width, movie_height),
+            scale_mode: Default::default(),
+            align: Default::default(),
+            letterbox_enabled: true,
+            show_menu: true,
+            quality: Default::default(),
+            focus_rect: true,
+            zoom: 1.0,
+        }
+    }
+
+    pub fn movie_size(&self) -> (u32, u32) {
+        self.movie_size
+    }
+
+    pub fn viewport_size(&self) -> (u32, u32) {
+        self.viewport_size
+    }
+
+    /// Sets the size of the viewport, returning whether the size of the stage changed as a
+    /// result. This is only the case in `noScale` mode, and should fire `Stage.onResize`.
+    pub fn set_viewport_size(&mut self, width: u32, height: u32) -> bool {
+        let old_size = self.stage_size();
+        self.viewport_size = (width, height);
+        self.stage_size() != old_size
+    }
+
+    pub fn scale_mode(&self) -> StageScaleMode {
+        self.scale_mode
+    }
+
+    pub fn set_scale_mode(&mut self, scale_mode: StageScaleMode) {
+        self.scale_mode = scale_mode;
+    }
+
+    pub fn align(&self) -> StageAlign {
+        self.align
+    }
+
+    pub fn set_align(&mut self, align: StageAlign) {
+        self.align = align;
+    }
+
+    pub fn letterbox_enabled(&self) -> bool {
+        self.letterbox_enabled
+    }
+
+    pub fn set_letterbox_enabled(&mut self, letterbox_enabled: bool) {
+        self.letterbox_enabled = letterbox_enabled;
+    }
+
+    pub fn show_menu(&self) -> bool {
+        self.show_menu
+    }
+
+    pub fn set_show_menu(&mut self, show_menu: bool) {
+        self.show_menu = show_menu;
+    }
+
+    pub fn quality(&self) -> StageQuality {
+        self.quality
+    }
+
+    pub fn set_quality(&mut self, quality: StageQuality) {
+        self.quality = quality;
+    }
+
+    pub fn focus_rect(&self) -> bool {
+        self.focus_rect
+    }
+
+    pub fn set_focus_rect(&mut self, focus_rect: bool) {
+        self.focus_rect = focus_rect;
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Doubles the zoom factor, up to a limit.
+    pub fn zoom_in(&mut self) {
+        self.zoom = (self.zoom * 2.0).min(MAX_ZOOM);
+    }
+
+    /// Halves the zoom factor. The view can't be zoomed out further than the whole movie.
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom / 2.0).max(1.0);
+    }
+
+    /// Zooms out to show the whole movie.
+    pub fn reset_zoom(&mut self) {
+        self.zoom = 1.0;
+    }
+
+    /// Returns the size of the stage, as returned by `Stage.width` and `Stage.height`.
+    /// This is the size of the movie, except in `noScale` mode, where it's the size of the
+    /// viewport.
+    pub fn stage_size(&self) -> (Twips, Twips) {
+        let (width, height) = if self.scale_mode == StageScaleMode::NoScale {
+            self.viewport_size
+        } else {
+            self.movie_size
+        };
+        (
+            Twips::from_pixels(width.into()),
+            Twips::from_pixels(height.into()),
+        )
+    }
+
+    /// Returns the matrix that transforms the movie into the viewport.
+    pub fn view_matrix(&self) -> Matrix {
+        let (movie_width, movie_height) = (self.movie_size.0 as f32, self.movie_size.1 as f32);
+        let (viewport_width, viewport_height) =
+            (self.viewport_size.0 as f32, self.viewport_size.1 as f32);
+        let (scale_x, scale_y) = (viewport_width / movie_width, viewport_height / movie_height);
+        let (scale_x, scale_y) = match self.scale_mode {
+            StageScaleMode::ShowAll => {
+                let scale = scale_x.min(scale_y);
+                (scale, scale)
+            }
+            StageScaleMode::NoBorder => {
+                let scale = scale_x.max(scale_y);
+                (scale, scale)
+            }
+            StageScaleMode::ExactFit => (scale_x, scale_y),
+            StageScaleMode::NoScale => (1.0, 1.0),
+        };
+
+        let margin_x = self
+            .align
+            .horizontal
+            .offset(viewport_width - movie_width * scale_x);
+        let margin_y = self
+            .align
+            .vertical
+            .offset(viewport_height - movie_height * scale_y);
+
+        // Zoom around the center of the viewport.
+        let (center_x, center_y) = (viewport_width / 2.0, viewport_height / 2.0);
+        Matrix {
+            a: scale_x * self.zoom,
+            b: 0.0,
+            c: 0.0,
+            d: scale_y * self.zoom,
+            tx: (center_x + (margin_x - center_x) * self.zoom) * 20.0,
+            ty: (center_y + (margin_y - center_y) * self.zoom) * 20.0,
+        }
+    }
+
+    /// Returns the matrix that transforms the viewport into the movie, e.g. to find the
+    /// position of the mouse on the stage.
+    pub fn inverse_view_matrix(&self) -> Matrix {
+        let mut matrix = self.view_matrix();
+        matrix.invert();
+        matrix
+    }
+
+    /// Returns the area of the stage visible in the viewport.
+    pub fn view_bounds(&self) -> BoundingBox {
+        BoundingBox {
+            x_min: Twips::new(0),
+            y_min: Twips::new(0),
+            x_max: Twips::from_pixels(self.viewport_size.0.into()),
+            y_max: Twips::from_pixels(self.viewport_size.1.into()),
+            valid: true,
+        }
+        .transform(&self.inverse_view_matrix())
+    }
+
+    /// Returns the margins around the movie to mask.
+    ///
+    /// Only the margins left by `showAll` mode are masked; other scale modes fill the
+    /// viewport, or show content outside the movie's bounds as Flash does. Zoomed views
+    /// aren't masked either.
+    pub fn letterbox(&self) -> Letterbox {
+        if !self.letterbox_enabled || self.scale_mode != StageScaleMode::ShowAll || self.zoom > 1.0
+        {
+            return Letterbox::None;
+        }
+
+        let matrix = self.view_matrix();
+        let (left, top) = (matrix.tx / 20.0, matrix.ty / 20.0);
+        let right = self.viewport_size.0 as f32 - left - self.movie_size.0 as f32 * matrix.a;
+        let bottom = self.viewport_size.1 as f32 - top - self.movie_size.1 as f32 * matrix.d;
+        if left > 0.0 || right > 0.0 {
+            Letterbox::Pillarbox(left, right)
+        } else if top > 0.0 || bottom > 0.0 {
+            Letterbox::Letterbox(top, bottom)
+        } else {
+            Letterbox::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn assert_matrix_eq(matrix: Matrix, (a, d, tx, ty): (f32, f32, f32, f32)) {
+        assert_abs_diff_eq!(matrix.a, a, epsilon = 0.0001);
+        assert_abs_diff_eq!(matrix.d, d, epsilon = 0.0001);
+        assert_abs_diff_eq!(matrix.tx, tx, epsilon = 0.01);
+        assert_abs_diff_eq!(matrix.ty, ty, epsilon = 0.01);
+    }
+
+    #[test]
+    fn scale_modes() {
+        // A 400x300 movie in a 1000x300 viewport.
+        let mut stage = Stage::new(400, 300);
+        stage.set_viewport_size(1000, 300);
+        assert_matrix_eq(stage.view_matrix(), (1.0, 1.0, 300.0 * 20.0, 0.0));
+        assert_eq!(stage.letterbox(), Letterbox::Pillarbox(300.0, 300.0));
+
+        stage.set_scale_mode(StageScaleMode::NoBorder);
+        assert_matrix_eq(stage.view_matrix(), (2.5, 2.5, 0.0, -225.0 * 20.0));
+        assert_eq!(stage.letterbox(), Letterbox::None);
+
+        stage.set_scale_mode(StageScaleMode::ExactFit);
+        assert_matrix_eq(stage.view_matrix(), (2.5, 1.0, 0.0, 0.0));
+
+        stage.set_scale_mode(StageScaleMode::NoScale);
+        assert_matrix_eq(stage.view_matrix(), (1.0, 1.0, 300.0 * 20.0, 0.0));
+        assert_eq!(
+            stage.stage_size(),
+            (Twips::from_pixels(1000.0), Twips::from_pixels(300.0))
+        );
+        assert_eq!(stage.letterbox(), Letterbox::None);
+    }
+
+    #[test]
+    fn alignment() {
+        let mut stage = Stage::new(400, 300);
+        stage.set_viewport_size(1000, 300);
+        stage.set_align(StageAlign::from_name("r"));
+        assert_matrix_eq(stage.view_matrix(), (1.0, 1.0, 600.0 * 20.0, 0.0));
+        assert_eq!(stage.letterbox(), Letterbox::Pillarbox(600.0, 0.0));
+
+        stage.set_scale_mode(StageScaleMode::NoScale);
+        stage.set_viewport_size(1000, 500);
+        stage.set_align(StageAlign::from_name("BL"));
+        assert_matrix_eq(stage.view_matrix(), (1.0, 1.0, 0.0, 200.0 * 20.0));
+
+        stage.set_letterbox_enabled(false);
+        stage.set_scale_mode(StageScaleMode::ShowAll);
+        assert_eq!(stage.letterbox(), Letterbox::None);
+    }
+
+    #[test]
+    fn align_names() {
+        assert_eq!(StageAlign::from_name("").name(), "");
+        assert_eq!(StageAlign::from_name("lt").name(), "TL");
+        assert_eq!(StageAlign::from_name("RB").name(), "BR");
+        assert_eq!(StageAlign::from_name("TB").name(), "T");
+        assert_eq!(StageAlign::from_name("x").name(), "");
+        assert_eq!(
+            StageScaleMode::from_name("NOSCALE"),
+            Some(StageScaleMode::NoScale)
+        );
+        assert_eq!(StageScaleMode::from_name("fill"), None);
+    }
+
+    #[test]
+    fn zoom() {
+        // A 400x300 movie in a 1000x300 viewport, zoomed around (500, 150).
+        let mut stage = Stage::new(400, 300);
+        stage.set_viewport_size(1000, 300);
+        stage.zoom_in();
+        assert_matrix_eq(stage.view_matrix(), (2.0, 2.0, 100.0 * 20.0, -150.0 * 20.0));
+        assert_eq!(stage.letterbox(), Letterbox::None);
+
+        stage.zoom_out();
+        stage.zoom_out();
+        assert_abs_diff_eq!(stage.zoom(), 1.0);
+        assert_matrix_eq(stage.view_matrix(), (1.0, 1.0, 300.0 * 20.0, 0.0));
+
+        for _ in 0..10 {
+            stage.zoom_in();
+        }
+        assert_abs_diff_eq!(stage.zoom(), MAX_ZOOM);
+        stage.reset_zoom();
+        assert_abs_diff_eq!(stage.zoom(), 1.0);
+    }
+
+    #[test]
+    fn quality_names() {
+        assert_eq!(StageQuality::from_name("best"), Some(StageQuality::Best));
+        assert_eq!(
+            StageQuality::from_name("Low").map(StageQuality::name),
+            Some("LOW")
+        );
+        assert_eq!(StageQuality::from_name("ultra"), None);
+    }
+
+    #[test]
+    fn resize() {
+        let mut stage = Stage::new(400, 300);
+        assert!(!stage.set_viewport_size(800, 600));
+        stage.set_scale_mode(StageScaleMode::NoScale);
+        assert!(stage.set_viewport_size(1000, 600));
+        assert!(!stage.set_viewport_size(1000, 600));
+    }
+}
@@ -5,11 +5,15 @@
 use approx::assert_abs_diff_eq;
 use log::{Metadata, Record};
 use ruffle_core::backend::{
-    audio::NullAudioBackend, input::NullInputBackend, navigator::NullNavigatorBackend,
+    audio::{mixer::AudioMixer, recording::FrameClockAudioBackend, AudioBackend, NullAudioBackend},
+    input::{InputBackend, MouseCursor, NullInputBackend},
+    navigator::NullNavigatorBackend,
     render::NullRenderer,
 };
-use ruffle_core::Player;
+use ruffle_core::events::{KeyCode, MouseButton};
+use ruffle_core::{Player, PlayerEvent};
 use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
 
 type Error = Box<dyn std::error::Error>;
 
@@ -137,6 +141,7 @@ swf_tests! {
     (xml_inspect_createmethods, "avm1/xml_inspect_createmethods", 1),
     (xml_inspect_parsexml, "avm1/xml_inspect_parsexml", 1),
     (funky_function_calls, "avm1/funky_function_calls", 1),
+    (scale9grid, "avm1/scale9grid", 1),
 }
 
 #[test]
@@ -188,6 +193,127 @@ fn test_stage_object_properties() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn button_mode() -> Result<(), Error> {
+    test_swf_with_input(
+        "tests/swfs/avm1/button_mode/test.swf",
+        6,
+        "tests/swfs/avm1/button_mode/output.txt",
+        |player, frame| match frame {
+            2 => click(player, 50.0, 10.0),
+            3 | 4 => click(player, 10.0, 10.0),
+            5 => {
+                // Press `a`, and drag onto `btn` before releasing.
+                player.handle_event(PlayerEvent::MouseMove { x: 10.0, y: 10.0 });
+                player.handle_event(PlayerEvent::MouseDown {
+                    x: 10.0,
+                    y: 10.0,
+                    button: MouseButton::Left,
+                });
+                player.handle_event(PlayerEvent::MouseMove { x: 25.0, y: 125.0 });
+                player.handle_event(PlayerEvent::MouseUp {
+                    x: 25.0,
+                    y: 125.0,
+                    button: MouseButton::Left,
+                });
+            }
+            6 => click(player, 25.0, 125.0),
+            _ => (),
+        },
+    )
+}
+
+#[test]
+fn set_mask() -> Result<(), Error> {
+    test_swf_with_input(
+        "tests/swfs/avm1/set_mask/test.swf",
+        7,
+        "tests/swfs/avm1/set_mask/output.txt",
+        |player, frame| match frame {
+            2 | 5 => click(player, 10.0, 10.0),
+            3 => click(player, 80.0, 80.0),
+            4 => click(player, 90.0, 90.0),
+            6 | 7 => click(player, 50.0, 60.0),
+            _ => (),
+        },
+    )
+}
+
+#[test]
+fn stage_scale_mode() -> Result<(), Error> {
+    test_swf_with_input(
+        "tests/swfs/avm1/stage_scale_mode/test.swf",
+        3,
+        "tests/swfs/avm1/stage_scale_mode/output.txt",
+        |player, frame| match frame {
+            2 => player.set_viewport_dimensions(300, 150),
+            3 => player.set_viewport_dimensions(400, 300),
+            _ => (),
+        },
+    )
+}
+
+#[test]
+fn key_listener() -> Result<(), Error> {
+    test_swf_with_input(
+        "tests/swfs/avm1/key_listener/test.swf",
+        4,
+        "tests/swfs/avm1/key_listener/output.txt",
+        |player, frame| {
+            let (key_code, codepoint) = match frame {
+                2 => (KeyCode::A, Some('a')),
+                3 => (KeyCode::CapsLock, None),
+                4 => (KeyCode::Space, Some(' ')),
+                _ => return,
+            };
+            press_key(player, key_code, codepoint);
+            release_key(player, key_code);
+        },
+    )
+}
+
+#[test]
+fn mouse_wheel() -> Result<(), Error> {
+    test_swf_with_input(
+        "tests/swfs/avm1/mouse_wheel/test.swf",
+        4,
+        "tests/swfs/avm1/mouse_wheel/output.txt",
+        |player, frame| {
+            let (x, y, delta) = match frame {
+                2 => (150.0, 150.0, 3.0),
+                3 => (25.0, 25.0, -3.0),
+                4 => (25.0, 25.0, 3.0),
+                _ => return,
+            };
+            player.handle_event(PlayerEvent::MouseMove { x, y });
+            player.handle_event(PlayerEvent::MouseWheel { delta });
+        },
+    )
+}
+
+#[test]
+fn tab_index() -> Result<(), Error> {
+    test_swf_with_input(
+        "tests/swfs/avm1/tab_index/test.swf",
+        8,
+        "tests/swfs/avm1/tab_index/output.txt",
+        |player, frame| match frame {
+            2 | 3 | 4 | 6 | 7 => {
+                press_key(player, KeyCode::Tab, Some('\t'));
+                release_key(player, KeyCode::Tab);
+            }
+            5 => {
+                press_key(player, KeyCode::Shift, None);
+                press_key(player, KeyCode::Tab, Some('\t'));
+                release_key(player, KeyCode::Tab);
+                release_key(player, KeyCode::Shift);
+            }
+            8 => click(player, 150.0, 150.0),
+            _ => (),
+        },
+    )
+}
+
 /// Wrapper around string slice that makes debug output `{:?}` to print string same way as `{}`.
 /// Used in different `assert*!` macros in combination with `pretty_assertions` crate to make
 /// test failures to show nice diffs.
@@ -219,9 +345,20 @@ macro_rules! assert_eq {
 /// Loads an SWF and runs it through the Ruffle core for a number of frames.
 /// Tests that the trace output matches the given expected output.
 fn test_swf(swf_path: &str, num_frames: u32, expected_output_path: &str) -> Result<(), Error> {
+    test_swf_with_input(swf_path, num_frames, expected_output_path, |_, _| ())
+}
+
+/// Like `test_swf`, but calls `before_frame` with the player and the number of the frame that
+/// is about to run, starting from 1, so that the test can send input to the movie.
+fn test_swf_with_input(
+    swf_path: &str,
+    num_frames: u32,
+    expected_output_path: &str,
+    before_frame: impl FnMut(&mut TestPlayer, u32),
+) -> Result<(), Error> {
     let expected_output = std::fs::read_to_string(expected_output_path)?.replace("\r\n", "\n");
 
-    let trace_log = run_swf(swf_path, num_frames)?;
+    let trace_log = run_swf_with_input(swf_path, num_frames, before_frame)?;
     assert_eq!(
         trace_log, expected_output,
         "ruffle output != flash player output"
@@ -233,6 +370,14 @@ fn test_swf(swf_path: &str, num_frames: u32, expected_output_path: &str) -> Resu
 /// Loads an SWF and runs it through the Ruffle core for a number of frames.
 /// Tests that the trace output matches the given expected output.
 fn run_swf(swf_path: &str, num_frames: u32) -> Result<String, Error> {
+    run_swf_with_input(swf_path, num_frames, |_, _| ())
+}
+
+fn run_swf_with_input(
+    swf_path: &str,
+    num_frames: u32,
+    mut before_frame: impl FnMut(&mut TestPlayer, u32),
+) -> Result<String, Error> {
     let _ = log::set_logger(&TRACE_LOGGER).map(|()| log::set_max_level(log::LevelFilter::Info));
 
     let swf_data = std::fs::read(swf_path)?;
@@ -240,17 +385,208 @@ fn run_swf(swf_path: &str, num_frames: u32) -> Result<String, Error> {
         NullRenderer,
         NullAudioBackend::new(),
         NullNavigatorBackend::new(),
-        NullInputBackend::new(),
+        TestInputBackend::default(),
         swf_data,
     )?;
 
-    for _ in 0..num_frames {
+    for frame in 1..=num_frames {
+        before_frame(&mut player, frame);
         player.run_frame();
     }
 
     Ok(trace_log())
 }
 
+type TestPlayer = Player<NullAudioBackend, NullRenderer, NullNavigatorBackend, TestInputBackend>;
+
+/// An input backend that keeps track of the keys pressed with `press_key`, as the input backend
+/// of a desktop player does with the key events of its window.
+#[derive(Default)]
+struct TestInputBackend {
+    keys_down: Vec<KeyCode>,
+    caps_lock: bool,
+    last_key_code: Option<KeyCode>,
+    last_key_char: Option<char>,
+}
+
+impl InputBackend for TestInputBackend {
+    fn is_key_down(&self, key: KeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    fn is_key_toggled(&self, key: KeyCode) -> bool {
+        key == KeyCode::CapsLock && self.caps_lock
+    }
+
+    fn get_last_key_code(&self) -> KeyCode {
+        self.last_key_code.unwrap_or(KeyCode::Unknown)
+    }
+
+    fn get_last_key_char(&self) -> Option<char> {
+        self.last_key_char
+    }
+
+    fn mouse_visible(&self) -> bool {
+        true
+    }
+
+    fn hide_mouse(&mut self) {}
+
+    fn show_mouse(&mut self) {}
+
+    fn set_mouse_cursor(&mut self, _cursor: MouseCursor) {}
+}
+
+/// Moves the mouse to a point on the stage and clicks the left mouse button there.
+fn click(player: &mut TestPlayer, x: f64, y: f64) {
+    player.handle_event(PlayerEvent::MouseMove { x, y });
+    player.handle_event(PlayerEvent::MouseDown {
+        x,
+        y,
+        button: MouseButton::Left,
+    });
+    player.handle_event(PlayerEvent::MouseUp {
+        x,
+        y,
+        button: MouseButton::Left,
+    });
+}
+
+/// Presses a key, which types `codepoint` if it's a character key.
+fn press_key(player: &mut TestPlayer, key_code: KeyCode, codepoint: Option<char>) {
+    let input = player.input_mut();
+    input.keys_down.push(key_code);
+    if key_code == KeyCode::CapsLock {
+        input.caps_lock = !input.caps_lock;
+    }
+    input.last_key_code = Some(key_code);
+    input.last_key_char = codepoint;
+    player.handle_event(PlayerEvent::KeyDown { key_code });
+    if let Some(codepoint) = codepoint {
+        player.handle_event(PlayerEvent::TextInput { codepoint });
+    }
+}
+
+fn release_key(player: &mut TestPlayer, key_code: KeyCode) {
+    let input = player.input_mut();
+    input.keys_down.retain(|&key| key != key_code);
+    input.last_key_code = Some(key_code);
+    player.handle_event(PlayerEvent::KeyUp { key_code });
+}
+
+/// Runs an SWF in a headless player for a number of frames, and returns the audio it outputs.
+/// The audio is mixed to 44.1 kHz stereo, with the samples of each frame interleaved.
+fn run_swf_audio(swf_data: Vec<u8>, num_frames: u32) -> Result<Vec<f32>, Error> {
+    // Record the audio as it is mixed after each frame.
+    let output = Arc::new(Mutex::new(vec![]));
+    let mut audio = FrameClockAudioBackend::new(2, 44100);
+    let tap_output = Arc::clone(&output);
+    audio
+        .mixer_mut()
+        .set_tap(Some(Box::new(move |samples: &[f32]| {
+            tap_output.lock().unwrap().extend_from_slice(samples)
+        })));
+
+    let mut player = Player::new(
+        NullRenderer,
+        audio,
+        NullNavigatorBackend::new(),
+        NullInputBackend::new(),
+        swf_data,
+    )?;
+    for _ in 0..num_frames {
+        player.run_frame();
+    }
+
+    let output = output.lock().unwrap().clone();
+    Ok(output)
+}
+
+#[test]
+fn event_sound_audio() -> Result<(), Error> {
+    // The sound is four 16-bit samples at 44.1 kHz: 0.5, -0.5, 0.25, 0.0.
+    let swf_data = std::fs::read("tests/swfs/audio/event_sound/test.swf")?;
+    let output = run_swf_audio(swf_data, 2)?;
+
+    // Two frames at 60 fps, with the sound copied to both channels and silence after it.
+    let mut expected = vec![0.0; 735 * 2 * 2];
+    expected[..8].copy_from_slice(&[0.5, 0.5, -0.5, -0.5, 0.25, 0.25, 0.0, 0.0]);
+    assert_abs_diff_eq!(output[..], expected[..]);
+
+    Ok(())
+}
+
+/// An audio backend whose clock is only advanced by the test, so that a stream sound can be
+/// moved ahead of or behind the timeline.
+struct ManualClockAudioBackend {
+    mixer: AudioMixer,
+}
+
+impl ManualClockAudioBackend {
+    /// The output sample rate, chosen so that each sample frame is a millisecond.
+    const SAMPLE_RATE: u32 = 1000;
+
+    fn new() -> Self {
+        Self {
+            mixer: AudioMixer::new(1, Self::SAMPLE_RATE),
+        }
+    }
+
+    /// Plays the given number of milliseconds of audio.
+    fn advance(&mut self, ms: usize) {
+        self.mixer.mix(&mut vec![0.0; ms]);
+    }
+}
+
+impl AudioBackend for ManualClockAudioBackend {
+    ruffle_core::impl_audio_mixer_backend!(mixer);
+}
+
+#[test]
+fn stream_sound_sync() -> Result<(), Error> {
+    // A 10 fps movie that traces each frame, with a stream sound that lasts a little longer than
+    // the timeline. Frame 8 skips ahead to frame 15.
+    let swf_data = std::fs::read("tests/swfs/avm1/stream_sound_sync/test.swf")?;
+
+    let _ = log::set_logger(&TRACE_LOGGER).map(|()| log::set_max_level(log::LevelFilter::Info));
+    take_trace_log();
+    let mut player = Player::new(
+        NullRenderer,
+        ManualClockAudioBackend::new(),
+        NullNavigatorBackend::new(),
+        NullInputBackend::new(),
+        swf_data,
+    )?;
+    player.set_is_playing(true);
+
+    // The first frame runs by the clock, and starts the stream sound.
+    player.tick(100.0);
+    assert_eq!(take_trace_log(), "1\n");
+
+    // The timeline catches up when the audio is ahead, without waiting for the clock.
+    player.audio_mut().advance(500);
+    player.tick(1.0);
+    assert_eq!(take_trace_log(), "2\n3\n4\n5\n6\n");
+
+    // The timeline holds while it's level with the audio, even though its frames are due.
+    player.tick(300.0);
+    assert_eq!(take_trace_log(), "");
+    player.audio_mut().advance(100);
+    player.tick(1.0);
+    assert_eq!(take_trace_log(), "7\n");
+
+    // A goto stops the stream sound, which restarts from the destination frame, so the
+    // timeline follows the new stream rather than waiting for the old one to catch up.
+    player.audio_mut().advance(100);
+    player.tick(1.0);
+    assert_eq!(take_trace_log(), "8\n15\n");
+    player.audio_mut().advance(100);
+    player.tick(1.0);
+    assert_eq!(take_trace_log(), "16\n");
+
+    Ok(())
+}
+
 thread_local! {
     static TRACE_LOG: RefCell<String> = RefCell::new(String::new());
 }
@@ -264,6 +600,11 @@ fn trace_log() -> String {
     TRACE_LOG.with(|log| log.borrow().clone())
 }
 
+/// Returns the trace output so far, and clears it.
+fn take_trace_log() -> String {
+    TRACE_LOG.with(|log| log.replace(String::new()))
+}
+
 impl log::Log for TraceLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
         metadata.target() == "avm_trace"
@@ -0,0 +1,47 @@
+// `a` is a clip with two 20x20 squares, at x = 0 and x = 80.
+// `btn` is a 50x50 button at (0, 100).
+a.onPress = function() {
+    trace("a.onPress");
+};
+a.onRelease = function() {
+    trace("a.onRelease");
+};
+a.onReleaseOutside = function() {
+    trace("a.onReleaseOutside");
+};
+btn.onPress = function() {
+    trace("btn.onPress");
+};
+btn.onRelease = function() {
+    trace("btn.onRelease");
+};
+
+trace("// a.enabled");
+trace(a.enabled);
+trace("// a.trackAsMenu");
+trace(a.trackAsMenu);
+trace("// a.useHandCursor");
+trace(a.useHandCursor);
+trace("// btn.enabled");
+trace(btn.enabled);
+trace("// btn.trackAsMenu");
+trace(btn.trackAsMenu);
+trace("// btn.useHandCursor");
+trace(btn.useHandCursor);
+trace("// Click between the squares of a");
+// --- next frame ---
+trace("// Click a square of a");
+// --- next frame ---
+a.enabled = false;
+trace("// a.enabled = false, then click a square of a");
+trace(a.enabled);
+// --- next frame ---
+a.enabled = true;
+btn.trackAsMenu = true;
+trace("// btn.trackAsMenu = true, then press a and release over btn");
+trace(btn.trackAsMenu);
+// --- next frame ---
+btn.enabled = false;
+trace("// btn.enabled = false, then click btn");
+trace(btn.enabled);
+// --- next frame ---
@@ -0,0 +1,31 @@
+var listener = {};
+listener.onKeyDown = function() {
+    var code = Key.getCode();
+    trace("onKeyDown: code " + code + ", ascii " + Key.getAscii() + ", isDown " + Key.isDown(code));
+};
+listener.onKeyUp = function() {
+    var code = Key.getCode();
+    trace("onKeyUp: code " + code + ", ascii " + Key.getAscii() + ", isDown " + Key.isDown(code));
+};
+trace("// Key.addListener(listener)");
+trace(Key.addListener(listener));
+
+trace("// Key.isToggled(Key.CAPSLOCK)");
+trace(Key.isToggled(Key.CAPSLOCK));
+trace("// Press and release A");
+// --- next frame ---
+trace("// Press and release Caps Lock");
+// --- next frame ---
+trace("// Key.isToggled(Key.CAPSLOCK)");
+trace(Key.isToggled(Key.CAPSLOCK));
+trace("// Key.removeListener(listener)");
+trace(Key.removeListener(listener));
+trace("// Press and release Space");
+// --- next frame ---
+trace("// Key.getCode()");
+trace(Key.getCode());
+trace("// Key.getAscii()");
+trace(Key.getAscii());
+trace("// Key.isDown(Key.SPACE)");
+trace(Key.isDown(Key.SPACE));
+// --- next frame ---
@@ -0,0 +1,15 @@
+// `btn` is a 50x50 button at (0, 0).
+var listener = {};
+listener.onMouseWheel = function(delta, target) {
+    trace("onMouseWheel: " + delta + ", " + (target == btn));
+};
+trace("// Mouse.addListener(listener)");
+trace(Mouse.addListener(listener));
+trace("// Scroll up outside btn");
+// --- next frame ---
+trace("// Scroll down over btn");
+// --- next frame ---
+trace("// Mouse.removeListener(listener)");
+trace(Mouse.removeListener(listener));
+trace("// Scroll up over btn");
+// --- next frame ---
@@ -0,0 +1,19 @@
+// The clip has a grid from DefineScalingGrid.
+var grid = clip.scale9Grid;
+trace("// clip.scale9Grid");
+trace(grid.x);
+trace(grid.y);
+trace(grid.width);
+trace(grid.height);
+
+clip.scale9Grid = {x: 10, y: 15, width: 30, height: 40};
+grid = clip.scale9Grid;
+trace("// clip.scale9Grid = {x: 10, y: 15, width: 30, height: 40}");
+trace(grid.x);
+trace(grid.y);
+trace(grid.width);
+trace(grid.height);
+
+clip.scale9Grid = null;
+trace("// clip.scale9Grid = null");
+trace(clip.scale9Grid);
@@ -0,0 +1,24 @@
+// `content` is a 100x100 clip at (0, 0).
+// `triangle` is a clip with a triangle over the top left half of `content`.
+// `square` is a 20x20 clip at (80, 80).
+content.onRelease = function() {
+    trace("content.onRelease");
+};
+
+content.setMask(triangle);
+trace("// content.setMask(triangle), then click inside the triangle");
+// --- next frame ---
+trace("// Click outside the triangle, inside its bounds");
+// --- next frame ---
+content.setMask(square);
+trace("// content.setMask(square), then click inside the square");
+// --- next frame ---
+trace("// Click inside the triangle, outside the square");
+// --- next frame ---
+content.setMask(null);
+trace("// content.setMask(null), then click outside both");
+// --- next frame ---
+content.setMask(triangle);
+square.setMask(triangle);
+trace("// square.setMask(triangle), then click content outside the triangle");
+// --- next frame ---
@@ -0,0 +1,43 @@
+// The movie is 200x100.
+trace("// Stage.scaleMode");
+trace(Stage.scaleMode);
+trace("// Stage.align");
+trace(Stage.align);
+trace("// Stage.width, Stage.height");
+trace(Stage.width);
+trace(Stage.height);
+
+Stage.align = "br";
+trace("// Stage.align = \"br\"");
+trace(Stage.align);
+Stage.align = "LTRB";
+trace("// Stage.align = \"LTRB\"");
+trace(Stage.align);
+Stage.align = "xyz";
+trace("// Stage.align = \"xyz\"");
+trace(Stage.align);
+
+Stage.scaleMode = "EXACTFIT";
+trace("// Stage.scaleMode = \"EXACTFIT\"");
+trace(Stage.scaleMode);
+Stage.scaleMode = "bogus";
+trace("// Stage.scaleMode = \"bogus\"");
+trace(Stage.scaleMode);
+
+var listener = {};
+listener.onResize = function() {
+    trace("onResize: " + Stage.width + "x" + Stage.height);
+};
+Stage.addListener(listener);
+trace("// Resize the viewport to 300x150");
+// --- next frame ---
+Stage.scaleMode = "noScale";
+trace("// Stage.scaleMode = \"noScale\", then resize the viewport to 400x300");
+trace(Stage.width);
+trace(Stage.height);
+// --- next frame ---
+Stage.scaleMode = "showAll";
+trace("// Stage.scaleMode = \"showAll\"");
+trace(Stage.width);
+trace(Stage.height);
+// --- next frame ---
@@ -0,0 +1,42 @@
+// A 10 fps movie with a stream sound that lasts a little longer than the timeline.
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// Skip ahead to frame 15.
+_root.gotoAndPlay(15);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
+// --- next frame ---
+trace(_root._currentframe);
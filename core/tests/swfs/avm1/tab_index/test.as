@@ -0,0 +1,38 @@
+// `a`, `b` and `c` are 50x50 buttons at (0, 0), (60, 0) and (0, 60).
+a.onSetFocus = function(old) {
+    trace("a.onSetFocus: " + old._name);
+};
+a.onKillFocus = function(focused) {
+    trace("a.onKillFocus: " + focused._name);
+};
+b.onSetFocus = function(old) {
+    trace("b.onSetFocus: " + old._name);
+};
+b.onKillFocus = function(focused) {
+    trace("b.onKillFocus: " + focused._name);
+};
+c.onSetFocus = function(old) {
+    trace("c.onSetFocus: " + old._name);
+};
+c.onKillFocus = function(focused) {
+    trace("c.onKillFocus: " + focused._name);
+};
+
+a.tabIndex = 2;
+b.tabIndex = 1;
+trace("// a.tabIndex = 2, b.tabIndex = 1, then press Tab");
+// --- next frame ---
+trace("// Press Tab");
+// --- next frame ---
+trace("// Press Tab");
+// --- next frame ---
+trace("// Press Shift+Tab");
+// --- next frame ---
+a.tabIndex = undefined;
+b.tabIndex = undefined;
+trace("// a.tabIndex = undefined, b.tabIndex = undefined, then press Tab");
+// --- next frame ---
+trace("// Press Tab");
+// --- next frame ---
+trace("// Click outside the buttons");
+// --- next frame ---
@@ -0,0 +1,271 @@
+//! A line-based console for the AVM1 debugger, read from stdin.
+
+use ruffle_core::backend::{
+    audio::AudioBackend, input::InputBackend, navigator::NavigatorBackend, render::RenderBackend,
+};
+use ruffle_core::debugger::{Breakpoint, DebugLocation, StepMode};
+use ruffle_core::Player;
+use std::io::BufRead;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+
+const HELP: &str = "Commands:
+  break <character id> <frame|*> <offset>  Add a breakpoint on an action in a clip
+  break swf <offset>                       Add a breakpoint on an action in the SWF
+  delete <n>                               Remove breakpoint number <n>
+  breakpoints                              List breakpoints
+  pause                                    Pause on the next action
+  continue (c)                             Resume execution
+  step (s)                                 Step into
+  next (n)                                 Step over
+  out (o)                                  Step out of the current function
+  backtrace (bt)                           Show the AVM stack
+  frame <n>                                Select stack frame <n> for inspection
+  inspect (i)                              Show this, scope chain, registers, stack and locals
+  print (p) <path>                         Inspect a value, e.g. `p this.clip._x`";
+
+pub struct DebugConsole {
+    commands: Receiver<String>,
+
+    /// The stack frame selected for inspection, counting from the innermost.
+    selected_frame: usize,
+
+    /// The location we last reported pausing at, so that each pause is only announced once.
+    reported_pause: Option<DebugLocation>,
+}
+
+impl DebugConsole {
+    /// Starts reading debugger commands from stdin on a background thread.
+    pub fn new() -> Self {
+        let (sender, commands) = channel();
+        std::thread::spawn(move || {
+            let stdin = std::io::stdin();
+            for line in stdin.lock().lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+        println!("AVM1 debugger enabled. Type `help` for a list of commands.");
+
+        Self {
+            commands,
+            selected_frame: 0,
+            reported_pause: None,
+        }
+    }
+
+    /// Announces any new pause, then runs any commands entered since the last poll.
+    pub fn poll<A, R, N, I>(&mut self, player: &mut Player<A, R, N, I>)
+    where
+        A: AudioBackend,
+        R: RenderBackend,
+        N: NavigatorBackend,
+        I: InputBackend,
+    {
+        self.report_pause(player);
+
+        loop {
+            match self.commands.try_recv() {
+                Ok(line) => {
+                    self.run_command(player, line.trim());
+                    self.report_pause(player);
+                }
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+    }
+
+    fn report_pause<A, R, N, I>(&mut self, player: &mut Player<A, R, N, I>)
+    where
+        A: AudioBackend,
+        R: RenderBackend,
+        N: NavigatorBackend,
+        I: InputBackend,
+    {
+        let paused_at = player.debugger_paused_at();
+        if paused_at != self.reported_pause {
+            if paused_at.is_some() {
+                self.selected_frame = 0;
+                if let Some(frame) = player.debugger_stack_trace().first() {
+                    println!(
+                        "Paused in {} (character {}, frame {}) at offset {} (SWF offset {})",
+                        frame.clip_path,
+                        frame.location.character_id,
+                        format_frame(frame.location.frame),
+                        frame.location.offset,
+                        frame.location.swf_offset
+                    );
+                    if let Some(action) = &frame.action {
+                        println!("  {}", action);
+                    }
+                }
+            }
+            self.reported_pause = paused_at;
+        }
+    }
+
+    fn run_command<A, R, N, I>(&mut self, player: &mut Player<A, R, N, I>, line: &str)
+    where
+        A: AudioBackend,
+        R: RenderBackend,
+        N: NavigatorBackend,
+        I: InputBackend,
+    {
+        let mut args = line.split_whitespace();
+        let command = match args.next() {
+            Some(command) => command,
+            None => return,
+        };
+        let args: Vec<&str> = args.collect();
+
+        match (command, &args[..]) {
+            ("help", _) | ("h", _) => println!("{}", HELP),
+            ("break", ["swf", offset]) | ("b", ["swf", offset]) => match parse_number(offset) {
+                Some(offset) => player.debugger_add_breakpoint(Breakpoint::SwfOffset(offset)),
+                None => println!("Invalid offset: {}", offset),
+            },
+            ("break", [character_id, frame, offset]) | ("b", [character_id, frame, offset]) => {
+                let character_id = parse_number(character_id);
+                let frame = if *frame == "*" {
+                    Some(None)
+                } else {
+                    parse_number(frame).map(|frame| Some(frame as u16))
+                };
+                let offset = parse_number(offset);
+                match (character_id, frame, offset) {
+                    (Some(character_id), Some(frame), Some(offset)) => player
+                        .debugger_add_breakpoint(Breakpoint::Action {
+                            character_id: character_id as u16,
+                            frame,
+                            offset,
+                        }),
+                    _ => println!("Usage: break <character id> <frame|*> <offset>"),
+                }
+            }
+            ("delete", [index]) | ("d", [index]) => {
+                let breakpoints = player.debugger_breakpoints();
+                match parse_number(index).and_then(|i| breakpoints.get(i)) {
+                    Some(breakpoint) => {
+                        player.debugger_remove_breakpoint(breakpoint);
+                    }
+                    None => println!("No breakpoint {}", index),
+                }
+            }
+            ("breakpoints", _) => {
+                for (i, breakpoint) in player.debugger_breakpoints().iter().enumerate() {
+                    println!("{}: {:?}", i, breakpoint);
+                }
+            }
+            ("pause", _) => player.debugger_pause(),
+            ("continue", _) | ("c", _) => self.resume(player, None),
+            ("step", _) | ("s", _) => self.resume(player, Some(StepMode::Into)),
+            ("next", _) | ("n", _) => self.resume(player, Some(StepMode::Over)),
+            ("out", _) | ("o", _) => self.resume(player, Some(StepMode::Out)),
+            ("backtrace", _) | ("bt", _) => {
+                for (i, frame) in player.debugger_stack_trace().iter().enumerate() {
+                    let marker = if i == self.selected_frame { "*" } else { " " };
+                    println!(
+                        "{}{}: {}{} (character {}, frame {}) offset {}: {}",
+                        marker,
+                        i,
+                        frame.clip_path,
+                        if frame.is_function { " [function]" } else { "" },
+                        frame.location.character_id,
+                        format_frame(frame.location.frame),
+                        frame.location.offset,
+                        frame.action.as_ref().map(String::as_str).unwrap_or("<end>")
+                    );
+                }
+            }
+            ("frame", [index]) | ("f", [index]) => match parse_number(index) {
+                Some(index) if index < player.debugger_stack_trace().len() => {
+                    self.selected_frame = index
+                }
+                _ => println!("No stack frame {}", index),
+            },
+            ("inspect", _) | ("i", _) => match player.debugger_inspect_frame(self.selected_frame) {
+                Some(inspection) => {
+                    println!("this = {}", inspection.this);
+                    println!("Scope chain:");
+                    for scope in &inspection.scope_chain {
+                        println!(
+                            "  {} {}: {}",
+                            scope.class,
+                            scope.object,
+                            scope.variables.join(", ")
+                        );
+                    }
+                    println!("Registers:");
+                    for (i, register) in inspection.registers.iter().enumerate() {
+                        println!("  r{} = {}", i, register);
+                    }
+                    println!("Stack:");
+                    for value in inspection.stack.iter().rev() {
+                        println!("  {}", value);
+                    }
+                    println!("Locals:");
+                    for (name, value) in &inspection.locals {
+                        println!("  {} = {}", name, value);
+                    }
+                }
+                None => println!("No stack frame {}", self.selected_frame),
+            },
+            ("print", [path]) | ("p", [path]) => {
+                match player.debugger_inspect_value(self.selected_frame, path) {
+                    Ok(inspection) => {
+                        println!("{} = {}", path, inspection.value);
+                        for (depth, properties) in inspection.prototype_chain.iter().enumerate() {
+                            if depth == 0 {
+                                println!("Own properties:");
+                            } else {
+                                println!("Prototype {}:", depth);
+                            }
+                            for (name, value) in properties {
+                                println!("  {} = {}", name, value);
+                            }
+                        }
+                    }
+                    Err(e) => println!("Unable to inspect {}: {}", path, e),
+                }
+            }
+            _ => println!(
+                "Unknown command: {}. Type `help` for a list of commands.",
+                line
+            ),
+        }
+    }
+
+    fn resume<A, R, N, I>(&mut self, player: &mut Player<A, R, N, I>, step: Option<StepMode>)
+    where
+        A: AudioBackend,
+        R: RenderBackend,
+        N: NavigatorBackend,
+        I: InputBackend,
+    {
+        if player.debugger_is_paused() {
+            player.debugger_resume(step);
+        } else {
+            println!("Not paused");
+        }
+    }
+}
+
+fn format_frame(frame: Option<u16>) -> String {
+    frame
+        .map(|frame| frame.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal number.
+fn parse_number(s: &str) -> Option<usize> {
+    if s.starts_with("0x") {
+        usize::from_str_radix(&s[2..], 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
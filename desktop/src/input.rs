@@ -1,22 +1,37 @@
 use glium::Display;
-use ruffle_core::backend::input::InputBackend;
-use ruffle_core::events::{KeyCode, PlayerEvent};
+use ruffle_core::backend::input::{InputBackend, MouseCursor};
+use ruffle_core::events::{key_code_to_char, KeyCode, MouseButton, PlayerEvent};
 use std::collections::HashSet;
-use winit::event::{ElementState, VirtualKeyCode, WindowEvent};
+use winit::event::{
+    ElementState, MouseButton as WinitMouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
+use winit::window::CursorIcon;
+
+/// The number of lines that one notch of the mouse wheel scrolls, as reported to Flash content.
+const LINES_PER_NOTCH: f64 = 3.0;
+
+/// The number of pixels of a precise scroll, e.g. from a touchpad, that make up one line.
+const PIXELS_PER_LINE: f64 = 16.0;
 
 pub struct WinitInputBackend {
     keys_down: HashSet<VirtualKeyCode>,
+    mouse_buttons_down: HashSet<MouseButton>,
     display: Display,
+    toggled_keys: HashSet<KeyCode>,
     cursor_visible: bool,
     last_key: KeyCode,
+    last_key_char: Option<char>,
 }
 
 impl WinitInputBackend {
     pub fn new(display: Display) -> Self {
         Self {
             keys_down: HashSet::new(),
+            mouse_buttons_down: HashSet::new(),
+            toggled_keys: HashSet::new(),
             cursor_visible: true,
             last_key: KeyCode::Unknown,
+            last_key_char: None,
             display,
         }
     }
@@ -27,35 +42,78 @@ impl WinitInputBackend {
             WindowEvent::KeyboardInput { input, .. } => match input.state {
                 ElementState::Pressed => {
                     if let Some(key) = input.virtual_keycode {
-                        self.keys_down.insert(key);
+                        // Held keys repeat their key down events, but only toggle once.
+                        let is_repeat = !self.keys_down.insert(key);
                         if let Some(key_code) = winit_to_ruffle_key_code(key) {
-                            self.last_key = key_code;
+                            if !is_repeat {
+                                self.toggle_key(key_code);
+                            }
+                            self.set_last_key(key_code);
                             return Some(PlayerEvent::KeyDown { key_code });
                         } else {
                             self.last_key = KeyCode::Unknown;
+                            self.last_key_char = None;
                         }
                     }
                 }
                 ElementState::Released => {
                     if let Some(key) = input.virtual_keycode {
-                        self.keys_down.remove(&key);
+                        // Ignore releases of keys that were pressed before the window had focus.
+                        if self.keys_down.remove(&key) {
+                            if let Some(key_code) = winit_to_ruffle_key_code(key) {
+                                self.set_last_key(key_code);
+                                return Some(PlayerEvent::KeyUp { key_code });
+                            }
+                        }
                     }
                 }
             },
             WindowEvent::ReceivedCharacter(codepoint) => {
+                // The typed character arrives after its key event, and is more accurate than
+                // the guess made from the key code.
+                self.last_key_char = Some(codepoint);
                 return Some(PlayerEvent::TextInput { codepoint });
             }
             _ => (),
         }
         None
     }
+
+    /// Records whether a mouse button is held, for `Key.isDown`.
+    pub fn set_mouse_button_down(&mut self, button: MouseButton, down: bool) {
+        if down {
+            self.mouse_buttons_down.insert(button);
+        } else {
+            self.mouse_buttons_down.remove(&button);
+        }
+    }
+
+    /// Records the last key that was pressed or released, for `Key.getCode` and `Key.getAscii`.
+    fn set_last_key(&mut self, key_code: KeyCode) {
+        self.last_key = key_code;
+        self.last_key_char = key_code_to_char(key_code, self.is_key_down(KeyCode::Shift));
+    }
+
+    /// Flips the state of a lock key when it is pressed. The initial state of the lock keys
+    /// is unknown, so they are assumed to be off.
+    fn toggle_key(&mut self, key_code: KeyCode) {
+        if let KeyCode::CapsLock | KeyCode::NumLock | KeyCode::ScrollLock = key_code {
+            if !self.toggled_keys.remove(&key_code) {
+                self.toggled_keys.insert(key_code);
+            }
+        }
+    }
 }
 
 impl InputBackend for WinitInputBackend {
     fn is_key_down(&self, key: KeyCode) -> bool {
         match key {
             KeyCode::Unknown => false,
+            KeyCode::MouseLeft => self.mouse_buttons_down.contains(&MouseButton::Left),
+            KeyCode::MouseRight => self.mouse_buttons_down.contains(&MouseButton::Right),
+            KeyCode::MouseMiddle => self.mouse_buttons_down.contains(&MouseButton::Middle),
             KeyCode::Backspace => self.keys_down.contains(&VirtualKeyCode::Back),
+            KeyCode::Tab => self.keys_down.contains(&VirtualKeyCode::Tab),
             KeyCode::Return => self.keys_down.contains(&VirtualKeyCode::Return),
             KeyCode::Shift => {
                 self.keys_down.contains(&VirtualKeyCode::LShift)
@@ -145,6 +203,7 @@ impl InputBackend for WinitInputBackend {
             KeyCode::Insert => self.keys_down.contains(&VirtualKeyCode::Insert),
             KeyCode::Delete => self.keys_down.contains(&VirtualKeyCode::Delete),
             KeyCode::Pause => self.keys_down.contains(&VirtualKeyCode::Pause),
+            KeyCode::NumLock => self.keys_down.contains(&VirtualKeyCode::Numlock),
             KeyCode::ScrollLock => self.keys_down.contains(&VirtualKeyCode::Scroll),
             KeyCode::F1 => self.keys_down.contains(&VirtualKeyCode::F1),
             KeyCode::F2 => self.keys_down.contains(&VirtualKeyCode::F2),
@@ -161,10 +220,18 @@ impl InputBackend for WinitInputBackend {
         }
     }
 
+    fn is_key_toggled(&self, key: KeyCode) -> bool {
+        self.toggled_keys.contains(&key)
+    }
+
     fn get_last_key_code(&self) -> KeyCode {
         self.last_key
     }
 
+    fn get_last_key_char(&self) -> Option<char> {
+        self.last_key_char
+    }
+
     fn mouse_visible(&self) -> bool {
         self.cursor_visible
     }
@@ -178,6 +245,15 @@ impl InputBackend for WinitInputBackend {
         self.display.gl_window().window().set_cursor_visible(true);
         self.cursor_visible = true;
     }
+
+    fn set_mouse_cursor(&mut self, cursor: MouseCursor) {
+        let icon = match cursor {
+            MouseCursor::Arrow => CursorIcon::Default,
+            MouseCursor::Hand => CursorIcon::Hand,
+            MouseCursor::IBeam => CursorIcon::Text,
+        };
+        self.display.gl_window().window().set_cursor_icon(icon);
+    }
 }
 
 /// Converts a winit `VirtualKeyCode` into a Ruffle `KeyCode`.
@@ -185,6 +261,7 @@ impl InputBackend for WinitInputBackend {
 fn winit_to_ruffle_key_code(key_code: VirtualKeyCode) -> Option<KeyCode> {
     let out = match key_code {
         VirtualKeyCode::Back => KeyCode::Backspace,
+        VirtualKeyCode::Tab => KeyCode::Tab,
         VirtualKeyCode::Return => KeyCode::Return,
         VirtualKeyCode::LShift | VirtualKeyCode::RShift => KeyCode::Shift,
         VirtualKeyCode::LControl | VirtualKeyCode::RControl => KeyCode::Control,
@@ -265,6 +342,7 @@ fn winit_to_ruffle_key_code(key_code: VirtualKeyCode) -> Option<KeyCode> {
         VirtualKeyCode::Insert => KeyCode::Insert,
         VirtualKeyCode::Delete => KeyCode::Delete,
         VirtualKeyCode::Pause => KeyCode::Pause,
+        VirtualKeyCode::Numlock => KeyCode::NumLock,
         VirtualKeyCode::Scroll => KeyCode::ScrollLock,
         VirtualKeyCode::F1 => KeyCode::F1,
         VirtualKeyCode::F2 => KeyCode::F2,
@@ -282,3 +360,23 @@ fn winit_to_ruffle_key_code(key_code: VirtualKeyCode) -> Option<KeyCode> {
     };
     Some(out)
 }
+
+/// Converts a winit `MouseButton` into a Ruffle `MouseButton`.
+/// Returns `None` for extra buttons that Flash doesn't support.
+pub fn winit_to_ruffle_mouse_button(button: WinitMouseButton) -> Option<MouseButton> {
+    match button {
+        WinitMouseButton::Left => Some(MouseButton::Left),
+        WinitMouseButton::Right => Some(MouseButton::Right),
+        WinitMouseButton::Middle => Some(MouseButton::Middle),
+        WinitMouseButton::Other(_) => None,
+    }
+}
+
+/// Converts a winit `MouseScrollDelta` into the number of lines scrolled, as reported by
+/// `Mouse.onMouseWheel`.
+pub fn winit_to_ruffle_wheel_delta(delta: MouseScrollDelta) -> f64 {
+    match delta {
+        MouseScrollDelta::LineDelta(_, y) => f64::from(y) * LINES_PER_NOTCH,
+        MouseScrollDelta::PixelDelta(position) => position.y / PIXELS_PER_LINE,
+    }
+}
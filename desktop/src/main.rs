@@ -1,6 +1,7 @@
 #![allow(clippy::unneeded_field_pattern)]
 
 mod audio;
+mod debugger;
 mod input;
 mod navigator;
 mod render;
@@ -8,17 +9,21 @@ mod render;
 use crate::render::GliumRenderBackend;
 use glutin::{
     dpi::{LogicalSize, PhysicalPosition},
-    event::{ElementState, MouseButton, WindowEvent},
+    event::{ElementState, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::WindowBuilder,
     ContextBuilder,
 };
 use ruffle_core::{
+    backend::audio::mixer::AudioMixer,
+    backend::audio::recording::{FrameClockAudioBackend, WavWriter},
     backend::audio::{AudioBackend, NullAudioBackend},
     backend::render::RenderBackend,
     Player,
 };
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 use structopt::StructOpt;
 
@@ -27,6 +32,40 @@ use structopt::StructOpt;
 struct Opt {
     #[structopt(name = "FILE", parse(from_os_str))]
     input_path: PathBuf,
+
+    /// Enable the AVM1 debugger console on stdin
+    #[structopt(long = "debugger")]
+    debugger: bool,
+
+    /// Run the movie at its frame rate instead of keeping it in sync with its stream sound
+    #[structopt(long = "no-audio-sync")]
+    no_audio_sync: bool,
+
+    /// Record the audio output to a WAV file
+    #[structopt(long = "record-audio", parse(from_os_str))]
+    record_audio: Option<PathBuf>,
+
+    /// Mix the audio as frames run instead of playing it, so that recordings only depend on
+    /// the frames that run. Implies --no-audio-sync
+    #[structopt(long = "frame-clock-audio")]
+    frame_clock_audio: bool,
+
+    /// Load a TrueType or OpenType font file to render device fonts with
+    #[structopt(long = "device-font", parse(from_os_str), number_of_values = 1)]
+    device_fonts: Vec<PathBuf>,
+
+    /// Map a font name to a loaded device font family, e.g. "_serif=DejaVu Serif"
+    #[structopt(long = "map-device-font", number_of_values = 1)]
+    device_font_mappings: Vec<String>,
+
+    /// Render text with advanced anti-aliasing as shapes instead of from a cache of
+    /// rasterized glyphs
+    #[structopt(long = "no-glyph-atlas")]
+    no_glyph_atlas: bool,
+
+    /// Don't draw black bars around the movie when it doesn't fill the window
+    #[structopt(long = "no-letterbox")]
+    no_letterbox: bool,
 }
 
 fn main() {
@@ -34,7 +73,7 @@ fn main() {
 
     let opt = Opt::from_args();
 
-    let ret = run_player(opt.input_path);
+    let ret = run_player(opt);
 
     if let Err(e) = ret {
         eprintln!("Fatal error:\n{}", e);
@@ -42,7 +81,9 @@ fn main() {
     }
 }
 
-fn run_player(input_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+fn run_player(opt: Opt) -> Result<(), Box<dyn std::error::Error>> {
+    let input_path = opt.input_path;
+    let record_audio_path = opt.record_audio;
     let swf_data = std::fs::read(&input_path)?;
 
     let event_loop = EventLoop::new();
@@ -56,19 +97,56 @@ fn run_player(input_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
         .with_srgb(true)
         .with_stencil_buffer(8)
         .build_windowed(window_builder, &event_loop)?;
-    let audio: Box<dyn AudioBackend> = match audio::CpalAudioBackend::new() {
-        Ok(audio) => Box::new(audio),
-        Err(e) => {
-            log::error!("Unable to create audio device: {}", e);
-            Box::new(NullAudioBackend::new())
+    let audio: Box<dyn AudioBackend> = if opt.frame_clock_audio {
+        let mut audio = FrameClockAudioBackend::new(2, 44100);
+        if let Some(path) = &record_audio_path {
+            record_audio(audio.mixer_mut(), path)?;
+        }
+        Box::new(audio)
+    } else {
+        match audio::CpalAudioBackend::new() {
+            Ok(mut audio) => {
+                if let Some(path) = &record_audio_path {
+                    record_audio(audio.mixer_mut(), path)?;
+                }
+                Box::new(audio)
+            }
+            Err(e) => {
+                log::error!("Unable to create audio device: {}", e);
+                if record_audio_path.is_some() {
+                    log::error!(
+                        "Unable to record audio without an audio device; use --frame-clock-audio"
+                    );
+                }
+                Box::new(NullAudioBackend::new())
+            }
         }
     };
-    let renderer = GliumRenderBackend::new(windowed_context)?;
-    let navigator = navigator::ExternalNavigatorBackend::new(); //TODO: actually implement this backend type
+    let mut renderer = GliumRenderBackend::new(windowed_context)?;
+    renderer.set_use_glyph_atlas(!opt.no_glyph_atlas);
+    let navigator = navigator::ExternalNavigatorBackend::new(
+        input_path.parent().map(PathBuf::from).unwrap_or_default(),
+    );
     let display = renderer.display().clone();
     let input = input::WinitInputBackend::new(display.clone());
     let mut player = Player::new(renderer, audio, navigator, input, swf_data)?;
     player.set_is_playing(true); // Desktop player will auto-play.
+    player.set_sync_to_stream_sound(!opt.no_audio_sync && !opt.frame_clock_audio);
+
+    for path in &opt.device_fonts {
+        let families = player
+            .add_device_font(std::fs::read(path)?)
+            .map_err(|e| format!("Unable to load font {}: {}", path.display(), e))?;
+        log::info!("Loaded device fonts {:?} from {}", families, path.display());
+    }
+    for mapping in &opt.device_font_mappings {
+        let mut parts = mapping.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(family)) => player.map_device_font(name, family)?,
+            _ => return Err(format!("Invalid device font mapping: {}", mapping).into()),
+        }
+    }
+    player.set_letterbox_enabled(!opt.no_letterbox);
 
     let logical_size: LogicalSize = (player.movie_width(), player.movie_height()).into();
     let hidpi_factor = display.gl_window().window().hidpi_factor();
@@ -79,6 +157,12 @@ fn run_player(input_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
         .gl_window()
         .resize(logical_size.to_physical(hidpi_factor));
 
+    let mut debug_console = if opt.debugger {
+        Some(debugger::DebugConsole::new())
+    } else {
+        None
+    };
+
     let mut mouse_pos = PhysicalPosition::new(0.0, 0.0);
     let mut time = Instant::now();
     loop {
@@ -108,23 +192,36 @@ fn run_player(input_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
                         };
                         player.handle_event(event);
                     }
-                    WindowEvent::MouseInput {
-                        button: MouseButton::Left,
-                        state: pressed,
-                        ..
-                    } => {
-                        let event = if pressed == ElementState::Pressed {
-                            ruffle_core::PlayerEvent::MouseDown {
-                                x: mouse_pos.x,
-                                y: mouse_pos.y,
-                            }
-                        } else {
-                            ruffle_core::PlayerEvent::MouseUp {
-                                x: mouse_pos.x,
-                                y: mouse_pos.y,
+                    WindowEvent::MouseInput { button, state, .. } => {
+                        if let Some(button) = input::winit_to_ruffle_mouse_button(button) {
+                            let pressed = state == ElementState::Pressed;
+                            player.input_mut().set_mouse_button_down(button, pressed);
+                            // A right click closes the context menu if it's open, or opens it.
+                            let show_context_menu = pressed
+                                && button == ruffle_core::events::MouseButton::Right
+                                && !player.is_context_menu_open();
+                            let event = if pressed {
+                                ruffle_core::PlayerEvent::MouseDown {
+                                    x: mouse_pos.x,
+                                    y: mouse_pos.y,
+                                    button,
+                                }
+                            } else {
+                                ruffle_core::PlayerEvent::MouseUp {
+                                    x: mouse_pos.x,
+                                    y: mouse_pos.y,
+                                    button,
+                                }
+                            };
+                            player.handle_event(event);
+                            if show_context_menu {
+                                player.show_context_menu(mouse_pos.x, mouse_pos.y);
                             }
-                        };
-                        player.handle_event(event);
+                        }
+                    }
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        let delta = input::winit_to_ruffle_wheel_delta(delta);
+                        player.handle_event(ruffle_core::PlayerEvent::MouseWheel { delta });
                     }
                     WindowEvent::CursorLeft { .. } => {
                         player.handle_event(ruffle_core::PlayerEvent::MouseLeft)
@@ -149,8 +246,24 @@ fn run_player(input_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
                     player.tick(dt as f64 / 1000.0);
                 }
 
+                if let Some(debug_console) = &mut debug_console {
+                    debug_console.poll(&mut player);
+                }
+
                 *control_flow = ControlFlow::WaitUntil(new_time + player.time_til_next_frame());
             }
         });
     }
 }
+
+/// Records the output of `mixer` to a WAV file.
+fn record_audio(mixer: &mut AudioMixer, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let file = BufWriter::new(File::create(path)?);
+    let writer = WavWriter::new(
+        file,
+        mixer.num_output_channels(),
+        mixer.output_sample_rate(),
+    )?;
+    mixer.set_tap(Some(writer.into_tap()));
+    Ok(())
+}
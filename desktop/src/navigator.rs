@@ -1,18 +1,32 @@
 //! Navigator backend for web
 
+use generational_arena::Arena;
 use log;
-use ruffle_core::backend::navigator::{NavigationMethod, NavigatorBackend};
+use ruffle_core::backend::navigator::{
+    NavigationMethod, NavigatorBackend, RequestHandle, RequestStatus,
+};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
 use url::Url;
 use webbrowser;
 
 /// Implementation of `NavigatorBackend` for non-web environments that can call
 /// out to a web browser.
-pub struct ExternalNavigatorBackend {}
+pub struct ExternalNavigatorBackend {
+    /// The directory containing the movie. Relative URLs are loaded from this directory.
+    base_path: PathBuf,
+
+    /// The files being read by `fetch` on background threads.
+    requests: Arena<Receiver<std::io::Result<Vec<u8>>>>,
+}
 
 impl ExternalNavigatorBackend {
-    pub fn new() -> Self {
-        ExternalNavigatorBackend {}
+    pub fn new(base_path: PathBuf) -> Self {
+        ExternalNavigatorBackend {
+            base_path,
+            requests: Arena::new(),
+        }
     }
 }
 
@@ -60,4 +74,44 @@ impl NavigatorBackend for ExternalNavigatorBackend {
             Err(e) => log::error!("Could not open URL {}: {}", modified_url, e),
         };
     }
+
+    fn fetch(&mut self, url: &str) -> Option<RequestHandle> {
+        // Only local files are supported.
+        let path = match Url::parse(url) {
+            Ok(parsed_url) if parsed_url.scheme() == "file" => parsed_url.to_file_path().ok()?,
+            Ok(_) => {
+                log::error!("Unable to fetch {}: Only local files are supported", url);
+                return None;
+            }
+            Err(_) => self.base_path.join(url),
+        };
+
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(std::fs::read(path));
+        });
+        Some(self.requests.insert(receiver))
+    }
+
+    fn poll_request(&mut self, request: RequestHandle, data: &mut Vec<u8>) -> RequestStatus {
+        let result = match self.requests.get(request) {
+            Some(receiver) => receiver.try_recv(),
+            None => return RequestStatus::Failed,
+        };
+
+        let status = match result {
+            Ok(Ok(file_data)) => {
+                data.extend(file_data);
+                RequestStatus::Complete
+            }
+            Ok(Err(e)) => {
+                log::error!("Unable to fetch file: {}", e);
+                RequestStatus::Failed
+            }
+            Err(TryRecvError::Empty) => return RequestStatus::Loading { bytes_total: None },
+            Err(TryRecvError::Disconnected) => RequestStatus::Failed,
+        };
+        self.requests.remove(request);
+        status
+    }
 }
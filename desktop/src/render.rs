@@ -7,9 +7,11 @@ use lyon::tessellation::{
     geometry_builder::{BuffersBuilder, FillVertexConstructor, VertexBuffers},
     FillAttributes, FillTessellator, StrokeAttributes, StrokeTessellator, StrokeVertexConstructor,
 };
+use ruffle_core::backend::render::glyph_atlas::{AtlasGlyph, GlyphAtlas};
 use ruffle_core::backend::render::swf::{self, FillStyle};
 use ruffle_core::backend::render::{
-    BitmapHandle, BitmapInfo, Color, Letterbox, RenderBackend, ShapeHandle, Transform,
+    BitmapHandle, BitmapInfo, Color, Letterbox, RenderBackend, ShapeHandle, TextRenderSettings,
+    Transform,
 };
 use ruffle_core::shape_utils::{DrawCommand, DrawPath};
 use std::convert::TryInto;
@@ -35,6 +37,9 @@ pub struct GliumRenderBackend {
     viewport_width: f32,
     viewport_height: f32,
     view_matrix: [[f32; 4]; 4],
+    glyph_atlas: GlyphAtlas,
+    glyph_textures: Vec<glium::Texture2d>,
+    use_glyph_atlas: bool,
 }
 
 impl GliumRenderBackend {
@@ -107,6 +112,9 @@ impl GliumRenderBackend {
             test_stencil_mask: 0,
             next_stencil_mask: 1,
             mask_stack: vec![],
+            glyph_atlas: GlyphAtlas::new(),
+            glyph_textures: vec![],
+            use_glyph_atlas: true,
         };
         renderer.build_matrices();
         Ok(renderer)
@@ -163,6 +171,105 @@ impl GliumRenderBackend {
         &self.display
     }
 
+    /// Sets whether text with advanced anti-aliasing is drawn from a cache of rasterized
+    /// glyphs. If disabled, the glyphs are rendered as shapes.
+    pub fn set_use_glyph_atlas(&mut self, use_glyph_atlas: bool) {
+        self.use_glyph_atlas = use_glyph_atlas;
+    }
+
+    /// Uploads the glyph atlas pages that have changed to their textures.
+    fn update_glyph_textures(&mut self) {
+        let display = &self.display;
+        let textures = &mut self.glyph_textures;
+        textures.truncate(self.glyph_atlas.num_pages());
+        self.glyph_atlas.update_pages(|i, page| {
+            let size = GlyphAtlas::PAGE_SIZE;
+            let image = glium::texture::RawImage2d::from_raw_rgba(page.to_rgba(), (size, size));
+            if let Some(texture) = textures.get(i) {
+                texture.write(
+                    glium::Rect {
+                        left: 0,
+                        bottom: 0,
+                        width: size,
+                        height: size,
+                    },
+                    image,
+                );
+            } else {
+                textures.push(glium::texture::Texture2d::new(display, image).unwrap());
+            }
+        });
+    }
+
+    /// Draws a glyph from the glyph atlas as a pixel-aligned quad.
+    fn render_atlas_glyph(&mut self, glyph: AtlasGlyph, transform: &Transform) {
+        self.update_glyph_textures();
+
+        let target = self.target.as_mut().unwrap();
+        let draw = &self.meshes[self.quad_shape.0].draws[0];
+
+        let world_matrix = [
+            [glyph.width as f32, 0.0, 0.0, 0.0],
+            [0.0, glyph.height as f32, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [glyph.left as f32, glyph.top as f32, 0.0, 1.0],
+        ];
+
+        // Map the quad to the area of the glyph in the atlas page.
+        let page_size = GlyphAtlas::PAGE_SIZE as f32;
+        let matrix = [
+            [glyph.width as f32 / page_size, 0.0, 0.0],
+            [0.0, glyph.height as f32 / page_size, 0.0],
+            [glyph.x as f32 / page_size, glyph.y as f32 / page_size, 1.0],
+        ];
+
+        use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction};
+        let texture = &self.glyph_textures[glyph.page]
+            .sampled()
+            .magnify_filter(MagnifySamplerFilter::Nearest)
+            .minify_filter(MinifySamplerFilter::Nearest)
+            .wrap_function(SamplerWrapFunction::Clamp);
+
+        let uniforms = BitmapUniformsFull {
+            view_matrix: self.view_matrix,
+            world_matrix,
+            mult_color: [
+                transform.color_transform.r_mult,
+                transform.color_transform.g_mult,
+                transform.color_transform.b_mult,
+                transform.color_transform.a_mult,
+            ],
+            add_color: [
+                transform.color_transform.r_add,
+                transform.color_transform.g_add,
+                transform.color_transform.b_add,
+                transform.color_transform.a_add,
+            ],
+            matrix,
+            texture,
+        };
+
+        let mut draw_parameters = DrawParameters::default();
+        mask_draw_parameters(
+            &mut draw_parameters,
+            self.num_masks,
+            self.num_masks_active,
+            self.write_stencil_mask,
+            self.test_stencil_mask,
+        );
+        draw_parameters.blend = bitmap_blend();
+
+        target
+            .draw(
+                &draw.vertex_buffer,
+                &draw.index_buffer,
+                &self.bitmap_shader_program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+    }
+
     fn register_shape_internal(&mut self, shape: &swf::Shape) -> ShapeHandle {
         let handle = ShapeHandle(self.meshes.len());
         let paths = ruffle_core::shape_utils::swf_shape_to_paths(shape);
@@ -535,7 +642,9 @@ impl RenderBackend for GliumRenderBackend {
             },
             shape: glyph.shape_records.clone(),
         };
-        self.register_shape_internal(&shape)
+        let handle = self.register_shape_internal(&shape);
+        self.glyph_atlas.register_glyph(handle, glyph);
+        handle
     }
 
     fn register_bitmap_jpeg(
@@ -836,6 +945,24 @@ impl RenderBackend for GliumRenderBackend {
         }
     }
 
+    fn render_glyph(
+        &mut self,
+        shape: ShapeHandle,
+        transform: &Transform,
+        settings: &TextRenderSettings,
+    ) {
+        let glyph = if self.use_glyph_atlas {
+            self.glyph_atlas.get(shape, &transform.matrix, settings)
+        } else {
+            None
+        };
+        if let Some(glyph) = glyph {
+            self.render_atlas_glyph(glyph, transform);
+        } else {
+            self.render_shape(shape, transform);
+        }
+    }
+
     fn draw_pause_overlay(&mut self) {}
 
     fn draw_letterbox(&mut self, letterbox: Letterbox) {
@@ -843,13 +970,13 @@ impl RenderBackend for GliumRenderBackend {
         let black = Some((0.0, 0.0, 0.0, 1.0));
         match letterbox {
             Letterbox::None => (),
-            Letterbox::Letterbox(margin_height) => {
+            Letterbox::Letterbox(margin_top, margin_bottom) => {
                 target.clear(
                     Some(&glium::Rect {
                         left: 0,
                         bottom: 0,
                         width: self.viewport_width as u32,
-                        height: margin_height as u32,
+                        height: margin_bottom as u32,
                     }),
                     black,
                     true,
@@ -859,9 +986,9 @@ impl RenderBackend for GliumRenderBackend {
                 target.clear(
                     Some(&glium::Rect {
                         left: 0,
-                        bottom: (self.viewport_height - margin_height) as u32,
+                        bottom: (self.viewport_height - margin_top) as u32,
                         width: self.viewport_width as u32,
-                        height: margin_height as u32,
+                        height: margin_top as u32,
                     }),
                     black,
                     true,
@@ -869,12 +996,12 @@ impl RenderBackend for GliumRenderBackend {
                     None,
                 );
             }
-            Letterbox::Pillarbox(margin_width) => {
+            Letterbox::Pillarbox(margin_left, margin_right) => {
                 target.clear(
                     Some(&glium::Rect {
                         left: 0,
                         bottom: 0,
-                        width: margin_width as u32,
+                        width: margin_left as u32,
                         height: self.viewport_height as u32,
                     }),
                     black,
@@ -884,9 +1011,9 @@ impl RenderBackend for GliumRenderBackend {
                 );
                 target.clear(
                     Some(&glium::Rect {
-                        left: (self.viewport_width - margin_width) as u32,
+                        left: (self.viewport_width - margin_right) as u32,
                         bottom: 0,
-                        width: margin_width as u32,
+                        width: margin_right as u32,
                         height: self.viewport_height as u32,
                     }),
                     black,
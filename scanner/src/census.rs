@@ -0,0 +1,180 @@
+//! A census of the SWF features used by a movie.
+
+use ruffle_core::swf::avm1::read::Reader as Avm1Reader;
+use ruffle_core::swf::avm1::types::Action;
+use ruffle_core::swf::lazy::{LazyTag, TagIter};
+use ruffle_core::swf::{self, BlendMode, Compression, Filter, Swf, Tag, TagCode};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A summary of what a SWF file contains.
+#[derive(Debug, Default)]
+pub struct Census {
+    pub version: u8,
+    pub compression: String,
+    pub frame_rate: f32,
+    pub width: f64,
+    pub height: f64,
+    pub num_frames: u16,
+
+    /// The number of occurrences of each tag, including tags nested inside of `DefineSprite`.
+    pub tags: BTreeMap<String, u32>,
+
+    /// The number of occurrences of each AVM1 action, including function bodies.
+    pub actions: BTreeMap<String, u32>,
+
+    /// Whether the movie contains any AVM2 code.
+    pub has_abc: bool,
+
+    pub filters: BTreeSet<String>,
+
+    /// Any blend modes used other than `Normal`.
+    pub blend_modes: BTreeSet<String>,
+
+    pub audio_codecs: BTreeSet<String>,
+}
+
+impl Census {
+    /// Takes a census of a parsed SWF.
+    /// `data` is the original, possibly compressed, file that `swf` was parsed from.
+    pub fn new(data: &[u8], swf: &Swf) -> Self {
+        let header = &swf.header;
+        let mut census = Census {
+            version: header.version,
+            compression: match header.compression {
+                Compression::None => "None",
+                Compression::Zlib => "Zlib",
+                Compression::Lzma => "LZMA",
+            }
+            .to_string(),
+            frame_rate: header.frame_rate,
+            width: (header.stage_size.x_max - header.stage_size.x_min).to_pixels(),
+            height: (header.stage_size.y_max - header.stage_size.y_min).to_pixels(),
+            num_frames: header.num_frames,
+            ..Default::default()
+        };
+
+        // The parsed tags don't keep their original tag codes, so count those from the raw stream.
+        if let Err(e) = census.count_raw_tags(data) {
+            log::warn!("Census: Unable to count tags: {}", e);
+        }
+        census.visit_tags(&swf.tags);
+        census
+    }
+
+    fn count_raw_tags(&mut self, data: &[u8]) -> Result<(), swf::error::Error> {
+        let swf_buf = swf::decompress_swf(data)?;
+        self.count_tag_list(swf_buf.tags())
+    }
+
+    fn count_tag_list(&mut self, tags: TagIter<'_>) -> Result<(), swf::error::Error> {
+        for tag in tags {
+            let tag = tag?;
+            *self.tags.entry(TagCode::name(tag.tag_code)).or_insert(0) += 1;
+            if tag.code() == Some(TagCode::DefineSprite) {
+                if let LazyTag::DefineSprite { tags, .. } = tag.parse_lazy()? {
+                    self.count_tag_list(tags)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_tags(&mut self, tags: &[Tag]) {
+        for tag in tags {
+            match tag {
+                Tag::DefineButton(button) | Tag::DefineButton2(button) => {
+                    for record in &button.records {
+                        self.visit_filters(&record.filters);
+                        self.visit_blend_mode(record.blend_mode);
+                    }
+                    for action in &button.actions {
+                        self.visit_actions(&action.action_data);
+                    }
+                }
+                Tag::DefineSound(sound) => {
+                    self.audio_codecs
+                        .insert(format!("{:?}", sound.format.compression));
+                }
+                Tag::DefineSprite(sprite) => self.visit_tags(&sprite.tags),
+                Tag::DoAbc(_) => self.has_abc = true,
+                Tag::DoAction(action_data) => self.visit_actions(action_data),
+                Tag::DoInitAction { action_data, .. } => self.visit_actions(action_data),
+                Tag::PlaceObject(place_object) => {
+                    self.visit_filters(&place_object.filters);
+                    self.visit_blend_mode(place_object.blend_mode);
+                    for clip_action in &place_object.clip_actions {
+                        self.visit_actions(&clip_action.action_data);
+                    }
+                }
+                Tag::SoundStreamHead(stream) | Tag::SoundStreamHead2(stream) => {
+                    self.audio_codecs
+                        .insert(format!("{:?}", stream.stream_format.compression));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn visit_filters(&mut self, filters: &[Filter]) {
+        for filter in filters {
+            self.filters.insert(variant_name(filter));
+        }
+    }
+
+    fn visit_blend_mode(&mut self, blend_mode: BlendMode) {
+        if blend_mode != BlendMode::Normal {
+            self.blend_modes.insert(format!("{:?}", blend_mode));
+        }
+    }
+
+    fn visit_actions(&mut self, action_data: &[u8]) {
+        let mut reader = Avm1Reader::new(action_data, self.version);
+        while let Ok(Some(action)) = reader.read_action() {
+            let name = match action {
+                Action::Unknown { opcode, .. } => format!("Unknown(0x{:02x})", opcode),
+                _ => variant_name(&action),
+            };
+            *self.actions.entry(name).or_insert(0) += 1;
+
+            match action {
+                Action::DefineFunction { actions, .. } => self.visit_actions(actions),
+                Action::DefineFunction2(function) => self.visit_actions(function.actions),
+                Action::Try(try_block) => {
+                    self.visit_actions(try_block.try_actions);
+                    if let Some((_, catch_actions)) = try_block.catch {
+                        self.visit_actions(catch_actions);
+                    }
+                    if let Some(finally_actions) = try_block.finally {
+                        self.visit_actions(finally_actions);
+                    }
+                }
+                Action::With { actions } => self.visit_actions(actions),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Returns the name of an enum variant, stripping any fields from its `Debug` output.
+fn variant_name<T: std::fmt::Debug>(value: &T) -> String {
+    let debug = format!("{:?}", value);
+    debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Formats a histogram as a single CSV field, e.g. `ShowFrame: 10, DoAction: 2`.
+pub fn format_histogram(histogram: &BTreeMap<String, u32>) -> String {
+    histogram
+        .iter()
+        .map(|(name, count)| format!("{}: {}", name, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats a set of names as a single CSV field.
+pub fn format_set(set: &BTreeSet<String>) -> String {
+    set.iter().cloned().collect::<Vec<_>>().join(", ")
+}
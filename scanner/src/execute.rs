@@ -0,0 +1,93 @@
+//! Running movies in a headless player, to find failures that only occur at runtime.
+
+use crate::logging;
+use ruffle_core::backend::{
+    audio::NullAudioBackend, input::NullInputBackend, navigator::NullNavigatorBackend,
+    render::NullRenderer,
+};
+use ruffle_core::Player;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+/// The problems encountered while executing a movie.
+#[derive(Debug, Default)]
+pub struct ExecutionResults {
+    /// The message of the panic that stopped execution, if any.
+    pub panic: Option<String>,
+
+    /// The error that prevented the player from being created, if any.
+    pub error: Option<String>,
+
+    /// Errors raised while executing AVM1 bytecode.
+    pub avm1_errors: Vec<String>,
+
+    /// AVM1 opcodes that are not implemented.
+    pub unknown_opcodes: Vec<String>,
+
+    /// Tags that were unknown or failed to be handled.
+    pub unhandled_tags: Vec<String>,
+
+    /// Whether the time budget ran out before all frames were run.
+    pub timed_out: bool,
+}
+
+/// Runs a movie for up to `num_frames` frames using null backends.
+///
+/// The time budget is checked between frames, so a movie stuck inside of a single frame
+/// will not be interrupted.
+pub fn execute_swf(swf_data: Vec<u8>, num_frames: u32, time_budget: Duration) -> ExecutionResults {
+    let mut results = ExecutionResults::default();
+    let start = Instant::now();
+
+    logging::start_capture();
+    let outcome = catch_unwind(AssertUnwindSafe(|| {
+        run_frames(swf_data, num_frames, start + time_budget)
+    }));
+    let captured = logging::finish_capture();
+
+    match outcome {
+        Ok(Ok(timed_out)) => results.timed_out = timed_out,
+        Ok(Err(e)) => results.error = Some(e.to_string()),
+        Err(e) => results.panic = Some(panic_message(e)),
+    }
+
+    results.avm1_errors = captured.avm1_errors.into_iter().collect();
+    results.unknown_opcodes = captured.unknown_opcodes.into_iter().collect();
+    results.unhandled_tags = captured.unhandled_tags.into_iter().collect();
+    results
+}
+
+/// Runs the movie, returning whether the deadline was reached before all frames ran.
+fn run_frames(
+    swf_data: Vec<u8>,
+    num_frames: u32,
+    deadline: Instant,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut player = Player::new(
+        NullRenderer,
+        NullAudioBackend::new(),
+        NullNavigatorBackend::new(),
+        NullInputBackend::new(),
+        swf_data,
+    )?;
+
+    for _ in 0..num_frames {
+        if Instant::now() > deadline {
+            return Ok(true);
+        }
+        player.run_frame();
+    }
+
+    Ok(false)
+}
+
+/// Extracts a readable message from a caught panic payload.
+pub fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
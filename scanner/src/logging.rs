@@ -0,0 +1,99 @@
+//! Log capturing, used to categorize the problems that Ruffle reports while running a movie.
+
+use log::{Level, Log, Metadata, Record};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+thread_local! {
+    static CAPTURED: RefCell<Option<CapturedLog>> = RefCell::new(None);
+}
+
+/// Categorized messages logged by Ruffle during a scan.
+#[derive(Debug, Default)]
+pub struct CapturedLog {
+    /// Errors raised while executing AVM1 bytecode.
+    pub avm1_errors: BTreeSet<String>,
+
+    /// AVM1 opcodes that are not implemented.
+    pub unknown_opcodes: BTreeSet<String>,
+
+    /// Tags that were unknown or failed to be handled.
+    pub unhandled_tags: BTreeSet<String>,
+}
+
+impl CapturedLog {
+    fn capture(&mut self, message: &str) {
+        if let Some(opcode) = after_prefix(message, "Unknown AVM1 opcode: ") {
+            self.unknown_opcodes.insert(opcode.to_string());
+        } else if let Some(error) = after_prefix(message, "AVM1 error: ") {
+            // Unknown opcodes also bubble up as a generic AVM1 error.
+            if error != "Unknown op" {
+                self.avm1_errors.insert(error.to_string());
+            }
+        } else if let Some(tag) = after_prefix(message, "Unknown tag code: ")
+            .or_else(|| after_prefix(message, "Error running definition tag: "))
+        {
+            self.unhandled_tags.insert(tag.to_string());
+        }
+    }
+}
+
+fn after_prefix<'a>(message: &'a str, prefix: &str) -> Option<&'a str> {
+    if message.starts_with(prefix) {
+        Some(&message[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// Starts capturing log messages on the current thread.
+/// Any previously captured messages are discarded.
+pub fn start_capture() {
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(CapturedLog::default()));
+}
+
+/// Stops capturing log messages on the current thread, and returns what was captured.
+pub fn finish_capture() -> CapturedLog {
+    CAPTURED
+        .with(|captured| captured.borrow_mut().take())
+        .unwrap_or_default()
+}
+
+/// A logger that categorizes warnings and errors while a capture is active,
+/// and forwards every record to `env_logger` as usual.
+pub struct ScanLogger {
+    inner: env_logger::Logger,
+}
+
+impl ScanLogger {
+    /// Installs the scan logger as the global logger.
+    pub fn init() -> Result<(), log::SetLoggerError> {
+        let inner = env_logger::Builder::from_default_env().build();
+        log::set_max_level(log::LevelFilter::Warn.max(inner.filter()));
+        log::set_boxed_logger(Box::new(ScanLogger { inner }))
+    }
+}
+
+impl Log for ScanLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= Level::Warn {
+            CAPTURED.with(|captured| {
+                if let Some(captured) = &mut *captured.borrow_mut() {
+                    captured.capture(&record.args().to_string());
+                }
+            });
+        }
+
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
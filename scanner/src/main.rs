@@ -1,18 +1,102 @@
+mod census;
+mod execute;
+mod logging;
+mod sandbox;
+
+use crate::census::{format_histogram, format_set, Census};
+use crate::execute::{execute_swf, panic_message};
+use crate::logging::ScanLogger;
 use indicatif::{ProgressBar, ProgressStyle};
 use path_slash::PathExt;
 use ruffle_core::swf::read_swf;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::OpenOptions;
 use std::path::{Path, PathBuf};
 
 use std::panic::catch_unwind;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
 use structopt::StructOpt;
 use walkdir::{DirEntry, WalkDir};
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 struct FileResults {
+    #[serde(rename = "Filename")]
     name: String,
+    #[serde(rename = "Error")]
     error: Option<String>,
+    #[serde(rename = "Version")]
+    version: Option<u8>,
+    #[serde(rename = "Compression")]
+    compression: Option<String>,
+    #[serde(rename = "Frame Rate")]
+    frame_rate: Option<f32>,
+    #[serde(rename = "Width")]
+    width: Option<f64>,
+    #[serde(rename = "Height")]
+    height: Option<f64>,
+    #[serde(rename = "Frames")]
+    num_frames: Option<u16>,
+    #[serde(rename = "Tags")]
+    tags: Option<String>,
+    #[serde(rename = "AVM1 Actions")]
+    actions: Option<String>,
+    #[serde(rename = "Has DoAbc")]
+    has_abc: Option<bool>,
+    #[serde(rename = "Filters")]
+    filters: Option<String>,
+    #[serde(rename = "Blend Modes")]
+    blend_modes: Option<String>,
+    #[serde(rename = "Audio Codecs")]
+    audio_codecs: Option<String>,
+    #[serde(rename = "Execution Panic")]
+    execution_panic: Option<String>,
+    #[serde(rename = "Execution Error")]
+    execution_error: Option<String>,
+    #[serde(rename = "AVM1 Errors")]
+    avm1_errors: Option<String>,
+    #[serde(rename = "Unknown Opcodes")]
+    unknown_opcodes: Option<String>,
+    #[serde(rename = "Unhandled Tags")]
+    unhandled_tags: Option<String>,
+    #[serde(rename = "Timeout")]
+    timeout: bool,
+}
+
+impl FileResults {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+
+    fn set_census(&mut self, census: Census) {
+        self.version = Some(census.version);
+        self.compression = Some(census.compression);
+        self.frame_rate = Some(census.frame_rate);
+        self.width = Some(census.width);
+        self.height = Some(census.height);
+        self.num_frames = Some(census.num_frames);
+        self.tags = Some(format_histogram(&census.tags));
+        self.actions = Some(format_histogram(&census.actions));
+        self.has_abc = Some(census.has_abc);
+        self.filters = Some(format_set(&census.filters));
+        self.blend_modes = Some(format_set(&census.blend_modes));
+        self.audio_codecs = Some(format_set(&census.audio_codecs));
+    }
+
+    fn has_errors(&self) -> bool {
+        self.error.is_some()
+            || self.execution_panic.is_some()
+            || self.execution_error.is_some()
+            || self.avm1_errors.is_some()
+            || self.unknown_opcodes.is_some()
+            || self.unhandled_tags.is_some()
+            || self.timeout
+    }
 }
 
 #[derive(StructOpt, Debug)]
@@ -28,6 +112,38 @@ struct Opt {
     /// Filenames to ignore
     #[structopt(short = "i", long = "ignore")]
     ignore: Vec<String>,
+
+    /// Execute each movie in a headless player, instead of only parsing it
+    #[structopt(short = "x", long = "execute")]
+    execute: bool,
+
+    /// The number of frames to run each movie for when executing
+    #[structopt(long = "frames", default_value = "30")]
+    num_frames: u32,
+
+    /// The time budget (in seconds) for executing each movie
+    #[structopt(long = "max-time", default_value = "10")]
+    max_time: u64,
+
+    /// The number of files to scan in parallel (defaults to the number of CPUs)
+    #[structopt(short = "j", long = "jobs")]
+    jobs: Option<usize>,
+
+    /// Scan each file in a separate process, isolating aborts and infinite loops
+    #[structopt(long = "sandbox")]
+    sandbox: bool,
+
+    /// The time (in seconds) after which a sandboxed scan of a file is killed
+    #[structopt(long = "timeout", default_value = "60")]
+    timeout: u64,
+
+    /// Skip files already present in an existing results file, and append to it
+    #[structopt(long = "resume")]
+    resume: bool,
+
+    /// Scans a single file and prints its results to stdout. Used by `--sandbox`.
+    #[structopt(long = "scan-single", hidden = true, parse(from_os_str))]
+    scan_single: Option<PathBuf>,
 }
 
 fn find_files(root: &Path, ignore: &[String]) -> Vec<DirEntry> {
@@ -51,50 +167,106 @@ fn find_files(root: &Path, ignore: &[String]) -> Vec<DirEntry> {
     results
 }
 
-fn scan_file(file: DirEntry, name: String) -> FileResults {
-    let data = match std::fs::read(file.path()) {
+/// Returns the name of a file as written to the results, relative to the scanned directory.
+fn file_name(path: &Path, opt: &Opt) -> String {
+    path.strip_prefix(&opt.input_path)
+        .unwrap_or(path)
+        .to_slash_lossy()
+}
+
+/// Reads the names of the files that have already been scanned into an existing results file.
+fn read_scanned_names(path: &Path) -> Result<HashSet<String>, std::io::Error> {
+    let mut names = HashSet::new();
+    if !path.exists() {
+        return Ok(names);
+    }
+
+    let mut reader = csv::Reader::from_path(path)?;
+    let index = reader.headers()?.iter().position(|h| h == "Filename");
+    if let Some(index) = index {
+        for record in reader.records() {
+            if let Some(name) = record?.get(index) {
+                names.insert(name.to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
+fn scan_file(path: &Path, name: String, opt: &Opt) -> FileResults {
+    let mut results = FileResults::new(name);
+    let data = match std::fs::read(path) {
         Ok(data) => data,
         Err(e) => {
-            return {
-                FileResults {
-                    name,
-                    error: Some(format!("File error: {}", e.to_string())),
-                }
-            }
+            results.error = Some(format!("File error: {}", e.to_string()));
+            return results;
         }
     };
 
-    match catch_unwind(|| read_swf(&data[..])) {
+    match catch_unwind(|| read_swf(&data[..]).map(|swf| Census::new(&data, &swf))) {
         Ok(swf) => match swf {
-            Ok(_swf) => FileResults { name, error: None },
-            Err(e) => FileResults {
-                name,
-                error: Some(format!("Parse error: {}", e.to_string())),
-            },
-        },
-        Err(e) => match e.downcast::<String>() {
-            Ok(e) => FileResults {
-                name,
-                error: Some(format!("PANIC: {}", e.to_string())),
-            },
-            Err(_) => FileResults {
-                name,
-                error: Some("PANIC".to_string()),
-            },
+            Ok(census) => results.set_census(census),
+            Err(e) => results.error = Some(format!("Parse error: {}", e.to_string())),
         },
+        Err(e) => results.error = Some(format!("PANIC: {}", panic_message(e))),
+    }
+
+    if opt.execute && results.error.is_none() {
+        let execution = execute_swf(data, opt.num_frames, Duration::from_secs(opt.max_time));
+        results.execution_panic = execution.panic;
+        results.execution_error = execution.error;
+        results.avm1_errors = join_messages(execution.avm1_errors);
+        results.unknown_opcodes = join_messages(execution.unknown_opcodes);
+        results.unhandled_tags = join_messages(execution.unhandled_tags);
+        results.timeout = execution.timed_out;
+    }
+
+    results
+}
+
+/// Joins captured messages into a single CSV field, or `None` if there are none.
+fn join_messages(messages: Vec<String>) -> Option<String> {
+    if messages.is_empty() {
+        None
+    } else {
+        Some(messages.join("\n"))
     }
 }
 
 fn main() -> Result<(), std::io::Error> {
-    env_logger::init();
+    ScanLogger::init().expect("Unable to initialize logger");
 
     let opt = Opt::from_args();
-    let to_scan = find_files(&opt.input_path, &opt.ignore);
+    if let Some(path) = &opt.scan_single {
+        return sandbox::scan_single(path, &opt);
+    }
+
+    let scanned = if opt.resume {
+        read_scanned_names(&opt.output_path)?
+    } else {
+        HashSet::new()
+    };
+    let to_scan: Vec<PathBuf> = find_files(&opt.input_path, &opt.ignore)
+        .into_iter()
+        .map(|entry| entry.into_path())
+        .filter(|path| !scanned.contains(&file_name(path, &opt)))
+        .collect();
     let total = to_scan.len() as u64;
     let mut good = 0;
     let mut bad = 0;
     let progress = ProgressBar::new(total);
-    let mut writer = csv::Writer::from_path(opt.output_path)?;
+
+    // Results are written as soon as each file is scanned, so that an interrupted scan can be resumed.
+    let append = opt.resume && std::fs::metadata(&opt.output_path).map_or(false, |m| m.len() > 0);
+    let output = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(&opt.output_path)?;
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(!append)
+        .from_writer(output);
 
     progress.set_style(
         ProgressStyle::default_bar()
@@ -104,29 +276,48 @@ fn main() -> Result<(), std::io::Error> {
             .progress_chars("##-"),
     );
 
-    writer.write_record(&["Filename", "Error"])?;
+    let jobs = opt.jobs.unwrap_or_else(num_cpus::get).max(1);
+    let opt = Arc::new(opt);
+    let queue = Arc::new(Mutex::new(to_scan.into_iter()));
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..jobs {
+        let opt = opt.clone();
+        let queue = queue.clone();
+        let sender = sender.clone();
+        std::thread::spawn(move || loop {
+            let path = match queue.lock().unwrap().next() {
+                Some(path) => path,
+                None => break,
+            };
+            let name = file_name(&path, &opt);
+            let result = if opt.sandbox {
+                sandbox::scan_file_sandboxed(&path, name, &opt)
+            } else {
+                scan_file(&path, name, &opt)
+            };
+            if sender.send(result).is_err() {
+                break;
+            }
+        });
+    }
+    drop(sender);
 
-    for file in to_scan {
-        let name = file
-            .path()
-            .strip_prefix(&opt.input_path)
-            .unwrap_or_else(|_| file.path())
-            .to_slash_lossy();
+    for result in receiver {
         progress.inc(1);
-        progress.set_message(&name);
-        let result = scan_file(file, name);
+        progress.set_message(&result.name);
 
-        if result.error.is_none() {
+        if !result.has_errors() {
             good += 1;
         } else {
             bad += 1;
         }
 
         writer.serialize(result)?;
+        writer.flush()?;
     }
 
     progress.finish_with_message(&format!(
-        "Scanned {} swf files. {} successfully parsed, {} encountered errors",
+        "Scanned {} swf files. {} successfully scanned, {} encountered errors",
         total, good, bad
     ));
 
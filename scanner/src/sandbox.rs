@@ -0,0 +1,93 @@
+//! Scanning files in child processes, so that aborts and infinite loops can't take down the scan.
+
+use crate::{file_name, scan_file, FileResults, Opt};
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Scans a single file and writes its results to stdout as a headerless CSV record.
+/// This is the entry point of the child process spawned by `scan_file_sandboxed`.
+pub fn scan_single(path: &Path, opt: &Opt) -> Result<(), std::io::Error> {
+    let result = scan_file(path, file_name(path, opt), opt);
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(std::io::stdout());
+    writer.serialize(result)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Scans a file in a child process, killing it if it runs for longer than the timeout.
+pub fn scan_file_sandboxed(path: &Path, name: String, opt: &Opt) -> FileResults {
+    let mut results = FileResults::new(name);
+
+    let mut command = match std::env::current_exe() {
+        Ok(exe) => Command::new(exe),
+        Err(e) => {
+            results.error = Some(format!("Sandbox error: {}", e));
+            return results;
+        }
+    };
+    command
+        .arg("--scan-single")
+        .arg(path)
+        .arg("--frames")
+        .arg(opt.num_frames.to_string())
+        .arg("--max-time")
+        .arg(opt.max_time.to_string());
+    if opt.execute {
+        command.arg("--execute");
+    }
+    command
+        .arg(&opt.input_path)
+        .arg(&opt.output_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            results.error = Some(format!("Sandbox error: {}", e));
+            return results;
+        }
+    };
+
+    // Read the output on another thread, so that the child can't block on a full pipe.
+    let mut stdout = child.stdout.take().unwrap();
+    let output = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        output
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(opt.timeout);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+        }
+    };
+    let output = output.join().unwrap_or_default();
+
+    match status {
+        Some(status) => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(&output[..]);
+            match reader.deserialize().next() {
+                Some(Ok(child_results)) => return child_results,
+                _ => results.execution_panic = Some(format!("Process crashed: {}", status)),
+            }
+        }
+        None => results.timeout = true,
+    }
+
+    results
+}
@@ -0,0 +1,16 @@
+use std::fs::File;
+use std::io::BufReader;
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: disassemble <file.swf>");
+            std::process::exit(1);
+        }
+    };
+    let file = File::open(path).unwrap();
+    let reader = BufReader::new(file);
+    let swf = swf::read_swf(reader).unwrap();
+    print!("{}", swf::avm1::disassemble::disassemble_swf(&swf));
+}
@@ -0,0 +1,448 @@
+//! A disassembler producing readable listings of AVM1 bytecode.
+//!
+//! Jump targets are replaced with labels, `ConstantPool` references are expanded to the
+//! strings they refer to, and the bodies of functions, `Try` and `With` blocks are nested
+//! inside of the action that contains them. Offsets are given in bytes, relative to the
+//! start of the code block containing the action.
+//!
+//! Each top-level block of code starts with an empty constant pool. Flash keeps the
+//! constant pool around between blocks, so references to a pool defined in an earlier
+//! block are shown as unresolved.
+
+use crate::avm1::read::Reader;
+use crate::avm1::types::*;
+use crate::types::{Swf, Tag};
+use std::collections::BTreeMap;
+
+/// Disassembles a single block of AVM1 code, such as the contents of a `DoAction` tag.
+pub fn disassemble(action_data: &[u8], version: u8) -> String {
+    let mut disassembler = Disassembler::new(version);
+    disassembler.block(action_data, &[]);
+    disassembler.output
+}
+
+/// Disassembles all of the AVM1 code in a SWF, including code in sprites, buttons and
+/// clip events.
+pub fn disassemble_swf(swf: &Swf) -> String {
+    let mut disassembler = Disassembler::new(swf.header.version);
+    disassembler.tags(&swf.tags, "root");
+    disassembler.output
+}
+
+struct Disassembler {
+    version: u8,
+    indent: usize,
+    output: String,
+}
+
+impl Disassembler {
+    fn new(version: u8) -> Self {
+        Self {
+            version,
+            indent: 0,
+            output: String::new(),
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        // Nested blocks line up with the text of the action containing them.
+        for _ in 0..self.indent {
+            self.output.push_str("       ");
+        }
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    /// Writes a line without an offset, lined up with the text of the actions in the block.
+    fn text_line(&mut self, text: &str) {
+        self.line(&format!("{:5}  {}", "", text));
+    }
+
+    fn tags(&mut self, tags: &[Tag], clip: &str) {
+        let mut frame = 1;
+        for tag in tags {
+            match tag {
+                Tag::DefineButton(button) | Tag::DefineButton2(button) => {
+                    for action in &button.actions {
+                        let mut conditions: Vec<_> = action
+                            .conditions
+                            .iter()
+                            .map(|condition| format!("{:?}", condition))
+                            .collect();
+                        conditions.sort();
+                        let mut header = format!(
+                            "Button {}, conditions: {}",
+                            button.id,
+                            conditions.join(", ")
+                        );
+                        if let Some(key_code) = action.key_code {
+                            header.push_str(&format!(", key code: {}", key_code));
+                        }
+                        self.top_level_block(&header, &action.action_data);
+                    }
+                }
+                Tag::DefineSprite(sprite) => {
+                    self.tags(&sprite.tags, &format!("sprite {}", sprite.id))
+                }
+                Tag::DoAction(action_data) => {
+                    let header = format!("DoAction, {} frame {}", clip, frame);
+                    self.top_level_block(&header, action_data);
+                }
+                Tag::DoInitAction { id, action_data } => {
+                    let header = format!("DoInitAction, sprite {}", id);
+                    self.top_level_block(&header, action_data);
+                }
+                Tag::PlaceObject(place_object) => {
+                    for clip_action in &place_object.clip_actions {
+                        let mut events: Vec<_> = clip_action
+                            .events
+                            .iter()
+                            .map(|event| format!("{:?}", event))
+                            .collect();
+                        events.sort();
+                        let mut header = format!(
+                            "ClipAction, {} frame {} depth {}, events: {}",
+                            clip,
+                            frame,
+                            place_object.depth,
+                            events.join(", ")
+                        );
+                        if let Some(key_code) = clip_action.key_code {
+                            header.push_str(&format!(", key code: {}", key_code));
+                        }
+                        self.top_level_block(&header, &clip_action.action_data);
+                    }
+                }
+                Tag::ShowFrame => frame += 1,
+                _ => (),
+            }
+        }
+    }
+
+    fn top_level_block(&mut self, header: &str, action_data: &[u8]) {
+        self.line(&format!("; {}", header));
+        self.block(action_data, &[]);
+        self.line("");
+    }
+
+    /// Disassembles a block of code, starting with the given constant pool.
+    fn block<'a>(&mut self, action_data: &'a [u8], constant_pool: &[&'a str]) {
+        let mut constant_pool = constant_pool.to_vec();
+
+        // Read the whole block first, so that labels can be assigned to jump targets.
+        let mut actions = vec![];
+        let mut error = None;
+        let mut reader = Reader::new(action_data, self.version);
+        // Function bodies and other nested blocks don't always end with an `End` action.
+        while reader.pos() < action_data.len() {
+            let offset = reader.pos();
+            match reader.read_action() {
+                Ok(Some(action)) => actions.push((offset, reader.pos(), action)),
+                Ok(None) => break,
+                Err(e) => {
+                    error = Some((offset, e));
+                    break;
+                }
+            }
+        }
+
+        let mut labels = BTreeMap::new();
+        for (_, end, action) in &actions {
+            match action {
+                Action::If { offset } | Action::Jump { offset } => {
+                    let target = *end as isize + isize::from(*offset);
+                    labels.insert(target, 0);
+                }
+                _ => (),
+            }
+        }
+        for (i, label) in labels.values_mut().enumerate() {
+            *label = i + 1;
+        }
+
+        for (offset, end, action) in &actions {
+            if let Some(label) = labels.get(&(*offset as isize)) {
+                self.line(&format!("L{}:", label));
+            }
+            let text = match action {
+                Action::If { offset } | Action::Jump { offset } => {
+                    let target = *end as isize + isize::from(*offset);
+                    format!("{} L{}", action_name(action), labels[&target])
+                }
+                Action::Push(values) => {
+                    let values: Vec<_> = values
+                        .iter()
+                        .map(|value| format_value(value, &constant_pool))
+                        .collect();
+                    format!("Push {}", values.join(", "))
+                }
+                _ => format_action(action),
+            };
+            self.line(&format!("{:5}  {}", offset, text));
+
+            match action {
+                Action::ConstantPool(constants) => constant_pool = constants.clone(),
+                Action::DefineFunction { actions: body, .. } => {
+                    self.nested_block(body, &constant_pool);
+                    self.text_line("}");
+                }
+                Action::DefineFunction2(function) => {
+                    self.nested_block(function.actions, &constant_pool);
+                    self.text_line("}");
+                }
+                Action::Try(try_block) => {
+                    self.nested_block(try_block.try_actions, &constant_pool);
+                    if let Some((catch_var, catch_actions)) = &try_block.catch {
+                        let catch_var = match catch_var {
+                            CatchVar::Var(name) => name.to_string(),
+                            CatchVar::Register(register) => format!("register{}", register),
+                        };
+                        self.text_line(&format!("}} catch ({}) {{", catch_var));
+                        self.nested_block(catch_actions, &constant_pool);
+                    }
+                    if let Some(finally_actions) = try_block.finally {
+                        self.text_line("} finally {");
+                        self.nested_block(finally_actions, &constant_pool);
+                    }
+                    self.text_line("}");
+                }
+                Action::With { actions: body } => {
+                    self.nested_block(body, &constant_pool);
+                    self.text_line("}");
+                }
+                _ => (),
+            }
+        }
+
+        let end = action_data.len() as isize;
+        if let Some((offset, e)) = error {
+            self.line(&format!("{:5}  ; Error: {}", offset, e));
+        } else if let Some(label) = labels.get(&end) {
+            self.line(&format!("L{}:", label));
+            self.line(&format!("{:5}  ; End", end));
+        }
+
+        // Jumps into the middle of an action or outside of the block can't be given a label.
+        for (target, label) in labels.range(..0).chain(labels.range(end + 1..)) {
+            self.line(&format!(
+                "; L{} is outside of this block: {}",
+                label, target
+            ));
+        }
+        for (target, label) in labels.range(0..end) {
+            if !actions
+                .iter()
+                .any(|(offset, _, _)| *offset as isize == *target)
+            {
+                self.line(&format!(
+                    "; L{} is not on an action boundary: {}",
+                    label, target
+                ));
+            }
+        }
+    }
+
+    fn nested_block(&mut self, action_data: &[u8], constant_pool: &[&str]) {
+        self.indent += 1;
+        self.block(action_data, constant_pool);
+        self.indent -= 1;
+    }
+}
+
+/// Formats an action and its parameters. Nested code blocks are not included.
+fn format_action(action: &Action) -> String {
+    match action {
+        Action::ConstantPool(constants) => {
+            let constants: Vec<_> = constants.iter().map(|s| format!("{:?}", s)).collect();
+            format!("ConstantPool {}", constants.join(", "))
+        }
+        Action::DefineFunction { name, params, .. } => {
+            format!("DefineFunction {}({}) {{", name, params.join(", "))
+        }
+        Action::DefineFunction2(function) => {
+            let params: Vec<_> = function
+                .params
+                .iter()
+                .map(|param| match param.register_index {
+                    Some(register) => format!("register{}: {}", register, param.name),
+                    None => param.name.to_string(),
+                })
+                .collect();
+            let flags = [
+                (function.preload_parent, "preload_parent"),
+                (function.preload_root, "preload_root"),
+                (function.suppress_super, "suppress_super"),
+                (function.preload_super, "preload_super"),
+                (function.suppress_arguments, "suppress_arguments"),
+                (function.preload_arguments, "preload_arguments"),
+                (function.suppress_this, "suppress_this"),
+                (function.preload_this, "preload_this"),
+                (function.preload_global, "preload_global"),
+            ];
+            let flags: Vec<_> = flags
+                .iter()
+                .filter(|(set, _)| *set)
+                .map(|(_, name)| *name)
+                .collect();
+            format!(
+                "DefineFunction2 {}({}) registers: {} [{}] {{",
+                function.name,
+                params.join(", "),
+                function.register_count,
+                flags.join(", ")
+            )
+        }
+        Action::GetUrl { url, target } => format!("GetUrl {:?}, {:?}", url, target),
+        Action::GetUrl2 {
+            send_vars_method,
+            is_target_sprite,
+            is_load_vars,
+        } => format!(
+            "GetUrl2 method: {:?}, target sprite: {}, load vars: {}",
+            send_vars_method, is_target_sprite, is_load_vars
+        ),
+        Action::GotoFrame(frame) => format!("GotoFrame {}", frame),
+        Action::GotoFrame2 {
+            set_playing,
+            scene_offset,
+        } => format!(
+            "GotoFrame2 play: {}, scene offset: {}",
+            set_playing, scene_offset
+        ),
+        Action::GotoLabel(label) => format!("GotoLabel {:?}", label),
+        Action::SetTarget(target) => format!("SetTarget {:?}", target),
+        Action::StoreRegister(register) => format!("StoreRegister {}", register),
+        Action::Try(_) => "Try {".to_string(),
+        Action::WaitForFrame {
+            frame,
+            num_actions_to_skip,
+        } => format!("WaitForFrame {}, skip: {}", frame, num_actions_to_skip),
+        Action::WaitForFrame2 {
+            num_actions_to_skip,
+        } => format!("WaitForFrame2 skip: {}", num_actions_to_skip),
+        Action::With { .. } => "With {".to_string(),
+        Action::Unknown { opcode, data } => {
+            format!("Unknown 0x{:02x} ({} bytes)", opcode, data.len())
+        }
+        _ => action_name(action),
+    }
+}
+
+/// Returns the name of an action, without any of its parameters.
+fn action_name(action: &Action) -> String {
+    let debug = format!("{:?}", action);
+    debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn format_value(value: &Value, constant_pool: &[&str]) -> String {
+    match value {
+        Value::Undefined => "undefined".to_string(),
+        Value::Null => "null".to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Int(value) => value.to_string(),
+        Value::Float(value) => format!("{:?}f", value),
+        Value::Double(value) => format!("{:?}", value),
+        Value::Str(value) => format!("{:?}", value),
+        Value::Register(register) => format!("register{}", register),
+        Value::ConstantPool(index) => match constant_pool.get(usize::from(*index)) {
+            Some(value) => format!("{:?}", value),
+            None => format!("constant{} (unresolved)", index),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avm1::write::Writer;
+
+    fn assemble(actions: &[Action]) -> Vec<u8> {
+        let mut data = vec![];
+        let mut writer = Writer::new(&mut data, 8);
+        for action in actions {
+            writer.write_action(action).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn jump_labels() {
+        let data = assemble(&[
+            Action::Push(vec![Value::Bool(true)]),
+            Action::If { offset: 1 },
+            Action::Play,
+            Action::Stop,
+            Action::Jump { offset: -17 },
+        ]);
+        assert_eq!(
+            disassemble(&data, 8),
+            concat!(
+                "L1:\n",
+                "    0  Push true\n",
+                "    5  If L2\n",
+                "   10  Play\n",
+                "L2:\n",
+                "   11  Stop\n",
+                "   12  Jump L1\n",
+            )
+        );
+    }
+
+    #[test]
+    fn constant_pool() {
+        let data = assemble(&[
+            Action::Push(vec![Value::ConstantPool(0)]),
+            Action::ConstantPool(vec!["foo", "bar"]),
+            Action::Push(vec![Value::ConstantPool(1), Value::Int(2)]),
+        ]);
+        assert_eq!(
+            disassemble(&data, 8),
+            concat!(
+                "    0  Push constant0 (unresolved)\n",
+                "    5  ConstantPool \"foo\", \"bar\"\n",
+                "   18  Push \"bar\", 2\n",
+            )
+        );
+    }
+
+    #[test]
+    fn nested_function() {
+        let inner_body = assemble(&[Action::Stop]);
+        let body = assemble(&[
+            Action::Push(vec![Value::ConstantPool(0)]),
+            Action::Trace,
+            Action::DefineFunction {
+                name: "g",
+                params: vec![],
+                actions: &inner_body,
+            },
+        ]);
+        let data = assemble(&[
+            Action::ConstantPool(vec!["hello"]),
+            Action::DefineFunction {
+                name: "f",
+                params: vec!["a", "b"],
+                actions: &body,
+            },
+            Action::Play,
+        ]);
+        assert_eq!(
+            disassemble(&data, 8),
+            concat!(
+                "    0  ConstantPool \"hello\"\n",
+                "   11  DefineFunction f(a, b) {\n",
+                "           0  Push \"hello\"\n",
+                "           5  Trace\n",
+                "           6  DefineFunction g() {\n",
+                "                  0  Stop\n",
+                "              }\n",
+                "       }\n",
+                "   40  Play\n",
+            )
+        );
+    }
+}
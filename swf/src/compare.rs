@@ -0,0 +1,148 @@
+#![allow(clippy::float_cmp)]
+
+//! Comparing SWFs by their contents, rather than by how they are encoded.
+//!
+//! Two SWFs that decode to the same tags will play identically, even if they were written
+//! with different compression, bit widths or tag header sizes.
+
+use crate::types::*;
+
+/// Returns a description of each difference between two SWFs, or an empty `Vec` if they
+/// are equivalent.
+///
+/// Compression is ignored. If `ignore_debug_tags` is set, tags that only carry information
+/// for authoring tools and debuggers are ignored as well.
+///
+/// # Example
+/// ```
+/// let data = std::fs::read("tests/swfs/DefineSprite.swf").unwrap();
+/// let swf = swf::read_swf(&data[..]).unwrap();
+/// let mut output = Vec::new();
+/// swf::write_swf(&swf, &mut output).unwrap();
+/// let round_tripped = swf::read_swf(&output[..]).unwrap();
+/// assert!(swf::compare::differences(&swf, &round_tripped, false).is_empty());
+/// ```
+pub fn differences(left: &Swf, right: &Swf, ignore_debug_tags: bool) -> Vec<String> {
+    let mut differences = vec![];
+    let (l, r) = (&left.header, &right.header);
+    if l.version != r.version {
+        differences.push(format!("header: version {} != {}", l.version, r.version));
+    }
+    if l.stage_size != r.stage_size {
+        differences.push(format!(
+            "header: stage size {:?} != {:?}",
+            l.stage_size, r.stage_size
+        ));
+    }
+    if l.frame_rate != r.frame_rate {
+        differences.push(format!(
+            "header: frame rate {} != {}",
+            l.frame_rate, r.frame_rate
+        ));
+    }
+    if l.num_frames != r.num_frames {
+        differences.push(format!(
+            "header: frame count {} != {}",
+            l.num_frames, r.num_frames
+        ));
+    }
+
+    compare_tag_lists(
+        "root",
+        &left.tags,
+        &right.tags,
+        ignore_debug_tags,
+        &mut differences,
+    );
+    differences
+}
+
+/// Returns whether two SWFs are equivalent, as defined by `differences`.
+pub fn equivalent(left: &Swf, right: &Swf, ignore_debug_tags: bool) -> bool {
+    differences(left, right, ignore_debug_tags).is_empty()
+}
+
+/// Returns whether a tag only carries information for authoring tools and debuggers.
+pub fn is_debug_tag(tag: &Tag) -> bool {
+    match tag {
+        Tag::DebugId(_)
+        | Tag::EnableDebugger(_)
+        | Tag::EnableTelemetry { .. }
+        | Tag::Metadata(_)
+        | Tag::ProductInfo(_) => true,
+        _ => false,
+    }
+}
+
+fn compare_tag_lists(
+    path: &str,
+    left: &[Tag],
+    right: &[Tag],
+    ignore_debug_tags: bool,
+    differences: &mut Vec<String>,
+) {
+    let left = visible_tags(left, ignore_debug_tags);
+    let right = visible_tags(right, ignore_debug_tags);
+
+    for (&(i, l), &(j, r)) in left.iter().zip(right.iter()) {
+        match (l, r) {
+            (Tag::DefineSprite(l), Tag::DefineSprite(r)) if l.id == r.id => {
+                let sprite_path = format!("{} tag {}/{} (sprite {})", path, i, j, l.id);
+                if l.num_frames != r.num_frames {
+                    differences.push(format!(
+                        "{}: frame count {} != {}",
+                        sprite_path, l.num_frames, r.num_frames
+                    ));
+                }
+                compare_tag_lists(
+                    &sprite_path,
+                    &l.tags,
+                    &r.tags,
+                    ignore_debug_tags,
+                    differences,
+                );
+            }
+            _ if l != r => differences.push(format!(
+                "{} tag {}/{}: {} != {}",
+                path,
+                i,
+                j,
+                describe(l),
+                describe(r)
+            )),
+            _ => (),
+        }
+    }
+
+    if left.len() != right.len() {
+        differences.push(format!(
+            "{}: {} tags != {} tags",
+            path,
+            left.len(),
+            right.len()
+        ));
+    }
+}
+
+/// Returns the tags to compare, along with their indices in the tag list.
+fn visible_tags(tags: &[Tag], ignore_debug_tags: bool) -> Vec<(usize, &Tag)> {
+    tags.iter()
+        .enumerate()
+        .filter(|(_, tag)| !(ignore_debug_tags && is_debug_tag(tag)))
+        .collect()
+}
+
+/// Describes a tag for a difference report, truncating large tags.
+fn describe(tag: &Tag) -> String {
+    const MAX_LEN: usize = 200;
+    let mut description = format!("{:?}", tag);
+    if description.len() > MAX_LEN {
+        let mut end = MAX_LEN;
+        while !description.is_char_boundary(end) {
+            end -= 1;
+        }
+        description.truncate(end);
+        description.push_str("...");
+    }
+    description
+}
@@ -0,0 +1,413 @@
+//! Reading the tags of an SWF on demand, without copying their data.
+//!
+//! `read_swf` decodes every tag up front, making owned copies of every bitmap, sound and
+//! action block. The types here instead borrow from the decompressed SWF data, and only
+//! decode a tag when asked to. Large payloads, such as image and sound data, are never
+//! copied, and `DefineSprite` tags expose their nested tags as another `TagIter`.
+//!
+//! # Example
+//! ```
+//! use swf::lazy::LazyTag;
+//!
+//! let data = std::fs::read("tests/swfs/DefineSprite.swf").unwrap();
+//! let swf_buf = swf::decompress_swf(&data[..]).unwrap();
+//! for tag in swf_buf.tags() {
+//!     let tag = tag.unwrap();
+//!     if let Ok(LazyTag::DefineSprite { id, tags, .. }) = tag.parse_lazy() {
+//!         println!("Sprite {} has {} tags", id, tags.count());
+//!     }
+//! }
+//! ```
+
+use crate::error::{Error, Result};
+use crate::read::{Reader, SwfRead};
+use crate::tag_code::TagCode;
+use crate::types::*;
+
+/// An SWF file decompressed into memory, whose tags can be read on demand.
+/// This is returned by `swf::decompress_swf`.
+pub struct SwfBuf {
+    pub header: Header,
+
+    /// The decompressed tag data, following the header.
+    pub data: Vec<u8>,
+}
+
+impl SwfBuf {
+    /// Returns an iterator over the tags of the main timeline.
+    pub fn tags(&self) -> TagIter<'_> {
+        TagIter::new(&self.data, self.header.version)
+    }
+}
+
+/// An iterator over the tags in a block of SWF data.
+///
+/// Every tag is returned, including the final `End` tag, after which iteration stops.
+/// A tag whose length extends past the end of the data is truncated, as Flash does.
+/// If a tag header can't be read, an error is returned and iteration stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagIter<'a> {
+    data: &'a [u8],
+    version: u8,
+    is_finished: bool,
+}
+
+impl<'a> TagIter<'a> {
+    /// Creates an iterator over the tags in `data`, which should begin with a tag header.
+    pub fn new(data: &'a [u8], version: u8) -> Self {
+        Self {
+            data,
+            version,
+            is_finished: false,
+        }
+    }
+
+    /// The SWF data following the last tag returned.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    fn read_tag(&mut self) -> Result<TagSlice<'a>> {
+        let mut reader = Reader::new(self.data, self.version);
+        let (tag_code, length) = reader.read_tag_code_and_length()?;
+        let rest: &'a [u8] = *reader.get_ref();
+        if length > rest.len() {
+            log::warn!(
+                "{} tag extends past end of data, truncating",
+                TagCode::name(tag_code)
+            );
+        }
+        let (data, rest) = rest.split_at(length.min(rest.len()));
+        self.data = rest;
+        Ok(TagSlice {
+            tag_code,
+            data,
+            version: self.version,
+        })
+    }
+}
+
+impl<'a> Iterator for TagIter<'a> {
+    type Item = Result<TagSlice<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished || self.data.is_empty() {
+            return None;
+        }
+
+        let tag = self.read_tag();
+        match tag {
+            Ok(ref tag) if tag.tag_code == TagCode::End as u16 => self.is_finished = true,
+            Err(_) => self.is_finished = true,
+            _ => (),
+        }
+        Some(tag)
+    }
+}
+
+/// An undecoded tag, borrowed from the SWF data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TagSlice<'a> {
+    pub tag_code: u16,
+
+    /// The body of the tag, not including its header.
+    pub data: &'a [u8],
+
+    version: u8,
+}
+
+impl<'a> TagSlice<'a> {
+    /// Returns the type of this tag, or `None` if the tag code is unknown.
+    pub fn code(&self) -> Option<TagCode> {
+        TagCode::from_u16(self.tag_code)
+    }
+
+    /// Decodes this tag, copying all of its data.
+    pub fn parse(&self) -> Result<Tag> {
+        let mut reader = Reader::new(self.data, self.version);
+        reader
+            .read_tag_with_code(self.tag_code, self.data.len())
+            .map_err(|e| Error::swf_parse_error_with_source(self.tag_code, e))
+    }
+
+    /// Decodes this tag, borrowing any large payloads instead of copying them.
+    pub fn parse_lazy(&self) -> Result<LazyTag<'a>> {
+        self.read_lazy()
+            .map_err(|e| Error::swf_parse_error_with_source(self.tag_code, e))
+    }
+
+    fn read_lazy(&self) -> Result<LazyTag<'a>> {
+        let mut reader = Reader::new(self.data, self.version);
+        let tag = match self.code() {
+            Some(TagCode::DefineBinaryData) => {
+                let id = reader.read_u16()?;
+                reader.read_u32()?; // Reserved
+                LazyTag::DefineBinaryData {
+                    id,
+                    data: *reader.get_ref(),
+                }
+            }
+            Some(TagCode::DefineBits) => LazyTag::DefineBits {
+                id: reader.read_u16()?,
+                jpeg_data: *reader.get_ref(),
+            },
+            Some(TagCode::DefineBitsJpeg2) => LazyTag::DefineBitsJpeg2 {
+                id: reader.read_u16()?,
+                jpeg_data: *reader.get_ref(),
+            },
+            Some(TagCode::DefineBitsJpeg3) | Some(TagCode::DefineBitsJpeg4) => {
+                let version = if self.code() == Some(TagCode::DefineBitsJpeg3) {
+                    3
+                } else {
+                    4
+                };
+                let id = reader.read_u16()?;
+                let data_size = reader.read_u32()? as usize;
+                let deblocking = if version >= 4 {
+                    reader.read_fixed8()?
+                } else {
+                    0.0
+                };
+                let rest: &'a [u8] = *reader.get_ref();
+                if data_size > rest.len() {
+                    return Err(Error::invalid_data("Invalid JPEG data size"));
+                }
+                let (data, alpha_data) = rest.split_at(data_size);
+                LazyTag::DefineBitsJpeg3 {
+                    id,
+                    version,
+                    deblocking,
+                    data,
+                    alpha_data,
+                }
+            }
+            Some(TagCode::DefineBitsLossless) | Some(TagCode::DefineBitsLossless2) => {
+                let version = if self.code() == Some(TagCode::DefineBitsLossless) {
+                    1
+                } else {
+                    2
+                };
+                let id = reader.read_u16()?;
+                let format = match reader.read_u8()? {
+                    3 => BitmapFormat::ColorMap8,
+                    4 if version == 1 => BitmapFormat::Rgb15,
+                    5 => BitmapFormat::Rgb32,
+                    _ => return Err(Error::invalid_data("Invalid bitmap format.")),
+                };
+                let width = reader.read_u16()?;
+                let height = reader.read_u16()?;
+                let num_colors = if format == BitmapFormat::ColorMap8 {
+                    reader.read_u8()?
+                } else {
+                    0
+                };
+                LazyTag::DefineBitsLossless {
+                    version,
+                    id,
+                    format,
+                    width,
+                    height,
+                    num_colors,
+                    data: *reader.get_ref(),
+                }
+            }
+            Some(TagCode::DefineSound) => LazyTag::DefineSound {
+                id: reader.read_u16()?,
+                format: reader.read_sound_format()?,
+                num_samples: reader.read_u32()?,
+                data: *reader.get_ref(),
+            },
+            Some(TagCode::DefineSprite) => LazyTag::DefineSprite {
+                id: reader.read_u16()?,
+                num_frames: reader.read_u16()?,
+                tags: TagIter::new(*reader.get_ref(), self.version),
+            },
+            Some(TagCode::DoAbc) => {
+                let flags = reader.read_u32()?;
+                let rest: &'a [u8] = *reader.get_ref();
+                let name_len = rest
+                    .iter()
+                    .position(|&c| c == 0)
+                    .ok_or_else(|| Error::invalid_data("Invalid string data"))?;
+                let name = std::str::from_utf8(&rest[..name_len])
+                    .map_err(|_| Error::invalid_data("Invalid string data"))?;
+                LazyTag::DoAbc {
+                    name,
+                    is_lazy_initialize: flags & 1 != 0,
+                    data: &rest[name_len + 1..],
+                }
+            }
+            Some(TagCode::DoAction) => LazyTag::DoAction(self.data),
+            Some(TagCode::DoInitAction) => LazyTag::DoInitAction {
+                id: reader.read_u16()?,
+                action_data: *reader.get_ref(),
+            },
+            Some(TagCode::JpegTables) => LazyTag::JpegTables(self.data),
+            Some(TagCode::SoundStreamBlock) => LazyTag::SoundStreamBlock(self.data),
+            Some(TagCode::VideoFrame) => LazyTag::VideoFrame {
+                stream_id: reader.read_u16()?,
+                frame_num: reader.read_u16()?,
+                data: *reader.get_ref(),
+            },
+            _ => LazyTag::Other(reader.read_tag_with_code(self.tag_code, self.data.len())?),
+        };
+        Ok(tag)
+    }
+}
+
+/// A decoded tag that borrows its large payloads from the SWF data.
+///
+/// The variants mirror those of `Tag`. Tags without large payloads are fully decoded
+/// into `Other`.
+#[derive(Debug, PartialEq)]
+pub enum LazyTag<'a> {
+    DefineBinaryData {
+        id: CharacterId,
+        data: &'a [u8],
+    },
+    DefineBits {
+        id: CharacterId,
+        jpeg_data: &'a [u8],
+    },
+    DefineBitsJpeg2 {
+        id: CharacterId,
+        jpeg_data: &'a [u8],
+    },
+    DefineBitsJpeg3 {
+        id: CharacterId,
+        version: u8,
+        deblocking: f32,
+        data: &'a [u8],
+        alpha_data: &'a [u8],
+    },
+    DefineBitsLossless {
+        version: u8,
+        id: CharacterId,
+        format: BitmapFormat,
+        width: u16,
+        height: u16,
+        num_colors: u8,
+        data: &'a [u8],
+    },
+    DefineSound {
+        id: CharacterId,
+        format: SoundFormat,
+        num_samples: u32,
+        data: &'a [u8],
+    },
+    DefineSprite {
+        id: CharacterId,
+        num_frames: u16,
+        tags: TagIter<'a>,
+    },
+    DoAbc {
+        name: &'a str,
+        is_lazy_initialize: bool,
+        data: &'a [u8],
+    },
+    DoAction(&'a [u8]),
+    DoInitAction {
+        id: CharacterId,
+        action_data: &'a [u8],
+    },
+    JpegTables(&'a [u8]),
+    SoundStreamBlock(&'a [u8]),
+    VideoFrame {
+        stream_id: CharacterId,
+        frame_num: u16,
+        data: &'a [u8],
+    },
+    Other(Tag),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_swf(name: &str) -> SwfBuf {
+        let data = std::fs::read(format!("tests/swfs/{}", name)).unwrap();
+        crate::decompress_swf(&data[..]).unwrap()
+    }
+
+    #[test]
+    fn lazy_tags_match_read_swf() {
+        for name in &[
+            "DefineSprite.swf",
+            "DefineBitsLossless.swf",
+            "DefineSound.swf",
+        ] {
+            let data = std::fs::read(format!("tests/swfs/{}", name)).unwrap();
+            let swf = crate::read_swf(&data[..]).unwrap();
+            let swf_buf = test_swf(name);
+            let tags: Vec<Tag> = swf_buf
+                .tags()
+                .map(|tag| tag.unwrap())
+                .filter(|tag| tag.code() != Some(TagCode::End))
+                .map(|tag| tag.parse().unwrap())
+                .collect();
+            assert_eq!(tags, swf.tags, "{}", name);
+        }
+    }
+
+    #[test]
+    fn lazy_sprite_tags() {
+        let swf_buf = test_swf("DefineSprite.swf");
+        let tags = swf_buf
+            .tags()
+            .find_map(|tag| match tag.unwrap().parse_lazy().unwrap() {
+                LazyTag::DefineSprite { tags, .. } => Some(tags),
+                _ => None,
+            })
+            .unwrap();
+        let codes: Vec<_> = tags.map(|tag| tag.unwrap().code()).collect();
+        assert!(codes.contains(&Some(TagCode::ShowFrame)));
+        assert_eq!(codes.last(), Some(&Some(TagCode::End)));
+    }
+
+    #[test]
+    fn lazy_bitmap_is_borrowed() {
+        let swf_buf = test_swf("DefineBitsLossless.swf");
+        let start = swf_buf.data.as_ptr() as usize;
+        let end = start + swf_buf.data.len();
+        for tag in swf_buf.tags() {
+            if let LazyTag::DefineBitsLossless { data, .. } = tag.unwrap().parse_lazy().unwrap() {
+                let data_start = data.as_ptr() as usize;
+                assert!(data_start >= start && data_start + data.len() <= end);
+                return;
+            }
+        }
+        panic!("No DefineBitsLossless tag found");
+    }
+
+    #[test]
+    fn truncated_tag() {
+        // DoAction with a length of 10, but only 2 bytes of data.
+        let data = [0b00_001010, 0b11, 0x06, 0x00];
+        let mut tags = TagIter::new(&data, 8);
+        let tag = tags.next().unwrap().unwrap();
+        assert_eq!(tag.code(), Some(TagCode::DoAction));
+        assert_eq!(tag.data, [0x06, 0x00]);
+        assert!(tags.next().is_none());
+    }
+
+    #[test]
+    fn invalid_tag_header() {
+        let data = [0b01_111111, 0, 1];
+        let mut tags = TagIter::new(&data, 8);
+        assert!(tags.next().unwrap().is_err());
+        assert!(tags.next().is_none());
+    }
+
+    #[test]
+    fn stops_after_end() {
+        let data = [0b01_000000, 0, 0, 0, 0b01_000000, 0];
+        let mut tags = TagIter::new(&data, 8);
+        assert_eq!(
+            tags.next().unwrap().unwrap().code(),
+            Some(TagCode::ShowFrame)
+        );
+        assert_eq!(tags.next().unwrap().unwrap().code(), Some(TagCode::End));
+        assert!(tags.next().is_none());
+        assert_eq!(tags.remaining(), [0b01_000000, 0]);
+    }
+}
@@ -20,7 +20,9 @@ extern crate xz2;
 
 pub mod avm1;
 pub mod avm2;
+pub mod compare;
 pub mod error;
+pub mod lazy;
 pub mod read;
 mod tag_code;
 mod types;
@@ -30,7 +32,7 @@ pub mod write;
 mod test_data;
 
 /// Reexports
-pub use read::{read_swf, read_swf_header};
+pub use read::{decompress_swf, read_swf, read_swf_header, read_swf_raw};
 pub use tag_code::TagCode;
 pub use types::*;
-pub use write::write_swf;
+pub use write::{write_swf, write_swf_raw};
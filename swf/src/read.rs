@@ -5,6 +5,8 @@
 )]
 
 use crate::error::{Error, Result};
+use crate::lazy::SwfBuf;
+use crate::tag_code::TagCode;
 use crate::types::*;
 use byteorder::{LittleEndian, ReadBytesExt};
 use enumset::EnumSet;
@@ -30,35 +32,12 @@ pub fn read_swf<R: Read>(input: R) -> Result<Swf> {
     let mut reader = swf_stream.reader;
 
     // Decompress all of SWF into memory at once.
-    let mut data = if header.compression == Compression::Lzma {
-        // TODO: The LZMA decoder is still funky.
-        // It always errors, and doesn't return all the data if you use read_to_end,
-        // but read_exact at least returns the data... why?
-        // Does the decoder need to be flushed somehow?
-        let mut data = vec![0u8; swf_stream.uncompressed_length];
-        let _ = reader.get_mut().read_exact(&mut data);
-        data
-    } else {
-        let mut data = Vec::with_capacity(swf_stream.uncompressed_length);
-        if let Err(e) = reader.get_mut().read_to_end(&mut data) {
-            log::error!("Error decompressing SWF, may be corrupt: {}", e);
-        }
-        data
-    };
-    let version = header.version;
-
-    // Some SWF streams may not be compressed correctly,
-    // (e.g. incorrect data length in the stream), so decompressing
-    // may throw an error even though the data otherwise comes
-    // through the stream.
-    // We'll still try to parse what we get if the full decompression fails.
-    if let Err(e) = reader.get_mut().read_to_end(&mut data) {
-        log::warn!("Error decompressing SWF stream, may be corrupt: {}", e);
-    }
-    if data.len() != swf_stream.uncompressed_length {
-        log::warn!("SWF length doesn't match header, may be corrupt");
-    }
-    let mut reader = Reader::new(&data[..], version);
+    let data = read_decompressed_data(
+        reader.get_mut(),
+        &header.compression,
+        swf_stream.uncompressed_length,
+    );
+    let mut reader = Reader::new(&data[..], header.version);
 
     Ok(Swf {
         header,
@@ -66,18 +45,114 @@ pub fn read_swf<R: Read>(input: R) -> Result<Swf> {
     })
 }
 
-/// Parses an SWF header and returns a `Reader` that can be used
-/// to read the SWF tags inside the SWF file.
+/// Parses an SWF without decoding its tags.
 ///
-/// Returns an `Error` if this is not a valid SWF file.
+/// Use this instead of `read_swf` when the SWF must be written back out exactly as it was,
+/// aside from any tags that are deliberately changed.
 ///
 /// # Example
 /// ```
 /// let data = std::fs::read("tests/swfs/DefineSprite.swf").unwrap();
-/// let swf_stream = swf::read_swf_header(&data[..]).unwrap();
-/// println!("FPS: {}", swf_stream.header.frame_rate);
+/// let swf = swf::read_swf_raw(&data[..]).unwrap();
+/// println!("Number of tags: {}", swf.tags.len());
 /// ```
-pub fn read_swf_header<'a, R: Read + 'a>(mut input: R) -> Result<SwfStream<'a>> {
+pub fn read_swf_raw<R: Read>(input: R) -> Result<RawSwf> {
+    let mut decompressed = decompress_stream(input)?;
+    let data = read_decompressed_data(
+        &mut decompressed.input,
+        &decompressed.compression,
+        decompressed.uncompressed_length,
+    );
+
+    let mut reader = Reader::new(&data[..], decompressed.version);
+    let stage_size = reader.read_rectangle()?;
+    let frame_rate = reader.read_fixed8()?;
+    let num_frames = reader.read_u16()?;
+    let header_len = data.len() - reader.get_ref().len();
+
+    let mut tags = Vec::new();
+    let mut end_tag = None;
+    while !reader.get_ref().is_empty() {
+        let tag_start = data.len() - reader.get_ref().len();
+        let (tag_code, length) = reader.read_tag_code_and_length()?;
+        let data_start = data.len() - reader.get_ref().len();
+        let remaining: &[u8] = *reader.get_ref();
+        let tag_data = remaining
+            .get(..length)
+            .ok_or_else(|| Error::invalid_data("Tag length extends past end of SWF"))?;
+        let tag = RawTag {
+            tag_code,
+            is_long_header: data_start - tag_start > 2,
+            data: tag_data.to_vec(),
+        };
+        *reader.get_mut() = &remaining[length..];
+        if tag_code == TagCode::End as u16 {
+            end_tag = Some(tag);
+            break;
+        }
+        tags.push(tag);
+    }
+
+    Ok(RawSwf {
+        header: Header {
+            version: decompressed.version,
+            compression: decompressed.compression,
+            stage_size,
+            frame_rate,
+            num_frames,
+        },
+        header_data: data[..header_len].to_vec(),
+        tags,
+        end_tag,
+        trailing_data: reader.get_ref().to_vec(),
+    })
+}
+
+/// Decompresses an SWF into memory, so that its tags can be read on demand.
+///
+/// Unlike `read_swf`, no tags are decoded until they are requested through
+/// `SwfBuf::tags`.
+///
+/// # Example
+/// ```
+/// let data = std::fs::read("tests/swfs/DefineSprite.swf").unwrap();
+/// let swf_buf = swf::decompress_swf(&data[..]).unwrap();
+/// for tag in swf_buf.tags() {
+///     let tag = tag.unwrap();
+///     println!("{} ({} bytes)", swf::TagCode::name(tag.tag_code), tag.data.len());
+/// }
+/// ```
+pub fn decompress_swf<R: Read>(input: R) -> Result<SwfBuf> {
+    let swf_stream = read_swf_header(input)?;
+    let header = swf_stream.header;
+    let mut reader = swf_stream.reader;
+    let data = read_decompressed_data(
+        reader.get_mut(),
+        &header.compression,
+        swf_stream.uncompressed_length,
+    );
+    Ok(SwfBuf { header, data })
+}
+
+impl RawTag {
+    /// Decodes this tag.
+    pub fn parse(&self, version: u8) -> Result<Tag> {
+        let mut reader = Reader::new(&self.data[..], version);
+        reader
+            .read_tag_with_code(self.tag_code, self.data.len())
+            .map_err(|e| Error::swf_parse_error_with_source(self.tag_code, e))
+    }
+}
+
+/// The decompressed stream of an SWF file, positioned after the uncompressed length.
+struct DecompressedStream<'a> {
+    compression: Compression,
+    version: u8,
+    uncompressed_length: usize,
+    input: Box<dyn Read + 'a>,
+}
+
+fn decompress_stream<'a, R: Read + 'a>(mut input: R) -> Result<DecompressedStream<'a>> {
     // Read SWF header.
     let compression = Reader::read_compression_type(&mut input)?;
     let version = input.read_u8()?;
@@ -109,7 +184,67 @@ pub fn read_swf_header<'a, R: Read + 'a>(mut input: R) -> Result<SwfStream<'a>>
         }
     };
 
-    let mut reader = Reader::new(decompressed_input, version);
+    Ok(DecompressedStream {
+        compression,
+        version,
+        uncompressed_length: uncompressed_length.try_into().unwrap(),
+        input: decompressed_input,
+    })
+}
+
+/// Reads the rest of a decompressed SWF stream into memory.
+fn read_decompressed_data<R: Read>(
+    input: &mut R,
+    compression: &Compression,
+    uncompressed_length: usize,
+) -> Vec<u8> {
+    let mut data = if *compression == Compression::Lzma {
+        // TODO: The LZMA decoder is still funky.
+        // It always errors, and doesn't return all the data if you use read_to_end,
+        // but read_exact at least returns the data... why?
+        // Does the decoder need to be flushed somehow?
+        let mut data = vec![0u8; uncompressed_length];
+        let _ = input.read_exact(&mut data);
+        data
+    } else {
+        let mut data = Vec::with_capacity(uncompressed_length);
+        if let Err(e) = input.read_to_end(&mut data) {
+            log::error!("Error decompressing SWF, may be corrupt: {}", e);
+        }
+        data
+    };
+
+    // Some SWF streams may not be compressed correctly,
+    // (e.g. incorrect data length in the stream), so decompressing
+    // may throw an error even though the data otherwise comes
+    // through the stream.
+    // We'll still try to parse what we get if the full decompression fails.
+    if let Err(e) = input.read_to_end(&mut data) {
+        log::warn!("Error decompressing SWF stream, may be corrupt: {}", e);
+    }
+    if data.len() != uncompressed_length {
+        log::warn!("SWF length doesn't match header, may be corrupt");
+    }
+    data
+}
+
+/// Parses an SWF header and returns a `Reader` that can be used
+/// to read the SWF tags inside the SWF file.
+///
+/// Returns an `Error` if this is not a valid SWF file.
+///
+/// # Example
+/// ```
+/// let data = std::fs::read("tests/swfs/DefineSprite.swf").unwrap();
+/// let swf_stream = swf::read_swf_header(&data[..]).unwrap();
+/// println!("FPS: {}", swf_stream.header.frame_rate);
+/// ```
+pub fn read_swf_header<'a, R: Read + 'a>(input: R) -> Result<SwfStream<'a>> {
+    let decompressed = decompress_stream(input)?;
+    let version = decompressed.version;
+    let compression = decompressed.compression;
+    let uncompressed_length = decompressed.uncompressed_length;
+    let mut reader = Reader::new(decompressed.input, version);
     let stage_size = reader.read_rectangle()?;
     let frame_rate = reader.read_fixed8()?;
     let num_frames = reader.read_u16()?;
@@ -122,7 +257,7 @@ pub fn read_swf_header<'a, R: Read + 'a>(mut input: R) -> Result<SwfStream<'a>>
     };
     Ok(SwfStream {
         header,
-        uncompressed_length: uncompressed_length.try_into().unwrap(),
+        uncompressed_length,
         reader,
     })
 }
@@ -342,6 +477,11 @@ impl<R: Read> Reader<R> {
         }
     }
 
+    /// Returns the SWF version used to decode tags.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
     /// Returns a reference to the underlying `Reader`.
     pub fn get_ref(&self) -> &R {
         &self.input
@@ -374,9 +514,8 @@ impl<R: Read> Reader<R> {
         tag
     }
 
-    fn read_tag_with_code(&mut self, tag_code: u16, length: usize) -> Result<Tag> {
+    pub(crate) fn read_tag_with_code(&mut self, tag_code: u16, length: usize) -> Result<Tag> {
         let mut tag_reader = Reader::new(self.input.by_ref().take(length as u64), self.version);
-        use crate::tag_code::TagCode;
         let tag = match TagCode::from_u16(tag_code) {
             Some(TagCode::End) => Tag::End,
             Some(TagCode::ShowFrame) => Tag::ShowFrame,
@@ -1104,7 +1243,7 @@ impl<R: Read> Reader<R> {
         ))
     }
 
-    fn read_csm_text_settings(&mut self) -> Result<Tag> {
+    pub fn read_csm_text_settings(&mut self) -> Result<Tag> {
         let id = self.read_character_id()?;
         let flags = self.read_u8()?;
         let thickness = self.read_f32()?;
@@ -1351,7 +1490,7 @@ impl<R: Read> Reader<R> {
         })
     }
 
-    fn read_define_font_align_zones(&mut self) -> Result<Tag> {
+    pub fn read_define_font_align_zones(&mut self) -> Result<Tag> {
         let id = self.read_character_id()?;
         let thickness = match self.read_u8()? {
             0b00_000000 => FontThickness::Thin,
@@ -2569,6 +2708,7 @@ impl<R: Read> Reader<R> {
         }
 
         Ok(Text {
+            version,
             id,
             bounds,
             matrix,
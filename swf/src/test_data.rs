@@ -1881,6 +1881,7 @@ pub fn tag_tests() -> Vec<TagTestData> {
         (
             1,
             Tag::DefineText(Box::new(Text {
+                version: 1,
                 id: 2,
                 bounds: Rectangle {
                     x_min: Twips::from_pixels(1.2),
@@ -22,6 +22,43 @@ pub struct SwfStream<'a> {
     pub reader: crate::read::Reader<Box<dyn std::io::Read + 'a>>,
 }
 
+/// An SWF file whose tags are kept in their original encoded form.
+/// This is returned by the `swf::read_swf_raw` convenience method.
+///
+/// Writing a `RawSwf` with `swf::write_swf_raw` reproduces the uncompressed SWF data
+/// byte-for-byte, including the bit widths and padding chosen by the tool that created it.
+/// Tags can be removed, or replaced using `RawTag::from_tag`, without disturbing the rest.
+#[derive(Debug, PartialEq)]
+pub struct RawSwf {
+    pub header: Header,
+
+    /// The encoded stage size, frame rate and frame count as they appear in the file.
+    /// These are written instead of re-encoding `header`, unless `header` has been changed.
+    pub header_data: Vec<u8>,
+
+    /// The tags of the main timeline, not including the final `End` tag.
+    pub tags: Vec<RawTag>,
+
+    /// The `End` tag that closes the main timeline. Some tools leave it out at the end of
+    /// the file, in which case this is `None` and it's left out when writing.
+    pub end_tag: Option<RawTag>,
+
+    /// Any data after the `End` tag, such as padding, which is written back unchanged.
+    pub trailing_data: Vec<u8>,
+}
+
+/// An undecoded SWF tag.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RawTag {
+    pub tag_code: u16,
+
+    /// Whether the tag was written with a long header, even if its length would have fit
+    /// in a short header.
+    pub is_long_header: bool,
+
+    pub data: Vec<u8>,
+}
+
 /// The header of an SWF file.
 ///
 /// Notably contains the compression format used by the rest of the SWF data.
@@ -946,6 +983,8 @@ pub struct FontInfo {
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Text {
+    /// 1 for `DefineText`, 2 for `DefineText2`, which has alpha in its text colors.
+    pub version: u8,
     pub id: CharacterId,
     pub bounds: Rectangle,
     pub matrix: Matrix,
@@ -34,15 +34,7 @@ use std::io::{self, Write};
 /// let output = Vec::new();
 /// swf::write_swf(&swf, output).unwrap();
 /// ```
-pub fn write_swf<W: Write>(swf: &Swf, mut output: W) -> Result<()> {
-    let signature = match swf.header.compression {
-        Compression::None => b"FWS",
-        Compression::Zlib => b"CWS",
-        Compression::Lzma => b"ZWS",
-    };
-    output.write_all(&signature[..])?;
-    output.write_u8(swf.header.version)?;
-
+pub fn write_swf<W: Write>(swf: &Swf, output: W) -> Result<()> {
     // Write SWF body.
     let mut swf_body = Vec::new();
     {
@@ -56,28 +48,103 @@ pub fn write_swf<W: Write>(swf: &Swf, mut output: W) -> Result<()> {
         writer.write_tag_list(&swf.tags)?;
     }
 
-    // Write SWF header.
+    write_swf_body(&swf.header, &swf_body, output)
+}
+
+/// Writes an SWF file read by `swf::read_swf_raw` to an output stream.
+///
+/// The uncompressed SWF data is identical to the original file, except for any changes
+/// made to the header or tags.
+///
+/// # Example
+/// ```
+/// let data = std::fs::read("tests/swfs/DefineSprite.swf").unwrap();
+/// let mut swf = swf::read_swf_raw(&data[..]).unwrap();
+/// // Strip any metadata.
+/// swf.tags.retain(|tag| tag.tag_code != swf::TagCode::Metadata as u16);
+/// let mut output = Vec::new();
+/// swf::write_swf_raw(&swf, &mut output).unwrap();
+/// ```
+pub fn write_swf_raw<W: Write>(swf: &RawSwf, output: W) -> Result<()> {
+    let mut swf_body = Vec::new();
+    {
+        let mut writer = Writer::new(&mut swf_body, swf.header.version);
+
+        // Keep the original encoding of the header, unless it has been changed.
+        if is_header_data_current(swf) {
+            writer.output.write_all(&swf.header_data)?;
+        } else {
+            writer.write_rectangle(&swf.header.stage_size)?;
+            writer.write_fixed8(swf.header.frame_rate)?;
+            writer.write_u16(swf.header.num_frames)?;
+        }
+
+        for tag in swf.tags.iter().chain(&swf.end_tag) {
+            writer.write_raw_tag(tag)?;
+        }
+        writer.output.write_all(&swf.trailing_data)?;
+    }
+
+    write_swf_body(&swf.header, &swf_body, output)
+}
+
+/// Returns whether `header_data` still encodes the values in `header`.
+fn is_header_data_current(swf: &RawSwf) -> bool {
+    use crate::read::SwfRead;
+    let mut reader = crate::read::Reader::new(&swf.header_data[..], swf.header.version);
+    reader.read_rectangle().ok().as_ref() == Some(&swf.header.stage_size)
+        && reader.read_fixed8().ok() == Some(swf.header.frame_rate)
+        && reader.read_u16().ok() == Some(swf.header.num_frames)
+        && reader.get_ref().is_empty()
+}
+
+/// Writes the SWF header followed by the SWF body, compressed as specified by the header.
+fn write_swf_body<W: Write>(header: &Header, swf_body: &[u8], mut output: W) -> Result<()> {
+    let signature = match header.compression {
+        Compression::None => b"FWS",
+        Compression::Zlib => b"CWS",
+        Compression::Lzma => b"ZWS",
+    };
+    output.write_all(&signature[..])?;
+    output.write_u8(header.version)?;
+
     // Uncompressed SWF length.
     output.write_u32::<LittleEndian>(swf_body.len() as u32 + 8)?;
 
     // Compress SWF body.
-    match swf.header.compression {
+    match header.compression {
         Compression::None => {
-            output.write_all(&swf_body)?;
+            output.write_all(swf_body)?;
         }
 
-        Compression::Zlib => write_zlib_swf(&mut output, &swf_body)?,
+        Compression::Zlib => write_zlib_swf(&mut output, swf_body)?,
 
         // LZMA header.
         // SWF format has a mangled LZMA header, so we have to do some magic to conver the
         // standard LZMA header to SWF format.
         // https://adobe.ly/2s8oYzn
-        Compression::Lzma => write_lzma_swf(&mut output, &swf_body)?,
+        Compression::Lzma => write_lzma_swf(&mut output, swf_body)?,
     };
 
     Ok(())
 }
 
+impl RawTag {
+    /// Encodes a tag, so that it can replace a tag in a `RawSwf`.
+    pub fn from_tag(tag: &Tag, version: u8) -> Result<RawTag> {
+        let mut buf = Vec::new();
+        Writer::new(&mut buf, version).write_tag(tag)?;
+        let mut reader = crate::read::Reader::new(&buf[..], version);
+        let (tag_code, length) = reader.read_tag_code_and_length()?;
+        let data = reader.get_ref().to_vec();
+        Ok(RawTag {
+            tag_code,
+            is_long_header: buf.len() - data.len() > 2,
+            data: data[..length].to_vec(),
+        })
+    }
+}
+
 #[cfg(feature = "flate2")]
 fn write_zlib_swf<W: Write>(mut output: W, swf_body: &[u8]) -> Result<()> {
     use flate2::write::ZlibEncoder;
@@ -303,7 +370,7 @@ impl<W: Write> Writer<W> {
     }
 
     fn write_fbits(&mut self, num_bits: u8, n: f32) -> Result<()> {
-        self.write_ubits(num_bits, (n * 65536f32) as u32)
+        self.write_ubits(num_bits, (n * 65536f32) as i32 as u32)
     }
 
     fn write_encoded_u32(&mut self, mut n: u32) -> Result<()> {
@@ -570,16 +637,21 @@ impl<W: Write> Writer<W> {
             }
 
             Tag::DefineBitsJpeg3(ref jpeg) => {
+                let (tag_code, header_len) = if jpeg.version >= 4 {
+                    (TagCode::DefineBitsJpeg4, 8)
+                } else {
+                    (TagCode::DefineBitsJpeg3, 6)
+                };
                 self.write_tag_header(
-                    TagCode::DefineBitsJpeg3,
-                    (jpeg.data.len() + jpeg.alpha_data.len() + 6) as u32,
+                    tag_code,
+                    (jpeg.data.len() + jpeg.alpha_data.len() + header_len) as u32,
                 )?;
                 self.write_u16(jpeg.id)?;
+                self.write_u32(jpeg.data.len() as u32)?;
+                // TODO(Herschel): Verify deblocking parameter is zero in version 3.
                 if jpeg.version >= 4 {
                     self.write_fixed8(jpeg.deblocking)?;
                 }
-                // TODO(Herschel): Verify deblocking parameter is zero in version 3.
-                self.write_u32(jpeg.data.len() as u32)?;
                 self.output.write_all(&jpeg.data)?;
                 self.output.write_all(&jpeg.alpha_data)?;
             }
@@ -1056,8 +1128,11 @@ impl<W: Write> Writer<W> {
                 writer.write_button_record(record, 1)?;
             }
             writer.write_u8(0)?; // End button records
-                                 // TODO: Assert we have some action.
-            writer.output.write_all(&button.actions[0].action_data)?;
+            if let Some(action) = button.actions.first() {
+                writer.output.write_all(&action.action_data)?;
+            } else {
+                writer.write_u8(0)?; // End action
+            }
         }
         self.write_tag_header(TagCode::DefineButton, buf.len() as u32)?;
         self.output.write_all(&buf)?;
@@ -2535,7 +2610,11 @@ impl<W: Write> Writer<W> {
                     writer.write_character_id(id)?;
                 }
                 if let Some(ref color) = record.color {
-                    writer.write_rgb(color)?;
+                    if text.version == 1 {
+                        writer.write_rgb(color)?;
+                    } else {
+                        writer.write_rgba(color)?;
+                    }
                 }
                 if let Some(x) = record.x_offset {
                     writer.write_i16(x.get() as i16)?; // TODO(Herschel): Handle overflow.
@@ -2554,7 +2633,12 @@ impl<W: Write> Writer<W> {
             }
             writer.write_u8(0)?; // End of text records.
         }
-        self.write_tag_header(TagCode::DefineText, buf.len() as u32)?;
+        let tag_code = if text.version == 1 {
+            TagCode::DefineText
+        } else {
+            TagCode::DefineText2
+        };
+        self.write_tag_header(tag_code, buf.len() as u32)?;
         self.output.write_all(&buf)?;
         Ok(())
     }
@@ -2682,6 +2766,7 @@ impl<W: Write> Writer<W> {
     }
 
     fn write_debug_id(&mut self, debug_id: &DebugId) -> Result<()> {
+        self.write_tag_header(TagCode::DebugId, debug_id.len() as u32)?;
         self.get_inner().write_all(debug_id)?;
         Ok(())
     }
@@ -2704,6 +2789,18 @@ impl<W: Write> Writer<W> {
         Ok(())
     }
 
+    fn write_raw_tag(&mut self, tag: &RawTag) -> Result<()> {
+        let length = tag.data.len() as u32;
+        if tag.is_long_header && length < 0b111111 {
+            self.write_u16(tag.tag_code << 6 | 0b111111)?;
+            self.write_u32(length)?;
+        } else {
+            self.write_tag_code_and_length(tag.tag_code, length)?;
+        }
+        self.output.write_all(&tag.data)?;
+        Ok(())
+    }
+
     fn write_tag_list(&mut self, tags: &[Tag]) -> Result<()> {
         // TODO: Better error handling. Can skip errored tags, unless EOF.
         for tag in tags {
@@ -2802,6 +2899,125 @@ mod tests {
         }
     }
 
+    /// Returns the names and contents of the test SWFs that can be read with the enabled
+    /// features.
+    fn test_swfs() -> Vec<(String, Vec<u8>)> {
+        let mut swfs = vec![];
+        for entry in std::fs::read_dir("tests/swfs").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|e| e.to_str()) != Some("swf") {
+                continue;
+            }
+            let data = std::fs::read(&path).unwrap();
+            if data.starts_with(b"ZWS") && !cfg!(feature = "lzma") {
+                continue;
+            }
+            swfs.push((path.display().to_string(), data));
+        }
+        swfs
+    }
+
+    #[test]
+    fn round_trip_swfs() {
+        for (name, data) in test_swfs() {
+            let swf = crate::read_swf(&data[..]).unwrap();
+            let mut buf = Vec::new();
+            write_swf(&swf, &mut buf).unwrap();
+            let round_tripped = crate::read_swf(&buf[..]).unwrap();
+            let differences = crate::compare::differences(&swf, &round_tripped, false);
+            assert!(
+                differences.is_empty(),
+                "{} changed when written:\n{}",
+                name,
+                differences.join("\n")
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip_raw_swfs() {
+        for (name, data) in test_swfs() {
+            let mut swf = crate::read_swf_raw(&data[..]).unwrap();
+            swf.header.compression = Compression::None;
+            let mut buf = Vec::new();
+            write_swf_raw(&swf, &mut buf).unwrap();
+            let round_tripped = crate::read_swf_raw(&buf[..]).unwrap();
+            assert_eq!(swf, round_tripped, "{} changed when written", name);
+        }
+    }
+
+    #[test]
+    fn round_trip_raw_tags() {
+        for (name, data) in test_swfs() {
+            let swf = crate::read_swf_raw(&data[..]).unwrap();
+            let version = swf.header.version;
+            for raw_tag in &swf.tags {
+                let tag = raw_tag.parse(version).unwrap();
+                let round_tripped = RawTag::from_tag(&tag, version)
+                    .unwrap()
+                    .parse(version)
+                    .unwrap();
+                assert_eq!(
+                    tag,
+                    round_tripped,
+                    "{}: {} changed when written",
+                    name,
+                    TagCode::name(raw_tag.tag_code)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn write_raw_swf_with_changed_header() {
+        let data = std::fs::read("tests/swfs/DefineSprite.swf").unwrap();
+        let mut swf = crate::read_swf_raw(&data[..]).unwrap();
+        swf.header.num_frames += 1;
+        let mut buf = Vec::new();
+        write_swf_raw(&swf, &mut buf).unwrap();
+        let round_tripped = crate::read_swf_raw(&buf[..]).unwrap();
+        assert_eq!(round_tripped.header.num_frames, swf.header.num_frames);
+        assert_eq!(round_tripped.tags, swf.tags);
+    }
+
+    #[test]
+    fn write_raw_swf_with_trailing_data() {
+        let data = std::fs::read("tests/swfs/DefineSprite.swf").unwrap();
+        let mut swf = crate::read_swf_raw(&data[..]).unwrap();
+        swf.header.compression = Compression::None;
+        swf.trailing_data = vec![0, 0, 0xff];
+        let mut buf = Vec::new();
+        write_swf_raw(&swf, &mut buf).unwrap();
+        // The `End` tag, followed by the trailing data.
+        assert!(buf.ends_with(&[0, 0, 0, 0, 0xff]));
+        let round_tripped = crate::read_swf_raw(&buf[..]).unwrap();
+        assert_eq!(round_tripped, swf);
+    }
+
+    #[test]
+    fn write_raw_swf_without_end_tag() {
+        let data = std::fs::read("tests/swfs/DefineSprite.swf").unwrap();
+        let mut swf = crate::read_swf_raw(&data[..]).unwrap();
+        swf.header.compression = Compression::None;
+        swf.end_tag = None;
+        let mut buf = Vec::new();
+        write_swf_raw(&swf, &mut buf).unwrap();
+        let round_tripped = crate::read_swf_raw(&buf[..]).unwrap();
+        assert_eq!(round_tripped, swf);
+    }
+
+    #[test]
+    fn write_raw_tag_long_header() {
+        let tag = RawTag {
+            tag_code: TagCode::ShowFrame as u16,
+            is_long_header: true,
+            data: vec![],
+        };
+        let mut buf = Vec::new();
+        Writer::new(&mut buf, 8).write_raw_tag(&tag).unwrap();
+        assert_eq!(buf, [0b01_111111, 0, 0, 0, 0, 0]);
+    }
+
     #[test]
     fn write_fixed8() {
         let mut buf = Vec::new();
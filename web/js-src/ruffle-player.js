@@ -16,6 +16,7 @@ export class RufflePlayer extends HTMLElement {
         self.dynamic_styles = self.shadow.getElementById("dynamic_styles");
         self.canvas = self.shadow.getElementById("player");
         self.instance = null;
+        self.device_fonts = [];
 
         self.Ruffle = load_ruffle();
 
@@ -77,6 +78,19 @@ export class RufflePlayer extends HTMLElement {
         return false;
     }
 
+    /**
+     * Loads a TrueType or OpenType font file to render text in fonts that
+     * aren't embedded in the movie. The font is kept for any movie that
+     * this player plays later.
+     */
+    add_device_font(data) {
+        let font_data = new Uint8Array(data);
+        this.device_fonts.push(font_data);
+        if (this.instance) {
+            this.instance.add_device_font(font_data);
+        }
+    }
+
     async stream_swf_url(url) {
         //TODO: Actually stream files...
         try {
@@ -117,6 +131,9 @@ export class RufflePlayer extends HTMLElement {
             });
             
             this.instance = Ruffle.new(this.canvas, new Uint8Array(data));
+            for (let font_data of this.device_fonts) {
+                this.instance.add_device_font(font_data);
+            }
         } else {
             console.warn("Ignoring attempt to play a disconnected or suspended Ruffle element");
         }
@@ -0,0 +1,127 @@
+//! The context menu, shown as an HTML overlay over the page.
+use ruffle_core::context_menu::ContextMenuItem;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Document, Element, HtmlElement, MouseEvent};
+
+/// The attribute holding the index of the item that an element picks.
+const INDEX_ATTRIBUTE: &str = "data-index";
+
+/// The styles of the menu, scoped to its class names.
+const STYLE: &str = "
+.ruffle-context-menu {
+    position: fixed;
+    z-index: 2147483647;
+    min-width: 120px;
+    padding: 3px 0;
+    background: #f0f0f0;
+    border: 1px solid #808080;
+    box-shadow: 2px 2px 4px rgba(0, 0, 0, 0.3);
+    color: #000;
+    font: 12px sans-serif;
+    cursor: default;
+    user-select: none;
+}
+.ruffle-context-menu-item {
+    position: relative;
+    margin: 0 2px;
+    padding: 4px 16px 4px 22px;
+    white-space: nowrap;
+}
+.ruffle-context-menu-item:hover {
+    background: #3399ff;
+    color: #fff;
+}
+.ruffle-context-menu-item.disabled {
+    color: #8c8c8c;
+    background: none;
+}
+.ruffle-context-menu-item.checked::before {
+    content: \"\\2713\";
+    position: absolute;
+    left: 6px;
+}
+.ruffle-context-menu-separator {
+    height: 1px;
+    margin: 4px 2px;
+    background: #c8c8c8;
+}";
+
+/// Shows a menu with the given items at a position in the window, moving it so that it stays
+/// inside the window. Returns the element of the menu.
+pub fn show_menu(
+    document: &Document,
+    items: &[ContextMenuItem],
+    x: i32,
+    y: i32,
+) -> Result<HtmlElement, JsValue> {
+    let menu = create_element(document, "div", "ruffle-context-menu")?;
+    let style = document.create_element("style")?;
+    style.set_text_content(Some(STYLE));
+    menu.append_child(&style)?;
+
+    for (i, item) in items.iter().enumerate() {
+        if item.separator_before && i > 0 {
+            let separator = create_element(document, "div", "ruffle-context-menu-separator")?;
+            menu.append_child(&separator)?;
+        }
+
+        let mut class_name = String::from("ruffle-context-menu-item");
+        if !item.enabled {
+            class_name.push_str(" disabled");
+        }
+        if item.checked {
+            class_name.push_str(" checked");
+        }
+        let element = create_element(document, "div", &class_name)?;
+        element.set_text_content(Some(&item.caption));
+        if item.enabled {
+            element.set_attribute(INDEX_ATTRIBUTE, &i.to_string())?;
+        }
+        menu.append_child(&element)?;
+    }
+
+    let body = document.body().ok_or("Expected body")?;
+    body.append_child(&menu)?;
+
+    // Open towards the top-left if the menu doesn't fit below and to the right.
+    let (window_width, window_height) = match document.document_element() {
+        Some(root) => (root.client_width(), root.client_height()),
+        None => (std::i32::MAX, std::i32::MAX),
+    };
+    let (width, height) = (menu.offset_width(), menu.offset_height());
+    let left = if x + width > window_width {
+        x - width
+    } else {
+        x
+    };
+    let top = if y + height > window_height {
+        y - height
+    } else {
+        y
+    };
+    let style = menu.style();
+    style.set_property("left", &format!("{}px", left.max(0)))?;
+    style.set_property("top", &format!("{}px", top.max(0)))?;
+
+    Ok(menu)
+}
+
+/// Returns the index of the item that was clicked in a menu, if it can be picked.
+pub fn clicked_item(event: &MouseEvent) -> Option<usize> {
+    let target = event.target()?;
+    let element = target
+        .dyn_ref::<Element>()?
+        .closest(&format!("[{}]", INDEX_ATTRIBUTE))
+        .ok()??;
+    element.get_attribute(INDEX_ATTRIBUTE)?.parse().ok()
+}
+
+fn create_element(
+    document: &Document,
+    tag: &str,
+    class_name: &str,
+) -> Result<HtmlElement, JsValue> {
+    let element: HtmlElement = document.create_element(tag)?.unchecked_into();
+    element.set_class_name(class_name);
+    Ok(element)
+}
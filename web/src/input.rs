@@ -1,37 +1,105 @@
 use crate::utils::JsResult;
-use ruffle_core::backend::input::InputBackend;
-use ruffle_core::events::KeyCode;
+use ruffle_core::backend::input::{InputBackend, MouseCursor};
+use ruffle_core::events::{key_code_to_char, KeyCode, MouseButton};
 use std::collections::HashSet;
-use web_sys::HtmlCanvasElement;
+use web_sys::{HtmlCanvasElement, KeyboardEvent, WheelEvent};
+
+/// The number of lines that one notch of the mouse wheel scrolls, as reported to Flash content.
+const LINES_PER_NOTCH: f64 = 3.0;
+
+/// The number of pixels that browsers typically scroll for one notch of the mouse wheel.
+const PIXELS_PER_NOTCH: f64 = 100.0;
+
+/// The number of lines in a page, for wheel events that scroll by pages.
+const LINES_PER_PAGE: f64 = 20.0;
 
 /// An implementation of `InputBackend` utilizing `web_sys` bindings to input
 /// APIs
 pub struct WebInputBackend {
     keys_down: HashSet<String>,
+    mouse_buttons_down: HashSet<MouseButton>,
+    toggled_keys: HashSet<KeyCode>,
     canvas: HtmlCanvasElement,
     cursor_visible: bool,
+    cursor: MouseCursor,
     last_key: KeyCode,
+    last_key_char: Option<char>,
 }
 
 impl WebInputBackend {
     pub fn new(canvas: &HtmlCanvasElement) -> Self {
         Self {
             keys_down: HashSet::new(),
+            mouse_buttons_down: HashSet::new(),
+            toggled_keys: HashSet::new(),
             canvas: canvas.clone(),
             cursor_visible: true,
+            cursor: MouseCursor::Arrow,
             last_key: KeyCode::Unknown,
+            last_key_char: None,
         }
     }
 
-    /// Register a key press for a given code string.
-    pub fn keydown(&mut self, code: String) {
-        self.last_key = web_to_ruffle_key_code(&code).unwrap_or_else(|| KeyCode::Unknown);
+    /// Register a key press for a given code string, and the character it typed, if any.
+    pub fn keydown(&mut self, code: String, key_char: Option<char>) {
+        self.set_last_key(&code, key_char);
         self.keys_down.insert(code);
     }
 
     /// Register a key release for a given code string.
-    pub fn keyup(&mut self, code: String) {
-        self.keys_down.remove(&code);
+    /// Returns `false` if the key wasn't down, e.g. because it was pressed before the player
+    /// had focus.
+    pub fn keyup(&mut self, code: String, key_char: Option<char>) -> bool {
+        self.set_last_key(&code, key_char);
+        self.keys_down.remove(&code)
+    }
+
+    /// Records whether a mouse button is held, for `Key.isDown`.
+    pub fn set_mouse_button_down(&mut self, button: MouseButton, down: bool) {
+        if down {
+            self.mouse_buttons_down.insert(button);
+        } else {
+            self.mouse_buttons_down.remove(&button);
+        }
+    }
+
+    /// Updates the state of the lock keys from a keyboard event.
+    pub fn update_toggled_keys(&mut self, event: &KeyboardEvent) {
+        for &(key_code, name) in &[
+            (KeyCode::CapsLock, "CapsLock"),
+            (KeyCode::NumLock, "NumLock"),
+            (KeyCode::ScrollLock, "ScrollLock"),
+        ] {
+            if event.get_modifier_state(name) {
+                self.toggled_keys.insert(key_code);
+            } else {
+                self.toggled_keys.remove(&key_code);
+            }
+        }
+    }
+
+    /// Sets the CSS cursor of the canvas to the current cursor, or hides it.
+    fn update_cursor(&self) {
+        let cursor = if !self.cursor_visible {
+            "none"
+        } else {
+            match self.cursor {
+                MouseCursor::Arrow => "auto",
+                MouseCursor::Hand => "pointer",
+                MouseCursor::IBeam => "text",
+            }
+        };
+        self.canvas
+            .style()
+            .set_property("cursor", cursor)
+            .warn_on_error();
+    }
+
+    /// Records the last key that was pressed or released, for `Key.getCode` and `Key.getAscii`.
+    fn set_last_key(&mut self, code: &str, key_char: Option<char>) {
+        self.last_key = web_to_ruffle_key_code(code).unwrap_or(KeyCode::Unknown);
+        // Control keys such as Enter don't have a printable `KeyboardEvent.key`.
+        self.last_key_char = key_char.or_else(|| key_code_to_char(self.last_key, false));
     }
 }
 
@@ -39,8 +107,12 @@ impl InputBackend for WebInputBackend {
     fn is_key_down(&self, key: KeyCode) -> bool {
         match key {
             KeyCode::Unknown => false,
+            KeyCode::MouseLeft => self.mouse_buttons_down.contains(&MouseButton::Left),
+            KeyCode::MouseRight => self.mouse_buttons_down.contains(&MouseButton::Right),
+            KeyCode::MouseMiddle => self.mouse_buttons_down.contains(&MouseButton::Middle),
             KeyCode::Backspace => self.keys_down.contains("Backspace"),
-            KeyCode::Return => self.keys_down.contains("Return"),
+            KeyCode::Tab => self.keys_down.contains("Tab"),
+            KeyCode::Return => self.keys_down.contains("Enter"),
             KeyCode::Shift => {
                 self.keys_down.contains("ShiftLeft") || self.keys_down.contains("ShiftRight")
             }
@@ -126,6 +198,7 @@ impl InputBackend for WebInputBackend {
             KeyCode::Insert => self.keys_down.contains("Insert"),
             KeyCode::Delete => self.keys_down.contains("Delete"),
             KeyCode::Pause => self.keys_down.contains("Pause"),
+            KeyCode::NumLock => self.keys_down.contains("NumLock"),
             KeyCode::ScrollLock => self.keys_down.contains("ScrollLock"),
             KeyCode::F1 => self.keys_down.contains("F1"),
             KeyCode::F2 => self.keys_down.contains("F2"),
@@ -142,28 +215,35 @@ impl InputBackend for WebInputBackend {
         }
     }
 
+    fn is_key_toggled(&self, key: KeyCode) -> bool {
+        self.toggled_keys.contains(&key)
+    }
+
     fn get_last_key_code(&self) -> KeyCode {
         self.last_key
     }
 
+    fn get_last_key_char(&self) -> Option<char> {
+        self.last_key_char
+    }
+
     fn mouse_visible(&self) -> bool {
         self.cursor_visible
     }
 
     fn hide_mouse(&mut self) {
-        self.canvas
-            .style()
-            .set_property("cursor", "none")
-            .warn_on_error();
         self.cursor_visible = false;
+        self.update_cursor();
     }
 
     fn show_mouse(&mut self) {
-        self.canvas
-            .style()
-            .set_property("cursor", "auto")
-            .warn_on_error();
         self.cursor_visible = true;
+        self.update_cursor();
+    }
+
+    fn set_mouse_cursor(&mut self, cursor: MouseCursor) {
+        self.cursor = cursor;
+        self.update_cursor();
     }
 }
 
@@ -172,6 +252,7 @@ impl InputBackend for WebInputBackend {
 pub fn web_to_ruffle_key_code(key_code: &str) -> Option<KeyCode> {
     let out = match key_code {
         "Backspace" => KeyCode::Backspace,
+        "Tab" => KeyCode::Tab,
         "Enter" => KeyCode::Return,
         "ShiftLeft" | "ShiftRight" => KeyCode::Shift,
         "ControlLeft" | "ControlRight" => KeyCode::Control,
@@ -252,6 +333,7 @@ pub fn web_to_ruffle_key_code(key_code: &str) -> Option<KeyCode> {
         "Insert" => KeyCode::Insert,
         "Delete" => KeyCode::Delete,
         "Pause" => KeyCode::Pause,
+        "NumLock" => KeyCode::NumLock,
         "ScrollLock" => KeyCode::ScrollLock,
         "F1" => KeyCode::F1,
         "F2" => KeyCode::F2,
@@ -270,6 +352,30 @@ pub fn web_to_ruffle_key_code(key_code: &str) -> Option<KeyCode> {
     Some(out)
 }
 
+/// Converts a Web `MouseEvent.button` value into a Ruffle `MouseButton`.
+/// Returns `None` for extra buttons that Flash doesn't support.
+pub fn web_to_ruffle_mouse_button(button: i16) -> Option<MouseButton> {
+    match button {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    }
+}
+
+/// Converts the vertical delta of a `WheelEvent` into the number of lines scrolled, as
+/// reported by `Mouse.onMouseWheel`.
+pub fn web_to_ruffle_wheel_delta(event: &WheelEvent) -> f64 {
+    let lines = match event.delta_mode() {
+        WheelEvent::DOM_DELTA_LINE => event.delta_y(),
+        WheelEvent::DOM_DELTA_PAGE => event.delta_y() * LINES_PER_PAGE,
+        _ => event.delta_y() / PIXELS_PER_NOTCH * LINES_PER_NOTCH,
+    };
+    // Web deltas are positive when scrolling down, and Flash deltas are positive when
+    // scrolling up.
+    -lines
+}
+
 /// Converts a Web `KeyboardEvent.key` value into a character codepoint.
 /// Returns `None` if they input was not a printable character.
 pub fn web_key_to_codepoint(key: &str) -> Option<char> {
@@ -1,5 +1,6 @@
 //! Ruffle web frontend.
 mod audio;
+mod context_menu;
 mod input;
 mod navigator;
 mod render;
@@ -14,7 +15,10 @@ use js_sys::Uint8Array;
 use ruffle_core::{backend::render::RenderBackend, PlayerEvent};
 use std::{cell::RefCell, error::Error, num::NonZeroI32};
 use wasm_bindgen::{prelude::*, JsCast, JsValue};
-use web_sys::{Element, EventTarget, HtmlCanvasElement, KeyboardEvent, PointerEvent};
+use web_sys::{
+    Element, EventTarget, HtmlCanvasElement, HtmlElement, KeyboardEvent, MouseEvent, Node,
+    PointerEvent, WheelEvent,
+};
 
 thread_local! {
     /// We store the actual instances of the ruffle core in a static pool.
@@ -43,12 +47,26 @@ struct RuffleInstance {
     mouse_move_callback: Option<Closure<dyn FnMut(PointerEvent)>>,
     mouse_down_callback: Option<Closure<dyn FnMut(PointerEvent)>>,
     mouse_up_callback: Option<Closure<dyn FnMut(PointerEvent)>>,
+    mouse_wheel_callback: Option<Closure<dyn FnMut(WheelEvent)>>,
     window_mouse_down_callback: Option<Closure<dyn FnMut(PointerEvent)>>,
     key_down_callback: Option<Closure<dyn FnMut(KeyboardEvent)>>,
     key_up_callback: Option<Closure<dyn FnMut(KeyboardEvent)>>,
+    context_menu_callback: Option<Closure<dyn FnMut(MouseEvent)>>,
+    context_menu_click_callback: Option<Closure<dyn FnMut(MouseEvent)>>,
+    context_menu: Option<HtmlElement>, // The context menu shown over the page, if it's open.
     has_focus: bool,
 }
 
+impl RuffleInstance {
+    /// Closes the context menu without picking an item, if it's open.
+    fn close_context_menu(&mut self) {
+        if let Some(menu) = self.context_menu.take() {
+            menu.remove();
+            self.core.clear_context_menu();
+        }
+    }
+}
+
 /// An opaque handle to a `RuffleInstance` inside the pool.
 ///
 /// This type is exported to JS, and is used to interact with the library.
@@ -79,6 +97,43 @@ impl Ruffle {
         // Player is dropped at this point.
         Ok(())
     }
+
+    /// Loads a TrueType or OpenType font file to render device fonts with, returning the family
+    /// names of the fonts in it. Without any, device fonts use the built-in font.
+    pub fn add_device_font(&mut self, font_data: Uint8Array) -> Result<js_sys::Array, JsValue> {
+        let mut data = vec![0; font_data.length() as usize];
+        font_data.copy_to(&mut data[..]);
+
+        INSTANCES.with(|instances| {
+            let mut instances = instances.borrow_mut();
+            let instance = instances
+                .get_mut(self.0)
+                .ok_or("Player has been destroyed")?;
+            let families = instance
+                .core
+                .add_device_font(data)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            Ok(families
+                .iter()
+                .map(|family| JsValue::from_str(family))
+                .collect())
+        })
+    }
+
+    /// Maps a font name, such as `_sans`, `_serif` or `_typewriter`, to a family loaded with
+    /// `add_device_font`.
+    pub fn map_device_font(&mut self, name: &str, family: &str) -> Result<(), JsValue> {
+        INSTANCES.with(|instances| {
+            let mut instances = instances.borrow_mut();
+            let instance = instances
+                .get_mut(self.0)
+                .ok_or("Player has been destroyed")?;
+            instance
+                .core
+                .map_device_font(name, family)
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        })
+    }
 }
 
 impl Ruffle {
@@ -98,9 +153,7 @@ impl Ruffle {
         let navigator = WebNavigatorBackend::new();
         let input = WebInputBackend::new(&canvas);
 
-        let mut core = ruffle_core::Player::new(renderer, audio, navigator, input, data)?;
-        let frame_rate = core.frame_rate();
-        core.audio_mut().set_frame_rate(frame_rate);
+        let core = ruffle_core::Player::new(renderer, audio, navigator, input, data)?;
         // Create instance.
         let instance = RuffleInstance {
             core,
@@ -114,8 +167,12 @@ impl Ruffle {
             mouse_down_callback: None,
             window_mouse_down_callback: None,
             mouse_up_callback: None,
+            mouse_wheel_callback: None,
             key_down_callback: None,
             key_up_callback: None,
+            context_menu_callback: None,
+            context_menu_click_callback: None,
+            context_menu: None,
             timestamp: None,
             has_focus: false,
         };
@@ -181,11 +238,20 @@ impl Ruffle {
                                     .unchecked_ref::<Element>()
                                     .set_pointer_capture(js_event.pointer_id());
                             }
-                            let event = PlayerEvent::MouseDown {
-                                x: f64::from(js_event.offset_x()) * instance.device_pixel_ratio,
-                                y: f64::from(js_event.offset_y()) * instance.device_pixel_ratio,
-                            };
-                            instance.core.handle_event(event);
+                            if let Some(button) =
+                                input::web_to_ruffle_mouse_button(js_event.button())
+                            {
+                                instance
+                                    .core
+                                    .input_mut()
+                                    .set_mouse_button_down(button, true);
+                                let event = PlayerEvent::MouseDown {
+                                    x: f64::from(js_event.offset_x()) * instance.device_pixel_ratio,
+                                    y: f64::from(js_event.offset_y()) * instance.device_pixel_ratio,
+                                    button,
+                                };
+                                instance.core.handle_event(event);
+                            }
                             js_event.prevent_default();
                         }
                     });
@@ -205,13 +271,25 @@ impl Ruffle {
             // Create window mouse down handler.
             {
                 let window_mouse_down_callback =
-                    Closure::wrap(Box::new(move |_js_event: PointerEvent| {
+                    Closure::wrap(Box::new(move |js_event: PointerEvent| {
                         INSTANCES.with(|instances| {
                             let mut instances = instances.borrow_mut();
                             if let Some(instance) = instances.get_mut(index) {
                                 // If we actually clicked on the canvas, this will be reset to true
                                 // after the event bubbles down to the canvas.
                                 instance.has_focus = false;
+
+                                // Clicking outside of the context menu closes it.
+                                let in_context_menu =
+                                    match (&instance.context_menu, js_event.target()) {
+                                        (Some(menu), Some(target)) => {
+                                            menu.contains(target.dyn_ref::<Node>())
+                                        }
+                                        _ => false,
+                                    };
+                                if !in_context_menu {
+                                    instance.close_context_menu();
+                                }
                             }
                         });
                     }) as Box<dyn FnMut(PointerEvent)>);
@@ -238,11 +316,20 @@ impl Ruffle {
                                     .unchecked_ref::<Element>()
                                     .release_pointer_capture(js_event.pointer_id());
                             }
-                            let event = PlayerEvent::MouseUp {
-                                x: f64::from(js_event.offset_x()) * instance.device_pixel_ratio,
-                                y: f64::from(js_event.offset_y()) * instance.device_pixel_ratio,
-                            };
-                            instance.core.handle_event(event);
+                            if let Some(button) =
+                                input::web_to_ruffle_mouse_button(js_event.button())
+                            {
+                                instance
+                                    .core
+                                    .input_mut()
+                                    .set_mouse_button_down(button, false);
+                                let event = PlayerEvent::MouseUp {
+                                    x: f64::from(js_event.offset_x()) * instance.device_pixel_ratio,
+                                    y: f64::from(js_event.offset_y()) * instance.device_pixel_ratio,
+                                    button,
+                                };
+                                instance.core.handle_event(event);
+                            }
                             if instance.has_focus {
                                 js_event.prevent_default();
                             }
@@ -261,6 +348,111 @@ impl Ruffle {
                 instance.mouse_up_callback = Some(mouse_up_callback);
             }
 
+            // Create mouse wheel handler.
+            {
+                let mouse_wheel_callback = Closure::wrap(Box::new(move |js_event: WheelEvent| {
+                    INSTANCES.with(move |instances| {
+                        let mut instances = instances.borrow_mut();
+                        if let Some(instance) = instances.get_mut(index) {
+                            let delta = input::web_to_ruffle_wheel_delta(&js_event);
+                            instance
+                                .core
+                                .handle_event(PlayerEvent::MouseWheel { delta });
+                            // Keep the page from scrolling while the movie has focus.
+                            if instance.has_focus {
+                                js_event.prevent_default();
+                            }
+                        }
+                    });
+                })
+                    as Box<dyn FnMut(WheelEvent)>);
+                let canvas_events: &EventTarget = canvas.as_ref();
+                canvas_events
+                    .add_event_listener_with_callback(
+                        "wheel",
+                        mouse_wheel_callback.as_ref().unchecked_ref(),
+                    )
+                    .unwrap();
+                let instance = instances.get_mut(index).unwrap();
+                instance.mouse_wheel_callback = Some(mouse_wheel_callback);
+            }
+
+            // Create context menu handler.
+            {
+                let context_menu_callback = Closure::wrap(Box::new(move |js_event: MouseEvent| {
+                    js_event.prevent_default();
+                    INSTANCES.with(|instances| {
+                        let mut instances = instances.borrow_mut();
+                        if let Some(instance) = instances.get_mut(index) {
+                            instance.close_context_menu();
+                            let items = instance.core.prepare_context_menu();
+                            if items.is_empty() {
+                                instance.core.clear_context_menu();
+                                return;
+                            }
+
+                            let document = web_sys::window().unwrap().document().unwrap();
+                            let menu = context_menu::show_menu(
+                                &document,
+                                &items,
+                                js_event.client_x(),
+                                js_event.client_y(),
+                            );
+                            match menu {
+                                Ok(menu) => {
+                                    // Clicks on the items are handled by a single listener on the menu.
+                                    if let Some(callback) = &instance.context_menu_click_callback {
+                                        let menu_events: &EventTarget = menu.as_ref();
+                                        menu_events
+                                            .add_event_listener_with_callback(
+                                                "click",
+                                                callback.as_ref().unchecked_ref(),
+                                            )
+                                            .unwrap();
+                                    }
+                                    instance.context_menu = Some(menu);
+                                }
+                                Err(e) => {
+                                    log::error!("Unable to show context menu: {:?}", e);
+                                    instance.core.clear_context_menu();
+                                }
+                            }
+                        }
+                    });
+                })
+                    as Box<dyn FnMut(MouseEvent)>);
+                let canvas_events: &EventTarget = canvas.as_ref();
+                canvas_events
+                    .add_event_listener_with_callback(
+                        "contextmenu",
+                        context_menu_callback.as_ref().unchecked_ref(),
+                    )
+                    .unwrap();
+                let instance = instances.get_mut(index).unwrap();
+                instance.context_menu_callback = Some(context_menu_callback);
+            }
+
+            // Create context menu item click handler.
+            {
+                let context_menu_click_callback =
+                    Closure::wrap(Box::new(move |js_event: MouseEvent| {
+                        INSTANCES.with(|instances| {
+                            let mut instances = instances.borrow_mut();
+                            if let Some(instance) = instances.get_mut(index) {
+                                if let Some(item) = context_menu::clicked_item(&js_event) {
+                                    if let Some(menu) = instance.context_menu.take() {
+                                        menu.remove();
+                                    }
+                                    instance.has_focus = true;
+                                    instance.core.run_context_menu_callback(item);
+                                }
+                            }
+                        });
+                    }) as Box<dyn FnMut(MouseEvent)>);
+                let instance = instances.get_mut(index).unwrap();
+                instance.context_menu_click_callback = Some(context_menu_click_callback);
+            }
+
             // Create click event handler.
             // {
             //     let click_callback = Closure::wrap(Box::new(move |_| {
@@ -290,22 +482,29 @@ impl Ruffle {
                 let key_down_callback = Closure::wrap(Box::new(move |js_event: KeyboardEvent| {
                     INSTANCES.with(|instances| {
                         if let Some(instance) = instances.borrow_mut().get_mut(index) {
+                            if instance.context_menu.is_some() && js_event.key() == "Escape" {
+                                instance.close_context_menu();
+                                js_event.prevent_default();
+                                return;
+                            }
+
                             if instance.has_focus {
                                 let code = js_event.code();
-                                instance.core.input_mut().keydown(code.clone());
+                                let key_char = input::web_key_to_codepoint(&js_event.key());
+                                let input = instance.core.input_mut();
+                                input.keydown(code.clone(), key_char);
+                                input.update_toggled_keys(&js_event);
 
-                                if let Some(codepoint) =
-                                    input::web_key_to_codepoint(&js_event.key())
-                                {
+                                if let Some(key_code) = input::web_to_ruffle_key_code(&code) {
                                     instance
                                         .core
-                                        .handle_event(PlayerEvent::TextInput { codepoint });
+                                        .handle_event(PlayerEvent::KeyDown { key_code });
                                 }
 
-                                if let Some(key_code) = input::web_to_ruffle_key_code(&code) {
+                                if let Some(codepoint) = key_char {
                                     instance
                                         .core
-                                        .handle_event(PlayerEvent::KeyDown { key_code });
+                                        .handle_event(PlayerEvent::TextInput { codepoint });
                                 }
 
                                 js_event.prevent_default();
@@ -331,7 +530,17 @@ impl Ruffle {
                     INSTANCES.with(|instances| {
                         if let Some(instance) = instances.borrow_mut().get_mut(index) {
                             if instance.has_focus {
-                                instance.core.input_mut().keyup(js_event.code());
+                                let code = js_event.code();
+                                let key_char = input::web_key_to_codepoint(&js_event.key());
+                                let input = instance.core.input_mut();
+                                let was_down = input.keyup(code.clone(), key_char);
+                                input.update_toggled_keys(&js_event);
+
+                                if was_down {
+                                    if let Some(key_code) = input::web_to_ruffle_key_code(&code) {
+                                        instance.core.handle_event(PlayerEvent::KeyUp { key_code });
+                                    }
+                                }
                                 js_event.prevent_default();
                             }
                         }
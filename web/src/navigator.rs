@@ -1,15 +1,39 @@
 //! Navigator backend for web
 
-use ruffle_core::backend::navigator::{NavigationMethod, NavigatorBackend};
+use generational_arena::Arena;
+use js_sys::{Function, Promise, Reflect, Uint8Array};
+use ruffle_core::backend::navigator::{
+    NavigationMethod, NavigatorBackend, RequestHandle, RequestStatus,
+};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use wasm_bindgen::JsCast;
-use web_sys::window;
+use std::rc::Rc;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{window, Response};
 
-pub struct WebNavigatorBackend {}
+/// The progress of a request started by `fetch`.
+#[derive(Default)]
+struct RequestState {
+    /// Data that has been received but not yet returned by `poll_request`.
+    data: Vec<u8>,
+    bytes_total: Option<usize>,
+    complete: bool,
+    failed: bool,
+}
+
+type SharedRequestState = Rc<RefCell<RequestState>>;
+
+pub struct WebNavigatorBackend {
+    /// The requests started by `fetch`, shared with the tasks loading them.
+    requests: Arena<SharedRequestState>,
+}
 
 impl WebNavigatorBackend {
     pub fn new() -> Self {
-        WebNavigatorBackend {}
+        WebNavigatorBackend {
+            requests: Arena::new(),
+        }
     }
 }
 
@@ -72,4 +96,84 @@ impl NavigatorBackend for WebNavigatorBackend {
             };
         }
     }
+
+    fn fetch(&mut self, url: &str) -> Option<RequestHandle> {
+        let state = SharedRequestState::default();
+        let url = url.to_string();
+        let load_state = Rc::clone(&state);
+        spawn_local(async move {
+            if let Err(e) = load(&url, &load_state).await {
+                log::error!("Unable to fetch {}: {:?}", url, e);
+                load_state.borrow_mut().failed = true;
+            }
+        });
+        Some(self.requests.insert(state))
+    }
+
+    fn poll_request(&mut self, request: RequestHandle, data: &mut Vec<u8>) -> RequestStatus {
+        let state = match self.requests.get(request) {
+            Some(state) => Rc::clone(state),
+            None => return RequestStatus::Failed,
+        };
+        let mut state = state.borrow_mut();
+
+        if state.failed {
+            self.requests.remove(request);
+            return RequestStatus::Failed;
+        }
+
+        data.append(&mut state.data);
+        if state.complete {
+            self.requests.remove(request);
+            RequestStatus::Complete
+        } else {
+            RequestStatus::Loading {
+                bytes_total: state.bytes_total,
+            }
+        }
+    }
+}
+
+/// Fetches `url`, appending the body to `state` as each chunk arrives.
+///
+/// The body is read through a `ReadableStream` so that the data received so far can be
+/// returned while the rest is still downloading. Network errors, including failed loads
+/// of local files, reject the `fetch` promise.
+async fn load(url: &str, state: &SharedRequestState) -> Result<(), JsValue> {
+    let window = window().ok_or("No window")?;
+    let response: Response = JsFuture::from(window.fetch_with_str(url))
+        .await?
+        .dyn_into()?;
+    if !response.ok() {
+        return Err(format!("HTTP status {}", response.status()).into());
+    }
+
+    state.borrow_mut().bytes_total = response
+        .headers()
+        .get("Content-Length")?
+        .and_then(|len| len.parse().ok());
+
+    let body = response.body().ok_or("No response body")?;
+    let reader = call_method(&body, "getReader")?;
+    loop {
+        let result = JsFuture::from(call_method(&reader, "read")?.dyn_into::<Promise>()?).await?;
+        if Reflect::get(&result, &"done".into())?.is_truthy() {
+            break;
+        }
+        let chunk = Uint8Array::new(&Reflect::get(&result, &"value".into())?);
+        let mut state = state.borrow_mut();
+        let start = state.data.len();
+        state.data.resize(start + chunk.length() as usize, 0);
+        chunk.copy_to(&mut state.data[start..]);
+    }
+
+    state.borrow_mut().complete = true;
+    Ok(())
+}
+
+/// Calls the method `name` of `object` with no arguments.
+fn call_method(object: &JsValue, name: &str) -> Result<JsValue, JsValue> {
+    Reflect::get(object, &name.into())?
+        .dyn_into::<Function>()?
+        .call0(object)
 }
@@ -1,7 +1,8 @@
 use crate::utils::JsResult;
+use ruffle_core::backend::render::glyph_atlas::{AtlasGlyph, GlyphAtlas};
 use ruffle_core::backend::render::{
     swf, swf::CharacterId, BitmapHandle, BitmapInfo, Color, Letterbox, RenderBackend, ShapeHandle,
-    Transform,
+    TextRenderSettings, Transform,
 };
 use ruffle_core::color_transform::ColorTransform;
 use ruffle_core::shape_utils::DrawCommand;
@@ -10,7 +11,7 @@ use std::convert::TryInto;
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{
     CanvasGradient, CanvasPattern, CanvasRenderingContext2d, Element, HtmlCanvasElement,
-    HtmlImageElement, Path2d, SvgsvgElement,
+    HtmlImageElement, ImageData, Path2d, SvgsvgElement,
 };
 
 pub struct WebCanvasRenderBackend {
@@ -27,6 +28,8 @@ pub struct WebCanvasRenderBackend {
     viewport_height: u32,
     use_color_transform_hack: bool,
     pixelated_property_value: &'static str,
+    glyph_atlas: GlyphAtlas,
+    glyph_pages: Vec<(HtmlCanvasElement, CanvasRenderingContext2d)>,
 }
 
 /// Canvas-drawable shape data extracted from an SWF file.
@@ -217,6 +220,8 @@ impl WebCanvasRenderBackend {
             } else {
                 "pixelated"
             },
+            glyph_atlas: GlyphAtlas::new(),
+            glyph_pages: vec![],
         };
         Ok(renderer)
     }
@@ -358,6 +363,69 @@ impl WebCanvasRenderBackend {
         self.context.set_filter("none");
         self.context.set_global_alpha(1.0);
     }
+
+    /// Copies the glyph atlas pages that have changed to their canvases.
+    fn update_glyph_pages(&mut self) {
+        let pages = &mut self.glyph_pages;
+        pages.truncate(self.glyph_atlas.num_pages());
+        self.glyph_atlas.update_pages(|i, page| {
+            if i >= pages.len() {
+                let document = web_sys::window().unwrap().document().unwrap();
+                let canvas: HtmlCanvasElement = document
+                    .create_element("canvas")
+                    .unwrap()
+                    .dyn_into()
+                    .unwrap();
+                canvas.set_width(GlyphAtlas::PAGE_SIZE);
+                canvas.set_height(GlyphAtlas::PAGE_SIZE);
+                let context: CanvasRenderingContext2d = canvas
+                    .get_context("2d")
+                    .unwrap()
+                    .unwrap()
+                    .dyn_into()
+                    .unwrap();
+                pages.push((canvas, context));
+            }
+
+            let mut rgba: Vec<u8> = page
+                .alpha()
+                .iter()
+                .flat_map(|&a| vec![255, 255, 255, a])
+                .collect();
+            match ImageData::new_with_u8_clamped_array_and_sh(
+                wasm_bindgen::Clamped(&mut rgba),
+                GlyphAtlas::PAGE_SIZE,
+                GlyphAtlas::PAGE_SIZE,
+            ) {
+                Ok(image_data) => pages[i]
+                    .1
+                    .put_image_data(&image_data, 0.0, 0.0)
+                    .warn_on_error(),
+                Err(e) => log::warn!("Unable to create glyph atlas page: {:?}", e),
+            }
+        });
+    }
+
+    /// Draws a glyph from the glyph atlas at a whole pixel position.
+    fn render_atlas_glyph(&mut self, glyph: AtlasGlyph, transform: &Transform) {
+        self.update_glyph_pages();
+        self.context.reset_transform().unwrap();
+        self.set_color_filter(transform);
+        let _ = self
+            .context
+            .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                &self.glyph_pages[glyph.page].0,
+                glyph.x.into(),
+                glyph.y.into(),
+                glyph.width.into(),
+                glyph.height.into(),
+                glyph.left.into(),
+                glyph.top.into(),
+                glyph.width.into(),
+                glyph.height.into(),
+            );
+        self.clear_color_filter();
+    }
 }
 
 impl RenderBackend for WebCanvasRenderBackend {
@@ -422,7 +490,9 @@ impl RenderBackend for WebCanvasRenderBackend {
             },
             shape: glyph.shape_records.clone(),
         };
-        self.register_shape(&shape)
+        let handle = self.register_shape(&shape);
+        self.glyph_atlas.register_glyph(handle, glyph);
+        handle
     }
 
     fn register_bitmap_jpeg(
@@ -610,6 +680,19 @@ impl RenderBackend for WebCanvasRenderBackend {
         }
     }
 
+    fn render_glyph(
+        &mut self,
+        shape: ShapeHandle,
+        transform: &Transform,
+        settings: &TextRenderSettings,
+    ) {
+        if let Some(glyph) = self.glyph_atlas.get(shape, &transform.matrix, settings) {
+            self.render_atlas_glyph(glyph, transform);
+        } else {
+            self.render_shape(shape, transform);
+        }
+    }
+
     fn draw_pause_overlay(&mut self) {
         let width = f64::from(self.canvas.width());
         let height = f64::from(self.canvas.height());
@@ -630,23 +713,23 @@ impl RenderBackend for WebCanvasRenderBackend {
 
         match letterbox {
             Letterbox::None => (),
-            Letterbox::Letterbox(margin_height) => {
+            Letterbox::Letterbox(margin_top, margin_bottom) => {
                 self.context
-                    .fill_rect(0.0, 0.0, self.viewport_width.into(), margin_height.into());
+                    .fill_rect(0.0, 0.0, self.viewport_width.into(), margin_top.into());
                 self.context.fill_rect(
                     0.0,
-                    (self.viewport_height as f32 - margin_height).into(),
+                    (self.viewport_height as f32 - margin_bottom).into(),
                     self.viewport_width.into(),
-                    self.viewport_height.into(),
+                    margin_bottom.into(),
                 );
             }
-            Letterbox::Pillarbox(margin_width) => {
+            Letterbox::Pillarbox(margin_left, margin_right) => {
                 self.context
-                    .fill_rect(0.0, 0.0, margin_width.into(), self.viewport_height.into());
+                    .fill_rect(0.0, 0.0, margin_left.into(), self.viewport_height.into());
                 self.context.fill_rect(
-                    (self.viewport_width as f32 - margin_width).into(),
+                    (self.viewport_width as f32 - margin_right).into(),
                     0.0,
-                    margin_width.into(),
+                    margin_right.into(),
                     self.viewport_height.into(),
                 );
             }